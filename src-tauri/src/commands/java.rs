@@ -1,4 +1,4 @@
-use crate::services::java_detector;
+use crate::services::{java_detector, java_runtime_catalogue};
 use once_cell::sync::Lazy;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
@@ -21,12 +21,11 @@ pub async fn validate_java_path(path: String) -> Result<java_detector::JavaInfo,
         .map_err(|e| format!("Java 路径验证任务失败: {}", e))?
 }
 
-#[tauri::command]
-pub async fn install_java<R: tauri::Runtime>(
-    _app: tauri::AppHandle<R>,
+async fn run_java_install<R: tauri::Runtime>(
     window: tauri::Window<R>,
     url: String,
     version_name: String,
+    sha256: String,
 ) -> Result<String, String> {
     use crate::services::java_installer;
 
@@ -41,7 +40,8 @@ pub async fn install_java<R: tauri::Runtime>(
     }
 
     let result =
-        java_installer::download_and_install_java(url, version_name, window, cancel_flag).await;
+        java_installer::download_and_install_java(url, version_name, sha256, window, cancel_flag)
+            .await;
 
     // Clear flag after done
     if let Ok(mut lock) = JAVA_INSTALL_CANCEL_FLAG.lock() {
@@ -63,3 +63,51 @@ pub async fn cancel_java_install() -> Result<(), String> {
 
     Ok(())
 }
+
+#[tauri::command]
+pub async fn list_java_packages(
+    major_version: u32,
+) -> Result<Vec<java_runtime_catalogue::JavaPackage>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        java_runtime_catalogue::list_java_packages(major_version)
+    })
+    .await
+    .map_err(|e| format!("查询 Java 发行包任务失败: {}", e))?
+}
+
+/// 从 Temurin/Zulu 目录解析安装包并按 sha256 校验后安装
+#[tauri::command]
+pub async fn install_java_package<R: tauri::Runtime>(
+    window: tauri::Window<R>,
+    distribution: String,
+    major_version: u32,
+) -> Result<String, String> {
+    let distribution: java_runtime_catalogue::JavaDistribution = distribution.parse()?;
+    let package = tauri::async_runtime::spawn_blocking(move || {
+        java_runtime_catalogue::resolve_java_package(distribution, major_version)
+    })
+    .await
+    .map_err(|e| format!("查询 Java 发行包任务失败: {}", e))??;
+
+    let version_name = package.runtime_name();
+    let sha256 = package
+        .sha256
+        .ok_or_else(|| format!("{} 未提供 sha256，拒绝安装", package.file_name))?;
+    run_java_install(window, package.download_url, version_name, sha256).await
+}
+
+#[tauri::command]
+pub async fn list_java_runtimes() -> Result<Vec<java_runtime_catalogue::ManagedRuntime>, String> {
+    tauri::async_runtime::spawn_blocking(java_runtime_catalogue::list_managed_runtimes)
+        .await
+        .map_err(|e| format!("读取运行时列表任务失败: {}", e))?
+}
+
+#[tauri::command]
+pub async fn remove_java_runtime(name: String) -> Result<(), String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        java_runtime_catalogue::remove_managed_runtime(&name)
+    })
+    .await
+    .map_err(|e| format!("删除运行时任务失败: {}", e))?
+}
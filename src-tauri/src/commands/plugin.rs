@@ -1,15 +1,30 @@
 use crate::models::plugin::{
-    BatchInstallError, BatchInstallResult, PluginInfo, PluginInstallResult, PluginUpdateInfo,
+    BatchInstallError, BatchInstallResult, MarketPluginInfo, PluginInfo, PluginInstallResult,
+    PluginSignature, PluginUpdateInfo, SignatureStatus,
 };
+use crate::models::settings::{MarketSource, OFFICIAL_MARKET_URL};
 use crate::plugins::api::{
     BufferedComponentEvent, BufferedContextMenuEvent, BufferedSidebarEvent, BufferedUiEvent,
 };
-use crate::plugins::manager::PluginManager;
+use crate::plugins::commands::{execute_builtin, CommandEntry, CommandTarget};
+use crate::plugins::grants::{GrantDecision, PermissionAuditEntry, PermissionGrant};
+use crate::plugins::harness::{self, PluginTestReport};
+use crate::plugins::logs::{self, PluginLogEntry, PluginLogQuery};
+use crate::plugins::manager::{get_runtime, PluginManager};
+use crate::plugins::market::{is_market_source_url, MarketCatalog, MarketFetcher, SourceLocation};
+use crate::plugins::packager::{self, PluginPackageResult};
+use crate::plugins::resolver::{
+    check_plan_previewed, DependencyPlan, DependencyResolver, PlanAction,
+};
+use crate::plugins::runtime::{plugin_http_log, HttpRequestLogEntry};
+use crate::plugins::updater::{
+    has_critical_permission, keeps_signature_trust, AutoUpdateReport, PluginUpdateFailure,
+    PluginUpdateResult, UpdatePolicy,
+};
+use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use url::Url;
 
-const MARKET_BASE_URL: &str = "https://sealantern-studio.github.io/plugin-market";
-
 const ALLOWED_DOWNLOAD_DOMAINS: &[&str] = &[
     "localhost",
     "sealanternpluginmarket.little100.top",
@@ -61,6 +76,144 @@ pub fn enable_plugin(
     manager.enable_plugin(&plugin_id)
 }
 
+#[tauri::command]
+pub fn confirm_unsigned_plugin(
+    plugin_id: String,
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Result<(), String> {
+    validate_plugin_id(&plugin_id)?;
+    let mut manager = manager.lock().unwrap_or_else(|e| e.into_inner());
+    manager.confirm_unsigned_plugin(&plugin_id)
+}
+
+#[tauri::command]
+pub fn get_plugin_permission_grants(
+    plugin_id: String,
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Result<Vec<PermissionGrant>, String> {
+    validate_plugin_id(&plugin_id)?;
+    let manager = manager.lock().unwrap_or_else(|e| e.into_inner());
+    manager.get_permission_grants(&plugin_id)
+}
+
+#[tauri::command]
+pub fn set_plugin_permission_grant(
+    plugin_id: String,
+    permission: String,
+    decision: GrantDecision,
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Result<(), String> {
+    validate_plugin_id(&plugin_id)?;
+    let mut manager = manager.lock().unwrap_or_else(|e| e.into_inner());
+    manager.set_permission_grant(&plugin_id, &permission, decision)
+}
+
+#[tauri::command]
+pub fn get_pending_permission_prompts(
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Vec<serde_json::Value> {
+    let manager = manager.lock().unwrap_or_else(|e| e.into_inner());
+    manager.pending_permission_prompts()
+}
+
+#[tauri::command]
+pub fn get_plugin_permission_audit(
+    plugin_id: Option<String>,
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Result<Vec<PermissionAuditEntry>, String> {
+    if let Some(ref id) = plugin_id {
+        validate_plugin_id(id)?;
+    }
+    let manager = manager.lock().unwrap_or_else(|e| e.into_inner());
+    Ok(manager.get_permission_audit_log(plugin_id.as_deref()))
+}
+
+#[tauri::command]
+pub fn get_plugin_network_log(plugin_id: String) -> Result<Vec<HttpRequestLogEntry>, String> {
+    validate_plugin_id(&plugin_id)?;
+    Ok(plugin_http_log(&plugin_id))
+}
+
+fn plugin_log_path(
+    plugin_id: &str,
+    manager: &tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> std::path::PathBuf {
+    manager
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .plugin_log_path(plugin_id)
+}
+
+/// 查询插件持久化的日志，未指定条件时返回最新的若干条
+#[tauri::command]
+pub fn query_plugin_logs(
+    plugin_id: String,
+    query: Option<PluginLogQuery>,
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Result<Vec<PluginLogEntry>, String> {
+    validate_plugin_id(&plugin_id)?;
+    let log_path = plugin_log_path(&plugin_id, &manager);
+    logs::query_logs(&log_path, &query.unwrap_or_default())
+}
+
+/// 拉取 id 大于 after_id 的新日志，供日志查看器持续追加
+#[tauri::command]
+pub fn tail_plugin_logs(
+    plugin_id: String,
+    after_id: i64,
+    limit: Option<usize>,
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Result<Vec<PluginLogEntry>, String> {
+    validate_plugin_id(&plugin_id)?;
+    let log_path = plugin_log_path(&plugin_id, &manager);
+    let query = PluginLogQuery {
+        after_id: Some(after_id),
+        limit,
+        ..Default::default()
+    };
+    logs::query_logs(&log_path, &query)
+}
+
+#[tauri::command]
+pub fn clear_plugin_logs(
+    plugin_id: String,
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Result<(), String> {
+    validate_plugin_id(&plugin_id)?;
+    let log_path = plugin_log_path(&plugin_id, &manager);
+    logs::clear_logs(&plugin_id, &log_path)
+}
+
+/// 导出插件诊断包（zip），返回写入的路径
+#[tauri::command]
+pub fn export_plugin_diagnostics(
+    plugin_id: String,
+    output_path: String,
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Result<String, String> {
+    validate_plugin_id(&plugin_id)?;
+    let (plugin_dir, log_path, settings, info) = {
+        let manager = manager.lock().unwrap_or_else(|e| e.into_inner());
+        let info = manager.diagnostic_info(&plugin_id)?;
+        let plugin_dir = manager
+            .plugins()
+            .get(&plugin_id)
+            .map(|p| std::path::PathBuf::from(&p.path))
+            .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
+        let settings = manager
+            .get_plugin_settings(&plugin_id)
+            .unwrap_or_else(|e| serde_json::json!({ "error": e }));
+        (plugin_dir, manager.plugin_log_path(&plugin_id), settings, info)
+    };
+
+    let mut output = std::path::PathBuf::from(output_path);
+    if output.extension().is_none_or(|ext| ext != "zip") {
+        output.set_extension("zip");
+    }
+    logs::write_diagnostic_bundle(&output, &plugin_dir, &log_path, &settings, &info)?;
+    Ok(output.to_string_lossy().to_string())
+}
+
 #[tauri::command]
 pub fn disable_plugin(
     plugin_id: String,
@@ -197,78 +350,13 @@ pub async fn check_plugin_update(
         plugin_info.manifest.version.clone()
     };
 
-    let pid = plugin_id.clone();
-
-    tokio::task::spawn_blocking(move || {
-        let client = reqwest::blocking::Client::builder()
-            .user_agent("SeaLantern")
-            .build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-
-        let index_url = format!("{}/api/plugins.json", MARKET_BASE_URL);
-        let index: serde_json::Value = client
-            .get(&index_url)
-            .send()
-            .map_err(|e| format!("Failed to fetch plugins index: {}", e))?
-            .json()
-            .map_err(|e| format!("Failed to parse plugins index: {}", e))?;
-
-        let plugin_path = index
-            .get("paths")
-            .and_then(|v| v.as_array())
-            .and_then(|arr| {
-                arr.iter().find(|v| {
-                    v.as_str()
-                        .map(|s| {
-                            let parts: Vec<&str> = s.split('/').collect();
-                            parts.get(2).map(|p| *p == pid.as_str()).unwrap_or(false)
-                        })
-                        .unwrap_or(false)
-                })
-            })
-            .and_then(|v| v.as_str())
-            .map(|s| s.to_string());
-
-        let plugin_path = match plugin_path {
-            Some(p) => p,
-            None => return Ok(None),
-        };
-
-        let plugin_url = format!("{}/{}", MARKET_BASE_URL, plugin_path);
-        let response = client
-            .get(&plugin_url)
-            .send()
-            .map_err(|e| format!("Failed to fetch plugin info: {}", e))?;
-
-        if !response.status().is_success() {
-            if response.status().as_u16() == 404 {
-                return Ok(None);
-            }
-            return Err(format!("Market API returned error: {}", response.status()));
-        }
-
-        let market_info: crate::models::plugin::MarketPluginInfo = response
-            .json()
-            .map_err(|e| format!("Failed to parse market response: {}", e))?;
-
-        let latest = match market_info.version {
-            Some(ref v) if !v.is_empty() => v.clone(),
-            _ => return Ok(None),
-        };
-        if PluginManager::is_newer_version(&latest, &current_version) {
-            Ok(Some(PluginUpdateInfo {
-                plugin_id: pid,
-                current_version,
-                latest_version: latest,
-                download_url: market_info.download_url,
-                changelog: market_info.changelog,
-            }))
-        } else {
-            Ok(None)
-        }
-    })
-    .await
-    .map_err(|e| format!("Task join error: {}", e))?
+    let market = tokio::task::spawn_blocking(|| fetch_market_index(None))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))??;
+    Ok(market
+        .into_iter()
+        .find(|p| p.id == plugin_id)
+        .and_then(|market_info| find_update(plugin_id, current_version, market_info)))
 }
 
 #[tauri::command]
@@ -283,167 +371,105 @@ pub async fn check_all_plugin_updates(
             .collect()
     };
 
-    tokio::task::spawn_blocking(move || {
-        let client = reqwest::blocking::Client::builder()
-            .user_agent("SeaLantern")
-            .build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+    let market = tokio::task::spawn_blocking(|| fetch_market_index(None))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))??;
+    let mut market: HashMap<String, MarketPluginInfo> =
+        market.into_iter().map(|p| (p.id.clone(), p)).collect();
+
+    Ok(plugin_versions
+        .into_iter()
+        .filter_map(|(plugin_id, current_version)| {
+            let market_info = market.remove(&plugin_id)?;
+            find_update(plugin_id, current_version, market_info)
+        })
+        .collect())
+}
 
-        let index_url = format!("{}/api/plugins.json", MARKET_BASE_URL);
-        let index: serde_json::Value = client
-            .get(&index_url)
-            .send()
-            .map_err(|e| format!("Failed to fetch plugins index: {}", e))?
-            .json()
-            .map_err(|e| format!("Failed to parse plugins index: {}", e))?;
-
-        let paths: Vec<String> = index
-            .get("paths")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default();
+fn find_update(
+    plugin_id: String,
+    current_version: String,
+    market_info: MarketPluginInfo,
+) -> Option<PluginUpdateInfo> {
+    let latest = market_info.version.filter(|v| !v.is_empty())?;
+    if !PluginManager::is_newer_version(&latest, &current_version) {
+        return None;
+    }
+    Some(PluginUpdateInfo {
+        plugin_id,
+        current_version,
+        latest_version: latest,
+        download_url: market_info.download_url,
+        changelog: market_info.changelog,
+    })
+}
 
-        let path_map: std::collections::HashMap<String, String> = paths
-            .iter()
-            .filter_map(|p| {
-                let parts: Vec<&str> = p.split('/').collect();
-                parts.get(2).map(|id| (id.to_string(), p.clone()))
-            })
-            .collect();
-
-        let mut updates = Vec::new();
-        for (plugin_id, current_version) in plugin_versions {
-            let plugin_path = match path_map.get(&plugin_id) {
-                Some(p) => p.clone(),
-                None => continue,
-            };
-            let url = format!("{}/{}", MARKET_BASE_URL, plugin_path);
-            if let Ok(response) = client.get(&url).send() {
-                if response.status().is_success() {
-                    if let Ok(market_info) =
-                        response.json::<crate::models::plugin::MarketPluginInfo>()
-                    {
-                        if let Some(ref latest) = market_info.version {
-                            if PluginManager::is_newer_version(latest, &current_version) {
-                                updates.push(PluginUpdateInfo {
-                                    plugin_id,
-                                    current_version,
-                                    latest_version: latest.clone(),
-                                    download_url: market_info.download_url,
-                                    changelog: market_info.changelog,
-                                });
-                            }
-                        }
-                    }
-                }
-            }
+/// 未指定地址时使用设置中的市场源，指定时只拉取该地址
+fn market_sources(market_url: Option<String>) -> Vec<MarketSource> {
+    match market_url.filter(|u| !u.trim().is_empty()) {
+        Some(url) => vec![MarketSource {
+            id: "custom".to_string(),
+            name: url.clone(),
+            url,
+            enabled: true,
+        }],
+        None => {
+            crate::services::global::settings_manager()
+                .get()
+                .plugin_market_sources
         }
-        Ok(updates)
-    })
-    .await
-    .map_err(|e| format!("Task join error: {}", e))?
+    }
+}
+
+/// 并行拉取并合并所有市场源（阻塞调用）
+fn fetch_market_catalog_blocking(market_url: Option<String>) -> Result<MarketCatalog, String> {
+    let sources = market_sources(market_url);
+    if !sources.iter().any(|s| s.enabled) {
+        return Err("没有启用的插件市场源".to_string());
+    }
+    let fetcher = MarketFetcher::new(&crate::utils::path::get_app_data_dir())?;
+    Ok(fetcher.fetch_catalog(&sources))
+}
+
+/// 拉取市场中的全部插件条目，所有源都失败时返回错误（阻塞调用）
+fn fetch_market_index(market_url: Option<String>) -> Result<Vec<MarketPluginInfo>, String> {
+    let catalog = fetch_market_catalog_blocking(market_url)?;
+    if catalog.plugins.is_empty() && !catalog.errors.is_empty() {
+        return Err(catalog
+            .errors
+            .iter()
+            .map(|e| format!("{}: {}", e.source, e.error))
+            .collect::<Vec<_>>()
+            .join("; "));
+    }
+    Ok(catalog.plugins)
+}
+
+/// 拉取所有市场源的插件与分类，单个源失败时在 errors 中报告
+#[tauri::command]
+pub async fn fetch_market_catalog() -> Result<MarketCatalog, String> {
+    tokio::task::spawn_blocking(|| fetch_market_catalog_blocking(None))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
 }
 
 #[tauri::command]
 pub async fn fetch_market_plugins(
     _manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
     market_url: Option<String>,
-) -> Result<Vec<crate::models::plugin::MarketPluginInfo>, String> {
-    let base_url = market_url.unwrap_or_else(|| MARKET_BASE_URL.to_string());
-    let base_url = base_url.trim_end_matches('/').to_string();
-    tokio::task::spawn_blocking(move || {
-        let client = reqwest::blocking::Client::builder()
-            .user_agent("SeaLantern")
-            .build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-
-        let index_url = format!("{}/api/plugins.json", base_url);
-        let index_resp = client
-            .get(&index_url)
-            .send()
-            .map_err(|e| format!("Failed to fetch plugins index: {}", e))?;
-        if !index_resp.status().is_success() {
-            return Err(format!("Plugins index returned status: {}", index_resp.status()));
-        }
-        let index_json: serde_json::Value = index_resp
-            .json()
-            .map_err(|e| format!("Failed to parse plugins index: {}", e))?;
-
-        let paths: Vec<String> = index_json
-            .get("paths")
-            .and_then(|v| v.as_array())
-            .map(|arr| {
-                arr.iter()
-                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
-                    .collect()
-            })
-            .unwrap_or_default();
-
-        let mut all_plugins: Vec<crate::models::plugin::MarketPluginInfo> = Vec::new();
-
-        for plugin_path in &paths {
-            let plugin_url = format!("{}/{}", base_url, plugin_path);
-            let plugin_resp = match client.get(&plugin_url).send() {
-                Ok(r) if r.status().is_success() => r,
-                _ => continue,
-            };
-            let plugin_json: serde_json::Value = match plugin_resp.json() {
-                Ok(v) => v,
-                Err(_) => continue,
-            };
-            if let Ok(mut plugin) =
-                serde_json::from_value::<crate::models::plugin::MarketPluginInfo>(plugin_json)
-            {
-                let parts: Vec<&str> = plugin_path.split('/').collect();
-                if parts.len() >= 3 {
-                    let username = parts[1];
-                    let plugin_folder = parts[2];
-                    if plugin.repo.is_empty() {
-                        plugin.repo = format!("{}/{}", username, plugin_folder);
-                    }
-                    if plugin.author.name.is_empty() {
-                        plugin.author.name = username.to_string();
-                        if plugin.author.url.is_none() {
-                            plugin.author.url = Some(format!("https://github.com/{}", username));
-                        }
-                    }
-                }
-                plugin._path = Some(plugin_path.clone());
-                all_plugins.push(plugin);
-            }
-        }
-
-        Ok(all_plugins)
-    })
-    .await
-    .map_err(|e| format!("Task join error: {}", e))?
+) -> Result<Vec<MarketPluginInfo>, String> {
+    tokio::task::spawn_blocking(move || fetch_market_index(market_url))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
 }
 
 #[tauri::command]
 pub async fn fetch_market_categories(
     market_url: Option<String>,
 ) -> Result<serde_json::Value, String> {
-    let base_url = market_url.unwrap_or_else(|| MARKET_BASE_URL.to_string());
-    let base_url = base_url.trim_end_matches('/').to_string();
     tokio::task::spawn_blocking(move || {
-        let url = format!("{}/api/categories.json", base_url);
-        let response = reqwest::blocking::Client::builder()
-            .user_agent("SeaLantern")
-            .build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?
-            .get(&url)
-            .send()
-            .map_err(|e| format!("Failed to fetch categories: {}", e))?;
-        if !response.status().is_success() {
-            return Ok(serde_json::Value::Object(Default::default()));
-        }
-        response
-            .json()
-            .map_err(|e| format!("Failed to parse categories: {}", e))
+        let catalog = fetch_market_catalog_blocking(market_url)?;
+        Ok(serde_json::Value::Object(catalog.categories))
     })
     .await
     .map_err(|e| format!("Task join error: {}", e))?
@@ -455,19 +481,15 @@ pub async fn fetch_market_plugin_detail(
     plugin_path: String,
     market_url: Option<String>,
 ) -> Result<serde_json::Value, String> {
-    let base_url = market_url.unwrap_or_else(|| MARKET_BASE_URL.to_string());
-    let base_url = base_url.trim_end_matches('/').to_string();
+    let base_url = market_url.unwrap_or_else(|| OFFICIAL_MARKET_URL.to_string());
     tokio::task::spawn_blocking(move || {
-        let url = format!("{}/{}", base_url, plugin_path);
-        let response = reqwest::blocking::get(&url)
-            .map_err(|e| format!("Failed to fetch plugin detail: {}", e))?;
-
-        if !response.status().is_success() {
-            return Err(format!("Market API returned status: {}", response.status()));
-        }
+        let location = SourceLocation::parse(&base_url)?;
+        let fetcher = MarketFetcher::new(&crate::utils::path::get_app_data_dir())?;
+        let body = fetcher
+            .get_text(&location, &plugin_path)?
+            .ok_or_else(|| format!("Failed to fetch plugin detail: {} not found", plugin_path))?;
 
-        let json: serde_json::Value = response
-            .json()
+        let json: serde_json::Value = serde_json::from_str(&body)
             .map_err(|e| format!("Failed to parse plugin detail: {}", e))?;
 
         Ok(json)
@@ -561,6 +583,154 @@ fn resolve_github_download_url(
     }
 }
 
+/// 市场插件包的下载来源
+struct MarketPackageSource {
+    plugin_id: String,
+    download_url: Option<String>,
+    repo: Option<String>,
+    download_type: Option<String>,
+    release_asset: Option<String>,
+    branch: Option<String>,
+    version: Option<String>,
+    sha256: Option<String>,
+    signature: Option<String>,
+}
+
+impl From<&MarketPluginInfo> for MarketPackageSource {
+    fn from(info: &MarketPluginInfo) -> Self {
+        Self {
+            plugin_id: info.id.clone(),
+            download_url: info.download_url.clone(),
+            repo: Some(info.repo.clone()).filter(|r| !r.is_empty()),
+            download_type: info.download_type.clone(),
+            release_asset: info.release_asset.clone(),
+            branch: info.branch.clone(),
+            version: None,
+            sha256: info.sha256.clone(),
+            signature: info.signature.clone(),
+        }
+    }
+}
+
+fn market_download_dir() -> std::path::PathBuf {
+    std::env::temp_dir().join("sealantern_market_downloads")
+}
+
+/// 下载插件包并在落盘前校验哈希与签名，返回临时 ZIP 路径（阻塞调用）
+fn download_market_package(
+    source: MarketPackageSource,
+) -> Result<(std::path::PathBuf, PluginSignature), String> {
+    let trusted_publishers = crate::services::global::settings_manager()
+        .get()
+        .trusted_plugin_publishers;
+
+    let temp_dir = market_download_dir();
+    std::fs::create_dir_all(&temp_dir)
+        .map_err(|e| format!("Failed to create temp directory: {}", e))?;
+
+    let zip_path = temp_dir.join(format!("{}.zip", source.plugin_id));
+
+    let final_download_url = if let Some(url) = source.download_url {
+        url
+    } else if let Some(ref r) = source.repo {
+        if r.starts_with("http://") || r.starts_with("https://") {
+            r.clone()
+        } else {
+            let (url, _version) = resolve_github_download_url(
+                r,
+                source.download_type.as_deref(),
+                source.release_asset.as_deref(),
+                source.branch.as_deref(),
+                source.version.as_deref(),
+            )?;
+            url
+        }
+    } else {
+        return Err("No download source specified".to_string());
+    };
+
+    const MAX_DOWNLOAD_SIZE: u64 = 50 * 1024 * 1024;
+
+    let bytes = if final_download_url.starts_with("file://") {
+        read_local_package(&final_download_url, MAX_DOWNLOAD_SIZE)?
+    } else {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent("SeaLantern")
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+
+        let response = client
+            .get(&final_download_url)
+            .header("Accept", "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", "2022-11-28")
+            .send()
+            .map_err(|e| format!("Failed to download plugin: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Download failed with status: {}", response.status()));
+        }
+
+        response
+            .bytes()
+            .map_err(|e| format!("Failed to read download response: {}", e))?
+            .to_vec()
+    };
+
+    if bytes.len() as u64 > MAX_DOWNLOAD_SIZE {
+        return Err(format!(
+            "Downloaded file exceeds max size ({}MB > 50MB)",
+            bytes.len() / 1024 / 1024
+        ));
+    }
+
+    // 解压前校验哈希与签名
+    let package_signature = crate::plugins::signature::verify_package(
+        &bytes,
+        source.sha256.as_deref(),
+        source.signature.as_deref(),
+        &trusted_publishers,
+    )?;
+
+    std::fs::write(&zip_path, &bytes)
+        .map_err(|e| format!("Failed to save downloaded file: {}", e))?;
+
+    Ok((zip_path, package_signature))
+}
+
+/// 读取本地市场源中的插件包，只允许读取已配置的本地市场源目录内的文件
+fn read_local_package(download_url: &str, max_size: u64) -> Result<Vec<u8>, String> {
+    let url = Url::parse(download_url).map_err(|e| format!("下载地址无效: {}", e))?;
+    let sources = crate::services::global::settings_manager()
+        .get()
+        .plugin_market_sources;
+    if !is_market_source_url(&sources, &url) {
+        return Err(format!("本地插件包 {} 不在已配置的市场源目录中", download_url));
+    }
+    let path = url
+        .to_file_path()
+        .map_err(|_| format!("下载地址无效: {}", download_url))?;
+    let size = std::fs::metadata(&path)
+        .map_err(|e| format!("Failed to read plugin package: {}", e))?
+        .len();
+    if size > max_size {
+        return Err(format!("Downloaded file exceeds max size ({}MB > 50MB)", size / 1024 / 1024));
+    }
+    std::fs::read(&path).map_err(|e| format!("Failed to read plugin package: {}", e))
+}
+
+fn remove_market_download(zip_path: &std::path::Path) {
+    if let Err(e) = std::fs::remove_file(zip_path) {
+        eprintln!("[WARN] Failed to remove temporary zip file: {}", e);
+    }
+    if let Err(e) = std::fs::remove_dir(market_download_dir()) {
+        if !e.to_string().contains("directory not empty") {
+            eprintln!("[WARN] Failed to remove temporary directory: {}", e);
+        }
+    }
+}
+
+/// 市场插件包的 sha256 与签名只取自后端拉取的市场索引，不接受前端传入的值；
+/// 指定了与索引不同的版本或索引中没有该插件时按未签名处理
 #[tauri::command]
 #[allow(clippy::too_many_arguments)]
 pub async fn install_from_market(
@@ -575,9 +745,34 @@ pub async fn install_from_market(
 ) -> Result<PluginInstallResult, String> {
     validate_plugin_id(&plugin_id)?;
 
+    let market = tokio::task::spawn_blocking(|| fetch_market_index(None))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))??;
+    let catalogue_entry = market.into_iter().find(|p| {
+        p.id == plugin_id && (version.is_none() || p.version.as_deref() == version.as_deref())
+    });
+
+    let source = MarketPackageSource {
+        plugin_id,
+        download_url,
+        repo,
+        download_type,
+        release_asset,
+        branch,
+        version,
+        sha256: catalogue_entry.as_ref().and_then(|p| p.sha256.clone()),
+        signature: catalogue_entry.and_then(|p| p.signature),
+    };
+    install_market_package(&manager, source).await
+}
+
+async fn install_market_package(
+    manager: &Arc<Mutex<PluginManager>>,
+    source: MarketPackageSource,
+) -> Result<PluginInstallResult, String> {
     {
         let mgr = manager.lock().unwrap_or_else(|e| e.into_inner());
-        if let Some(existing) = mgr.plugins().get(&plugin_id) {
+        if let Some(existing) = mgr.plugins().get(&source.plugin_id) {
             if matches!(existing.state, crate::models::plugin::PluginState::Enabled) {
                 return Err(format!(
                     "插件 '{}' 正在运行中，请先禁用后再进行更新",
@@ -587,14 +782,21 @@ pub async fn install_from_market(
         }
     }
 
-    let untrusted_url = if let Some(ref url) = download_url {
+    let untrusted_url = if let Some(ref url) = source.download_url {
         match Url::parse(url) {
             Ok(parsed_url) => {
                 let hostname = parsed_url.host_str().unwrap_or("");
                 let trusted = ALLOWED_DOWNLOAD_DOMAINS.iter().any(|domain| {
                     hostname == *domain || hostname.ends_with(&format!(".{}", domain))
                 });
-                !trusted
+                // 用户自己配置的市场源（含内网与本地目录）同样视为可信
+                let configured = is_market_source_url(
+                    &crate::services::global::settings_manager()
+                        .get()
+                        .plugin_market_sources,
+                    &parsed_url,
+                );
+                !trusted && !configured
             }
             Err(_) => true,
         }
@@ -602,99 +804,360 @@ pub async fn install_from_market(
         false
     };
 
-    let pid = plugin_id.clone();
+    let (zip_path, package_signature) =
+        tokio::task::spawn_blocking(move || download_market_package(source))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
 
-    let zip_path = tokio::task::spawn_blocking(move || {
-        let temp_dir = std::env::temp_dir().join("sealantern_market_downloads");
-        std::fs::create_dir_all(&temp_dir)
-            .map_err(|e| format!("Failed to create temp directory: {}", e))?;
-
-        let zip_path = temp_dir.join(format!("{}.zip", pid));
-
-        let final_download_url = if let Some(url) = download_url {
-            url
-        } else if let Some(ref r) = repo {
-            if r.starts_with("http://") || r.starts_with("https://") {
-                r.clone()
-            } else {
-                let (url, _version) = resolve_github_download_url(
-                    r,
-                    download_type.as_deref(),
-                    release_asset.as_deref(),
-                    branch.as_deref(),
-                    version.as_deref(),
-                )?;
-                url
+    let result = {
+        let mut mgr = manager.lock().unwrap_or_else(|e| e.into_inner());
+        mgr.install_plugin(&zip_path).map(|mut r| {
+            if let Some(info) =
+                mgr.record_plugin_signature(&r.plugin.manifest.id, package_signature)
+            {
+                r.plugin = info;
             }
-        } else {
-            return Err("No download source specified".to_string());
-        };
+            r
+        })
+    };
+    remove_market_download(&zip_path);
 
-        const MAX_DOWNLOAD_SIZE: u64 = 50 * 1024 * 1024;
+    result.map(|mut r| {
+        r.untrusted_url = untrusted_url;
+        r
+    })
+}
 
-        let client = reqwest::blocking::Client::builder()
-            .user_agent("SeaLantern")
-            .build()
-            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+/// 执行依赖计划时最多安装 / 升级的插件数量
+const MAX_PLAN_INSTALLS: usize = 32;
 
-        let response = client
-            .get(&final_download_url)
-            .header("Accept", "application/vnd.github+json")
-            .header("X-GitHub-Api-Version", "2022-11-28")
-            .send()
-            .map_err(|e| format!("Failed to download plugin: {}", e))?;
+fn resolve_dependency_plan(
+    manager: &Arc<Mutex<PluginManager>>,
+    plugin_id: &str,
+    market: &[MarketPluginInfo],
+    include_optional: bool,
+) -> Result<DependencyPlan, String> {
+    let mgr = manager.lock().unwrap_or_else(|e| e.into_inner());
+    DependencyResolver::new(mgr.plugins(), market, include_optional).resolve(plugin_id)
+}
 
-        if !response.status().is_success() {
-            return Err(format!("Download failed with status: {}", response.status()));
-        }
+async fn fetch_market_for_plan(
+    market_url: Option<String>,
+) -> Result<Vec<MarketPluginInfo>, String> {
+    tokio::task::spawn_blocking(move || fetch_market_index(market_url))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
 
-        let bytes = response
-            .bytes()
-            .map_err(|e| format!("Failed to read download response: {}", e))?;
+/// 预览启用插件所需的安装 / 升级 / 启用步骤
+#[tauri::command]
+pub async fn preview_dependency_plan(
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+    plugin_id: String,
+    include_optional: Option<bool>,
+    market_url: Option<String>,
+) -> Result<DependencyPlan, String> {
+    validate_plugin_id(&plugin_id)?;
+    let market = fetch_market_for_plan(market_url).await?;
+    resolve_dependency_plan(&manager, &plugin_id, &market, include_optional.unwrap_or(false))
+}
 
-        if bytes.len() as u64 > MAX_DOWNLOAD_SIZE {
+/// 依次安装 / 升级计划中的插件，升级前被禁用的插件记入 reenable
+async fn install_plan_steps(
+    manager: &Arc<Mutex<PluginManager>>,
+    plugin_id: &str,
+    market: &[MarketPluginInfo],
+    include_optional: bool,
+    expected: &DependencyPlan,
+    reenable: &mut Vec<String>,
+) -> Result<DependencyPlan, String> {
+    let first = resolve_dependency_plan(manager, plugin_id, market, include_optional)?;
+    let same_steps = first.steps.len() == expected.steps.len()
+        && first.steps.iter().zip(&expected.steps).all(|(a, b)| {
+            a.plugin_id == b.plugin_id
+                && a.action == b.action
+                && a.target_version == b.target_version
+        });
+    if !same_steps {
+        return Err("依赖计划已变化，请重新预览后再执行".to_string());
+    }
+
+    let mut installed = HashSet::new();
+    let mut plan = first;
+    loop {
+        check_plan_previewed(expected, &plan)?;
+        let Some(step) = plan
+            .steps
+            .iter()
+            .find(|s| matches!(s.action, PlanAction::Install | PlanAction::Upgrade))
+        else {
+            return Ok(plan);
+        };
+        if !installed.insert(step.plugin_id.clone()) {
             return Err(format!(
-                "Downloaded file exceeds max size ({}MB > 50MB)",
-                bytes.len() / 1024 / 1024
+                "插件 '{}' 安装后版本仍不满足依赖要求，请检查市场索引中的版本号",
+                step.plugin_id
             ));
         }
+        if installed.len() > MAX_PLAN_INSTALLS {
+            return Err(format!("依赖计划需要安装的插件超过 {} 个", MAX_PLAN_INSTALLS));
+        }
+        let market_entry = step
+            .market
+            .clone()
+            .ok_or_else(|| format!("插件 '{}' 缺少市场信息", step.plugin_id))?;
+
+        if step.action == PlanAction::Upgrade {
+            let mut mgr = manager.lock().unwrap_or_else(|e| e.into_inner());
+            let enabled = mgr
+                .plugins()
+                .get(&step.plugin_id)
+                .is_some_and(|p| matches!(p.state, crate::models::plugin::PluginState::Enabled));
+            if enabled {
+                reenable.extend(mgr.disable_plugin(&step.plugin_id)?);
+            }
+        }
 
-        std::fs::write(&zip_path, &bytes)
-            .map_err(|e| format!("Failed to save downloaded file: {}", e))?;
+        install_market_package(manager, MarketPackageSource::from(&market_entry))
+            .await
+            .map_err(|e| format!("安装依赖 '{}' 失败: {}", market_entry.id, e))?;
 
-        Ok::<std::path::PathBuf, String>(zip_path)
-    })
-    .await
-    .map_err(|e| format!("Task join error: {}", e))?;
-
-    let zip_path = match zip_path {
-        Ok(p) => p,
-        Err(e) => {
-            let temp_dir = std::env::temp_dir().join("sealantern_market_downloads");
-            let _ = std::fs::remove_file(temp_dir.join(format!("{}.zip", plugin_id)));
-            return Err(e);
+        plan = resolve_dependency_plan(manager, plugin_id, market, include_optional)?;
+    }
+}
+
+/// 执行预览过的依赖计划：依次安装 / 升级依赖，再按拓扑顺序启用，返回启用的插件 id
+///
+/// 每装完一个插件都重新解析一次，市场条目未声明依赖时以安装后的 manifest 为准；
+/// 解析结果超出预览的计划时中止。无论成功与否，升级前被禁用的插件都会重新启用
+#[tauri::command]
+pub async fn execute_dependency_plan(
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+    plugin_id: String,
+    expected_plan: DependencyPlan,
+    include_optional: Option<bool>,
+    market_url: Option<String>,
+) -> Result<Vec<String>, String> {
+    validate_plugin_id(&plugin_id)?;
+    if expected_plan.plugin_id != plugin_id {
+        return Err("依赖计划与目标插件不一致".to_string());
+    }
+    let include_optional = include_optional.unwrap_or(false);
+    let market = fetch_market_for_plan(market_url).await?;
+
+    // 升级前被禁用（含级联禁用）的插件
+    let mut reenable = Vec::new();
+    let installed = install_plan_steps(
+        &manager,
+        &plugin_id,
+        &market,
+        include_optional,
+        &expected_plan,
+        &mut reenable,
+    )
+    .await;
+
+    let mut mgr = manager.lock().unwrap_or_else(|e| e.into_inner());
+    let mut enabled = Vec::new();
+    let result = installed.and_then(|plan| {
+        for step in &plan.steps {
+            mgr.enable_plugin(&step.plugin_id)
+                .map_err(|e| format!("启用插件 '{}' 失败: {}", step.plugin_id, e))?;
+            enabled.push(step.plugin_id.clone());
         }
-    };
+        Ok(())
+    });
+    for id in reenable {
+        if enabled.contains(&id) {
+            continue;
+        }
+        match mgr.enable_plugin(&id) {
+            Ok(()) => enabled.push(id),
+            Err(e) => eprintln!("[WARN] Failed to re-enable plugin '{}': {}", id, e),
+        }
+    }
+    result.map(|()| enabled)
+}
+
+#[tauri::command]
+pub fn get_plugin_update_policies(
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> HashMap<String, UpdatePolicy> {
+    let mgr = manager.lock().unwrap_or_else(|e| e.into_inner());
+    mgr.update_policies()
+}
+
+#[tauri::command]
+pub fn set_plugin_update_policy(
+    plugin_id: String,
+    policy: UpdatePolicy,
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Result<(), String> {
+    let mut mgr = manager.lock().unwrap_or_else(|e| e.into_inner());
+    mgr.set_update_policy(&plugin_id, policy)
+}
+
+/// 下载市场中的最新版本并替换已安装的插件，失败时自动回滚到原版本
+async fn download_and_apply_update(
+    manager: &Arc<Mutex<PluginManager>>,
+    market_entry: &MarketPluginInfo,
+    automatic: bool,
+) -> Result<PluginUpdateResult, String> {
+    let source = MarketPackageSource::from(market_entry);
+    let (zip_path, package_signature) =
+        tokio::task::spawn_blocking(move || download_market_package(source))
+            .await
+            .map_err(|e| format!("Task join error: {}", e))??;
 
     let result = {
         let mut mgr = manager.lock().unwrap_or_else(|e| e.into_inner());
-        mgr.install_plugin(&zip_path)
+        mgr.apply_plugin_update(&market_entry.id, &zip_path, package_signature, automatic)
     };
+    remove_market_download(&zip_path);
+    result
+}
 
-    if let Err(e) = std::fs::remove_file(&zip_path) {
-        eprintln!("[WARN] Failed to remove temporary zip file: {}", e);
+#[tauri::command]
+pub async fn update_plugin(
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+    plugin_id: String,
+) -> Result<PluginUpdateResult, String> {
+    validate_plugin_id(&plugin_id)?;
+    let market = tokio::task::spawn_blocking(|| fetch_market_index(None))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))??;
+    let market_entry = market
+        .into_iter()
+        .find(|p| p.id == plugin_id)
+        .ok_or_else(|| format!("插件市场中找不到插件 '{}'", plugin_id))?;
+
+    download_and_apply_update(&manager, &market_entry, false).await
+}
+
+#[tauri::command]
+pub fn rollback_plugin_update(
+    plugin_id: String,
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Result<PluginUpdateResult, String> {
+    let mut mgr = manager.lock().unwrap_or_else(|e| e.into_inner());
+    mgr.rollback_plugin_update(&plugin_id)
+}
+
+/// 市场条目带签名时，下载的包要么验证通过，要么被拒绝安装，不会记为 unsigned
+fn market_signature_status(entry: &MarketPluginInfo) -> SignatureStatus {
+    if entry
+        .signature
+        .as_deref()
+        .is_some_and(|s| !s.trim().is_empty())
+    {
+        SignatureStatus::Verified
+    } else {
+        SignatureStatus::Unsigned
     }
-    let temp_dir = std::env::temp_dir().join("sealantern_market_downloads");
-    if let Err(e) = std::fs::remove_dir(&temp_dir) {
-        if !e.to_string().contains("directory not empty") {
-            eprintln!("[WARN] Failed to remove temporary directory: {}", e);
+}
+
+/// 按各插件的更新策略检查并执行更新
+///
+/// notify 策略只报告可用更新；auto 策略在新旧版本都未申请 Critical 权限、
+/// 且市场条目的签名状态不低于已安装版本时直接更新，否则同样只报告，等待用户手动确认
+#[tauri::command]
+pub async fn run_plugin_auto_updates(
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Result<AutoUpdateReport, String> {
+    let installed: Vec<(String, String, Vec<String>, SignatureStatus, UpdatePolicy)> = {
+        let mgr = manager.lock().unwrap_or_else(|e| e.into_inner());
+        mgr.plugins()
+            .iter()
+            .map(|(id, info)| {
+                (
+                    id.clone(),
+                    info.manifest.version.clone(),
+                    info.manifest.permissions.clone(),
+                    info.signature.status,
+                    mgr.update_policy(id),
+                )
+            })
+            .filter(|(_, _, _, _, policy)| *policy != UpdatePolicy::Off)
+            .collect()
+    };
+    let mut report = AutoUpdateReport::default();
+    if installed.is_empty() {
+        return Ok(report);
+    }
+
+    let market = tokio::task::spawn_blocking(|| fetch_market_index(None))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))??;
+
+    for (plugin_id, current_version, permissions, signature_status, policy) in installed {
+        let Some(entry) = market.iter().find(|p| p.id == plugin_id) else {
+            continue;
+        };
+        let Some(latest) = entry.version.clone() else {
+            continue;
+        };
+        if !PluginManager::is_newer_version(&latest, &current_version) {
+            continue;
+        }
+
+        let automatic = policy == UpdatePolicy::Auto
+            && !has_critical_permission(&permissions)
+            && !has_critical_permission(&entry.permissions)
+            && keeps_signature_trust(signature_status, market_signature_status(entry));
+        if !automatic {
+            report.available.push(PluginUpdateInfo {
+                plugin_id,
+                current_version,
+                latest_version: latest,
+                download_url: entry.download_url.clone(),
+                changelog: entry.changelog.clone(),
+            });
+            continue;
+        }
+
+        match download_and_apply_update(&manager, entry, true).await {
+            Ok(result) => report.updated.push(result),
+            Err(error) => report.failed.push(PluginUpdateFailure { plugin_id, error }),
         }
     }
+    Ok(report)
+}
 
-    result.map(|mut r| {
-        r.untrusted_url = untrusted_url;
-        r
-    })
+/// 校验并打包插件目录，未指定输出目录时输出到插件目录下的 dist
+#[tauri::command]
+pub async fn package_plugin(
+    plugin_dir: String,
+    output_dir: Option<String>,
+) -> Result<PluginPackageResult, String> {
+    let plugin_dir = std::path::PathBuf::from(plugin_dir);
+    if !plugin_dir.join("manifest.json").is_file() {
+        return Err(format!("{} 中没有 manifest.json", plugin_dir.display()));
+    }
+    let output_dir = output_dir
+        .filter(|d| !d.trim().is_empty())
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|| plugin_dir.join("dist"));
+    tokio::task::spawn_blocking(move || packager::package_plugin(&plugin_dir, &output_dir))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
+}
+
+/// 在无界面的测试运行时中执行插件 test/ 目录下的 Lua 测试，仅在开发者模式下可用
+#[tauri::command]
+pub async fn run_plugin_tests(plugin_dir: String) -> Result<PluginTestReport, String> {
+    if !crate::services::global::settings_manager()
+        .get()
+        .developer_mode
+    {
+        return Err("仅在开发者模式下可以运行插件测试".to_string());
+    }
+    let plugin_dir = std::path::PathBuf::from(plugin_dir);
+    if !plugin_dir.join("manifest.json").is_file() {
+        return Err(format!("{} 中没有 manifest.json", plugin_dir.display()));
+    }
+    tokio::task::spawn_blocking(move || harness::run_plugin_tests(&plugin_dir))
+        .await
+        .map_err(|e| format!("Task join error: {}", e))?
 }
 
 #[tauri::command]
@@ -754,7 +1217,7 @@ pub fn context_menu_hide_notify(
     let runtimes = manager.get_shared_runtimes();
     let runtimes_guard = runtimes.read().unwrap_or_else(|e| e.into_inner());
     for runtime in runtimes_guard.values() {
-        let _ = runtime.call_context_menu_hide_callback();
+        runtime.post_context_menu_hide();
     }
     Ok(())
 }
@@ -772,7 +1235,7 @@ pub fn context_menu_show_notify(
     let runtimes_guard = runtimes.read().unwrap_or_else(|e| e.into_inner());
 
     for runtime in runtimes_guard.values() {
-        let _ = runtime.call_context_menu_show_callback(&context, target_data.clone(), x, y);
+        runtime.post_context_menu_show(&context, target_data.clone(), x, y);
     }
 
     Ok(())
@@ -788,16 +1251,45 @@ pub fn context_menu_callback(
 ) -> Result<(), String> {
     validate_plugin_id(&plugin_id)?;
 
+    // 回调执行期间不持有 PluginManager 锁和运行时表的锁
+    let runtimes = manager
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_shared_runtimes();
+    let runtime = get_runtime(&runtimes, &plugin_id)
+        .ok_or_else(|| format!("插件 '{}' 的运行时不存在", plugin_id))?;
+
+    runtime.call_context_menu_callback(&context, &item_id, target_data)
+}
+
+#[tauri::command]
+pub fn list_commands(manager: tauri::State<'_, Arc<Mutex<PluginManager>>>) -> Vec<CommandEntry> {
     let manager = manager.lock().unwrap_or_else(|e| e.into_inner());
+    manager.list_commands()
+}
 
-    let runtimes = manager.get_shared_runtimes();
-    let runtimes_guard = runtimes.read().unwrap_or_else(|e| e.into_inner());
+#[tauri::command]
+pub fn execute_command(
+    id: String,
+    args: Option<serde_json::Value>,
+    manager: tauri::State<'_, Arc<Mutex<PluginManager>>>,
+) -> Result<(), String> {
+    let args = args.unwrap_or(serde_json::Value::Null);
 
-    let runtime = runtimes_guard
-        .get(&plugin_id)
-        .ok_or_else(|| format!("插件 '{}' 的运行时不存在", plugin_id))?;
+    // 插件执行命令期间不持有 PluginManager 锁和运行时表的锁，onCommand 中调用 sl.plugins 等接口时不会死锁
+    let (target, runtimes) = {
+        let manager = manager.lock().unwrap_or_else(|e| e.into_inner());
+        (manager.resolve_command(&id)?, manager.get_shared_runtimes())
+    };
 
-    runtime.call_context_menu_callback(&context, &item_id, target_data)
+    match target {
+        CommandTarget::Builtin(command_id) => execute_builtin(&command_id, &args),
+        CommandTarget::Plugin { plugin_id, command_id } => {
+            let runtime = get_runtime(&runtimes, &plugin_id)
+                .ok_or_else(|| format!("插件 '{}' 的运行时不存在", plugin_id))?;
+            runtime.call_command(&command_id, args)
+        }
+    }
 }
 
 #[tauri::command]
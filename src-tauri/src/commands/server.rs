@@ -557,3 +557,66 @@ pub fn get_server_logs(id: String, since: usize, max_lines: Option<usize>) -> Ve
 pub fn update_server_name(id: String, name: String) -> Result<(), String> {
     manager().update_server_name(&id, &name)
 }
+
+#[tauri::command]
+pub async fn list_core_versions(core_type: String) -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        use crate::services::server_core_catalogue;
+        let core_type = server_core_catalogue::parse_catalogue_core_type(&core_type)?;
+        server_core_catalogue::list_core_mc_versions(core_type)
+    })
+    .await
+    .map_err(|e| format!("查询核心版本任务失败: {}", e))?
+}
+
+#[tauri::command]
+pub async fn list_core_builds(
+    core_type: String,
+    mc_version: String,
+) -> Result<Vec<crate::services::server_core_catalogue::CoreBuild>, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        use crate::services::server_core_catalogue;
+        let core_type = server_core_catalogue::parse_catalogue_core_type(&core_type)?;
+        server_core_catalogue::list_core_builds(core_type, &mc_version)
+    })
+    .await
+    .map_err(|e| format!("查询核心构建任务失败: {}", e))?
+}
+
+#[tauri::command]
+pub async fn update_server_core(
+    id: String,
+    build: Option<String>,
+) -> Result<ServerInstance, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        manager().update_server_core(&id, build.as_deref())
+    })
+    .await
+    .map_err(|e| format!("更新服务器核心任务失败: {}", e))?
+}
+
+#[tauri::command]
+pub async fn check_server_java(
+    id: String,
+) -> Result<crate::services::java_requirement::JavaCompatibilityReport, String> {
+    tauri::async_runtime::spawn_blocking(move || manager().check_server_java(&id))
+        .await
+        .map_err(|e| format!("检查 Java 兼容性任务失败: {}", e))?
+}
+
+#[tauri::command]
+pub fn list_jvm_presets() -> Vec<crate::services::jvm_presets::JvmFlagPreset> {
+    crate::services::jvm_presets::latest_presets()
+}
+
+#[tauri::command]
+pub async fn set_server_jvm_preset(
+    id: String,
+    preset_id: Option<String>,
+) -> Result<ServerInstance, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        manager().set_server_jvm_preset(&id, preset_id.as_deref())
+    })
+    .await
+    .map_err(|e| format!("设置 JVM 参数预设任务失败: {}", e))?
+}
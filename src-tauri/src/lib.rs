@@ -98,10 +98,19 @@ pub fn run() {
             server_commands::delete_server,
             server_commands::get_server_logs,
             server_commands::update_server_name,
+            server_commands::list_core_versions,
+            server_commands::list_core_builds,
+            server_commands::update_server_core,
+            server_commands::check_server_java,
+            server_commands::list_jvm_presets,
+            server_commands::set_server_jvm_preset,
             java_commands::detect_java,
             java_commands::validate_java_path,
-            java_commands::install_java,
             java_commands::cancel_java_install,
+            java_commands::list_java_packages,
+            java_commands::install_java_package,
+            java_commands::list_java_runtimes,
+            java_commands::remove_java_runtime,
             config_commands::read_config,
             config_commands::write_config,
             config_commands::read_server_properties,
@@ -152,6 +161,16 @@ pub fn run() {
             plugin_commands::list_plugins,
             plugin_commands::scan_plugins,
             plugin_commands::enable_plugin,
+            plugin_commands::confirm_unsigned_plugin,
+            plugin_commands::get_plugin_permission_grants,
+            plugin_commands::set_plugin_permission_grant,
+            plugin_commands::get_pending_permission_prompts,
+            plugin_commands::get_plugin_permission_audit,
+            plugin_commands::get_plugin_network_log,
+            plugin_commands::query_plugin_logs,
+            plugin_commands::tail_plugin_logs,
+            plugin_commands::clear_plugin_logs,
+            plugin_commands::export_plugin_diagnostics,
             plugin_commands::disable_plugin,
             plugin_commands::get_plugin_nav_items,
             plugin_commands::install_plugin,
@@ -165,11 +184,23 @@ pub fn run() {
             plugin_commands::check_plugin_update,
             plugin_commands::check_all_plugin_updates,
             plugin_commands::fetch_market_plugins,
+            plugin_commands::fetch_market_catalog,
             plugin_commands::fetch_market_categories,
             plugin_commands::fetch_market_plugin_detail,
             plugin_commands::install_from_market,
             plugin_commands::install_plugins_batch,
+            plugin_commands::package_plugin,
+            plugin_commands::run_plugin_tests,
+            plugin_commands::get_plugin_update_policies,
+            plugin_commands::set_plugin_update_policy,
+            plugin_commands::update_plugin,
+            plugin_commands::rollback_plugin_update,
+            plugin_commands::run_plugin_auto_updates,
+            plugin_commands::preview_dependency_plan,
+            plugin_commands::execute_dependency_plan,
             plugin_commands::context_menu_callback,
+            plugin_commands::list_commands,
+            plugin_commands::execute_command,
             plugin_commands::context_menu_show_notify,
             plugin_commands::context_menu_hide_notify,
             plugin_commands::on_locale_changed,
@@ -227,8 +258,9 @@ pub fn run() {
             let app_data_dir = crate::utils::path::get_app_data_dir();
             let plugins_dir = app_data_dir.join("plugins");
             let data_dir = app_data_dir.join("plugin_data");
+            let logs_dir = app_data_dir.join("plugin_logs");
 
-            let mut plugin_manager = PluginManager::new(plugins_dir, data_dir);
+            let mut plugin_manager = PluginManager::new(plugins_dir, data_dir, logs_dir);
 
             if let Err(e) = plugin_manager.scan_plugins() {
                 eprintln!("Failed to scan plugins: {}", e);
@@ -239,6 +271,9 @@ pub fn run() {
 
             let shared_runtimes = plugin_manager.get_shared_runtimes();
             let shared_runtimes_for_server_ready = Arc::clone(&shared_runtimes);
+            let shared_runtimes_for_server_events = Arc::clone(&shared_runtimes);
+            let shared_runtimes_for_timers = Arc::clone(&shared_runtimes);
+            let shared_runtimes_for_net = Arc::clone(&shared_runtimes);
             let api_registry = plugin_manager.get_api_registry();
 
             let manager = Arc::new(Mutex::new(plugin_manager));
@@ -252,9 +287,7 @@ pub fn run() {
                     .ok_or_else(|| format!("插件 '{}' 没有注册 API '{}'", target, api_name))?;
 
                 // 获取目标插件的runtime
-                let runtimes = shared_runtimes.read().unwrap_or_else(|e| e.into_inner());
-                let runtime = runtimes
-                    .get(target)
+                let runtime = plugins::manager::get_runtime(&shared_runtimes, target)
                     .ok_or_else(|| format!("插件 '{}' 的运行时不存在", target))?;
                 runtime.call_registered_api(&lua_fn_name, args)
             }));
@@ -387,6 +420,19 @@ pub fn run() {
                 },
             ));
 
+            let app_handle = app.handle().clone();
+            plugins::api::set_permission_prompt_handler(Arc::new(move |plugin_id, permissions| {
+                app_handle
+                    .emit(
+                        "plugin-permission-prompt",
+                        serde_json::json!({
+                            "plugin_id": plugin_id,
+                            "permissions": permissions,
+                        }),
+                    )
+                    .map_err(|e| format!("Failed to emit permission prompt: {}", e))
+            }));
+
             let app_handle = app.handle().clone();
             plugins::api::set_component_event_handler(Arc::new(move |_plugin_id, payload_json| {
                 let val: serde_json::Value =
@@ -426,11 +472,38 @@ pub fn run() {
                 plugins::api::set_server_ready_handler(Arc::new(move |server_id| {
                     let shared_runtimes = &shared_runtimes_for_server_ready;
                     let runtimes = shared_runtimes.read().unwrap_or_else(|e| e.into_inner());
-                    for (plugin_id, runtime) in runtimes.iter() {
-                        if let Err(e) = runtime.call_lifecycle_with_arg("onServerReady", server_id)
-                        {
-                            eprintln!("[WARN] plugin '{}' onServerReady failed: {}", plugin_id, e);
-                        }
+                    for runtime in runtimes.values() {
+                        runtime.post_lifecycle_with_arg("onServerReady", server_id);
+                    }
+                    Ok(())
+                }));
+
+                plugins::api::set_server_event_handler(Arc::new(move |event| {
+                    let runtimes = shared_runtimes_for_server_events
+                        .read()
+                        .unwrap_or_else(|e| e.into_inner());
+                    for runtime in runtimes.values() {
+                        runtime.dispatch_server_event(event.clone());
+                    }
+                    Ok(())
+                }));
+
+                plugins::api::set_timer_fired_handler(Arc::new(move |plugin_id, timer_id| {
+                    let runtimes = shared_runtimes_for_timers
+                        .read()
+                        .unwrap_or_else(|e| e.into_inner());
+                    if let Some(runtime) = runtimes.get(plugin_id) {
+                        runtime.fire_timer(timer_id);
+                    }
+                    Ok(())
+                }));
+
+                plugins::api::set_net_event_handler(Arc::new(move |event| {
+                    let runtimes = shared_runtimes_for_net
+                        .read()
+                        .unwrap_or_else(|e| e.into_inner());
+                    if let Some(runtime) = runtimes.get(&event.plugin_id) {
+                        runtime.dispatch_net_event(event);
                     }
                     Ok(())
                 }));
@@ -475,6 +548,34 @@ pub fn run() {
                 ));
             }
 
+            {
+                // 看门狗回调可能发生在持有 PluginManager 锁的调用链中，放到独立线程里禁用插件
+                let manager_for_watchdog = Arc::clone(&manager);
+                plugins::api::set_plugin_watchdog_handler(Arc::new(move |plugin_id, reason| {
+                    let manager = Arc::clone(&manager_for_watchdog);
+                    let plugin_id = plugin_id.to_string();
+                    let reason = reason.to_string();
+                    std::thread::spawn(move || {
+                        let mut manager = manager.lock().unwrap_or_else(|e| e.into_inner());
+                        match manager.disable_plugin(&plugin_id) {
+                            Ok(_) => {
+                                eprintln!("[WARN] plugin '{}' disabled: {}", plugin_id, reason)
+                            }
+                            Err(e) => eprintln!(
+                                "[WARN] failed to auto-disable plugin '{}': {}",
+                                plugin_id, e
+                            ),
+                        }
+                    });
+                    Ok(())
+                }));
+            }
+
+            plugins::dev_reload::start_dev_reload_watcher(
+                Arc::clone(&manager),
+                app.handle().clone(),
+            );
+
             app.manage(manager);
 
             let show_item = MenuItem::with_id(app, "show", "显示窗口", true, None::<&str>)?;
@@ -33,8 +33,19 @@ impl SemVer {
         Some(Self { major, minor, patch, prerelease })
     }
 
+    /// 支持单个比较式、空格分隔的区间（需同时满足）、`||` 分隔的备选以及 `*`
     pub fn satisfies(&self, requirement: &str) -> bool {
+        requirement.split("||").any(|range| {
+            let comparators = split_comparators(range);
+            !comparators.is_empty() && comparators.iter().all(|c| self.satisfies_comparator(c))
+        })
+    }
+
+    fn satisfies_comparator(&self, requirement: &str) -> bool {
         let req = requirement.trim();
+        if req == "*" || req.eq_ignore_ascii_case("x") {
+            return true;
+        }
 
         if let Some(stripped) = req.strip_prefix(">=") {
             if let Some(target) = SemVer::parse(stripped) {
@@ -106,6 +117,23 @@ impl SemVer {
     }
 }
 
+/// 把 ">= 1.0.0 <2.0.0" 拆成 [">=1.0.0", "<2.0.0"]，运算符与版本号之间允许有空格
+fn split_comparators(range: &str) -> Vec<String> {
+    let mut comparators = Vec::new();
+    let mut pending_op = String::new();
+    for token in range.split_whitespace() {
+        if token
+            .chars()
+            .all(|c| matches!(c, '>' | '<' | '=' | '^' | '~'))
+        {
+            pending_op.push_str(token);
+        } else {
+            comparators.push(format!("{}{}", std::mem::take(&mut pending_op), token));
+        }
+    }
+    comparators
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum PluginDependency {
@@ -186,9 +214,26 @@ pub struct MarketPluginInfo {
     pub release_asset: Option<String>,
     #[serde(default)]
     pub branch: Option<String>,
+    /// 插件包 ZIP 的 sha256（十六进制）
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// 发布者对插件包 ZIP 的 ed25519 签名（base64）
+    #[serde(default)]
+    pub signature: Option<String>,
+    /// 与插件 manifest 相同格式的依赖声明，供依赖解析使用
+    #[serde(default)]
+    pub dependencies: Vec<PluginDependency>,
+    #[serde(default)]
+    pub optional_dependencies: Vec<PluginDependency>,
 
     #[serde(default, skip_deserializing)]
     pub _path: Option<String>,
+    /// 条目来自的市场源 id
+    #[serde(default, skip_deserializing)]
+    pub source: Option<String>,
+    /// 市场源的根地址，详情等相对路径以此为基准
+    #[serde(default, skip_deserializing)]
+    pub source_url: Option<String>,
 }
 
 fn default_empty_value() -> serde_json::Value {
@@ -210,6 +255,8 @@ pub enum PluginState {
     Enabled,
     Disabled,
     Error(String),
+    /// manifest.engines.sealantern 与当前版本不兼容
+    Incompatible(String),
 }
 
 pub type PluginPermission = String;
@@ -252,7 +299,7 @@ pub fn get_all_permission_meta() -> Vec<PermissionMeta> {
         PermissionMeta {
             id: "storage",
             name: "存储",
-            description: "允许使用持久化存储 API",
+            description: "允许使用持久化存储与插件数据库（sl.storage、sl.db）",
             danger_level: PermissionDangerLevel::Normal,
             icon: "database",
         },
@@ -357,7 +404,6 @@ pub fn get_all_permission_meta() -> Vec<PermissionMeta> {
     ]
 }
 
-#[allow(dead_code)]
 pub fn get_permission_danger_level(permission_id: &str) -> PermissionDangerLevel {
     match permission_id {
         "execute_program" | "plugin_folder_access" | "ui.component.proxy" => {
@@ -388,6 +434,9 @@ pub struct PluginManifest {
     pub engines: Option<PluginEngines>,
     #[serde(default)]
     pub permissions: Vec<PluginPermission>,
+    /// network 权限可访问的主机或 URL 前缀，为空时不限制主机
+    #[serde(default)]
+    pub allowed_hosts: Vec<String>,
     #[serde(default)]
     pub ui: Option<PluginUiConfig>,
     #[serde(default)]
@@ -413,6 +462,10 @@ pub struct PluginManifest {
 
     #[serde(default)]
     pub include: Vec<String>,
+
+    /// 允许依赖本插件的插件通过 require("@<id>/模块") 加载的模块
+    #[serde(default)]
+    pub exports: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -443,6 +496,16 @@ pub struct PluginSettingField {
 
     #[serde(default)]
     pub maxlength: Option<u32>,
+
+    /// number 类型的取值范围
+    #[serde(default)]
+    pub min: Option<f64>,
+    #[serde(default)]
+    pub max: Option<f64>,
+
+    /// 旧版本中该项使用过的 key，更新时把旧值搬到新 key 上
+    #[serde(default)]
+    pub renamed_from: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -556,6 +619,31 @@ pub struct PluginInfo {
 
     #[serde(default)]
     pub missing_dependencies: Vec<MissingDependency>,
+
+    #[serde(default)]
+    pub signature: PluginSignature,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum SignatureStatus {
+    Verified,
+    #[default]
+    Unsigned,
+}
+
+/// 插件包的签名状态，安装时确定并持久化在插件数据目录的 plugin_signatures.json 中
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct PluginSignature {
+    pub status: SignatureStatus,
+    /// 验证通过的受信任发布者名称
+    #[serde(default)]
+    pub publisher: Option<String>,
+    #[serde(default)]
+    pub sha256: Option<String>,
+    /// 未签名插件申请高危权限时，用户是否已明确确认
+    #[serde(default)]
+    pub critical_confirmed: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
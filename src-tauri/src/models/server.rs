@@ -30,11 +30,21 @@ pub struct ServerInstance {
     pub max_memory: u32,
     pub min_memory: u32,
     pub jvm_args: Vec<String>,
+    /// 附加的 JVM 参数预设，渲染在 jvm_args 之前
+    #[serde(default)]
+    pub jvm_preset: Option<ServerJvmPreset>,
     pub port: u16,
     pub created_at: u64,
     pub last_started_at: Option<u64>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServerJvmPreset {
+    pub id: String,
+    /// 选择时的预设版本，预设更新后服务器仍使用该版本
+    pub revision: u32,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerStatusInfo {
     pub id: String,
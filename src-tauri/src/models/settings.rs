@@ -114,6 +114,46 @@ pub struct AppSettings {
     // 极简模式：关闭所有动效和特效
     #[serde(default)]
     pub minimal_mode: bool,
+
+    // 受信任的插件发布者公钥，用于校验插件市场安装包的签名
+    #[serde(default)]
+    pub trusted_plugin_publishers: Vec<TrustedPublisher>,
+
+    // 开发者模式下忽略插件 manifest 中 engines.sealantern 的版本要求
+    #[serde(default)]
+    pub allow_incompatible_plugins: bool,
+
+    // 插件市场源，按顺序合并，同一插件以靠前的源为准
+    #[serde(default = "default_plugin_market_sources")]
+    pub plugin_market_sources: Vec<MarketSource>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrustedPublisher {
+    pub name: String,
+    /// ed25519 公钥（base64，32 字节）
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MarketSource {
+    pub id: String,
+    pub name: String,
+    /// http(s) 地址、file:// 地址或本地目录；以 .json 结尾时视为聚合索引文件
+    pub url: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+pub const OFFICIAL_MARKET_URL: &str = "https://sealantern-studio.github.io/plugin-market";
+
+fn default_plugin_market_sources() -> Vec<MarketSource> {
+    vec![MarketSource {
+        id: "official".to_string(),
+        name: "SeaLantern".to_string(),
+        url: OFFICIAL_MARKET_URL.to_string(),
+        enabled: true,
+    }]
 }
 
 fn default_true() -> bool {
@@ -238,7 +278,11 @@ impl AppSettings {
             changed.push(SettingsGroup::Window);
         }
 
-        if self.developer_mode != other.developer_mode {
+        if self.developer_mode != other.developer_mode
+            || self.trusted_plugin_publishers != other.trusted_plugin_publishers
+            || self.allow_incompatible_plugins != other.allow_incompatible_plugins
+            || self.plugin_market_sources != other.plugin_market_sources
+        {
             changed.push(SettingsGroup::Developer);
         }
 
@@ -342,6 +386,15 @@ impl AppSettings {
         if let Some(v) = partial.minimal_mode {
             self.minimal_mode = v;
         }
+        if let Some(ref v) = partial.trusted_plugin_publishers {
+            self.trusted_plugin_publishers = v.clone();
+        }
+        if let Some(v) = partial.allow_incompatible_plugins {
+            self.allow_incompatible_plugins = v;
+        }
+        if let Some(ref v) = partial.plugin_market_sources {
+            self.plugin_market_sources = v.clone();
+        }
     }
 }
 
@@ -411,6 +464,12 @@ pub struct PartialSettings {
     pub last_run_path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub minimal_mode: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trusted_plugin_publishers: Option<Vec<TrustedPublisher>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub allow_incompatible_plugins: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin_market_sources: Option<Vec<MarketSource>>,
 }
 
 impl Default for AppSettings {
@@ -448,6 +507,9 @@ impl Default for AppSettings {
             close_action: "ask".to_string(),
             last_run_path: String::new(),
             minimal_mode: false,
+            trusted_plugin_publishers: Vec::new(),
+            allow_incompatible_plugins: false,
+            plugin_market_sources: default_plugin_market_sources(),
         }
     }
 }
@@ -1,6 +1,6 @@
 use serde_json::Value as JsonValue;
 use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex, OnceLock, RwLock};
 
 pub type ApiCallHandler =
@@ -39,10 +39,175 @@ pub type ServerReadyHandler = Arc<dyn Fn(&str) -> Result<(), String> + Send + Sy
 
 static SERVER_READY_HANDLER: RwLock<Option<ServerReadyHandler>> = RwLock::new(None);
 
+/// 服务器生命周期 / 控制台事件，由 ServerManager 与日志管道产生，分发给订阅了 sl.events 的插件
+#[derive(Debug, Clone)]
+pub struct ServerEvent {
+    pub event: String,
+    pub server_id: String,
+    pub payload: JsonValue,
+}
+
+pub type ServerEventHandler = Arc<dyn Fn(&ServerEvent) -> Result<(), String> + Send + Sync>;
+
+static SERVER_EVENT_HANDLER: RwLock<Option<ServerEventHandler>> = RwLock::new(None);
+
+// 事件经有界队列交给独立线程分发，插件回调再慢也不会阻塞服务器输出的读取
+const SERVER_EVENT_QUEUE_CAPACITY: usize = 4096;
+
+static SERVER_EVENT_QUEUE: OnceLock<std::sync::mpsc::SyncSender<ServerEvent>> = OnceLock::new();
+
+pub type TimerFiredHandler = Arc<dyn Fn(&str, u64) -> Result<(), String> + Send + Sync>;
+
+static TIMER_FIRED_HANDLER: RwLock<Option<TimerFiredHandler>> = RwLock::new(None);
+
+pub type PluginWatchdogHandler = Arc<dyn Fn(&str, &str) -> Result<(), String> + Send + Sync>;
+
+static PLUGIN_WATCHDOG_HANDLER: RwLock<Option<PluginWatchdogHandler>> = RwLock::new(None);
+
+pub type PermissionPromptHandler = Arc<dyn Fn(&str, &[String]) -> Result<(), String> + Send + Sync>;
+
+static PERMISSION_PROMPT_HANDLER: RwLock<Option<PermissionPromptHandler>> = RwLock::new(None);
+
+/// WebSocket 连接与流式 HTTP 请求在后台线程产生的事件，交回插件自己的线程执行回调
+#[derive(Debug, Clone)]
+pub enum NetEventKind {
+    Open,
+    Message {
+        data: Vec<u8>,
+        binary: bool,
+    },
+    Close {
+        code: u16,
+        reason: String,
+    },
+    Error(String),
+    Response {
+        status: u16,
+        headers: Vec<(String, String)>,
+    },
+    Chunk(Vec<u8>),
+    Done {
+        bytes: u64,
+        error: Option<String>,
+    },
+}
+
+impl NetEventKind {
+    /// 连接或请求已结束，之后不会再有该句柄的事件
+    pub fn is_terminal(&self) -> bool {
+        matches!(self, NetEventKind::Close { .. } | NetEventKind::Done { .. })
+    }
+}
+
+/// 数据事件占用句柄的一个待处理名额，事件被执行或丢弃时归还
+#[derive(Debug)]
+pub struct NetEventTicket(Arc<AtomicUsize>);
+
+impl NetEventTicket {
+    pub fn new(pending: &Arc<AtomicUsize>) -> Self {
+        pending.fetch_add(1, Ordering::SeqCst);
+        Self(Arc::clone(pending))
+    }
+}
+
+impl Drop for NetEventTicket {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[derive(Debug)]
+pub struct NetEvent {
+    pub plugin_id: String,
+    pub handle_id: u64,
+    pub kind: NetEventKind,
+    pub ticket: Option<NetEventTicket>,
+}
+
+pub type NetEventHandler = Arc<dyn Fn(NetEvent) -> Result<(), String> + Send + Sync>;
+
+static NET_EVENT_HANDLER: RwLock<Option<NetEventHandler>> = RwLock::new(None);
+
 pub type I18nEventHandler = Arc<dyn Fn(&str, &str, &str, &str) -> Result<(), String> + Send + Sync>;
 
 static I18N_EVENT_HANDLER: RwLock<Option<I18nEventHandler>> = RwLock::new(None);
 
+/// 被捕获插件发出的 UI / 日志 / 右键菜单 / 侧边栏 / 组件事件，供无界面测试断言
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct CapturedEvent {
+    /// ui / log / context_menu / sidebar / component
+    pub kind: String,
+    pub action: String,
+    /// UI 事件为元素 id，日志为调用位置，右键菜单为上下文，侧边栏为标签
+    pub target: String,
+    pub data: String,
+}
+
+// 处于捕获状态的插件事件只记录在这里，不进入快照缓冲，也不转发给前端
+static EVENT_CAPTURES: OnceLock<Mutex<HashMap<String, Vec<CapturedEvent>>>> = OnceLock::new();
+
+fn event_captures() -> &'static Mutex<HashMap<String, Vec<CapturedEvent>>> {
+    EVENT_CAPTURES.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+pub fn start_event_capture(plugin_id: &str) {
+    event_captures()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(plugin_id.to_string(), Vec::new());
+}
+
+pub fn stop_event_capture(plugin_id: &str) -> Vec<CapturedEvent> {
+    event_captures()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(plugin_id)
+        .unwrap_or_default()
+}
+
+pub fn captured_events(plugin_id: &str) -> Vec<CapturedEvent> {
+    event_captures()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(plugin_id)
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub fn clear_captured_events(plugin_id: &str) {
+    if let Some(events) = event_captures()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get_mut(plugin_id)
+    {
+        events.clear();
+    }
+}
+
+fn is_event_captured(plugin_id: &str) -> bool {
+    event_captures()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains_key(plugin_id)
+}
+
+/// 插件处于捕获状态时记录事件并返回 true，调用方随即跳过缓冲与转发
+fn capture_event(plugin_id: &str, kind: &str, action: &str, target: &str, data: &str) -> bool {
+    let mut captures = event_captures().lock().unwrap_or_else(|e| e.into_inner());
+    match captures.get_mut(plugin_id) {
+        Some(events) => {
+            events.push(CapturedEvent {
+                kind: kind.to_string(),
+                action: action.to_string(),
+                target: target.to_string(),
+                data: data.to_string(),
+            });
+            true
+        }
+        None => false,
+    }
+}
+
 #[derive(Clone, serde::Serialize)]
 pub struct BufferedUiEvent {
     pub plugin_id: String,
@@ -222,6 +387,9 @@ pub fn emit_ui_event(
     element_id: &str,
     html: &str,
 ) -> Result<(), String> {
+    if capture_event(plugin_id, "ui", action, element_id, html) {
+        return Ok(());
+    }
     buffer_ui_event(plugin_id, action, element_id, html);
 
     let handler = UI_EVENT_HANDLER.read().unwrap_or_else(|e| {
@@ -241,6 +409,20 @@ pub fn emit_ui_event(
 }
 
 pub fn emit_log_event(plugin_id: &str, level: &str, message: &str) -> Result<(), String> {
+    emit_log_event_at(plugin_id, level, message, None)
+}
+
+/// 发出插件日志并持久化，source 为 Lua 调用位置
+pub fn emit_log_event_at(
+    plugin_id: &str,
+    level: &str,
+    message: &str,
+    source: Option<&str>,
+) -> Result<(), String> {
+    if capture_event(plugin_id, "log", level, source.unwrap_or(""), message) {
+        return Ok(());
+    }
+    crate::plugins::logs::append_log(plugin_id, level, message, source);
     let handler = LOG_EVENT_HANDLER.read().unwrap_or_else(|e| {
         eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
         e.into_inner()
@@ -263,6 +445,9 @@ pub fn emit_context_menu_event(
     context: &str,
     items_json: &str,
 ) -> Result<(), String> {
+    if capture_event(plugin_id, "context_menu", action, context, items_json) {
+        return Ok(());
+    }
     buffer_context_menu_event(plugin_id, action, context, items_json);
 
     let handler = CONTEXT_MENU_HANDLER.read().unwrap_or_else(|e| {
@@ -385,6 +570,9 @@ pub fn emit_sidebar_event(
     label: &str,
     icon: &str,
 ) -> Result<(), String> {
+    if capture_event(plugin_id, "sidebar", action, label, icon) {
+        return Ok(());
+    }
     buffer_sidebar_event(plugin_id, action, label, icon);
 
     let handler = SIDEBAR_EVENT_HANDLER.read().unwrap_or_else(|e| {
@@ -413,6 +601,7 @@ pub fn emit_permission_log(
         .duration_since(std::time::UNIX_EPOCH)
         .unwrap_or_default()
         .as_millis() as u64;
+    crate::plugins::logs::append_permission_log(plugin_id, log_type, action, detail, timestamp);
 
     let handler = PERMISSION_LOG_HANDLER.read().unwrap_or_else(|e| {
         eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
@@ -502,6 +691,14 @@ pub fn clear_plugin_component_snapshot(plugin_id: &str) {
 }
 
 pub fn emit_component_event(plugin_id: &str, payload_json: &str) -> Result<(), String> {
+    if is_event_captured(plugin_id) {
+        let action = serde_json::from_str::<JsonValue>(payload_json)
+            .ok()
+            .and_then(|p| p.get("action").and_then(|v| v.as_str()).map(str::to_string))
+            .unwrap_or_default();
+        capture_event(plugin_id, "component", &action, "", payload_json);
+        return Ok(());
+    }
     buffer_component_event(plugin_id, payload_json);
     let handler = COMPONENT_EVENT_HANDLER.read().unwrap_or_else(|e| {
         eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
@@ -532,6 +729,140 @@ pub fn emit_server_ready(server_id: &str) -> Result<(), String> {
     }
 }
 
+pub fn set_server_event_handler(handler: ServerEventHandler) {
+    let mut h = SERVER_EVENT_HANDLER.write().unwrap_or_else(|e| {
+        eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
+        e.into_inner()
+    });
+    *h = Some(handler);
+}
+
+fn server_event_queue() -> &'static std::sync::mpsc::SyncSender<ServerEvent> {
+    SERVER_EVENT_QUEUE.get_or_init(|| {
+        let (tx, rx) = std::sync::mpsc::sync_channel::<ServerEvent>(SERVER_EVENT_QUEUE_CAPACITY);
+        std::thread::spawn(move || {
+            for event in rx {
+                let handler = SERVER_EVENT_HANDLER
+                    .read()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .clone();
+                if let Some(h) = handler {
+                    if let Err(e) = h(&event) {
+                        eprintln!("[WARN] 分发服务器事件 '{}' 失败: {}", event.event, e);
+                    }
+                }
+            }
+        });
+        tx
+    })
+}
+
+/// 投递服务器事件；队列已满时直接丢弃，不阻塞调用方
+pub fn emit_server_event(event: &str, server_id: &str, payload: JsonValue) -> Result<(), String> {
+    let has_handler = SERVER_EVENT_HANDLER
+        .read()
+        .map(|h| h.is_some())
+        .unwrap_or(false);
+    if !has_handler {
+        return Ok(());
+    }
+
+    server_event_queue()
+        .try_send(ServerEvent {
+            event: event.to_string(),
+            server_id: server_id.to_string(),
+            payload,
+        })
+        .map_err(|e| match e {
+            std::sync::mpsc::TrySendError::Full(_) => "插件事件队列已满，事件被丢弃".to_string(),
+            std::sync::mpsc::TrySendError::Disconnected(_) => "插件事件分发线程已退出".to_string(),
+        })
+}
+
+pub fn set_timer_fired_handler(handler: TimerFiredHandler) {
+    let mut h = TIMER_FIRED_HANDLER.write().unwrap_or_else(|e| {
+        eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
+        e.into_inner()
+    });
+    *h = Some(handler);
+}
+
+pub fn emit_timer_fired(plugin_id: &str, timer_id: u64) -> Result<(), String> {
+    let handler = TIMER_FIRED_HANDLER
+        .read()
+        .unwrap_or_else(|e| {
+            eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
+            e.into_inner()
+        })
+        .clone();
+    match handler {
+        Some(h) => h(plugin_id, timer_id),
+        None => Ok(()),
+    }
+}
+
+pub fn set_plugin_watchdog_handler(handler: PluginWatchdogHandler) {
+    let mut h = PLUGIN_WATCHDOG_HANDLER.write().unwrap_or_else(|e| {
+        eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
+        e.into_inner()
+    });
+    *h = Some(handler);
+}
+
+/// 插件多次违反资源限制时请求宿主禁用该插件
+pub fn emit_plugin_watchdog(plugin_id: &str, reason: &str) -> Result<(), String> {
+    let handler = PLUGIN_WATCHDOG_HANDLER.read().unwrap_or_else(|e| {
+        eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
+        e.into_inner()
+    });
+    match handler.as_ref() {
+        Some(h) => h(plugin_id, reason),
+        None => Ok(()),
+    }
+}
+
+pub fn set_permission_prompt_handler(handler: PermissionPromptHandler) {
+    let mut h = PERMISSION_PROMPT_HANDLER.write().unwrap_or_else(|e| {
+        eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
+        e.into_inner()
+    });
+    *h = Some(handler);
+}
+
+/// 插件有待用户决定的权限时通知前端弹窗
+pub fn emit_permission_prompt(plugin_id: &str, permissions: &[String]) -> Result<(), String> {
+    let handler = PERMISSION_PROMPT_HANDLER.read().unwrap_or_else(|e| {
+        eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
+        e.into_inner()
+    });
+    match handler.as_ref() {
+        Some(h) => h(plugin_id, permissions),
+        None => Ok(()),
+    }
+}
+
+pub fn set_net_event_handler(handler: NetEventHandler) {
+    let mut h = NET_EVENT_HANDLER.write().unwrap_or_else(|e| {
+        eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
+        e.into_inner()
+    });
+    *h = Some(handler);
+}
+
+pub fn emit_net_event(event: NetEvent) -> Result<(), String> {
+    let handler = NET_EVENT_HANDLER
+        .read()
+        .unwrap_or_else(|e| {
+            eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
+            e.into_inner()
+        })
+        .clone();
+    match handler {
+        Some(h) => h(event),
+        None => Ok(()),
+    }
+}
+
 pub fn set_i18n_event_handler(handler: I18nEventHandler) {
     let mut h = I18N_EVENT_HANDLER.write().unwrap_or_else(|e| {
         eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
@@ -0,0 +1,276 @@
+use crate::models::plugin::{PluginInfo, PluginState};
+use serde::Serialize;
+use std::collections::HashMap;
+
+// 命令面板：
+// 1) 命令来自内置命令（启动/停止/重启服务器）与已启用插件 manifest 中的 commands
+// 2) 命令的完整 id 为 "builtin:<id>" 或 "<插件 id>:<命令 id>"
+// 3) 快捷键统一规范化后比较，冲突时先登记的命令（内置优先，其次按插件 id 排序）生效，
+//    其余命令保留在列表中但快捷键不生效，并标出与谁冲突
+
+pub const BUILTIN_SOURCE: &str = "builtin";
+
+const MODIFIERS: [&str; 4] = ["Ctrl", "Alt", "Shift", "Meta"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandEntry {
+    /// 完整 id，execute_command 使用
+    pub id: String,
+    /// 插件 id，内置命令为 "builtin"
+    pub source: String,
+    pub command_id: String,
+    pub title: String,
+    /// 规范化后的快捷键，如 "Ctrl+Shift+P"
+    pub shortcut: Option<String>,
+    /// 快捷键是否生效；冲突中未抢到快捷键的命令为 false
+    pub shortcut_active: bool,
+    /// 与本命令快捷键冲突的其他命令的完整 id
+    pub conflicts: Vec<String>,
+}
+
+/// 把 "ctrl+shift+p"、"Shift+Control+P"、"Cmd+K" 等写法规范化为固定顺序的形式
+pub fn normalize_shortcut(shortcut: &str) -> Result<String, String> {
+    let mut modifiers = [false; 4];
+    let mut key: Option<String> = None;
+
+    for part in shortcut.split('+').map(str::trim) {
+        if part.is_empty() {
+            return Err(format!("快捷键 '{}' 格式无效", shortcut));
+        }
+        let modifier = match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => Some(0),
+            "alt" | "option" => Some(1),
+            "shift" => Some(2),
+            "meta" | "cmd" | "command" | "super" | "win" => Some(3),
+            _ => None,
+        };
+        match modifier {
+            Some(index) => {
+                if modifiers[index] {
+                    return Err(format!("快捷键 '{}' 中的修饰键重复", shortcut));
+                }
+                modifiers[index] = true;
+            }
+            None => {
+                if key.is_some() {
+                    return Err(format!("快捷键 '{}' 只能包含一个非修饰键", shortcut));
+                }
+                if !part.chars().all(|c| c.is_ascii_alphanumeric()) {
+                    return Err(format!("快捷键 '{}' 中的按键 '{}' 无效", shortcut, part));
+                }
+                // 字母键统一大写，多字符按键（F5、Space 等）统一首字母大写
+                let (first, rest) = part.split_at(1);
+                let normalized = first.to_ascii_uppercase() + &rest.to_ascii_lowercase();
+                key = Some(normalized);
+            }
+        }
+    }
+
+    let key = key.ok_or_else(|| format!("快捷键 '{}' 缺少按键", shortcut))?;
+    let is_function_key =
+        key.len() >= 2 && key.starts_with('F') && key[1..].chars().all(|c| c.is_ascii_digit());
+    if !modifiers.iter().any(|m| *m) && !is_function_key {
+        return Err(format!("快捷键 '{}' 至少需要一个修饰键（F1-F24 除外）", shortcut));
+    }
+
+    let mut parts: Vec<String> = MODIFIERS
+        .iter()
+        .zip(modifiers)
+        .filter(|(_, on)| *on)
+        .map(|(name, _)| name.to_string())
+        .collect();
+    parts.push(key);
+    Ok(parts.join("+"))
+}
+
+pub fn builtin_commands() -> Vec<CommandEntry> {
+    [
+        ("server.start", "启动服务器"),
+        ("server.stop", "停止服务器"),
+        ("server.restart", "重启服务器"),
+    ]
+    .into_iter()
+    .map(|(id, title)| CommandEntry {
+        id: format!("{}:{}", BUILTIN_SOURCE, id),
+        source: BUILTIN_SOURCE.to_string(),
+        command_id: id.to_string(),
+        title: title.to_string(),
+        shortcut: None,
+        shortcut_active: false,
+        conflicts: Vec::new(),
+    })
+    .collect()
+}
+
+/// 汇总内置命令与已启用插件的命令，并标记快捷键冲突
+pub fn collect_commands(plugins: &HashMap<String, PluginInfo>) -> Vec<CommandEntry> {
+    let mut entries = builtin_commands();
+
+    let mut enabled: Vec<&PluginInfo> = plugins
+        .values()
+        .filter(|info| matches!(info.state, PluginState::Enabled))
+        .collect();
+    enabled.sort_by(|a, b| a.manifest.id.cmp(&b.manifest.id));
+
+    for info in enabled {
+        for command in &info.manifest.commands {
+            entries.push(CommandEntry {
+                id: format!("{}:{}", info.manifest.id, command.id),
+                source: info.manifest.id.clone(),
+                command_id: command.id.clone(),
+                title: command.title.clone(),
+                shortcut: command
+                    .shortcut
+                    .as_deref()
+                    .and_then(|s| normalize_shortcut(s).ok()),
+                shortcut_active: false,
+                conflicts: Vec::new(),
+            });
+        }
+    }
+
+    let mut owners: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        if let Some(shortcut) = &entry.shortcut {
+            owners.entry(shortcut.clone()).or_default().push(index);
+        }
+    }
+    for indices in owners.values() {
+        for (position, &index) in indices.iter().enumerate() {
+            let conflicts = indices
+                .iter()
+                .filter(|&&other| other != index)
+                .map(|&other| entries[other].id.clone())
+                .collect();
+            entries[index].shortcut_active = position == 0;
+            entries[index].conflicts = conflicts;
+        }
+    }
+
+    entries
+}
+
+pub enum CommandTarget {
+    Builtin(String),
+    Plugin {
+        plugin_id: String,
+        command_id: String,
+    },
+}
+
+/// 校验完整命令 id：内置命令必须存在，插件命令必须来自已启用插件的 manifest
+pub fn resolve_command(
+    plugins: &HashMap<String, PluginInfo>,
+    id: &str,
+) -> Result<CommandTarget, String> {
+    let (source, command_id) = split_command_id(id)?;
+    if source == BUILTIN_SOURCE {
+        if !builtin_commands()
+            .iter()
+            .any(|c| c.command_id == command_id)
+        {
+            return Err(format!("未知的内置命令 '{}'", command_id));
+        }
+        return Ok(CommandTarget::Builtin(command_id.to_string()));
+    }
+
+    let info = plugins
+        .get(source)
+        .ok_or_else(|| format!("插件 '{}' 不存在", source))?;
+    if !matches!(info.state, PluginState::Enabled) {
+        return Err(format!("插件 '{}' 未启用", source));
+    }
+    if !info.manifest.commands.iter().any(|c| c.id == command_id) {
+        return Err(format!("插件 '{}' 没有声明命令 '{}'", source, command_id));
+    }
+    Ok(CommandTarget::Plugin {
+        plugin_id: source.to_string(),
+        command_id: command_id.to_string(),
+    })
+}
+
+/// 执行内置命令，服务器相关命令需要 args.server_id
+pub fn execute_builtin(command_id: &str, args: &serde_json::Value) -> Result<(), String> {
+    let server_id = || {
+        args.get("server_id")
+            .and_then(|v| v.as_str())
+            .filter(|id| !id.is_empty())
+            .ok_or_else(|| format!("命令 '{}' 需要参数 server_id", command_id))
+    };
+    let servers = crate::services::global::server_manager();
+    match command_id {
+        "server.start" => servers.start_server(server_id()?),
+        "server.stop" => servers.request_stop_server(server_id()?),
+        "server.restart" => servers.request_restart_server(server_id()?),
+        _ => Err(format!("未知的内置命令 '{}'", command_id)),
+    }
+}
+
+/// 把完整命令 id 拆成 (来源, 命令 id)
+pub fn split_command_id(id: &str) -> Result<(&str, &str), String> {
+    match id.split_once(':') {
+        Some((source, command)) if !source.is_empty() && !command.is_empty() => {
+            Ok((source, command))
+        }
+        _ => Err(format!("命令 id '{}' 格式应为 <来源>:<命令>", id)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::plugin::PluginManifest;
+
+    fn plugin(id: &str, commands: serde_json::Value) -> PluginInfo {
+        let manifest: PluginManifest = serde_json::from_value(serde_json::json!({
+            "id": id, "name": id, "version": "1.0.0", "description": "d",
+            "author": { "name": "a" }, "main": "main.lua", "commands": commands,
+        }))
+        .unwrap();
+        PluginInfo {
+            manifest,
+            state: PluginState::Enabled,
+            path: String::new(),
+            missing_dependencies: Vec::new(),
+            signature: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_shortcut_normalization_and_conflicts() {
+        assert_eq!(normalize_shortcut("shift+control+p").unwrap(), "Ctrl+Shift+P");
+        assert_eq!(normalize_shortcut("Cmd + k").unwrap(), "Meta+K");
+        assert_eq!(normalize_shortcut("f5").unwrap(), "F5");
+        assert!(normalize_shortcut("P").is_err());
+        assert!(normalize_shortcut("Ctrl+P+Q").is_err());
+        assert!(normalize_shortcut("Ctrl+").is_err());
+
+        let mut plugins = HashMap::new();
+        plugins.insert(
+            "b".to_string(),
+            plugin(
+                "b",
+                serde_json::json!([{ "id": "go", "title": "Go", "shortcut": "Ctrl+Shift+G" }]),
+            ),
+        );
+        plugins.insert(
+            "a".to_string(),
+            plugin(
+                "a",
+                serde_json::json!([{ "id": "run", "title": "Run", "shortcut": "shift+ctrl+g" }]),
+            ),
+        );
+        let mut disabled = plugin("c", serde_json::json!([{ "id": "x", "title": "X" }]));
+        disabled.state = PluginState::Disabled;
+        plugins.insert("c".to_string(), disabled);
+
+        let commands = collect_commands(&plugins);
+        assert_eq!(commands.len(), builtin_commands().len() + 2);
+        assert!(commands.iter().all(|c| c.source != "c"));
+        let run = commands.iter().find(|c| c.id == "a:run").unwrap();
+        let go = commands.iter().find(|c| c.id == "b:go").unwrap();
+        assert!(run.shortcut_active && !go.shortcut_active);
+        assert_eq!(run.conflicts, vec!["b:go".to_string()]);
+        assert_eq!(split_command_id("a:run").unwrap(), ("a", "run"));
+    }
+}
@@ -0,0 +1,226 @@
+use crate::models::plugin::PluginState;
+use crate::plugins::manager::PluginManager;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+
+// 开发者模式下的插件热重载：
+// 1) 仅在 AppSettings.developer_mode 开启时轮询已启用插件的目录，关闭后不做任何文件访问
+// 2) 以文件路径、大小与修改时间计算指纹，连续两次轮询指纹一致才重载，避免编辑器分段写入时反复重载
+// 3) 重载失败的插件继续被监视，修好后自动再次重载
+
+const POLL_INTERVAL: Duration = Duration::from_millis(800);
+const MAX_WATCH_DEPTH: usize = 8;
+
+#[derive(serde::Serialize, Clone)]
+struct PluginReloadedEvent {
+    plugin_id: String,
+    success: bool,
+    error: Option<String>,
+}
+
+pub fn start_dev_reload_watcher(manager: Arc<Mutex<PluginManager>>, app_handle: AppHandle) {
+    let spawned = std::thread::Builder::new()
+        .name("plugin-dev-reload".to_string())
+        .spawn(move || run_watcher(manager, app_handle));
+    if let Err(e) = spawned {
+        eprintln!("[WARN] Failed to start plugin dev reload watcher: {}", e);
+    }
+}
+
+fn run_watcher(manager: Arc<Mutex<PluginManager>>, app_handle: AppHandle) {
+    let mut known: HashMap<String, u64> = HashMap::new();
+    let mut pending: HashMap<String, u64> = HashMap::new();
+    let mut failed: HashSet<String> = HashSet::new();
+
+    loop {
+        std::thread::sleep(POLL_INTERVAL);
+
+        if !crate::services::global::settings_manager()
+            .get()
+            .developer_mode
+        {
+            known.clear();
+            pending.clear();
+            failed.clear();
+            continue;
+        }
+
+        let watched: Vec<(String, PathBuf)> = {
+            let manager = manager.lock().unwrap_or_else(|e| e.into_inner());
+            manager
+                .plugins()
+                .iter()
+                .filter(|(id, info)| {
+                    matches!(info.state, PluginState::Enabled) || failed.contains(*id)
+                })
+                .map(|(id, info)| (id.clone(), PathBuf::from(&info.path)))
+                .collect()
+        };
+
+        known.retain(|id, _| watched.iter().any(|(w, _)| w == id));
+        pending.retain(|id, _| watched.iter().any(|(w, _)| w == id));
+
+        for (plugin_id, plugin_dir) in watched {
+            let fingerprint = fingerprint_dir(&plugin_dir);
+            // 首次看到的插件只记录基线
+            let Some(&previous) = known.get(&plugin_id) else {
+                known.insert(plugin_id, fingerprint);
+                continue;
+            };
+            if previous == fingerprint {
+                pending.remove(&plugin_id);
+                continue;
+            }
+            if pending.get(&plugin_id) != Some(&fingerprint) {
+                pending.insert(plugin_id, fingerprint);
+                continue;
+            }
+
+            pending.remove(&plugin_id);
+            known.insert(plugin_id.clone(), fingerprint);
+
+            let result = manager
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .reload_plugin(&plugin_id);
+            let error = match result {
+                Ok(()) => {
+                    failed.remove(&plugin_id);
+                    None
+                }
+                Err(e) => {
+                    failed.insert(plugin_id.clone());
+                    Some(e)
+                }
+            };
+
+            let event = PluginReloadedEvent {
+                plugin_id,
+                success: error.is_none(),
+                error,
+            };
+            if let Err(e) = app_handle.emit("plugin-reloaded", event) {
+                eprintln!("[WARN] Failed to emit plugin-reloaded event: {}", e);
+            }
+        }
+    }
+}
+
+fn fingerprint_dir(dir: &Path) -> u64 {
+    let mut entries = Vec::new();
+    collect_entries(dir, dir, 0, &mut entries);
+    entries.sort();
+
+    let mut hasher = DefaultHasher::new();
+    entries.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn collect_entries(root: &Path, dir: &Path, depth: usize, out: &mut Vec<(String, u64, u128)>) {
+    if depth > MAX_WATCH_DEPTH {
+        return;
+    }
+    let Ok(read_dir) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in read_dir.flatten() {
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        // 跳过 .git、node_modules 等与插件运行无关的目录
+        if name.starts_with('.') || name == "node_modules" {
+            continue;
+        }
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if metadata.is_dir() {
+            collect_entries(root, &path, depth + 1, out);
+            continue;
+        }
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        let relative = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        out.push((relative, metadata.len(), modified));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "sealantern_test_dev_reload_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.lua"), "return {}").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_fingerprint_changes_when_file_is_modified() {
+        let dir = make_temp_dir("modified");
+        let before = fingerprint_dir(&dir);
+        fs::write(dir.join("main.lua"), "return { onLoad = function() end }").unwrap();
+        assert_ne!(before, fingerprint_dir(&dir));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_fingerprint_changes_when_file_is_added_or_removed() {
+        let dir = make_temp_dir("added");
+        let before = fingerprint_dir(&dir);
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        fs::write(dir.join("lib/util.lua"), "return {}").unwrap();
+        assert_ne!(before, fingerprint_dir(&dir));
+
+        fs::remove_dir_all(dir.join("lib")).unwrap();
+        assert_eq!(before, fingerprint_dir(&dir));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_hidden_entries_and_node_modules() {
+        let dir = make_temp_dir("ignored");
+        let before = fingerprint_dir(&dir);
+        fs::create_dir_all(dir.join(".git")).unwrap();
+        fs::write(dir.join(".git/HEAD"), "ref").unwrap();
+        fs::write(dir.join(".main.lua.swp"), "swap").unwrap();
+        fs::create_dir_all(dir.join("node_modules/pkg")).unwrap();
+        fs::write(dir.join("node_modules/pkg/index.js"), "").unwrap();
+        assert_eq!(before, fingerprint_dir(&dir));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_fingerprint_stops_below_max_watch_depth() {
+        let dir = make_temp_dir("depth");
+        let mut deep = dir.clone();
+        for i in 0..=MAX_WATCH_DEPTH + 1 {
+            deep = deep.join(format!("d{}", i));
+        }
+        fs::create_dir_all(&deep).unwrap();
+        let before = fingerprint_dir(&dir);
+        fs::write(deep.join("too_deep.lua"), "return {}").unwrap();
+        assert_eq!(before, fingerprint_dir(&dir));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,322 @@
+use crate::models::plugin::{get_permission_danger_level, PermissionDangerLevel};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// 插件权限授予记录：
+// 1) 每个插件的每项权限记为 granted / denied / ask，首次见到时按 manifest 声明播种
+// 2) 普通权限直接授予；Dangerous / Critical 权限记为 ask，由前端弹窗让用户决定
+// 3) 每次变更都追加到审计日志 permission_audit.jsonl，并通过权限日志推送给前端
+
+const GRANT_STORE_FILE: &str = "permission_grants.json";
+const AUDIT_LOG_FILE: &str = "permission_audit.jsonl";
+const MAX_AUDIT_ENTRIES: usize = 500;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GrantDecision {
+    Granted,
+    Denied,
+    Ask,
+}
+
+impl GrantDecision {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GrantDecision::Granted => "granted",
+            GrantDecision::Denied => "denied",
+            GrantDecision::Ask => "ask",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionGrant {
+    pub permission: String,
+    pub decision: GrantDecision,
+    pub danger_level: PermissionDangerLevel,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PermissionAuditEntry {
+    pub timestamp: u64,
+    pub plugin_id: String,
+    pub permission: String,
+    pub decision: GrantDecision,
+    /// seed（安装/首次加载时播种）、user（用户操作）
+    pub source: String,
+}
+
+pub struct PermissionGrantStore {
+    data_dir: PathBuf,
+    grants: HashMap<String, HashMap<String, GrantDecision>>,
+    /// 记录文件不存在（刚从旧版本升级）时，已声明的权限视为用户此前已同意
+    trust_existing: bool,
+}
+
+impl PermissionGrantStore {
+    pub fn load(data_dir: &Path) -> Self {
+        let path = data_dir.join(GRANT_STORE_FILE);
+        let (grants, trust_existing) = match fs::read_to_string(&path) {
+            Ok(content) => (
+                serde_json::from_str(&content).unwrap_or_else(|e| {
+                    eprintln!("[WARN] Failed to parse permission grants: {}", e);
+                    HashMap::new()
+                }),
+                false,
+            ),
+            Err(_) => (HashMap::new(), true),
+        };
+        Self {
+            data_dir: data_dir.to_path_buf(),
+            grants,
+            trust_existing,
+        }
+    }
+
+    /// 首次扫描完成后调用，之后新出现的权限都按危险等级播种
+    pub fn finish_migration(&mut self) {
+        if self.trust_existing {
+            self.trust_existing = false;
+            self.save();
+        }
+    }
+
+    /// 按 manifest 声明补齐缺失的记录，并移除 manifest 中已不再声明的权限
+    pub fn seed(&mut self, plugin_id: &str, declared: &[String]) {
+        let trust_existing = self.trust_existing;
+        let entry = self.grants.entry(plugin_id.to_string()).or_default();
+        let before = entry.len();
+        entry.retain(|permission, _| declared.contains(permission));
+        let mut changed = entry.len() != before;
+
+        let mut seeded = Vec::new();
+        for permission in declared {
+            if entry.contains_key(permission) {
+                continue;
+            }
+            let decision = if trust_existing
+                || get_permission_danger_level(permission) == PermissionDangerLevel::Normal
+            {
+                GrantDecision::Granted
+            } else {
+                GrantDecision::Ask
+            };
+            entry.insert(permission.clone(), decision);
+            seeded.push((permission.clone(), decision));
+            changed = true;
+        }
+
+        for (permission, decision) in seeded {
+            self.append_audit(plugin_id, &permission, decision, "seed");
+        }
+        if changed {
+            self.save();
+        }
+    }
+
+    pub fn decision(&self, plugin_id: &str, permission: &str) -> Option<GrantDecision> {
+        self.grants.get(plugin_id)?.get(permission).copied()
+    }
+
+    pub fn set(&mut self, plugin_id: &str, permission: &str, decision: GrantDecision) {
+        self.grants
+            .entry(plugin_id.to_string())
+            .or_default()
+            .insert(permission.to_string(), decision);
+        self.save();
+        self.append_audit(plugin_id, permission, decision, "user");
+    }
+
+    pub fn remove_plugin(&mut self, plugin_id: &str) {
+        if self.grants.remove(plugin_id).is_some() {
+            self.save();
+        }
+    }
+
+    /// 实际交给运行时的权限：只包含已授予的
+    pub fn effective_permissions(&self, plugin_id: &str, declared: &[String]) -> Vec<String> {
+        declared
+            .iter()
+            .filter(|p| self.decision(plugin_id, p) == Some(GrantDecision::Granted))
+            .cloned()
+            .collect()
+    }
+
+    pub fn grants_for(&self, plugin_id: &str, declared: &[String]) -> Vec<PermissionGrant> {
+        declared
+            .iter()
+            .map(|permission| PermissionGrant {
+                permission: permission.clone(),
+                decision: self
+                    .decision(plugin_id, permission)
+                    .unwrap_or(GrantDecision::Ask),
+                danger_level: get_permission_danger_level(permission),
+            })
+            .collect()
+    }
+
+    fn save(&self) {
+        match serde_json::to_string_pretty(&self.grants) {
+            Ok(json) => {
+                if let Err(e) = fs::write(self.data_dir.join(GRANT_STORE_FILE), json) {
+                    eprintln!("[WARN] Failed to save permission grants: {}", e);
+                }
+            }
+            Err(e) => eprintln!("[WARN] Failed to serialize permission grants: {}", e),
+        }
+    }
+
+    fn append_audit(
+        &self,
+        plugin_id: &str,
+        permission: &str,
+        decision: GrantDecision,
+        source: &str,
+    ) {
+        let entry = PermissionAuditEntry {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            plugin_id: plugin_id.to_string(),
+            permission: permission.to_string(),
+            decision,
+            source: source.to_string(),
+        };
+
+        let _ = crate::plugins::api::emit_permission_log(
+            plugin_id,
+            "grant",
+            permission,
+            &format!("{} ({})", decision.as_str(), source),
+        );
+
+        let Ok(line) = serde_json::to_string(&entry) else {
+            return;
+        };
+        let written = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.data_dir.join(AUDIT_LOG_FILE))
+            .and_then(|mut file| writeln!(file, "{}", line));
+        if let Err(e) = written {
+            eprintln!("[WARN] Failed to write permission audit log: {}", e);
+        }
+    }
+
+    /// 读取审计日志，最新的在前
+    pub fn audit_log(&self, plugin_id: Option<&str>) -> Vec<PermissionAuditEntry> {
+        let Ok(content) = fs::read_to_string(self.data_dir.join(AUDIT_LOG_FILE)) else {
+            return Vec::new();
+        };
+        content
+            .lines()
+            .rev()
+            .filter_map(|line| serde_json::from_str::<PermissionAuditEntry>(line).ok())
+            .filter(|entry| plugin_id.is_none_or(|id| entry.plugin_id == id))
+            .take(MAX_AUDIT_ENTRIES)
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "sealantern_test_grants_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 已有记录文件的存储：新权限按危险等级播种
+    fn existing_store(dir: &Path) -> PermissionGrantStore {
+        fs::write(dir.join(GRANT_STORE_FILE), "{}").unwrap();
+        PermissionGrantStore::load(dir)
+    }
+
+    fn declared() -> Vec<String> {
+        vec!["log".to_string(), "fs".to_string(), "execute_program".to_string()]
+    }
+
+    #[test]
+    fn test_seed_grants_normal_permissions_and_asks_for_dangerous_ones() {
+        let dir = make_temp_dir("seed");
+        let mut store = existing_store(&dir);
+        store.seed("demo", &declared());
+        assert_eq!(store.decision("demo", "log"), Some(GrantDecision::Granted));
+        assert_eq!(store.decision("demo", "fs"), Some(GrantDecision::Ask));
+        assert_eq!(store.decision("demo", "execute_program"), Some(GrantDecision::Ask));
+        assert_eq!(store.effective_permissions("demo", &declared()), vec!["log".to_string()]);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_seed_keeps_decisions_and_drops_undeclared_permissions() {
+        let dir = make_temp_dir("reseed");
+        let mut store = existing_store(&dir);
+        store.seed("demo", &declared());
+        store.set("demo", "fs", GrantDecision::Denied);
+
+        store.seed("demo", &["fs".to_string()]);
+        assert_eq!(store.decision("demo", "fs"), Some(GrantDecision::Denied));
+        assert_eq!(store.decision("demo", "log"), None);
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_missing_store_trusts_declared_permissions_until_migration_finishes() {
+        let dir = make_temp_dir("migration");
+        let mut store = PermissionGrantStore::load(&dir);
+        store.seed("old", &["execute_program".to_string()]);
+        assert_eq!(store.decision("old", "execute_program"), Some(GrantDecision::Granted));
+
+        store.finish_migration();
+        store.seed("new", &["execute_program".to_string()]);
+        assert_eq!(store.decision("new", "execute_program"), Some(GrantDecision::Ask));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_user_decisions_persist_across_reload() {
+        let dir = make_temp_dir("reload");
+        let mut store = existing_store(&dir);
+        store.seed("demo", &declared());
+        store.set("demo", "fs", GrantDecision::Granted);
+        store.set("demo", "execute_program", GrantDecision::Denied);
+
+        let reloaded = PermissionGrantStore::load(&dir);
+        assert_eq!(
+            reloaded.effective_permissions("demo", &declared()),
+            vec!["log".to_string(), "fs".to_string()]
+        );
+        assert_eq!(reloaded.decision("demo", "execute_program"), Some(GrantDecision::Denied));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_audit_log_lists_changes_newest_first_per_plugin() {
+        let dir = make_temp_dir("audit");
+        let mut store = existing_store(&dir);
+        store.seed("demo", &declared());
+        store.seed("other", &["log".to_string()]);
+        store.set("demo", "execute_program", GrantDecision::Denied);
+
+        let audit = store.audit_log(Some("demo"));
+        assert_eq!(audit.len(), 4);
+        assert_eq!(audit[0].permission, "execute_program");
+        assert_eq!(audit[0].decision, GrantDecision::Denied);
+        assert_eq!(audit[0].source, "user");
+        assert!(audit[1..].iter().all(|e| e.source == "seed"));
+        assert_eq!(store.audit_log(None).len(), 5);
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
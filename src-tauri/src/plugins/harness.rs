@@ -0,0 +1,882 @@
+use crate::models::plugin::{get_permission_danger_level, PermissionDangerLevel, PluginManifest};
+use crate::models::server::{ServerInstance, ServerStatus};
+use crate::plugins::api::{self, ServerEvent};
+use crate::plugins::loader::PluginLoader;
+use crate::plugins::runtime::helpers::json_value_from_lua;
+use crate::plugins::runtime::{PluginRuntime, ServerBackend};
+use mlua::{Function, Table, Value};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+
+// 插件无界面测试：
+// 1) 直接创建 PluginRuntime，sl.server / sl.console 接到内存中的假服务器，数据目录放在临时目录
+// 2) 运行时使用 "<插件 id>@test<n>" 作为 id，它发出的 UI / 日志 / 右键菜单 / 侧边栏 / 组件事件
+//    只被记录，不会进入界面，也不会和正在运行的同名插件混在一起
+// 3) 测试可模拟生命周期、命令、计时器、服务器事件与控制台日志行（日志行同时派生 player.* 事件）
+// 4) test/ 下每个 .lua 文件使用独立的运行时；文件返回函数表时每个函数是一个用例，
+//    否则整个文件作为一个用例，用例内通过 sl.test.assert_* 断言
+// 5) 测试中没有假实现、会真正作用于本机的权限（执行程序、访问其他插件目录、网络等）不授予，
+//    在报告中列出；sl.fs 只能访问临时数据目录
+
+pub const TEST_DIR_NAME: &str = "test";
+
+const TEST_CASE_TIME_BUDGET: Duration = Duration::from_secs(10);
+/// 除危险级别为 Critical 的权限外，测试中同样不授予的权限
+const WITHHELD_TEST_PERMISSIONS: &[&str] = &["network"];
+
+static NEXT_HARNESS_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SentCommand {
+    pub server_id: String,
+    pub command: String,
+}
+
+#[derive(Default)]
+struct FakeServerState {
+    servers: Vec<ServerInstance>,
+    statuses: HashMap<String, ServerStatus>,
+    logs: HashMap<String, Vec<String>>,
+    commands: Vec<SentCommand>,
+}
+
+/// 内存中的服务器列表，sl.console.send 发出的命令只记录不执行
+#[derive(Default)]
+pub struct FakeServerBackend {
+    state: Mutex<FakeServerState>,
+}
+
+impl FakeServerBackend {
+    pub fn add_server(&self, server_id: &str, name: &str, path: &Path, status: ServerStatus) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.servers.retain(|s| s.id != server_id);
+        state.servers.push(ServerInstance {
+            id: server_id.to_string(),
+            name: name.to_string(),
+            core_type: "vanilla".to_string(),
+            core_version: String::new(),
+            mc_version: "1.21".to_string(),
+            path: path.to_string_lossy().to_string(),
+            jar_path: String::new(),
+            startup_mode: "jar".to_string(),
+            custom_command: None,
+            java_path: String::new(),
+            max_memory: 2048,
+            min_memory: 1024,
+            jvm_args: Vec::new(),
+            jvm_preset: None,
+            port: 25565,
+            created_at: 0,
+            last_started_at: None,
+        });
+        state.statuses.insert(server_id.to_string(), status);
+    }
+
+    pub fn set_status(&self, server_id: &str, status: ServerStatus) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if !state.servers.iter().any(|s| s.id == server_id) {
+            return Err(format!("服务器不存在: {}", server_id));
+        }
+        state.statuses.insert(server_id.to_string(), status);
+        Ok(())
+    }
+
+    pub fn push_log(&self, server_id: &str, line: &str) {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .logs
+            .entry(server_id.to_string())
+            .or_default()
+            .push(line.to_string());
+    }
+
+    pub fn sent_commands(&self) -> Vec<SentCommand> {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .commands
+            .clone()
+    }
+}
+
+impl ServerBackend for FakeServerBackend {
+    fn list_servers(&self) -> Vec<ServerInstance> {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .servers
+            .clone()
+    }
+
+    fn server_status(&self, server_id: &str) -> ServerStatus {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .statuses
+            .get(server_id)
+            .cloned()
+            .unwrap_or(ServerStatus::Stopped)
+    }
+
+    fn running_server_ids(&self) -> Vec<String> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state
+            .servers
+            .iter()
+            .filter(|s| state.statuses.get(&s.id) == Some(&ServerStatus::Running))
+            .map(|s| s.id.clone())
+            .collect()
+    }
+
+    fn send_command(&self, server_id: &str, command: &str) -> Result<(), String> {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .commands
+            .push(SentCommand {
+                server_id: server_id.to_string(),
+                command: command.to_string(),
+            });
+        Ok(())
+    }
+
+    fn logs(&self, server_id: &str) -> Vec<String> {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .logs
+            .get(server_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn all_logs(&self) -> Vec<(String, Vec<String>)> {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .logs
+            .iter()
+            .map(|(id, lines)| (id.clone(), lines.clone()))
+            .collect()
+    }
+}
+
+fn parse_status(status: &str) -> Result<ServerStatus, String> {
+    match status {
+        "running" => Ok(ServerStatus::Running),
+        "stopped" => Ok(ServerStatus::Stopped),
+        "starting" => Ok(ServerStatus::Starting),
+        "stopping" => Ok(ServerStatus::Stopping),
+        "error" => Ok(ServerStatus::Error),
+        _ => Err(format!("未知的服务器状态: {}", status)),
+    }
+}
+
+pub struct PluginTestHarness {
+    runtime: Arc<PluginRuntime>,
+    runtime_id: String,
+    manifest: PluginManifest,
+    plugin_dir: PathBuf,
+    work_dir: PathBuf,
+    backend: Arc<FakeServerBackend>,
+    assertions: Arc<AtomicU32>,
+}
+
+impl PluginTestHarness {
+    /// 按 manifest 声明的权限（去掉测试中不授予的）创建运行时，并在 sl.test 中注入测试 API
+    pub fn new(plugin_dir: &Path) -> Result<Self, String> {
+        let manifest = PluginLoader::load_manifest(plugin_dir)?;
+        let n = NEXT_HARNESS_ID.fetch_add(1, Ordering::SeqCst);
+        let runtime_id = format!("{}@test{}", manifest.id, n);
+        let work_dir = std::env::temp_dir().join(format!(
+            "sealantern_plugin_test_{}_{}",
+            std::process::id(),
+            n
+        ));
+        let _ = fs::remove_dir_all(&work_dir);
+
+        let backend = Arc::new(FakeServerBackend::default());
+        let runtime = PluginRuntime::with_server_backend(
+            &runtime_id,
+            plugin_dir,
+            &work_dir.join("data"),
+            api::new_api_registry(),
+            granted_test_permissions(&manifest.permissions),
+            backend.clone(),
+        )?;
+        api::start_event_capture(&runtime_id);
+
+        let harness = Self {
+            runtime: Arc::new(runtime),
+            runtime_id,
+            manifest,
+            plugin_dir: plugin_dir.to_path_buf(),
+            work_dir,
+            backend,
+            assertions: Arc::new(AtomicU32::new(0)),
+        };
+        harness.install_test_api()?;
+        Ok(harness)
+    }
+
+    /// 执行主文件并调用 onLoad
+    pub fn load(&self) -> Result<(), String> {
+        self.runtime
+            .load_file(&self.plugin_dir.join(&self.manifest.main))?;
+        self.runtime.call_lifecycle("onLoad")
+    }
+
+    pub fn enable(&self) -> Result<(), String> {
+        self.runtime.call_lifecycle("onEnable")
+    }
+
+    pub fn disable(&self) -> Result<(), String> {
+        self.runtime.call_lifecycle("onDisable")
+    }
+
+    pub fn unload(&self) -> Result<(), String> {
+        self.runtime.call_lifecycle("onUnload")
+    }
+
+    fn install_test_api(&self) -> Result<(), String> {
+        let lua = &self.runtime.lua;
+        let sl: Table = lua
+            .globals()
+            .get("sl")
+            .map_err(|e| format!("Failed to get sl table: {}", e))?;
+        let test_table = lua
+            .create_table()
+            .map_err(|e| format!("Failed to create test table: {}", e))?;
+
+        macro_rules! set_fn {
+            ($name:literal, $func:expr) => {{
+                let func = lua
+                    .create_function($func)
+                    .map_err(|e| format!("Failed to create test.{}: {}", $name, e))?;
+                test_table
+                    .set($name, func)
+                    .map_err(|e| format!("Failed to set test.{}: {}", $name, e))?;
+            }};
+        }
+
+        let runtime = Arc::downgrade(&self.runtime);
+
+        let backend = self.backend.clone();
+        let work_dir = self.work_dir.clone();
+        set_fn!("add_server", move |_, (server_id, options): (String, Option<Table>)| {
+            let name = match &options {
+                Some(options) => options.get::<Option<String>>("name")?,
+                None => None,
+            };
+            let status = match &options {
+                Some(options) => options.get::<Option<String>>("status")?,
+                None => None,
+            };
+            let status = parse_status(status.as_deref().unwrap_or("running"))
+                .map_err(mlua::Error::runtime)?;
+            let path = add_fake_server(
+                &backend,
+                &work_dir,
+                &server_id,
+                name.as_deref().unwrap_or(&server_id),
+                status,
+            )
+            .map_err(mlua::Error::runtime)?;
+            Ok(path.to_string_lossy().to_string())
+        });
+
+        let backend = self.backend.clone();
+        set_fn!("set_status", move |_, (server_id, status): (String, String)| {
+            let status = parse_status(&status).map_err(mlua::Error::runtime)?;
+            backend
+                .set_status(&server_id, status)
+                .map_err(mlua::Error::runtime)
+        });
+
+        let backend = self.backend.clone();
+        let rt = runtime.clone();
+        set_fn!("server_log", move |_, (server_id, line): (String, String)| {
+            let runtime = upgrade_runtime(&rt)?;
+            simulate_server_log(&runtime, &backend, &server_id, &line).map_err(mlua::Error::runtime)
+        });
+
+        let rt = runtime.clone();
+        set_fn!(
+            "server_event",
+            move |_, (event, server_id, payload): (String, String, Value)| {
+                let mut payload = json_value_from_lua(&payload, 0)?;
+                if payload.is_null() {
+                    payload = serde_json::json!({});
+                }
+                if let Some(map) = payload.as_object_mut() {
+                    map.entry("server_id")
+                        .or_insert_with(|| JsonValue::String(server_id.clone()));
+                }
+                upgrade_runtime(&rt)?
+                    .dispatch_server_event(&ServerEvent { event, server_id, payload })
+                    .map_err(mlua::Error::runtime)
+            }
+        );
+
+        let rt = runtime.clone();
+        set_fn!("lifecycle", move |_, (event, arg): (String, Option<String>)| {
+            let runtime = upgrade_runtime(&rt)?;
+            match arg {
+                Some(arg) => runtime.call_lifecycle_with_arg(&event, &arg),
+                None => runtime.call_lifecycle(&event),
+            }
+            .map_err(mlua::Error::runtime)
+        });
+
+        let rt = runtime.clone();
+        set_fn!("command", move |_, (command_id, args): (String, Value)| {
+            let args = json_value_from_lua(&args, 0)?;
+            upgrade_runtime(&rt)?
+                .call_command(&command_id, args)
+                .map_err(mlua::Error::runtime)
+        });
+
+        let rt = runtime.clone();
+        set_fn!("fire_timer", move |_, timer_id: u64| {
+            upgrade_runtime(&rt)?
+                .fire_timer(timer_id)
+                .map_err(mlua::Error::runtime)
+        });
+
+        let runtime_id = self.runtime_id.clone();
+        set_fn!("events", move |lua, kind: Option<String>| {
+            let result = lua.create_table()?;
+            let events = api::captured_events(&runtime_id)
+                .into_iter()
+                .filter(|e| kind.as_deref().is_none_or(|k| e.kind == k));
+            for (i, event) in (1..).zip(events) {
+                let entry = lua.create_table()?;
+                entry.set("kind", event.kind)?;
+                entry.set("action", event.action)?;
+                entry.set("target", event.target)?;
+                entry.set("data", event.data)?;
+                result.set(i, entry)?;
+            }
+            Ok(result)
+        });
+
+        let runtime_id = self.runtime_id.clone();
+        set_fn!("clear_events", move |_, ()| {
+            api::clear_captured_events(&runtime_id);
+            Ok(())
+        });
+
+        let backend = self.backend.clone();
+        set_fn!("sent_commands", move |lua, ()| {
+            let result = lua.create_table()?;
+            for (i, sent) in (1..).zip(backend.sent_commands()) {
+                let entry = lua.create_table()?;
+                entry.set("server_id", sent.server_id)?;
+                entry.set("command", sent.command)?;
+                result.set(i, entry)?;
+            }
+            Ok(result)
+        });
+
+        let assertions = self.assertions.clone();
+        set_fn!("assert_eq", move |_,
+                                   (actual, expected, message): (
+            Value,
+            Value,
+            Option<String>
+        )| {
+            assertions.fetch_add(1, Ordering::SeqCst);
+            let actual = json_value_from_lua(&actual, 0)?;
+            let expected = json_value_from_lua(&expected, 0)?;
+            if actual == expected {
+                return Ok(());
+            }
+            Err(mlua::Error::runtime(format!(
+                "{}期望 {}，实际为 {}",
+                assertion_prefix(message),
+                expected,
+                actual
+            )))
+        });
+
+        let assertions = self.assertions.clone();
+        set_fn!("assert_true", move |_, (value, message): (Value, Option<String>)| {
+            assertions.fetch_add(1, Ordering::SeqCst);
+            if matches!(value, Value::Nil | Value::Boolean(false)) {
+                return Err(mlua::Error::runtime(format!(
+                    "{}期望为真值，实际为 {:?}",
+                    assertion_prefix(message),
+                    value
+                )));
+            }
+            Ok(())
+        });
+
+        let assertions = self.assertions.clone();
+        set_fn!("assert_contains", move |_,
+                                         (haystack, needle, message): (
+            String,
+            String,
+            Option<String>
+        )| {
+            assertions.fetch_add(1, Ordering::SeqCst);
+            if haystack.contains(&needle) {
+                return Ok(());
+            }
+            Err(mlua::Error::runtime(format!(
+                "{}期望包含 {:?}，实际为 {:?}",
+                assertion_prefix(message),
+                needle,
+                haystack
+            )))
+        });
+
+        let assertions = self.assertions.clone();
+        set_fn!("fail", move |_, message: Option<String>| -> mlua::Result<()> {
+            assertions.fetch_add(1, Ordering::SeqCst);
+            Err(mlua::Error::runtime(message.unwrap_or_else(|| "测试失败".to_string())))
+        });
+
+        sl.set("test", test_table)
+            .map_err(|e| format!("Failed to set sl.test: {}", e))
+    }
+
+    /// 执行测试文件，返回按名称排序的用例；文件未返回函数表时返回空列表
+    fn load_test_file(&self, path: &Path) -> Result<Vec<(String, Function)>, String> {
+        let bytes = fs::read(path).map_err(|e| format!("读取 {} 失败: {}", path.display(), e))?;
+        let content = String::from_utf8_lossy(&bytes).into_owned();
+        let chunk_name = path.strip_prefix(&self.plugin_dir).unwrap_or(path);
+        let chunk_name = format!("@{}", chunk_name.to_string_lossy().replace('\\', "/"));
+
+        let result: Value = self.runtime.with_budget(TEST_CASE_TIME_BUDGET, || {
+            self.runtime
+                .lua
+                .load(&content)
+                .set_name(chunk_name)
+                .set_mode(mlua::ChunkMode::Text)
+                .eval()
+                .map_err(|e| e.to_string())
+        })?;
+
+        let mut cases = Vec::new();
+        if let Value::Table(table) = result {
+            for pair in table.pairs::<Value, Value>() {
+                let (key, value) = pair.map_err(|e| e.to_string())?;
+                if let (Value::String(name), Value::Function(func)) = (key, value) {
+                    cases.push((name.to_string_lossy().to_string(), func));
+                }
+            }
+        }
+        cases.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(cases)
+    }
+
+    fn run_case(&self, name: &str, func: &Function) -> TestCaseReport {
+        self.assertions.store(0, Ordering::SeqCst);
+        let started = Instant::now();
+        let result = self
+            .runtime
+            .with_budget(TEST_CASE_TIME_BUDGET, || func.call::<()>(()).map_err(|e| e.to_string()));
+        TestCaseReport {
+            name: name.to_string(),
+            passed: result.is_ok(),
+            assertions: self.assertions.load(Ordering::SeqCst),
+            error: result.err(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// 供 Rust 侧测试直接驱动运行时，test/ 目录下的 Lua 测试通过 sl.test 使用同样的能力
+#[cfg(test)]
+impl PluginTestHarness {
+    pub fn backend(&self) -> &FakeServerBackend {
+        &self.backend
+    }
+
+    /// 添加一台运行中的假服务器，目录位于临时目录下，返回该目录
+    pub fn add_server(&self, server_id: &str) -> Result<PathBuf, String> {
+        add_fake_server(&self.backend, &self.work_dir, server_id, server_id, ServerStatus::Running)
+    }
+
+    /// 模拟服务器输出一行日志：写入日志并派发 server.log 及可识别的 player.* 事件
+    pub fn server_log(&self, server_id: &str, line: &str) -> Result<(), String> {
+        simulate_server_log(&self.runtime, &self.backend, server_id, line)
+    }
+
+    /// 运行时发出的全部界面与日志事件，按发生顺序排列
+    pub fn events(&self) -> Vec<api::CapturedEvent> {
+        api::captured_events(&self.runtime_id)
+    }
+}
+
+fn is_withheld_in_tests(permission: &str) -> bool {
+    get_permission_danger_level(permission) == PermissionDangerLevel::Critical
+        || WITHHELD_TEST_PERMISSIONS.contains(&permission)
+}
+
+fn granted_test_permissions(permissions: &[String]) -> Vec<String> {
+    permissions
+        .iter()
+        .filter(|p| !is_withheld_in_tests(p))
+        .cloned()
+        .collect()
+}
+
+impl Drop for PluginTestHarness {
+    fn drop(&mut self) {
+        self.runtime.cleanup();
+        api::stop_event_capture(&self.runtime_id);
+        let _ = fs::remove_dir_all(&self.work_dir);
+    }
+}
+
+fn add_fake_server(
+    backend: &FakeServerBackend,
+    work_dir: &Path,
+    server_id: &str,
+    name: &str,
+    status: ServerStatus,
+) -> Result<PathBuf, String> {
+    if server_id.is_empty()
+        || !server_id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        return Err(format!("服务器 id '{}' 只能包含字母、数字、- 和 _", server_id));
+    }
+    let path = work_dir.join("servers").join(server_id);
+    fs::create_dir_all(&path).map_err(|e| format!("创建服务器目录失败: {}", e))?;
+    backend.add_server(server_id, name, &path, status);
+    Ok(path)
+}
+
+fn simulate_server_log(
+    runtime: &PluginRuntime,
+    backend: &FakeServerBackend,
+    server_id: &str,
+    line: &str,
+) -> Result<(), String> {
+    backend.push_log(server_id, line);
+    let errors: Vec<String> =
+        crate::services::server_log_pipeline::plugin_line_events(server_id, line)
+            .iter()
+            .filter_map(|event| runtime.dispatch_server_event(event).err())
+            .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors.join("; "))
+    }
+}
+
+fn upgrade_runtime(runtime: &Weak<PluginRuntime>) -> mlua::Result<Arc<PluginRuntime>> {
+    runtime
+        .upgrade()
+        .ok_or_else(|| mlua::Error::runtime("测试运行时已销毁"))
+}
+
+fn assertion_prefix(message: Option<String>) -> String {
+    message.map(|m| format!("{}: ", m)).unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestCaseReport {
+    pub name: String,
+    pub passed: bool,
+    /// 用例执行过程中调用的断言次数
+    pub assertions: u32,
+    pub error: Option<String>,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TestFileReport {
+    /// 相对插件目录的路径，如 "test/commands.lua"
+    pub file: String,
+    pub cases: Vec<TestCaseReport>,
+    /// 加载插件或执行测试文件本身失败时的错误，此时没有用例结果
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginTestReport {
+    pub plugin_id: String,
+    /// manifest 声明了但测试中没有授予的权限
+    pub withheld_permissions: Vec<String>,
+    pub files: Vec<TestFileReport>,
+    pub passed: usize,
+    pub failed: usize,
+}
+
+/// 依次执行插件 test/ 目录下的 .lua 测试文件
+pub fn run_plugin_tests(plugin_dir: &Path) -> Result<PluginTestReport, String> {
+    let manifest = PluginLoader::load_manifest(plugin_dir)?;
+    let test_dir = plugin_dir.join(TEST_DIR_NAME);
+    let mut test_files: Vec<PathBuf> = fs::read_dir(&test_dir)
+        .map_err(|e| format!("读取测试目录 {} 失败: {}", test_dir.display(), e))?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "lua"))
+        .collect();
+    if test_files.is_empty() {
+        return Err(format!("{} 下没有 .lua 测试文件", test_dir.display()));
+    }
+    test_files.sort();
+
+    let files: Vec<TestFileReport> = test_files
+        .iter()
+        .map(|path| run_test_file(plugin_dir, path))
+        .collect();
+    let passed = files
+        .iter()
+        .flat_map(|f| &f.cases)
+        .filter(|c| c.passed)
+        .count();
+    let failed = files
+        .iter()
+        .map(|f| f.cases.iter().filter(|c| !c.passed).count() + usize::from(f.error.is_some()))
+        .sum();
+
+    let withheld_permissions = manifest
+        .permissions
+        .iter()
+        .filter(|p| is_withheld_in_tests(p))
+        .cloned()
+        .collect();
+
+    Ok(PluginTestReport {
+        plugin_id: manifest.id,
+        withheld_permissions,
+        files,
+        passed,
+        failed,
+    })
+}
+
+fn run_test_file(plugin_dir: &Path, path: &Path) -> TestFileReport {
+    let file = path
+        .strip_prefix(plugin_dir)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/");
+    let mut report = TestFileReport { file, cases: Vec::new(), error: None };
+
+    let harness = match PluginTestHarness::new(plugin_dir) {
+        Ok(harness) => harness,
+        Err(e) => {
+            report.error = Some(e);
+            return report;
+        }
+    };
+    if let Err(e) = harness.load().and_then(|_| harness.enable()) {
+        report.error = Some(e);
+        return report;
+    }
+
+    harness.assertions.store(0, Ordering::SeqCst);
+    let started = Instant::now();
+    match harness.load_test_file(path) {
+        Ok(cases) if cases.is_empty() => {
+            let name = path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default();
+            report.cases.push(TestCaseReport {
+                name,
+                passed: true,
+                assertions: harness.assertions.load(Ordering::SeqCst),
+                error: None,
+                duration_ms: started.elapsed().as_millis() as u64,
+            });
+        }
+        Ok(cases) => {
+            for (name, func) in &cases {
+                report.cases.push(harness.run_case(name, func));
+            }
+        }
+        Err(e) => report.error = Some(e),
+    }
+
+    let _ = harness.disable();
+    let _ = harness.unload();
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "sealantern_test_harness_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join(TEST_DIR_NAME)).unwrap();
+        dir
+    }
+
+    /// 创建一个在玩家加入时向服务器发送欢迎消息的插件，tests 为 test/ 下的文件名与内容
+    fn make_greeter_plugin(name: &str, tests: &[(&str, &str)]) -> PathBuf {
+        let root = make_temp_dir(name);
+        fs::write(
+            root.join("manifest.json"),
+            r#"{"id":"greeter","name":"Greeter","version":"1.0.0","description":"d",
+                "author":{"name":"a"},"main":"main.lua",
+                "permissions":["log","console","ui","network","execute_program"]}"#,
+        )
+        .unwrap();
+        fs::write(
+            root.join("main.lua"),
+            r#"
+            local plugin = {}
+            function plugin.onEnable()
+                sl.events.on("player.join", function(e)
+                    sl.console.send(e.server_id, "say welcome " .. e.player)
+                end)
+            end
+            return plugin
+            "#,
+        )
+        .unwrap();
+        for (file, content) in tests {
+            fs::write(root.join(TEST_DIR_NAME).join(file), content).unwrap();
+        }
+        root
+    }
+
+    #[test]
+    fn test_case_table_runs_each_function_in_name_order() {
+        let root = make_greeter_plugin(
+            "cases",
+            &[(
+                "join.lua",
+                r#"
+                return {
+                    greets_player = function()
+                        sl.test.add_server("s1")
+                        sl.test.server_log("s1", "[12:00:00] [Server thread/INFO]: Steve joined the game")
+                        local sent = sl.test.sent_commands()
+                        sl.test.assert_eq(#sent, 1)
+                        sl.test.assert_eq(sent[1].command, "say welcome Steve")
+                    end,
+                    records_log = function()
+                        sl.log.info("hello")
+                        sl.test.assert_eq(sl.test.events("log")[1].data, "hello")
+                    end,
+                }
+                "#,
+            )],
+        );
+
+        let report = run_plugin_tests(&root).unwrap();
+        assert_eq!(report.plugin_id, "greeter");
+        assert_eq!(report.files.len(), 1);
+        let file = &report.files[0];
+        assert!(file.error.is_none(), "{:?}", file.error);
+        let names: Vec<&str> = file.cases.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["greets_player", "records_log"]);
+        assert!(file.cases.iter().all(|c| c.passed), "{:?}", file.cases);
+        assert_eq!(file.cases[0].assertions, 2);
+        assert_eq!((report.passed, report.failed), (2, 0));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_failed_assertion_is_reported_with_its_message() {
+        let root = make_greeter_plugin(
+            "failure",
+            &[(
+                "math.lua",
+                r#"
+                return {
+                    fails = function() sl.test.assert_eq(1, 2, "numbers") end,
+                    passes = function() sl.test.assert_true(true) end,
+                }
+                "#,
+            )],
+        );
+
+        let report = run_plugin_tests(&root).unwrap();
+        let cases = &report.files[0].cases;
+        assert!(!cases[0].passed);
+        assert!(cases[0].error.as_deref().unwrap().contains("numbers"));
+        assert!(cases[1].passed);
+        assert_eq!((report.passed, report.failed), (1, 1));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_each_file_runs_in_its_own_runtime() {
+        let root = make_greeter_plugin(
+            "files",
+            &[
+                ("a_plain.lua", "SHARED = 1 sl.test.assert_eq(1, 1)"),
+                ("b_isolated.lua", "sl.test.assert_eq(SHARED, nil)"),
+                ("c_broken.lua", "error('boom')"),
+            ],
+        );
+
+        let report = run_plugin_tests(&root).unwrap();
+        let files: Vec<&str> = report.files.iter().map(|f| f.file.as_str()).collect();
+        assert_eq!(files, vec!["test/a_plain.lua", "test/b_isolated.lua", "test/c_broken.lua"]);
+        assert_eq!(report.files[0].cases[0].name, "a_plain");
+        assert_eq!(report.files[0].cases[0].assertions, 1);
+        assert!(report.files[1].error.is_none(), "{:?}", report.files[1].error);
+        assert!(report.files[2].error.as_deref().unwrap().contains("boom"));
+        assert_eq!((report.passed, report.failed), (2, 1));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_missing_lua_test_files_is_an_error() {
+        let root = make_greeter_plugin("empty", &[("notes.txt", "not a test")]);
+        let err = run_plugin_tests(&root).unwrap_err();
+        assert!(err.contains("没有 .lua 测试文件"), "{}", err);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_withheld_permissions_are_reported_and_not_granted() {
+        let root = make_greeter_plugin("withheld", &[("noop.lua", "")]);
+        let report = run_plugin_tests(&root).unwrap();
+        assert_eq!(report.withheld_permissions, vec!["network", "execute_program"]);
+
+        let harness = PluginTestHarness::new(&root).unwrap();
+        harness.load().unwrap();
+        let lua = &harness.runtime.lua;
+        assert!(lua.load("sl.http.get('http://127.0.0.1/')").exec().is_err());
+        assert!(lua.load("sl.process.exec('echo')").exec().is_err());
+        lua.load("sl.log.info('still logging')").exec().unwrap();
+        assert!(harness.events().iter().any(|e| e.data == "still logging"));
+        drop(harness);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_rust_harness_dispatches_player_events_from_log_lines() {
+        let root = make_greeter_plugin("rust", &[]);
+        let harness = PluginTestHarness::new(&root).unwrap();
+        harness.load().unwrap();
+        harness.enable().unwrap();
+        harness.add_server("s2").unwrap();
+        harness
+            .server_log("s2", "[12:00:01] [Server thread/INFO]: Alex joined the game")
+            .unwrap();
+        let sent = harness.backend().sent_commands();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].server_id, "s2");
+        assert_eq!(sent[0].command, "say welcome Alex");
+        drop(harness);
+        let _ = fs::remove_dir_all(&root);
+    }
+}
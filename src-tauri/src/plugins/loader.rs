@@ -1,4 +1,4 @@
-use crate::models::plugin::PluginManifest;
+use crate::models::plugin::{PluginManifest, SemVer};
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -41,6 +41,40 @@ impl PluginLoader {
         Ok(manifest)
     }
 
+    /// 检查 manifest.engines.sealantern 是否接受当前应用版本，不兼容时返回原因
+    pub fn check_engine_compatibility(
+        manifest: &PluginManifest,
+        app_version: &str,
+    ) -> Result<(), String> {
+        let Some(requirement) = manifest
+            .engines
+            .as_ref()
+            .and_then(|e| e.sealantern.as_deref())
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+        else {
+            return Ok(());
+        };
+
+        if SemVer::parse(app_version).is_some_and(|v| v.satisfies(requirement)) {
+            Ok(())
+        } else {
+            Err(format!(
+                "插件 '{}' 需要 SeaLantern {}，当前版本为 {}",
+                manifest.name, requirement, app_version
+            ))
+        }
+    }
+
+    /// 按当前版本检查兼容性；开发者模式下开启“允许不兼容插件”时跳过
+    pub fn ensure_engine_compatible(manifest: &PluginManifest) -> Result<(), String> {
+        let settings = crate::services::global::settings_manager().get();
+        if settings.developer_mode && settings.allow_incompatible_plugins {
+            return Ok(());
+        }
+        Self::check_engine_compatibility(manifest, env!("CARGO_PKG_VERSION"))
+    }
+
     pub fn validate_manifest(manifest: &PluginManifest) -> Result<(), String> {
         if manifest.id.trim().is_empty() {
             return Err("Manifest field 'id' is required".into());
@@ -104,6 +138,42 @@ impl PluginLoader {
             }
         }
 
+        for pattern in &manifest.allowed_hosts {
+            crate::plugins::runtime::validate_host_pattern(pattern)
+                .map_err(|e| format!("Plugin '{}': {}", manifest.id, e))?;
+        }
+
+        let mut command_ids = std::collections::HashSet::new();
+        for command in &manifest.commands {
+            if command.id.is_empty()
+                || !command
+                    .id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            {
+                return Err(format!(
+                    "Plugin '{}': command id '{}' is invalid. Only alphanumeric, '-', '_' and '.' are allowed.",
+                    manifest.id, command.id
+                ));
+            }
+            if !command_ids.insert(command.id.as_str()) {
+                return Err(format!(
+                    "Plugin '{}': duplicate command id '{}'",
+                    manifest.id, command.id
+                ));
+            }
+            if let Some(shortcut) = &command.shortcut {
+                crate::plugins::commands::normalize_shortcut(shortcut).map_err(|e| {
+                    format!("Plugin '{}': command '{}' {}", manifest.id, command.id, e)
+                })?;
+            }
+        }
+
+        for module in &manifest.exports {
+            crate::plugins::runtime::normalize_module_name(module)
+                .map_err(|e| format!("Plugin '{}': exports 中{}", manifest.id, e))?;
+        }
+
         Ok(())
     }
 }
@@ -198,6 +268,7 @@ mod tests {
             repository: None,
             engines: None,
             permissions: vec![],
+            allowed_hosts: vec![],
             ui: None,
             events: vec![],
             commands: vec![],
@@ -208,6 +279,7 @@ mod tests {
             sidebar: None,
             locales: None,
             include: vec![],
+            exports: vec![],
         };
         assert!(PluginLoader::validate_manifest(&manifest).is_ok());
     }
@@ -230,6 +302,7 @@ mod tests {
             repository: None,
             engines: None,
             permissions: vec![],
+            allowed_hosts: vec![],
             ui: None,
             events: vec![],
             commands: vec![],
@@ -240,8 +313,23 @@ mod tests {
             sidebar: None,
             locales: None,
             include: vec![],
+            exports: vec![],
         };
         let err = PluginLoader::validate_manifest(&manifest).unwrap_err();
         assert!(err.contains("id"));
     }
+
+    #[test]
+    fn test_check_engine_compatibility() {
+        let mut manifest: PluginManifest = serde_json::from_str(sample_manifest_json()).unwrap();
+        assert!(PluginLoader::check_engine_compatibility(&manifest, "1.2.0").is_ok());
+
+        manifest.engines = Some(crate::models::plugin::PluginEngines {
+            sealantern: Some(">= 1.0.0 <2.0.0 || ^3.1.0".into()),
+        });
+        assert!(PluginLoader::check_engine_compatibility(&manifest, "1.2.0").is_ok());
+        assert!(PluginLoader::check_engine_compatibility(&manifest, "3.4.1").is_ok());
+        assert!(PluginLoader::check_engine_compatibility(&manifest, "2.0.0").is_err());
+        assert!(PluginLoader::check_engine_compatibility(&manifest, "0.9.0").is_err());
+    }
 }
@@ -0,0 +1,509 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// 插件日志持久化：
+// 1) 启用插件时登记 插件 id → 日志文件，sl.log.* 与权限日志写入 <应用数据目录>/plugin_logs/<id>.db。
+//    日志有意不放在插件数据目录下：sl.fs 可以在数据目录内任意改名、覆盖和删除文件，
+//    放在那里插件就能篡改或清空自己的权限日志；独立目录下 sl.fs 与 sl.db 都无法读写
+// 2) 每个插件一把锁、一个缓存的连接，写入只会等待同一插件的其他日志；插件 cleanup 或删除时关闭连接
+// 3) 每张表最多保留 MAX_ROWS 条，每写入 PRUNE_INTERVAL 条清理一次最旧的记录
+// 4) sl.log.* 记录调用位置（如 main.lua:12）；查询支持按级别 / 关键字过滤、向前翻页与按 id 增量拉取
+// 5) 诊断包为 zip：manifest、设置（疑似密钥的值已打码）、插件信息、最近的插件日志与权限日志
+
+const MAX_ROWS: i64 = 20_000;
+const PRUNE_INTERVAL: u32 = 500;
+const DEFAULT_QUERY_LIMIT: usize = 200;
+const MAX_QUERY_LIMIT: usize = 5_000;
+const DIAGNOSTIC_LOG_LIMIT: usize = 2_000;
+const SECRET_KEY_HINTS: &[&str] = &["password", "passwd", "secret", "token", "apikey", "api_key"];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginLogEntry {
+    pub id: i64,
+    /// 毫秒时间戳
+    pub timestamp: u64,
+    pub level: String,
+    pub message: String,
+    /// 调用位置，如 "main.lua:12"；宿主代替插件记录的日志没有位置
+    pub source: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionLogEntry {
+    pub id: i64,
+    pub timestamp: u64,
+    pub log_type: String,
+    pub action: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PluginLogQuery {
+    /// 为空时不过滤级别
+    #[serde(default)]
+    pub levels: Vec<String>,
+    #[serde(default)]
+    pub search: Option<String>,
+    /// 只返回 id 小于它的记录，用于向前翻页
+    #[serde(default)]
+    pub before_id: Option<i64>,
+    /// 只返回 id 大于它的记录（从旧到新），用于增量拉取
+    #[serde(default)]
+    pub after_id: Option<i64>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+struct CachedConnection {
+    conn: Connection,
+    writes_since_prune: u32,
+}
+
+/// 已登记插件的日志文件与缓存的连接
+struct PluginLogFile {
+    path: PathBuf,
+    conn: Mutex<Option<CachedConnection>>,
+}
+
+static LOG_FILES: OnceLock<RwLock<HashMap<String, Arc<PluginLogFile>>>> = OnceLock::new();
+
+fn log_files() -> &'static RwLock<HashMap<String, Arc<PluginLogFile>>> {
+    LOG_FILES.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+fn log_file(plugin_id: &str) -> Option<Arc<PluginLogFile>> {
+    log_files()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(plugin_id)
+        .cloned()
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// 登记插件的日志文件，之后该插件的日志才会被持久化；已登记时保留现有连接
+pub fn register_plugin(plugin_id: &str, path: &Path) {
+    let mut files = log_files().write().unwrap_or_else(|e| e.into_inner());
+    if files.get(plugin_id).is_some_and(|file| file.path == path) {
+        return;
+    }
+    files.insert(
+        plugin_id.to_string(),
+        Arc::new(PluginLogFile {
+            path: path.to_path_buf(),
+            conn: Mutex::new(None),
+        }),
+    );
+}
+
+pub fn unregister_plugin(plugin_id: &str) {
+    close(plugin_id);
+    log_files()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(plugin_id);
+}
+
+/// 关闭缓存的连接，下次写入时重新打开
+pub fn close(plugin_id: &str) {
+    if let Some(file) = log_file(plugin_id) {
+        file.conn.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+}
+
+/// 删除日志文件及 WAL 附属文件
+pub fn remove_log_file(path: &Path) -> Result<(), String> {
+    for suffix in ["", "-wal", "-shm"] {
+        let mut name = path.as_os_str().to_os_string();
+        name.push(suffix);
+        let file = PathBuf::from(name);
+        if file.exists() {
+            fs::remove_file(&file).map_err(|e| format!("删除插件日志失败: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+fn open(path: &Path) -> Result<Connection, String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建插件日志目录失败: {}", e))?;
+    }
+    let conn = Connection::open(path).map_err(|e| format!("打开插件日志数据库失败: {}", e))?;
+    conn.busy_timeout(Duration::from_millis(2000))
+        .map_err(|e| format!("设置日志数据库参数失败: {}", e))?;
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| format!("设置日志数据库参数失败: {}", e))?;
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS plugin_logs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            level TEXT NOT NULL,
+            message TEXT NOT NULL,
+            source TEXT
+        );
+        CREATE TABLE IF NOT EXISTS permission_logs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            timestamp INTEGER NOT NULL,
+            log_type TEXT NOT NULL,
+            action TEXT NOT NULL,
+            detail TEXT NOT NULL
+        );",
+    )
+    .map_err(|e| format!("初始化插件日志数据库失败: {}", e))?;
+    Ok(conn)
+}
+
+/// 查询用的连接；日志文件不存在时返回 None，不为从未写过日志的插件创建文件
+fn open_existing(path: &Path) -> Result<Option<Connection>, String> {
+    if !path.is_file() {
+        return Ok(None);
+    }
+    open(path).map(Some)
+}
+
+fn with_connection(
+    plugin_id: &str,
+    f: impl FnOnce(&Connection) -> rusqlite::Result<usize>,
+) -> Result<(), String> {
+    let Some(file) = log_file(plugin_id) else {
+        return Ok(());
+    };
+
+    let mut guard = file.conn.lock().unwrap_or_else(|e| e.into_inner());
+    if guard.is_none() {
+        let conn = open(&file.path)?;
+        *guard = Some(CachedConnection { conn, writes_since_prune: 0 });
+    }
+    let Some(cached) = guard.as_mut() else {
+        return Ok(());
+    };
+
+    f(&cached.conn).map_err(|e| format!("写入插件日志失败: {}", e))?;
+    cached.writes_since_prune += 1;
+    if cached.writes_since_prune >= PRUNE_INTERVAL {
+        cached.writes_since_prune = 0;
+        for table in ["plugin_logs", "permission_logs"] {
+            let _ = cached.conn.execute(
+                &format!("DELETE FROM {0} WHERE id <= (SELECT MAX(id) FROM {0}) - ?1", table),
+                params![MAX_ROWS],
+            );
+        }
+    }
+    Ok(())
+}
+
+pub fn append_log(plugin_id: &str, level: &str, message: &str, source: Option<&str>) {
+    let result = with_connection(plugin_id, |conn| {
+        conn.execute(
+            "INSERT INTO plugin_logs (timestamp, level, message, source) VALUES (?1, ?2, ?3, ?4)",
+            params![now_millis() as i64, level, message, source],
+        )
+    });
+    if let Err(e) = result {
+        eprintln!("[WARN] 保存插件 '{}' 的日志失败: {}", plugin_id, e);
+    }
+}
+
+pub fn append_permission_log(
+    plugin_id: &str,
+    log_type: &str,
+    action: &str,
+    detail: &str,
+    timestamp: u64,
+) {
+    let result = with_connection(plugin_id, |conn| {
+        conn.execute(
+            "INSERT INTO permission_logs (timestamp, log_type, action, detail) VALUES (?1, ?2, ?3, ?4)",
+            params![timestamp as i64, log_type, action, detail],
+        )
+    });
+    if let Err(e) = result {
+        eprintln!("[WARN] 保存插件 '{}' 的权限日志失败: {}", plugin_id, e);
+    }
+}
+
+/// 按条件查询插件日志，结果从旧到新排列
+pub fn query_logs(path: &Path, query: &PluginLogQuery) -> Result<Vec<PluginLogEntry>, String> {
+    let Some(conn) = open_existing(path)? else {
+        return Ok(Vec::new());
+    };
+
+    let mut conditions = Vec::new();
+    let mut values: Vec<rusqlite::types::Value> = Vec::new();
+    if !query.levels.is_empty() {
+        let placeholders = vec!["?"; query.levels.len()].join(", ");
+        conditions.push(format!("level IN ({})", placeholders));
+        values.extend(query.levels.iter().cloned().map(Into::into));
+    }
+    if let Some(search) = query.search.as_deref().filter(|s| !s.is_empty()) {
+        let escaped = search
+            .replace('\\', "\\\\")
+            .replace('%', "\\%")
+            .replace('_', "\\_");
+        conditions.push("(message LIKE ? ESCAPE '\\' OR source LIKE ? ESCAPE '\\')".to_string());
+        values.push(format!("%{}%", escaped).into());
+        values.push(format!("%{}%", escaped).into());
+    }
+    if let Some(before_id) = query.before_id {
+        conditions.push("id < ?".to_string());
+        values.push(before_id.into());
+    }
+    if let Some(after_id) = query.after_id {
+        conditions.push("id > ?".to_string());
+        values.push(after_id.into());
+    }
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_QUERY_LIMIT)
+        .clamp(1, MAX_QUERY_LIMIT);
+
+    // 增量拉取从 after_id 往后取最早的若干条，其余情况取最新的若干条
+    let order = if query.after_id.is_some() {
+        "ASC"
+    } else {
+        "DESC"
+    };
+    let where_clause = if conditions.is_empty() {
+        String::new()
+    } else {
+        format!("WHERE {}", conditions.join(" AND "))
+    };
+    let sql = format!(
+        "SELECT id, timestamp, level, message, source FROM plugin_logs {} ORDER BY id {} LIMIT {}",
+        where_clause, order, limit
+    );
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("查询插件日志失败: {}", e))?;
+    let mut entries = stmt
+        .query_map(rusqlite::params_from_iter(values), |row| {
+            Ok(PluginLogEntry {
+                id: row.get(0)?,
+                timestamp: row.get::<_, i64>(1)? as u64,
+                level: row.get(2)?,
+                message: row.get(3)?,
+                source: row.get(4)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("查询插件日志失败: {}", e))?;
+    if query.after_id.is_none() {
+        entries.reverse();
+    }
+    Ok(entries)
+}
+
+/// 最近的权限日志，从旧到新排列
+pub fn recent_permission_logs(
+    path: &Path,
+    limit: usize,
+) -> Result<Vec<PermissionLogEntry>, String> {
+    let Some(conn) = open_existing(path)? else {
+        return Ok(Vec::new());
+    };
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, timestamp, log_type, action, detail FROM permission_logs
+             ORDER BY id DESC LIMIT ?1",
+        )
+        .map_err(|e| format!("查询权限日志失败: {}", e))?;
+    let mut entries = stmt
+        .query_map(params![limit as i64], |row| {
+            Ok(PermissionLogEntry {
+                id: row.get(0)?,
+                timestamp: row.get::<_, i64>(1)? as u64,
+                log_type: row.get(2)?,
+                action: row.get(3)?,
+                detail: row.get(4)?,
+            })
+        })
+        .and_then(|rows| rows.collect::<rusqlite::Result<Vec<_>>>())
+        .map_err(|e| format!("查询权限日志失败: {}", e))?;
+    entries.reverse();
+    Ok(entries)
+}
+
+/// 清空插件日志与权限日志
+pub fn clear_logs(plugin_id: &str, path: &Path) -> Result<(), String> {
+    // 持有该插件的日志锁，清空期间不会有新日志写入
+    let file = log_file(plugin_id);
+    let _guard = file.as_ref().map(|file| {
+        let mut guard = file.conn.lock().unwrap_or_else(|e| e.into_inner());
+        guard.take();
+        guard
+    });
+    if !path.is_file() {
+        return Ok(());
+    }
+    let conn = open(path)?;
+    conn.execute_batch("DELETE FROM plugin_logs; DELETE FROM permission_logs;")
+        .map_err(|e| format!("清空插件日志失败: {}", e))
+}
+
+/// 把疑似密钥的设置值替换为 "***"
+fn redact_settings(settings: &JsonValue) -> JsonValue {
+    match settings {
+        JsonValue::Object(map) => JsonValue::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let lower = key.to_lowercase();
+                    let value = if SECRET_KEY_HINTS.iter().any(|hint| lower.contains(hint))
+                        && !value.is_null()
+                    {
+                        JsonValue::String("***".to_string())
+                    } else {
+                        redact_settings(value)
+                    };
+                    (key.clone(), value)
+                })
+                .collect(),
+        ),
+        JsonValue::Array(items) => JsonValue::Array(items.iter().map(redact_settings).collect()),
+        other => other.clone(),
+    }
+}
+
+fn pretty_json<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, String> {
+    serde_json::to_vec_pretty(value).map_err(|e| format!("序列化诊断信息失败: {}", e))
+}
+
+/// 生成插件诊断包
+pub fn write_diagnostic_bundle(
+    output: &Path,
+    plugin_dir: &Path,
+    log_path: &Path,
+    settings: &JsonValue,
+    info: &JsonValue,
+) -> Result<(), String> {
+    let logs = query_logs(
+        log_path,
+        &PluginLogQuery {
+            limit: Some(DIAGNOSTIC_LOG_LIMIT),
+            ..Default::default()
+        },
+    )?;
+    let permission_logs = recent_permission_logs(log_path, DIAGNOSTIC_LOG_LIMIT)?;
+    let manifest = fs::read(plugin_dir.join("manifest.json"))
+        .map_err(|e| format!("读取 manifest.json 失败: {}", e))?;
+
+    let files: Vec<(&str, Vec<u8>)> = vec![
+        ("manifest.json", manifest),
+        ("settings.json", pretty_json(&redact_settings(settings))?),
+        ("plugin_info.json", pretty_json(info)?),
+        ("logs.json", pretty_json(&logs)?),
+        ("permission_logs.json", pretty_json(&permission_logs)?),
+    ];
+
+    if let Some(parent) = output.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("创建输出目录失败: {}", e))?;
+    }
+    let file = File::create(output).map_err(|e| format!("创建诊断包失败: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+    for (name, content) in files {
+        zip.start_file(name, options)
+            .map_err(|e| format!("写入诊断包失败: {}", e))?;
+        zip.write_all(&content)
+            .map_err(|e| format!("写入诊断包失败: {}", e))?;
+    }
+    zip.finish().map_err(|e| format!("写入诊断包失败: {}", e))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_persistence_query_and_bundle() {
+        let root = std::env::temp_dir()
+            .join(format!("sealantern_test_plugin_logs_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let log_path = root.join("plugin_logs/logger-test.db");
+        let plugin_dir = root.join("plugin");
+        fs::create_dir_all(&plugin_dir).unwrap();
+        fs::write(plugin_dir.join("manifest.json"), r#"{"id":"logger"}"#).unwrap();
+
+        append_log("logger-test", "info", "dropped before register", None);
+        register_plugin("logger-test", &log_path);
+        append_log("logger-test", "info", "started", Some("main.lua:3"));
+        append_log("logger-test", "warn", "disk 100% full", Some("main.lua:9"));
+        append_log("logger-test", "error", "boom", None);
+        append_permission_log("logger-test", "api_call", "sl.events.on", "server.log", 1);
+
+        let all = query_logs(&log_path, &PluginLogQuery::default()).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all[0].source.as_deref(), Some("main.lua:3"));
+
+        let errors = query_logs(
+            &log_path,
+            &PluginLogQuery {
+                levels: vec!["error".to_string()],
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(errors.len(), 1);
+        let searched = query_logs(
+            &log_path,
+            &PluginLogQuery {
+                search: Some("100%".to_string()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(searched.len(), 1);
+        assert_eq!(searched[0].level, "warn");
+
+        let tail = query_logs(
+            &log_path,
+            &PluginLogQuery {
+                after_id: Some(all[0].id),
+                limit: Some(1),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(tail.len(), 1);
+        assert_eq!(tail[0].message, "disk 100% full");
+
+        let bundle = root.join("out/diag.zip");
+        let settings = serde_json::json!({ "api_token": "abc", "interval": 5 });
+        write_diagnostic_bundle(&bundle, &plugin_dir, &log_path, &settings, &serde_json::json!({}))
+            .unwrap();
+        let mut archive = zip::ZipArchive::new(File::open(&bundle).unwrap()).unwrap();
+        assert_eq!(archive.len(), 5);
+        let mut saved_settings = String::new();
+        std::io::Read::read_to_string(
+            &mut archive.by_name("settings.json").unwrap(),
+            &mut saved_settings,
+        )
+        .unwrap();
+        assert!(saved_settings.contains("***") && !saved_settings.contains("abc"));
+        assert_eq!(recent_permission_logs(&log_path, 10).unwrap().len(), 1);
+
+        clear_logs("logger-test", &log_path).unwrap();
+        assert!(query_logs(&log_path, &PluginLogQuery::default())
+            .unwrap()
+            .is_empty());
+
+        unregister_plugin("logger-test");
+        remove_log_file(&log_path).unwrap();
+        assert!(!log_path.exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -1,23 +1,49 @@
 use crate::models::plugin::{
-    MissingDependency, PluginDependency, PluginInfo, PluginInstallResult, PluginState,
+    get_permission_danger_level, MissingDependency, PermissionDangerLevel, PluginDependency,
+    PluginInfo, PluginInstallResult, PluginSignature, PluginState, SignatureStatus,
 };
 use crate::plugins::api::{
-    emit_log_event, emit_ui_event, new_api_registry, ApiRegistry, ApiRegistryOps,
+    emit_log_event, emit_ui_event, new_api_registry, take_ui_event_snapshot, ApiRegistry,
+    ApiRegistryOps, BufferedUiEvent,
+};
+use crate::plugins::commands::{collect_commands, resolve_command, CommandEntry, CommandTarget};
+use crate::plugins::grants::{
+    GrantDecision, PermissionAuditEntry, PermissionGrant, PermissionGrantStore,
 };
 use crate::plugins::loader::PluginLoader;
-use crate::plugins::runtime::{kill_all_processes, PluginRuntime};
+use crate::plugins::packager;
+use crate::plugins::runtime::{kill_all_processes, PluginWorker};
+use crate::plugins::settings;
+use crate::plugins::signature::{load_signature_store, save_signature_store};
+use crate::plugins::updater::{
+    backup_dir, has_critical_permission, keeps_signature_trust, move_dir, remove_dir_if_exists,
+    staging_dir, PluginUpdateResult, UpdatePolicy, UpdateStore,
+};
 use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::{self, Read};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
-pub type SharedRuntimes = Arc<RwLock<HashMap<String, PluginRuntime>>>;
+/// 运行时句柄以 Arc 共享：调用方取出句柄后先释放锁再调用，避免慢插件卡住整张表
+pub type SharedRuntimes = Arc<RwLock<HashMap<String, Arc<PluginWorker>>>>;
 
 pub fn new_shared_runtimes() -> SharedRuntimes {
     Arc::new(RwLock::new(HashMap::new()))
 }
 
+/// 取出插件的运行时句柄，读锁在返回前释放
+pub fn get_runtime(runtimes: &SharedRuntimes, plugin_id: &str) -> Option<Arc<PluginWorker>> {
+    runtimes
+        .read()
+        .unwrap_or_else(|e| {
+            eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
+            e.into_inner()
+        })
+        .get(plugin_id)
+        .cloned()
+}
+
 pub struct PluginManager {
     plugins: HashMap<String, PluginInfo>,
 
@@ -27,11 +53,23 @@ pub struct PluginManager {
 
     data_dir: PathBuf,
 
+    /// 插件日志目录，与插件数据目录分开，插件无法通过 sl.fs 访问
+    logs_dir: PathBuf,
+
     api_registry: ApiRegistry,
+
+    signatures: HashMap<String, PluginSignature>,
+
+    grants: PermissionGrantStore,
+
+    updates: UpdateStore,
+
+    /// 签名记录文件不存在（刚从旧版本升级）时，已安装的插件视为用户此前已信任
+    trust_unrecorded_plugins: bool,
 }
 
 impl PluginManager {
-    pub fn new(plugins_dir: PathBuf, data_dir: PathBuf) -> Self {
+    pub fn new(plugins_dir: PathBuf, data_dir: PathBuf, logs_dir: PathBuf) -> Self {
         if let Err(e) = fs::create_dir_all(&plugins_dir) {
             eprintln!("[ERROR] Failed to create plugins directory: {}", e);
         }
@@ -39,12 +77,25 @@ impl PluginManager {
             eprintln!("[ERROR] Failed to create data directory: {}", e);
         }
 
+        let (signatures, trust_unrecorded_plugins) = match load_signature_store(&data_dir) {
+            Some(store) => (store, false),
+            None => (HashMap::new(), true),
+        };
+
+        let grants = PermissionGrantStore::load(&data_dir);
+        let updates = UpdateStore::load(&data_dir);
+
         Self {
             plugins: HashMap::new(),
             runtimes: new_shared_runtimes(),
             plugins_dir,
             data_dir,
+            logs_dir,
             api_registry: new_api_registry(),
+            signatures,
+            grants,
+            updates,
+            trust_unrecorded_plugins,
         }
     }
 
@@ -58,11 +109,16 @@ impl PluginManager {
 
     pub fn scan_plugins(&mut self) -> Result<Vec<PluginInfo>, String> {
         {
-            let mut runtimes = self.runtimes.write().unwrap_or_else(|e| {
-                eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
-                e.into_inner()
-            });
-            for (id, runtime) in runtimes.drain() {
+            let drained: Vec<(String, Arc<PluginWorker>)> = self
+                .runtimes
+                .write()
+                .unwrap_or_else(|e| {
+                    eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
+                    e.into_inner()
+                })
+                .drain()
+                .collect();
+            for (id, runtime) in drained {
                 kill_all_processes(&runtime.process_registry);
                 if let Err(e) = runtime.call_lifecycle("onDisable") {
                     eprintln!(
@@ -87,18 +143,37 @@ impl PluginManager {
             match PluginLoader::load_manifest(plugin_dir) {
                 Ok(manifest) => {
                     let state = match PluginLoader::validate_manifest(&manifest) {
-                        Ok(()) => PluginState::Loaded,
+                        Ok(()) => match PluginLoader::ensure_engine_compatible(&manifest) {
+                            Ok(()) => PluginState::Loaded,
+                            Err(e) => PluginState::Incompatible(e),
+                        },
                         Err(e) => {
                             eprintln!("Invalid manifest in {}: {}", plugin_dir.display(), e);
                             PluginState::Error(e)
                         }
                     };
 
+                    if self.trust_unrecorded_plugins {
+                        self.signatures
+                            .entry(manifest.id.clone())
+                            .or_insert_with(|| PluginSignature {
+                                critical_confirmed: true,
+                                ..Default::default()
+                            });
+                    }
+
+                    self.grants.seed(&manifest.id, &manifest.permissions);
+
                     let plugin_info = PluginInfo {
                         manifest: manifest.clone(),
                         state,
                         path: plugin_dir.to_string_lossy().to_string(),
                         missing_dependencies: Vec::new(),
+                        signature: self
+                            .signatures
+                            .get(&manifest.id)
+                            .cloned()
+                            .unwrap_or_default(),
                     };
 
                     self.plugins.insert(manifest.id.clone(), plugin_info);
@@ -109,6 +184,12 @@ impl PluginManager {
             }
         }
 
+        if self.trust_unrecorded_plugins {
+            self.trust_unrecorded_plugins = false;
+            save_signature_store(&self.data_dir, &self.signatures);
+        }
+        self.grants.finish_migration();
+
         self.update_all_missing_dependencies();
 
         Ok(self.plugins.values().cloned().collect())
@@ -140,6 +221,8 @@ impl PluginManager {
             return Ok(());
         }
 
+        PluginLoader::ensure_engine_compatible(&plugin_info.manifest)?;
+
         let missing_deps = self.check_dependencies(&plugin_info.manifest.dependencies);
         if !missing_deps.is_empty() {
             return Err(format!(
@@ -149,6 +232,25 @@ impl PluginManager {
             ));
         }
 
+        if plugin_info.signature.status == SignatureStatus::Unsigned
+            && !plugin_info.signature.critical_confirmed
+        {
+            let critical: Vec<&str> = plugin_info
+                .manifest
+                .permissions
+                .iter()
+                .map(String::as_str)
+                .filter(|p| get_permission_danger_level(p) == PermissionDangerLevel::Critical)
+                .collect();
+            if !critical.is_empty() {
+                return Err(format!(
+                    "插件 '{}' 未签名且申请了高危权限（{}），请先确认信任该插件后再启用",
+                    plugin_info.manifest.name,
+                    critical.join(", ")
+                ));
+            }
+        }
+
         let missing_optional = self.check_dependencies(&plugin_info.manifest.optional_dependencies);
         if !missing_optional.is_empty() {
             eprintln!(
@@ -169,9 +271,15 @@ impl PluginManager {
             )?;
         }
 
-        let permissions = plugin_info.manifest.permissions.clone();
+        // 新安装或 manifest 变更后补齐授予记录，运行时只拿到已授予的权限
+        self.grants
+            .seed(plugin_id, &plugin_info.manifest.permissions);
+        let permissions = self
+            .grants
+            .effective_permissions(plugin_id, &plugin_info.manifest.permissions);
 
-        let runtime = PluginRuntime::new(
+        crate::plugins::logs::register_plugin(plugin_id, &self.plugin_log_path(plugin_id));
+        let runtime = PluginWorker::spawn(
             plugin_id,
             &plugin_dir,
             &plugin_data_dir,
@@ -184,43 +292,46 @@ impl PluginManager {
 
         runtime.call_lifecycle("onLoad")?;
 
+        // 更新后首次启用：让插件迁移旧版本留下的数据，失败视为启用失败
+        if let Some(from_version) = self.updates.pending_migration(plugin_id) {
+            if let Err(e) = runtime.call_lifecycle_with_arg("onMigrate", &from_version) {
+                let _ = emit_log_event(plugin_id, "error", &format!("onMigrate 执行失败: {}", e));
+                runtime.cleanup();
+                kill_all_processes(&runtime.process_registry);
+                return Err(format!("插件从 {} 迁移数据失败: {}", from_version, e));
+            }
+        }
+
         {
             let mut runtimes = self.runtimes.write().unwrap_or_else(|e| {
                 eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
                 e.into_inner()
             });
-            runtimes.insert(plugin_id.to_string(), runtime);
+            runtimes.insert(plugin_id.to_string(), Arc::new(runtime));
         }
 
-        let enable_result = {
-            let runtimes = self.runtimes.read().unwrap_or_else(|e| {
-                eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
-                e.into_inner()
-            });
-
-            if let Some(r) = runtimes.get(plugin_id) {
-                r.call_lifecycle("onEnable")
-            } else {
-                Err("Runtime not found after insertion".to_string())
-            }
+        let enable_result = match get_runtime(&self.runtimes, plugin_id) {
+            Some(r) => r.call_lifecycle("onEnable"),
+            None => Err("Runtime not found after insertion".to_string()),
         };
 
         if let Err(e) = enable_result {
             let error_msg = format!("Failed to call onEnable: {}", e);
             let _ = emit_log_event(plugin_id, "error", &error_msg);
 
-            {
-                let mut runtimes = self.runtimes.write().unwrap_or_else(|e| {
+            let removed = self
+                .runtimes
+                .write()
+                .unwrap_or_else(|e| {
                     eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
                     e.into_inner()
-                });
-                if let Some(runtime) = runtimes.get_mut(plugin_id) {
-                    let _ = runtime.call_lifecycle("onDisable");
-                    let _ = runtime.call_lifecycle("onUnload");
-                    runtime.cleanup();
-                    kill_all_processes(&runtime.process_registry);
-                }
-                runtimes.remove(plugin_id);
+                })
+                .remove(plugin_id);
+            if let Some(runtime) = removed {
+                let _ = runtime.call_lifecycle("onDisable");
+                let _ = runtime.call_lifecycle("onUnload");
+                runtime.cleanup();
+                kill_all_processes(&runtime.process_registry);
             }
             return Err(format!("Failed to enable plugin: {}", e));
         }
@@ -228,14 +339,95 @@ impl PluginManager {
         if let Some(info) = self.plugins.get_mut(plugin_id) {
             info.state = PluginState::Enabled;
         }
+        self.updates.set_pending_migration(plugin_id, None);
 
         self.update_all_missing_dependencies();
 
         self.save_enabled_plugins();
 
+        self.prompt_pending_permissions(plugin_id);
+
         Ok(())
     }
 
+    fn pending_permissions(&self, plugin_id: &str) -> Vec<String> {
+        let Some(info) = self.plugins.get(plugin_id) else {
+            return Vec::new();
+        };
+        info.manifest
+            .permissions
+            .iter()
+            .filter(|p| self.grants.decision(plugin_id, p) == Some(GrantDecision::Ask))
+            .cloned()
+            .collect()
+    }
+
+    fn prompt_pending_permissions(&self, plugin_id: &str) {
+        let pending = self.pending_permissions(plugin_id);
+        if !pending.is_empty() {
+            if let Err(e) = crate::plugins::api::emit_permission_prompt(plugin_id, &pending) {
+                eprintln!("[WARN] Failed to emit permission prompt for '{}': {}", plugin_id, e);
+            }
+        }
+    }
+
+    /// 启动期间自动启用的插件产生的权限请求早于前端监听，由前端初始化时主动拉取
+    pub fn pending_permission_prompts(&self) -> Vec<serde_json::Value> {
+        self.plugins
+            .iter()
+            .filter(|(_, info)| matches!(info.state, PluginState::Enabled))
+            .filter_map(|(id, _)| {
+                let pending = self.pending_permissions(id);
+                (!pending.is_empty()).then(|| {
+                    serde_json::json!({
+                        "plugin_id": id,
+                        "permissions": pending,
+                    })
+                })
+            })
+            .collect()
+    }
+
+    pub fn get_permission_grants(&self, plugin_id: &str) -> Result<Vec<PermissionGrant>, String> {
+        let info = self
+            .plugins
+            .get(plugin_id)
+            .ok_or_else(|| format!("Plugin '{}' not found", plugin_id))?;
+        Ok(self
+            .grants
+            .grants_for(plugin_id, &info.manifest.permissions))
+    }
+
+    /// 授予、拒绝或撤销单项权限；插件运行中时立即重建对应的 sl.* 命名空间
+    pub fn set_permission_grant(
+        &mut self,
+        plugin_id: &str,
+        permission: &str,
+        decision: GrantDecision,
+    ) -> Result<(), String> {
+        let info = self
+            .plugins
+            .get(plugin_id)
+            .ok_or_else(|| format!("Plugin '{}' not found", plugin_id))?;
+        if !info.manifest.permissions.iter().any(|p| p == permission) {
+            return Err(format!(
+                "插件 '{}' 未在 manifest.json 中声明 '{}' 权限",
+                info.manifest.name, permission
+            ));
+        }
+
+        self.grants.set(plugin_id, permission, decision);
+
+        if let Some(runtime) = get_runtime(&self.runtimes, plugin_id) {
+            runtime.set_permission_granted(permission, decision == GrantDecision::Granted)?;
+        }
+        Ok(())
+    }
+
+    pub fn get_permission_audit_log(&self, plugin_id: Option<&str>) -> Vec<PermissionAuditEntry> {
+        self.grants.audit_log(plugin_id)
+    }
+
     pub fn disable_plugin(&mut self, plugin_id: &str) -> Result<Vec<String>, String> {
         let mut visited = HashSet::new();
         let disabled_plugins = self.disable_plugin_internal(plugin_id, &mut visited)?;
@@ -243,6 +435,73 @@ impl PluginManager {
         Ok(disabled_plugins)
     }
 
+    /// 开发者模式热重载：重新读取 manifest 并重建运行时
+    /// 插件数据目录（storage.json 等）保持不变，依赖它的插件不会被级联禁用；
+    /// 重载前注入的 UI 与 CSS 若新代码没有重新创建，会按快照补发
+    pub fn reload_plugin(&mut self, plugin_id: &str) -> Result<(), String> {
+        let plugin_info = self
+            .plugins
+            .get(plugin_id)
+            .ok_or_else(|| format!("Plugin '{}' not found", plugin_id))?
+            .clone();
+        let plugin_dir = PathBuf::from(&plugin_info.path);
+
+        let result = self.reload_plugin_inner(plugin_id, &plugin_dir);
+        match &result {
+            Ok(()) => {
+                let _ = emit_log_event(plugin_id, "info", "插件已热重载");
+            }
+            Err(e) => {
+                eprintln!("[WARN] Failed to reload plugin '{}': {}", plugin_id, e);
+                let _ = emit_log_event(plugin_id, "error", &format!("热重载失败: {}", e));
+            }
+        }
+        result
+    }
+
+    fn reload_plugin_inner(&mut self, plugin_id: &str, plugin_dir: &Path) -> Result<(), String> {
+        let manifest = PluginLoader::load_manifest(plugin_dir)?;
+        PluginLoader::validate_manifest(&manifest)?;
+        if manifest.id != plugin_id {
+            return Err(format!(
+                "热重载不支持修改插件 ID（'{}' -> '{}'），请重新扫描插件",
+                plugin_id, manifest.id
+            ));
+        }
+
+        let previous_ui: Vec<BufferedUiEvent> = take_ui_event_snapshot()
+            .into_iter()
+            .filter(|e| e.plugin_id == plugin_id)
+            .collect();
+
+        self.shutdown_runtime(plugin_id);
+        if let Some(info) = self.plugins.get_mut(plugin_id) {
+            info.manifest = manifest;
+            info.state = PluginState::Loaded;
+        }
+
+        self.enable_plugin(plugin_id).inspect_err(|e| {
+            if let Some(info) = self.plugins.get_mut(plugin_id) {
+                info.state = PluginState::Error(e.clone());
+            }
+        })?;
+
+        let current_ui = take_ui_event_snapshot();
+        for event in previous_ui {
+            let recreated = current_ui.iter().any(|c| {
+                c.plugin_id == event.plugin_id
+                    && c.element_id == event.element_id
+                    && c.action == event.action
+            });
+            if !recreated {
+                let _ =
+                    emit_ui_event(&event.plugin_id, &event.action, &event.element_id, &event.html);
+            }
+        }
+
+        Ok(())
+    }
+
     fn disable_plugin_internal(
         &mut self,
         plugin_id: &str,
@@ -274,18 +533,24 @@ impl PluginManager {
             }
         }
 
-        {
-            let runtimes = self.runtimes.read().unwrap_or_else(|e| {
-                eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
-                e.into_inner()
-            });
-            if let Some(runtime) = runtimes.get(plugin_id) {
-                if let Err(e) = runtime.call_lifecycle("onDisable") {
-                    eprintln!("Error calling onDisable for '{}': {}", plugin_id, e);
+        self.shutdown_runtime(plugin_id);
 
-                    let error_msg = format!("Failed to call onDisable: {}", e);
-                    let _ = emit_log_event(plugin_id, "error", &error_msg);
-                }
+        if let Some(info) = self.plugins.get_mut(plugin_id) {
+            info.state = PluginState::Disabled;
+        }
+
+        Ok(disabled_plugins)
+    }
+
+    /// 调用 onDisable 并销毁运行时，同时清理该插件注入的 UI 与注册的 API
+    fn shutdown_runtime(&self, plugin_id: &str) {
+        let runtime = get_runtime(&self.runtimes, plugin_id);
+        if let Some(runtime) = &runtime {
+            if let Err(e) = runtime.call_lifecycle("onDisable") {
+                eprintln!("Error calling onDisable for '{}': {}", plugin_id, e);
+
+                let error_msg = format!("Failed to call onDisable: {}", e);
+                let _ = emit_log_event(plugin_id, "error", &error_msg);
             }
         }
 
@@ -299,16 +564,10 @@ impl PluginManager {
 
         self.api_registry.clear_plugin_apis(plugin_id);
 
-        {
-            let runtimes = self.runtimes.read().unwrap_or_else(|e| {
-                eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
-                e.into_inner()
-            });
-            if let Some(runtime) = runtimes.get(plugin_id) {
-                runtime.cleanup();
+        if let Some(runtime) = &runtime {
+            runtime.cleanup();
 
-                kill_all_processes(&runtime.process_registry);
-            }
+            kill_all_processes(&runtime.process_registry);
         }
 
         {
@@ -318,12 +577,6 @@ impl PluginManager {
             });
             runtimes.remove(plugin_id);
         }
-
-        if let Some(info) = self.plugins.get_mut(plugin_id) {
-            info.state = PluginState::Disabled;
-        }
-
-        Ok(disabled_plugins)
     }
 
     fn check_dependencies(&self, dependencies: &[PluginDependency]) -> Vec<String> {
@@ -399,6 +652,11 @@ impl PluginManager {
         fs::create_dir_all(data_dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
 
         for pattern in includes {
+            // 含通配符的项与打包时一样展开，逐个复制匹配到的文件
+            if pattern.contains(['*', '?', '[']) {
+                Self::copy_included_glob(plugin_dir, data_dir, pattern)?;
+                continue;
+            }
             let clean = pattern.trim_end_matches('/');
             let src = plugin_dir.join(clean);
             if !src.exists() {
@@ -420,6 +678,33 @@ impl PluginManager {
         Ok(())
     }
 
+    fn copy_included_glob(plugin_dir: &Path, data_dir: &Path, pattern: &str) -> Result<(), String> {
+        let mut warnings = Vec::new();
+        let files = packager::list_files(plugin_dir, &mut warnings)?;
+        let matched = match packager::match_include(pattern, &files) {
+            Ok(matched) if !matched.is_empty() => matched,
+            Ok(_) => {
+                eprintln!("[插件] include 通配符没有匹配到文件，跳过: {}", pattern);
+                return Ok(());
+            }
+            Err(e) => {
+                eprintln!("[插件] {}，跳过", e);
+                return Ok(());
+            }
+        };
+        for relative in matched {
+            let src = plugin_dir.join(&relative);
+            let dest = data_dir.join(&relative);
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| format!("Failed to create parent dir: {}", e))?;
+            }
+            fs::copy(&src, &dest)
+                .map_err(|e| format!("Failed to copy {}: {}", src.display(), e))?;
+        }
+        Ok(())
+    }
+
     fn copy_dir_recursive(src: &Path, dest: &Path) -> Result<(), String> {
         fs::create_dir_all(dest)
             .map_err(|e| format!("Failed to create dir {}: {}", dest.display(), e))?;
@@ -537,6 +822,42 @@ impl PluginManager {
         nav_items
     }
 
+    /// 本地安装（目录或 ZIP）没有可校验的签名，一律记为未签名
+    fn record_local_install(&mut self, plugin_id: &str) -> PluginSignature {
+        let signature = PluginSignature::default();
+        self.signatures
+            .insert(plugin_id.to_string(), signature.clone());
+        save_signature_store(&self.data_dir, &self.signatures);
+        signature
+    }
+
+    /// 记录市场安装时的签名校验结果
+    pub fn record_plugin_signature(
+        &mut self,
+        plugin_id: &str,
+        signature: PluginSignature,
+    ) -> Option<PluginInfo> {
+        self.signatures
+            .insert(plugin_id.to_string(), signature.clone());
+        save_signature_store(&self.data_dir, &self.signatures);
+        let info = self.plugins.get_mut(plugin_id)?;
+        info.signature = signature;
+        Some(info.clone())
+    }
+
+    /// 用户确认信任未签名插件后，才允许其以高危权限启用
+    pub fn confirm_unsigned_plugin(&mut self, plugin_id: &str) -> Result<(), String> {
+        let info = self
+            .plugins
+            .get_mut(plugin_id)
+            .ok_or_else(|| format!("Plugin '{}' not found", plugin_id))?;
+        info.signature.critical_confirmed = true;
+        self.signatures
+            .insert(plugin_id.to_string(), info.signature.clone());
+        save_signature_store(&self.data_dir, &self.signatures);
+        Ok(())
+    }
+
     pub fn install_plugin(&mut self, path: &Path) -> Result<PluginInstallResult, String> {
         let plugin_info = if path.extension().is_some_and(|ext| ext == "zip") {
             self.install_plugin_from_zip(path)?
@@ -621,6 +942,7 @@ impl PluginManager {
                 .map_err(|e| format!("Failed to parse manifest.json: {}", e))?;
 
         PluginLoader::validate_manifest(&manifest)?;
+        PluginLoader::ensure_engine_compatible(&manifest)?;
 
         let plugin_id = manifest.id.clone();
 
@@ -659,6 +981,7 @@ impl PluginManager {
                 state: PluginState::Loaded,
                 path: target_dir.to_string_lossy().to_string(),
                 missing_dependencies: missing_deps,
+                signature: self.signatures.get(&plugin_id).cloned().unwrap_or_default(),
             };
 
             self.plugins.insert(plugin_id, plugin_info.clone());
@@ -682,6 +1005,7 @@ impl PluginManager {
             state: PluginState::Loaded,
             path: target_dir.to_string_lossy().to_string(),
             missing_dependencies: missing_deps,
+            signature: self.record_local_install(&plugin_id),
         };
 
         self.plugins.insert(plugin_id, plugin_info.clone());
@@ -701,6 +1025,8 @@ impl PluginManager {
                 .map_err(|e| format!("Failed to parse manifest.json: {}", e))?;
 
         PluginLoader::validate_manifest(&manifest)?;
+        // 市场安装（install_from_market）也走这里，在解压前拒绝不兼容的插件
+        PluginLoader::ensure_engine_compatible(&manifest)?;
 
         let plugin_id = manifest.id.clone();
 
@@ -733,6 +1059,7 @@ impl PluginManager {
             state: PluginState::Loaded,
             path: target_dir.to_string_lossy().to_string(),
             missing_dependencies: missing_deps,
+            signature: self.record_local_install(&plugin_id),
         };
 
         self.plugins.insert(plugin_id, plugin_info.clone());
@@ -878,28 +1205,332 @@ impl PluginManager {
         Ok(())
     }
 
-    pub fn get_plugin_settings(&self, plugin_id: &str) -> Result<serde_json::Value, String> {
-        let plugin_info = self
+    pub fn update_policies(&self) -> HashMap<String, UpdatePolicy> {
+        self.plugins
+            .keys()
+            .map(|id| (id.clone(), self.updates.policy(id)))
+            .collect()
+    }
+
+    pub fn update_policy(&self, plugin_id: &str) -> UpdatePolicy {
+        self.updates.policy(plugin_id)
+    }
+
+    pub fn set_update_policy(
+        &mut self,
+        plugin_id: &str,
+        policy: UpdatePolicy,
+    ) -> Result<(), String> {
+        if !self.plugins.contains_key(plugin_id) {
+            return Err(format!("Plugin not found: {}", plugin_id));
+        }
+        self.updates.set_policy(plugin_id, policy);
+        Ok(())
+    }
+
+    /// 用下载好的更新包替换插件，保留上一版本；运行中的插件会在换好后重新启用，
+    /// 启用失败时自动回滚。`automatic` 为 true 时拒绝申请了高危权限的新版本
+    pub fn apply_plugin_update(
+        &mut self,
+        plugin_id: &str,
+        zip_path: &Path,
+        signature: PluginSignature,
+        automatic: bool,
+    ) -> Result<PluginUpdateResult, String> {
+        let old_info = self
             .plugins
             .get(plugin_id)
+            .cloned()
             .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
+        let old_signature = self.signatures.get(plugin_id).cloned();
 
-        let plugin_path = PathBuf::from(&plugin_info.path);
-        let settings_path = plugin_path.join("settings.json");
+        let file = File::open(zip_path).map_err(|e| format!("Failed to open ZIP file: {}", e))?;
+        let mut archive =
+            zip::ZipArchive::new(file).map_err(|e| format!("Failed to read ZIP archive: {}", e))?;
+        let (manifest_content, prefix) = self.find_manifest_in_zip(&mut archive)?;
+        let manifest: crate::models::plugin::PluginManifest =
+            serde_json::from_str(&manifest_content)
+                .map_err(|e| format!("Failed to parse manifest.json: {}", e))?;
+        PluginLoader::validate_manifest(&manifest)?;
+        PluginLoader::ensure_engine_compatible(&manifest)?;
+
+        if manifest.id != plugin_id {
+            return Err(format!("更新包的插件 id '{}' 与 '{}' 不一致", manifest.id, plugin_id));
+        }
+        if !Self::is_newer_version(&manifest.version, &old_info.manifest.version) {
+            return Err(format!(
+                "更新包版本 {} 不高于当前版本 {}",
+                manifest.version, old_info.manifest.version
+            ));
+        }
+        if automatic && has_critical_permission(&manifest.permissions) {
+            return Err(format!(
+                "插件 '{}' 的新版本申请了高危权限，需要手动更新",
+                old_info.manifest.name
+            ));
+        }
+        let current_status = old_signature.as_ref().map(|s| s.status).unwrap_or_default();
+        if automatic && !keeps_signature_trust(current_status, signature.status) {
+            return Err(format!(
+                "插件 '{}' 的新版本签名状态低于当前版本，需要手动更新",
+                old_info.manifest.name
+            ));
+        }
+
+        let staging = staging_dir(&self.plugins_dir, plugin_id);
+        remove_dir_if_exists(&staging)?;
+        fs::create_dir_all(&staging).map_err(|e| format!("创建临时目录失败: {}", e))?;
+        if let Err(e) = self.extract_zip_to_dir(&mut archive, &prefix, &staging) {
+            let _ = remove_dir_if_exists(&staging);
+            return Err(e);
+        }
+
+        let was_enabled = matches!(old_info.state, PluginState::Enabled);
+        let disabled_dependents = if was_enabled {
+            self.disable_plugin(plugin_id)?
+        } else {
+            Vec::new()
+        };
+
+        let target = self.plugins_dir.join(plugin_id);
+        let backup = backup_dir(&self.plugins_dir, plugin_id);
+        let swapped = move_dir(&target, &backup).and_then(|_| {
+            move_dir(&staging, &target).inspect_err(|_| {
+                let _ = move_dir(&backup, &target);
+            })
+        });
+        if let Err(e) = swapped {
+            let _ = remove_dir_if_exists(&staging);
+            self.reenable_after_update(plugin_id, was_enabled, &disabled_dependents);
+            return Err(format!("替换插件文件失败: {}", e));
+        }
+        let new_manifest = match PluginLoader::load_manifest(&target)
+            .and_then(|m| PluginLoader::validate_manifest(&m).map(|_| m))
+        {
+            Ok(manifest) => manifest,
+            Err(e) => {
+                let _ = move_dir(&backup, &target);
+                self.reenable_after_update(plugin_id, was_enabled, &disabled_dependents);
+                return Err(format!("更新包无效: {}", e));
+            }
+        };
+
+        Self::migrate_settings_after_update(&backup, &target, &old_info, &new_manifest);
+
+        let from_version = old_info.manifest.version.clone();
+        let to_version = new_manifest.version.clone();
+        self.signatures
+            .insert(plugin_id.to_string(), signature.clone());
+        save_signature_store(&self.data_dir, &self.signatures);
+        let missing_dependencies = self.get_missing_dependencies(&new_manifest);
+        self.plugins.insert(
+            plugin_id.to_string(),
+            PluginInfo {
+                manifest: new_manifest,
+                state: PluginState::Disabled,
+                path: target.to_string_lossy().to_string(),
+                missing_dependencies,
+                signature,
+            },
+        );
+        self.updates
+            .set_pending_migration(plugin_id, Some(&from_version));
+        let _ = emit_log_event(
+            plugin_id,
+            "info",
+            &format!("插件已从 {} 更新到 {}", from_version, to_version),
+        );
+
+        if was_enabled {
+            if let Err(e) = self.enable_plugin(plugin_id) {
+                self.restore_previous_version(plugin_id, old_info, old_signature);
+                self.reenable_after_update(plugin_id, true, &disabled_dependents);
+                return Err(format!(
+                    "启用新版本 {} 失败，已回滚到 {}：{}",
+                    to_version, from_version, e
+                ));
+            }
+            self.reenable_after_update(plugin_id, false, &disabled_dependents);
+        }
+
+        Ok(PluginUpdateResult {
+            plugin_id: plugin_id.to_string(),
+            from_version,
+            to_version,
+        })
+    }
+
+    /// 把旧版本目录里的设置迁移到新版本，失败只记录日志；回滚时旧版本目录里的 settings.json 原样换回
+    fn migrate_settings_after_update(
+        backup: &Path,
+        target: &Path,
+        old_info: &PluginInfo,
+        new_manifest: &crate::models::plugin::PluginManifest,
+    ) {
+        let plugin_id = &new_manifest.id;
+        if !backup.join(settings::SETTINGS_FILE).is_file() {
+            return;
+        }
+        let migrated = settings::read_settings(backup).map(|old| {
+            settings::migrate_settings(
+                old_info.manifest.settings.as_deref().unwrap_or_default(),
+                new_manifest.settings.as_deref().unwrap_or_default(),
+                old,
+            )
+        });
+        if let Err(e) = migrated.and_then(|settings| settings::write_settings(target, &settings)) {
+            eprintln!("[WARN] Failed to migrate settings of plugin '{}': {}", plugin_id, e);
+            let _ = emit_log_event(plugin_id, "warn", &format!("迁移插件设置失败: {}", e));
+        }
+    }
 
-        if !settings_path.exists() {
-            return Ok(serde_json::json!({}));
+    /// 手动回滚到更新前保留的版本，当前版本与之互换，可以再次回滚回来
+    pub fn rollback_plugin_update(
+        &mut self,
+        plugin_id: &str,
+    ) -> Result<PluginUpdateResult, String> {
+        let current = self
+            .plugins
+            .get(plugin_id)
+            .cloned()
+            .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
+        let backup = backup_dir(&self.plugins_dir, plugin_id);
+        let previous = PluginLoader::load_manifest(&backup)
+            .map_err(|_| format!("插件 '{}' 没有可回滚的旧版本", current.manifest.name))?;
+        if previous.id != plugin_id {
+            return Err(format!("插件 '{}' 的备份已损坏", plugin_id));
+        }
+
+        let was_enabled = matches!(current.state, PluginState::Enabled);
+        let disabled_dependents = if was_enabled {
+            self.disable_plugin(plugin_id)?
+        } else {
+            Vec::new()
+        };
+
+        let target = self.plugins_dir.join(plugin_id);
+        let staging = staging_dir(&self.plugins_dir, plugin_id);
+        let swapped = move_dir(&target, &staging)
+            .and_then(|_| {
+                move_dir(&backup, &target).inspect_err(|_| {
+                    let _ = move_dir(&staging, &target);
+                })
+            })
+            .and_then(|_| move_dir(&staging, &backup));
+        if let Err(e) = swapped {
+            self.reenable_after_update(plugin_id, was_enabled, &disabled_dependents);
+            return Err(format!("回滚插件文件失败: {}", e));
+        }
+
+        let result = PluginUpdateResult {
+            plugin_id: plugin_id.to_string(),
+            from_version: current.manifest.version.clone(),
+            to_version: previous.version.clone(),
+        };
+        let missing_dependencies = self.get_missing_dependencies(&previous);
+        if let Some(info) = self.plugins.get_mut(plugin_id) {
+            info.manifest = previous;
+            info.state = PluginState::Disabled;
+            info.missing_dependencies = missing_dependencies;
+        }
+        self.updates.set_pending_migration(plugin_id, None);
+        let _ = emit_log_event(
+            plugin_id,
+            "info",
+            &format!("插件已从 {} 回滚到 {}", result.from_version, result.to_version),
+        );
+
+        self.reenable_after_update(plugin_id, was_enabled, &disabled_dependents);
+        Ok(result)
+    }
+
+    /// 启用新版本失败后把目录与记录恢复成更新前的状态
+    fn restore_previous_version(
+        &mut self,
+        plugin_id: &str,
+        old_info: PluginInfo,
+        old_signature: Option<PluginSignature>,
+    ) {
+        let target = self.plugins_dir.join(plugin_id);
+        let backup = backup_dir(&self.plugins_dir, plugin_id);
+        if let Err(e) = move_dir(&backup, &target) {
+            eprintln!("[ERROR] Failed to restore previous version of '{}': {}", plugin_id, e);
+            let _ = emit_log_event(plugin_id, "error", &format!("恢复旧版本失败: {}", e));
+        }
+
+        match old_signature {
+            Some(signature) => self.signatures.insert(plugin_id.to_string(), signature),
+            None => self.signatures.remove(plugin_id),
+        };
+        save_signature_store(&self.data_dir, &self.signatures);
+        self.updates.set_pending_migration(plugin_id, None);
+        self.plugins
+            .insert(plugin_id.to_string(), PluginInfo { state: PluginState::Disabled, ..old_info });
+    }
+
+    /// 更新或回滚结束后重新启用插件本身（可选）以及被级联禁用的插件，失败只记录日志
+    fn reenable_after_update(
+        &mut self,
+        plugin_id: &str,
+        include_self: bool,
+        dependents: &[String],
+    ) {
+        let ids = include_self
+            .then(|| plugin_id.to_string())
+            .into_iter()
+            .chain(dependents.iter().cloned());
+        for id in ids.collect::<Vec<_>>() {
+            if let Err(e) = self.enable_plugin(&id) {
+                eprintln!("[WARN] Failed to re-enable plugin '{}' after update: {}", id, e);
+                let _ = emit_log_event(&id, "error", &format!("更新后重新启用失败: {}", e));
+            }
         }
+    }
 
-        let content = std::fs::read_to_string(&settings_path)
-            .map_err(|e| format!("Failed to read settings file: {}", e))?;
+    pub fn get_plugin_settings(&self, plugin_id: &str) -> Result<serde_json::Value, String> {
+        let plugin_info = self
+            .plugins
+            .get(plugin_id)
+            .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
 
-        let settings: serde_json::Value = serde_json::from_str(&content)
-            .map_err(|e| format!("Failed to parse settings file: {}", e))?;
+        let settings = settings::read_settings(Path::new(&plugin_info.path))?;
+        let schema = plugin_info.manifest.settings.as_deref().unwrap_or_default();
+        Ok(serde_json::Value::Object(settings::normalize_settings(schema, settings)))
+    }
 
-        Ok(settings)
+    /// 插件的持久化日志文件
+    pub fn plugin_log_path(&self, plugin_id: &str) -> PathBuf {
+        self.logs_dir.join(format!("{}.db", plugin_id))
     }
 
+    /// 诊断包中的插件信息：状态、签名、权限授予情况与运行环境
+    pub fn diagnostic_info(&self, plugin_id: &str) -> Result<serde_json::Value, String> {
+        let info = self
+            .plugins
+            .get(plugin_id)
+            .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
+        let exported_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        Ok(serde_json::json!({
+            "plugin_id": plugin_id,
+            "name": info.manifest.name,
+            "version": info.manifest.version,
+            "state": info.state,
+            "path": info.path,
+            "missing_dependencies": info.missing_dependencies,
+            "signature": info.signature,
+            "permissions": self.grants.grants_for(plugin_id, &info.manifest.permissions),
+            "update_policy": self.updates.policy(plugin_id),
+            "app_version": env!("CARGO_PKG_VERSION"),
+            "os": std::env::consts::OS,
+            "arch": std::env::consts::ARCH,
+            "exported_at": exported_at,
+        }))
+    }
+
+    /// 按 manifest 声明校验后保存，有变化时通知运行中的插件 onSettingsChanged(diff)
     pub fn set_plugin_settings(
         &self,
         plugin_id: &str,
@@ -910,14 +1541,25 @@ impl PluginManager {
             .get(plugin_id)
             .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
 
-        let plugin_path = PathBuf::from(&plugin_info.path);
-        let settings_path = plugin_path.join("settings.json");
+        let plugin_path = Path::new(&plugin_info.path);
+        let schema = plugin_info.manifest.settings.as_deref().unwrap_or_default();
+        let new_settings = settings::validate_settings(schema, settings)?;
+        let old_settings = settings::read_settings(plugin_path)
+            .map(|old| settings::normalize_settings(schema, old))
+            .unwrap_or_default();
+        settings::write_settings(plugin_path, &new_settings)?;
 
-        let content = serde_json::to_string_pretty(&settings)
-            .map_err(|e| format!("Failed to serialize settings: {}", e))?;
-
-        std::fs::write(&settings_path, content)
-            .map_err(|e| format!("Failed to write settings file: {}", e))?;
+        let diff = settings::settings_diff(&old_settings, &new_settings);
+        if !diff.is_empty() && matches!(plugin_info.state, PluginState::Enabled) {
+            let runtimes = self.runtimes.read().unwrap_or_else(|e| {
+                eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
+                e.into_inner()
+            });
+            if let Some(runtime) = runtimes.get(plugin_id) {
+                runtime
+                    .post_lifecycle_with_json("onSettingsChanged", serde_json::Value::Object(diff));
+            }
+        }
 
         Ok(())
     }
@@ -1041,12 +1683,22 @@ impl PluginManager {
         };
 
         drop(_dropped_runtime);
+        crate::plugins::logs::unregister_plugin(plugin_id);
 
         let plugin_info = self
             .plugins
             .remove(plugin_id)
             .ok_or_else(|| format!("Plugin not found: {}", plugin_id))?;
 
+        if self.signatures.remove(plugin_id).is_some() {
+            save_signature_store(&self.data_dir, &self.signatures);
+        }
+        self.grants.remove_plugin(plugin_id);
+        self.updates.remove_plugin(plugin_id);
+        if let Err(e) = remove_dir_if_exists(&backup_dir(&self.plugins_dir, plugin_id)) {
+            eprintln!("[WARN] Failed to remove backup of plugin '{}': {}", plugin_id, e);
+        }
+
         let plugin_path = PathBuf::from(&plugin_info.path);
         if plugin_path.exists() {
             let mut last_error = None;
@@ -1069,6 +1721,10 @@ impl PluginManager {
             }
         }
 
+        if delete_data {
+            crate::plugins::logs::remove_log_file(&self.plugin_log_path(plugin_id))?;
+        }
+
         let data_dir = self.data_dir.join(plugin_id);
         if data_dir.exists() {
             let should_delete = delete_data || {
@@ -1124,6 +1780,14 @@ impl PluginManager {
         &self.plugins
     }
 
+    pub fn list_commands(&self) -> Vec<CommandEntry> {
+        collect_commands(&self.plugins)
+    }
+
+    pub fn resolve_command(&self, id: &str) -> Result<CommandTarget, String> {
+        resolve_command(&self.plugins, id)
+    }
+
     pub fn notify_page_changed(&self, path: &str) {
         let runtimes = self.runtimes.read().unwrap_or_else(|e| {
             eprintln!("[WARN] RwLock poisoned, recovering: {}", e);
@@ -1132,9 +1796,7 @@ impl PluginManager {
         for (id, runtime) in runtimes.iter() {
             if let Some(info) = self.plugins.get(id) {
                 if matches!(info.state, PluginState::Enabled) {
-                    if let Err(e) = runtime.call_lifecycle_with_arg("onPageChanged", path) {
-                        eprintln!("[WARN] Failed to call onPageChanged for plugin '{}': {}", id, e);
-                    }
+                    runtime.post_lifecycle_with_arg("onPageChanged", path);
                 }
             }
         }
@@ -1148,19 +1810,36 @@ impl PluginManager {
         for (id, runtime) in runtimes.iter() {
             if let Some(info) = self.plugins.get(id) {
                 if matches!(info.state, PluginState::Enabled) {
-                    if let Err(e) = runtime.call_lifecycle_with_arg("onLocaleChanged", locale) {
-                        eprintln!(
-                            "[WARN] Failed to call onLocaleChanged for plugin '{}': {}",
-                            id, e
-                        );
-                        let _ = emit_log_event(
-                            id,
-                            "error",
-                            &format!("Failed to call onLocaleChanged: {}", e),
-                        );
-                    }
+                    runtime.post_lifecycle_with_arg("onLocaleChanged", locale);
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_include_globs_are_expanded_on_install() {
+        let root = std::env::temp_dir().join(format!("sl_include_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let plugin_dir = root.join("plugin");
+        let data_dir = root.join("data");
+        fs::create_dir_all(plugin_dir.join("assets/img")).unwrap();
+        fs::create_dir_all(plugin_dir.join("config")).unwrap();
+        fs::write(plugin_dir.join("assets/img/a.png"), "a").unwrap();
+        fs::write(plugin_dir.join("assets/readme.txt"), "r").unwrap();
+        fs::write(plugin_dir.join("config/default.json"), "{}").unwrap();
+
+        let includes = vec!["assets/**/*.png".to_string(), "config/".to_string()];
+        PluginManager::copy_included_resources(&plugin_dir, &data_dir, &includes).unwrap();
+
+        assert!(data_dir.join("assets/img/a.png").is_file());
+        assert!(!data_dir.join("assets/readme.txt").exists());
+        assert!(data_dir.join("config/default.json").is_file());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,569 @@
+use crate::models::plugin::MarketPluginInfo;
+use crate::models::settings::MarketSource;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use url::Url;
+
+// 插件市场源：
+// 1) 市场源可以是 http(s) 地址、file:// 地址或本地目录，方便内网或离线环境自建市场
+// 2) 优先读取聚合索引 api/index.json（{ "plugins": [...], "categories": {...} }），
+//    没有时退回旧格式 api/plugins.json + 每个插件一个 JSON，逐个文件并行拉取
+// 3) 远程请求按 URL 缓存 ETag / Last-Modified 与响应内容，304 或网络失败时使用缓存
+// 4) 多个源并行拉取后按配置顺序合并，同一插件 id 以靠前的源为准，条目带上来源 source
+
+const CACHE_FILE: &str = "plugin_market_cache.json";
+const AGGREGATED_INDEX_PATH: &str = "api/index.json";
+const LEGACY_INDEX_PATH: &str = "api/plugins.json";
+const CATEGORIES_PATH: &str = "api/categories.json";
+const MAX_PARALLEL_FETCHES: usize = 8;
+
+/// 市场源的根位置，以及直接指定的聚合索引文件名
+#[derive(Debug, Clone, PartialEq)]
+pub enum SourceRoot {
+    Remote(String),
+    Local(PathBuf),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourceLocation {
+    pub root: SourceRoot,
+    pub index_file: Option<String>,
+}
+
+impl SourceLocation {
+    pub fn parse(url: &str) -> Result<Self, String> {
+        let url = url.trim().trim_end_matches(['/', '\\']);
+        if url.is_empty() {
+            return Err("市场源地址不能为空".to_string());
+        }
+
+        let root = if url.starts_with("http://") || url.starts_with("https://") {
+            SourceRoot::Remote(url.to_string())
+        } else if url.starts_with("file://") {
+            let path = Url::parse(url)
+                .ok()
+                .and_then(|u| u.to_file_path().ok())
+                .ok_or_else(|| format!("市场源地址 '{}' 无效", url))?;
+            SourceRoot::Local(path)
+        } else if Path::new(url).is_absolute() {
+            SourceRoot::Local(PathBuf::from(url))
+        } else {
+            return Err(format!("市场源地址 '{}' 必须是 http(s)、file:// 地址或绝对路径", url));
+        };
+
+        if !url.to_ascii_lowercase().ends_with(".json") {
+            return Ok(Self { root, index_file: None });
+        }
+        let (root, file) = match root {
+            SourceRoot::Remote(u) => {
+                let (base, file) = u.rsplit_once('/').unwrap_or((&u, ""));
+                (SourceRoot::Remote(base.to_string()), file.to_string())
+            }
+            SourceRoot::Local(path) => {
+                let file = path
+                    .file_name()
+                    .map(|f| f.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let base = path.parent().map(Path::to_path_buf).unwrap_or_default();
+                (SourceRoot::Local(base), file)
+            }
+        };
+        Ok(Self { root, index_file: Some(file) })
+    }
+
+    /// 把相对路径转换为可访问的地址，本地源返回 file:// 地址
+    pub fn resource_url(&self, relative: &str) -> String {
+        match &self.root {
+            SourceRoot::Remote(base) => format!("{}/{}", base, relative),
+            SourceRoot::Local(base) => Url::from_file_path(base.join(relative))
+                .map(|u| u.to_string())
+                .unwrap_or_else(|_| base.join(relative).to_string_lossy().to_string()),
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        match &self.root {
+            SourceRoot::Remote(base) => base.clone(),
+            SourceRoot::Local(base) => Url::from_directory_path(base)
+                .map(|u| u.to_string().trim_end_matches('/').to_string())
+                .unwrap_or_else(|_| base.to_string_lossy().to_string()),
+        }
+    }
+
+    /// 判断地址是否指向该市场源（同一主机端口下源路径内的地址，或本地源目录内的文件）
+    pub fn contains_url(&self, url: &Url) -> bool {
+        match &self.root {
+            SourceRoot::Remote(base) => Url::parse(base).ok().is_some_and(|b| {
+                let prefix = b.path().trim_end_matches('/');
+                b.host_str().is_some()
+                    && b.host_str() == url.host_str()
+                    && b.port_or_known_default() == url.port_or_known_default()
+                    && url
+                        .path()
+                        .strip_prefix(prefix)
+                        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            }),
+            SourceRoot::Local(base) => {
+                url.scheme() == "file"
+                    && url.to_file_path().is_ok_and(|p| {
+                        !p.components().any(|c| c.as_os_str() == "..") && p.starts_with(base)
+                    })
+            }
+        }
+    }
+}
+
+fn is_absolute_url(value: &str) -> bool {
+    value.contains("://")
+}
+
+fn validate_relative_path(relative: &str) -> Result<(), String> {
+    if relative.split(['/', '\\']).any(|segment| segment == "..")
+        || Path::new(relative).is_absolute()
+    {
+        return Err(format!("市场索引中的路径 '{}' 无效", relative));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct CacheEntry {
+    #[serde(default)]
+    etag: Option<String>,
+    #[serde(default)]
+    last_modified: Option<String>,
+    body: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MarketSourceError {
+    pub source: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MarketCatalog {
+    pub plugins: Vec<MarketPluginInfo>,
+    /// 各源分类合并后的结果，同名分类以靠前的源为准
+    pub categories: serde_json::Map<String, serde_json::Value>,
+    /// 拉取失败的源，其余源的结果照常返回
+    pub errors: Vec<MarketSourceError>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AggregatedIndex {
+    #[serde(default)]
+    plugins: Vec<serde_json::Value>,
+    #[serde(default)]
+    categories: serde_json::Map<String, serde_json::Value>,
+}
+
+struct SourceIndex {
+    plugins: Vec<MarketPluginInfo>,
+    categories: serde_json::Map<String, serde_json::Value>,
+}
+
+pub struct MarketFetcher {
+    client: reqwest::blocking::Client,
+    cache_path: PathBuf,
+    cache: Mutex<HashMap<String, CacheEntry>>,
+    dirty: AtomicBool,
+}
+
+impl MarketFetcher {
+    pub fn new(cache_dir: &Path) -> Result<Self, String> {
+        let client = reqwest::blocking::Client::builder()
+            .user_agent("SeaLantern")
+            .build()
+            .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+        let cache_path = cache_dir.join(CACHE_FILE);
+        let cache = fs::read_to_string(&cache_path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Ok(Self {
+            client,
+            cache_path,
+            cache: Mutex::new(cache),
+            dirty: AtomicBool::new(false),
+        })
+    }
+
+    fn save_cache(&self) {
+        if !self.dirty.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        let cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        match serde_json::to_string(&*cache) {
+            Ok(content) => {
+                if let Err(e) = fs::write(&self.cache_path, content) {
+                    eprintln!("[WARN] Failed to save plugin market cache: {}", e);
+                }
+            }
+            Err(e) => eprintln!("[WARN] Failed to serialize plugin market cache: {}", e),
+        }
+    }
+
+    /// 带 ETag 缓存的 GET，资源不存在时返回 None
+    fn get_remote(&self, url: &str) -> Result<Option<String>, String> {
+        let cached = self
+            .cache
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(url)
+            .cloned();
+
+        let mut request = self.client.get(url);
+        if let Some(entry) = &cached {
+            if let Some(etag) = &entry.etag {
+                request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+            }
+            if let Some(modified) = &entry.last_modified {
+                request = request.header(reqwest::header::IF_MODIFIED_SINCE, modified);
+            }
+        }
+
+        let response = match request.send() {
+            Ok(response) => response,
+            Err(e) => {
+                // 离线时使用上次的结果
+                return match cached {
+                    Some(entry) => Ok(Some(entry.body)),
+                    None => Err(format!("请求 {} 失败: {}", url, e)),
+                };
+            }
+        };
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_MODIFIED {
+            if let Some(entry) = cached {
+                return Ok(Some(entry.body));
+            }
+        }
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(format!("请求 {} 返回状态 {}", url, status));
+        }
+
+        let header = |name: reqwest::header::HeaderName| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string)
+        };
+        let etag = header(reqwest::header::ETAG);
+        let last_modified = header(reqwest::header::LAST_MODIFIED);
+        let body = response
+            .text()
+            .map_err(|e| format!("读取 {} 失败: {}", url, e))?;
+
+        if etag.is_some() || last_modified.is_some() {
+            self.cache
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(url.to_string(), CacheEntry { etag, last_modified, body: body.clone() });
+            self.dirty.store(true, Ordering::SeqCst);
+        }
+        Ok(Some(body))
+    }
+
+    /// 读取市场源中的一个文件，不存在时返回 None
+    pub fn get_text(
+        &self,
+        location: &SourceLocation,
+        relative: &str,
+    ) -> Result<Option<String>, String> {
+        validate_relative_path(relative)?;
+        match &location.root {
+            SourceRoot::Remote(_) => self.get_remote(&location.resource_url(relative)),
+            SourceRoot::Local(base) => {
+                let path = base.join(relative);
+                if !path.is_file() {
+                    return Ok(None);
+                }
+                fs::read_to_string(&path)
+                    .map(Some)
+                    .map_err(|e| format!("读取 {} 失败: {}", path.display(), e))
+            }
+        }
+    }
+
+    fn fetch_source(&self, source: &MarketSource) -> Result<SourceIndex, String> {
+        let location = SourceLocation::parse(&source.url)?;
+
+        let aggregated_path = location
+            .index_file
+            .clone()
+            .unwrap_or_else(|| AGGREGATED_INDEX_PATH.to_string());
+        let mut index = match self.get_text(&location, &aggregated_path)? {
+            Some(body) => parse_aggregated_index(&location, &body)?,
+            None if location.index_file.is_some() => {
+                return Err(format!("找不到市场索引 {}", location.resource_url(&aggregated_path)));
+            }
+            None => self.fetch_legacy_index(&location)?,
+        };
+
+        for plugin in &mut index.plugins {
+            plugin.source = Some(source.id.clone());
+            plugin.source_url = Some(location.base_url());
+        }
+        Ok(index)
+    }
+
+    fn fetch_legacy_index(&self, location: &SourceLocation) -> Result<SourceIndex, String> {
+        let body = self.get_text(location, LEGACY_INDEX_PATH)?.ok_or_else(|| {
+            format!("找不到市场索引 {}", location.resource_url(LEGACY_INDEX_PATH))
+        })?;
+        let index: serde_json::Value = serde_json::from_str(&body)
+            .map_err(|e| format!("Failed to parse plugins index: {}", e))?;
+        let paths: Vec<String> = index
+            .get("paths")
+            .and_then(|v| v.as_array())
+            .map(|arr| {
+                arr.iter()
+                    .filter_map(|v| v.as_str().map(|s| s.to_string()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let chunk_size = paths.len().div_ceil(MAX_PARALLEL_FETCHES).max(1);
+        let plugins = std::thread::scope(|scope| {
+            let handles: Vec<_> = paths
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .filter_map(|path| self.fetch_legacy_plugin(location, path))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_default())
+                .collect()
+        });
+
+        let categories = self
+            .get_text(location, CATEGORIES_PATH)
+            .ok()
+            .flatten()
+            .and_then(|body| serde_json::from_str(&body).ok())
+            .unwrap_or_default();
+
+        Ok(SourceIndex { plugins, categories })
+    }
+
+    fn fetch_legacy_plugin(
+        &self,
+        location: &SourceLocation,
+        plugin_path: &str,
+    ) -> Option<MarketPluginInfo> {
+        let body = self.get_text(location, plugin_path).ok().flatten()?;
+        let mut plugin: MarketPluginInfo = serde_json::from_str(&body).ok()?;
+
+        let parts: Vec<&str> = plugin_path.split('/').collect();
+        if parts.len() >= 3 {
+            let username = parts[1];
+            let plugin_folder = parts[2];
+            if plugin.repo.is_empty() {
+                plugin.repo = format!("{}/{}", username, plugin_folder);
+            }
+            if plugin.author.name.is_empty() {
+                plugin.author.name = username.to_string();
+                if plugin.author.url.is_none() {
+                    plugin.author.url = Some(format!("https://github.com/{}", username));
+                }
+            }
+        }
+
+        // 旧格式中的图标等相对路径以插件 JSON 所在目录为基准
+        let dir = plugin_path.rsplit_once('/').map(|(d, _)| d).unwrap_or("");
+        resolve_plugin_urls(location, dir, &mut plugin);
+        plugin._path = Some(plugin_path.to_string());
+        Some(plugin)
+    }
+
+    /// 并行拉取所有启用的源并按顺序合并
+    pub fn fetch_catalog(&self, sources: &[MarketSource]) -> MarketCatalog {
+        let enabled: Vec<&MarketSource> = sources.iter().filter(|s| s.enabled).collect();
+        let results: Vec<Result<SourceIndex, String>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = enabled
+                .iter()
+                .map(|source| scope.spawn(move || self.fetch_source(source)))
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|_| Err("拉取市场源时线程异常退出".to_string()))
+                })
+                .collect()
+        });
+        self.save_cache();
+
+        let mut catalog = MarketCatalog::default();
+        let mut seen = HashSet::new();
+        for (source, result) in enabled.into_iter().zip(results) {
+            match result {
+                Ok(index) => {
+                    for plugin in index.plugins {
+                        if seen.insert(plugin.id.clone()) {
+                            catalog.plugins.push(plugin);
+                        }
+                    }
+                    for (key, value) in index.categories {
+                        catalog.categories.entry(key).or_insert(value);
+                    }
+                }
+                Err(error) => catalog
+                    .errors
+                    .push(MarketSourceError { source: source.id.clone(), error }),
+            }
+        }
+        catalog
+    }
+}
+
+fn parse_aggregated_index(location: &SourceLocation, body: &str) -> Result<SourceIndex, String> {
+    let index: AggregatedIndex =
+        serde_json::from_str(body).map_err(|e| format!("Failed to parse market index: {}", e))?;
+    let plugins = index
+        .plugins
+        .into_iter()
+        .filter_map(|value| serde_json::from_value::<MarketPluginInfo>(value).ok())
+        .map(|mut plugin| {
+            resolve_plugin_urls(location, "", &mut plugin);
+            plugin
+        })
+        .collect();
+    Ok(SourceIndex { plugins, categories: index.categories })
+}
+
+/// 把条目中的相对下载地址与图标地址解析为绝对地址
+fn resolve_plugin_urls(location: &SourceLocation, dir: &str, plugin: &mut MarketPluginInfo) {
+    let resolve = |value: &mut Option<String>| {
+        if let Some(relative) = value
+            .as_deref()
+            .filter(|v| !v.is_empty() && !is_absolute_url(v))
+        {
+            let relative = relative.trim_start_matches("./");
+            *value = validate_relative_path(relative).ok().map(|_| {
+                if dir.is_empty() {
+                    location.resource_url(relative)
+                } else {
+                    location.resource_url(&format!("{}/{}", dir, relative))
+                }
+            });
+        }
+    };
+    resolve(&mut plugin.download_url);
+    resolve(&mut plugin.icon_url);
+}
+
+/// 配置的市场源中是否有源包含该地址，用于判断下载地址是否可信
+pub fn is_market_source_url(sources: &[MarketSource], url: &Url) -> bool {
+    sources
+        .iter()
+        .filter(|s| s.enabled)
+        .filter_map(|s| SourceLocation::parse(&s.url).ok())
+        .any(|location| location.contains_url(url))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_source_contains_only_urls_under_its_path() {
+        let location = SourceLocation::parse("https://example.com/market/index.json").unwrap();
+        let contains = |url: &str| location.contains_url(&Url::parse(url).unwrap());
+        assert!(contains("https://example.com/market/plugins/a.zip"));
+        assert!(!contains("https://example.com/other/a.zip"));
+        assert!(!contains("https://example.com/market-evil/a.zip"));
+        assert!(!contains("https://example.com/market/../other/a.zip"));
+        assert!(!contains("https://example.com:8443/market/a.zip"));
+        assert!(!contains("https://evil.com/market/a.zip"));
+    }
+
+    #[test]
+    fn test_local_sources_merge_in_order() {
+        let root = std::env::temp_dir().join(format!("sl_market_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        let internal = root.join("internal");
+        let legacy = root.join("legacy");
+        fs::create_dir_all(internal.join("api")).unwrap();
+        fs::create_dir_all(legacy.join("api")).unwrap();
+        fs::create_dir_all(legacy.join("plugins/alice/tool")).unwrap();
+
+        fs::write(
+            internal.join("api/index.json"),
+            r#"{"plugins":[
+                {"id":"tool","name":"Internal Tool","version":"2.0.0","download_url":"packages/tool.zip"},
+                {"id":"broken"}
+            ],"categories":{"ops":"运维"}}"#,
+        )
+        .unwrap();
+        fs::write(
+            legacy.join("api/plugins.json"),
+            r#"{"paths":["plugins/alice/tool/plugin.json","plugins/alice/other/plugin.json"]}"#,
+        )
+        .unwrap();
+        fs::write(
+            legacy.join("plugins/alice/tool/plugin.json"),
+            r#"{"id":"tool","name":"Public Tool","version":"1.0.0","icon_url":"icon.png"}"#,
+        )
+        .unwrap();
+        fs::write(legacy.join("api/categories.json"), r#"{"ops":"Ops","misc":"Misc"}"#).unwrap();
+
+        let source = |id: &str, url: String| MarketSource {
+            id: id.to_string(),
+            name: id.to_string(),
+            url,
+            enabled: true,
+        };
+        let fetcher = MarketFetcher::new(&root).unwrap();
+
+        let catalog = fetcher.fetch_catalog(&[
+            source("internal", Url::from_file_path(&internal).unwrap().to_string()),
+            source("legacy", legacy.to_string_lossy().to_string()),
+            source("missing", root.join("missing").to_string_lossy().to_string()),
+        ]);
+        assert_eq!(catalog.plugins.len(), 1);
+        let tool = &catalog.plugins[0];
+        assert_eq!(tool.source.as_deref(), Some("internal"));
+        let download = Url::parse(tool.download_url.as_deref().unwrap()).unwrap();
+        assert_eq!(download.to_file_path().unwrap(), internal.join("packages/tool.zip"));
+        assert_eq!(catalog.categories["ops"], "运维");
+        assert_eq!(catalog.categories["misc"], "Misc");
+        assert_eq!(catalog.errors.len(), 1);
+        assert_eq!(catalog.errors[0].source, "missing");
+
+        let catalog =
+            fetcher.fetch_catalog(&[source("legacy", legacy.to_string_lossy().to_string())]);
+        let tool = &catalog.plugins[0];
+        assert_eq!(tool._path.as_deref(), Some("plugins/alice/tool/plugin.json"));
+        assert!(tool
+            .icon_url
+            .as_deref()
+            .unwrap()
+            .ends_with("plugins/alice/tool/icon.png"));
+
+        let index = SourceLocation::parse("https://example.com/market/index.json").unwrap();
+        assert_eq!(index.root, SourceRoot::Remote("https://example.com/market".to_string()));
+        assert_eq!(index.index_file.as_deref(), Some("index.json"));
+        assert!(SourceLocation::parse("relative/dir").is_err());
+        assert!(fetcher.get_text(&index, "../secret").is_err());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -1,4 +1,15 @@
 pub mod api;
+pub mod commands;
+pub mod dev_reload;
+pub mod grants;
+pub mod harness;
 pub mod loader;
+pub mod logs;
 pub mod manager;
+pub mod market;
+pub mod packager;
+pub mod resolver;
 pub mod runtime;
+pub mod settings;
+pub mod signature;
+pub mod updater;
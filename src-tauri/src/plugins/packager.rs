@@ -0,0 +1,508 @@
+use crate::models::plugin::PluginManifest;
+use crate::plugins::harness::TEST_DIR_NAME;
+use crate::plugins::loader::PluginLoader;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+
+// 插件打包：
+// 1) 打包前完整校验 manifest：基本字段、main、icon、locales、settings 定义、include 通配符与 exports，
+//    所有问题一次性列出
+// 2) 所有 .lua 文件用 mlua 编译一遍检查语法（只编译不执行），拒绝预编译字节码
+// 3) 排除版本控制、编辑器、依赖目录、顶层 test/、临时文件与输出目录中此前生成的各版本包；
+//    文件按路径排序，时间戳与权限固定，同一份源码每次生成的 ZIP 字节完全一致
+// 4) 同时写出 <包名>.sha256，内容可直接填入市场索引的 sha256 字段
+
+const MAX_WALK_DEPTH: usize = 16;
+const EXCLUDED_DIRS: &[&str] =
+    &[".git", ".svn", ".hg", ".idea", ".vscode", ".vs", "node_modules", "__pycache__"];
+const EXCLUDED_FILES: &[&str] =
+    &[".DS_Store", "Thumbs.db", "desktop.ini", ".gitignore", ".gitattributes"];
+const EXCLUDED_SUFFIXES: &[&str] = &["~", ".swp", ".swo", ".tmp", ".bak", ".orig"];
+const ICON_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif", "svg", "webp", "ico", "bmp"];
+const SETTING_TYPES: &[&str] =
+    &["string", "number", "boolean", "select", "textarea", "checkbox", "color"];
+const LUA_BYTECODE_SIGNATURE: &[u8] = b"\x1bLua";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginPackageResult {
+    pub plugin_id: String,
+    pub version: String,
+    pub package_path: String,
+    pub sha256: String,
+    pub size: u64,
+    /// 包内文件的相对路径，按打包顺序排列
+    pub files: Vec<String>,
+    /// 不阻止打包的提示，如跳过的符号链接
+    pub warnings: Vec<String>,
+}
+
+fn is_dev_file(name: &str, is_dir: bool) -> bool {
+    if is_dir {
+        return EXCLUDED_DIRS.contains(&name);
+    }
+    EXCLUDED_FILES.contains(&name) || EXCLUDED_SUFFIXES.iter().any(|s| name.ends_with(s))
+}
+
+/// 列出目录下需要打包的文件的相对路径（以 '/' 分隔并排序），不跟随符号链接
+pub fn list_files(dir: &Path, warnings: &mut Vec<String>) -> Result<Vec<String>, String> {
+    let mut files = Vec::new();
+    walk(dir, "", 0, &mut files, warnings)?;
+    files.sort();
+    Ok(files)
+}
+
+fn walk(
+    dir: &Path,
+    prefix: &str,
+    depth: usize,
+    files: &mut Vec<String>,
+    warnings: &mut Vec<String>,
+) -> Result<(), String> {
+    if depth > MAX_WALK_DEPTH {
+        return Err(format!("目录 {} 层级过深", dir.display()));
+    }
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("读取目录 {} 失败: {}", dir.display(), e))?;
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let relative = if prefix.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", prefix, name)
+        };
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_symlink() {
+            warnings.push(format!("已跳过符号链接 {}", relative));
+            continue;
+        }
+        if is_dev_file(&name, file_type.is_dir()) {
+            continue;
+        }
+        // 顶层 test/ 只供无界面测试使用，不随插件分发
+        if depth == 0 && file_type.is_dir() && name == TEST_DIR_NAME {
+            continue;
+        }
+        if file_type.is_dir() {
+            walk(&entry.path(), &relative, depth + 1, files, warnings)?;
+        } else if file_type.is_file() {
+            files.push(relative);
+        }
+    }
+    Ok(())
+}
+
+fn is_safe_relative_path(path: &str) -> bool {
+    !path.is_empty()
+        && !Path::new(path).is_absolute()
+        && !path.split(['/', '\\']).any(|segment| segment == "..")
+}
+
+/// 展开 manifest.include 中的一项：不含通配符时匹配该文件或该目录下的所有文件，
+/// 含通配符时按 glob 匹配（'*' 不跨目录，'**' 跨任意层目录）
+pub fn match_include(pattern: &str, files: &[String]) -> Result<Vec<String>, String> {
+    let clean = pattern
+        .trim()
+        .trim_start_matches("./")
+        .trim_end_matches('/');
+    if !is_safe_relative_path(clean) {
+        return Err(format!("include 项 '{}' 必须是不含 '..' 的相对路径", pattern));
+    }
+
+    if !clean.contains(['*', '?', '[']) {
+        let dir_prefix = format!("{}/", clean);
+        return Ok(files
+            .iter()
+            .filter(|f| *f == clean || f.starts_with(&dir_prefix))
+            .cloned()
+            .collect());
+    }
+
+    let glob = glob::Pattern::new(clean)
+        .map_err(|e| format!("include 项 '{}' 不是有效的通配符: {}", pattern, e))?;
+    let options = glob::MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    Ok(files
+        .iter()
+        .filter(|f| glob.matches_with(f, options))
+        .cloned()
+        .collect())
+}
+
+fn is_valid_locale(code: &str) -> bool {
+    let mut parts = code.split('-');
+    let language = parts.next().unwrap_or("");
+    (2..=3).contains(&language.len())
+        && language.chars().all(|c| c.is_ascii_lowercase())
+        && parts.all(|p| (2..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+fn validate_settings(manifest: &PluginManifest, problems: &mut Vec<String>) {
+    let Some(fields) = &manifest.settings else {
+        return;
+    };
+    let mut keys = HashSet::new();
+    for field in fields {
+        let key = &field.key;
+        if key.is_empty()
+            || !key
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-' || c == '.')
+        {
+            problems.push(format!("settings 中的 key '{}' 无效", key));
+        }
+        if !keys.insert(key.as_str()) {
+            problems.push(format!("settings 中的 key '{}' 重复", key));
+        }
+        if !SETTING_TYPES.contains(&field.field_type.as_str()) {
+            problems.push(format!(
+                "settings.{} 的 type '{}' 不受支持，可用类型: {}",
+                key,
+                field.field_type,
+                SETTING_TYPES.join(", ")
+            ));
+            continue;
+        }
+
+        let options: Vec<&str> = field
+            .options
+            .iter()
+            .flatten()
+            .map(|o| o.value.as_str())
+            .collect();
+        if field.field_type == "select" && options.is_empty() {
+            problems.push(format!("settings.{} 是 select 类型，但没有 options", key));
+        }
+
+        let Some(default) = &field.default else {
+            continue;
+        };
+        let default_ok = match field.field_type.as_str() {
+            "number" => default.is_number(),
+            "boolean" | "checkbox" => default.is_boolean(),
+            "select" => default.as_str().is_some_and(|v| options.contains(&v)),
+            _ => default.is_string(),
+        };
+        if !default_ok {
+            problems.push(format!(
+                "settings.{} 的默认值 {} 与类型 '{}' 不符",
+                key, default, field.field_type
+            ));
+        }
+    }
+}
+
+/// 校验 manifest 与包内文件，返回所有发现的问题
+fn validate_package(manifest: &PluginManifest, files: &[String]) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Err(e) = PluginLoader::validate_manifest(manifest) {
+        problems.push(e);
+    }
+    let has_file = |path: &str| files.iter().any(|f| f == path);
+
+    let main = manifest.main.trim_start_matches("./");
+    if !main.ends_with(".lua") {
+        problems.push(format!("main '{}' 必须是 .lua 文件", manifest.main));
+    } else if !has_file(main) {
+        problems.push(format!("main 文件 '{}' 不存在或被排除", manifest.main));
+    }
+
+    let icon = manifest.icon.as_deref().unwrap_or("icon.png");
+    let icon = icon.trim_start_matches("./");
+    let extension = Path::new(icon)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.to_ascii_lowercase())
+        .unwrap_or_default();
+    if !is_safe_relative_path(icon) {
+        problems.push(format!("icon '{}' 必须是不含 '..' 的相对路径", icon));
+    } else if !ICON_EXTENSIONS.contains(&extension.as_str()) {
+        problems.push(format!(
+            "icon '{}' 的格式不受支持，可用格式: {}",
+            icon,
+            ICON_EXTENSIONS.join(", ")
+        ));
+    } else if manifest.icon.is_some() && !has_file(icon) {
+        problems.push(format!("icon 文件 '{}' 不存在", icon));
+    }
+
+    for (code, entry) in manifest.locales.iter().flatten() {
+        if !is_valid_locale(code) {
+            problems.push(format!("locales 中的语言代码 '{}' 无效，应形如 zh-CN、en-US", code));
+        }
+        let empty = |v: &Option<String>| v.as_deref().is_none_or(|s| s.trim().is_empty());
+        if empty(&entry.name) && empty(&entry.description) {
+            problems.push(format!("locales.{} 至少需要 name 或 description", code));
+        }
+    }
+
+    validate_settings(manifest, &mut problems);
+
+    for pattern in &manifest.include {
+        match match_include(pattern, files) {
+            Ok(matched) if matched.is_empty() => {
+                problems.push(format!("include 项 '{}' 没有匹配到任何文件", pattern));
+            }
+            Ok(_) => {}
+            Err(e) => problems.push(e),
+        }
+    }
+
+    for module in &manifest.exports {
+        let Ok(module) = crate::plugins::runtime::normalize_module_name(module) else {
+            continue;
+        };
+        if !has_file(&format!("{}.lua", module)) && !has_file(&format!("{}/init.lua", module)) {
+            problems.push(format!("exports 中的模块 '{}' 找不到对应的文件", module));
+        }
+    }
+
+    problems
+}
+
+/// 编译所有 Lua 文件检查语法，不执行任何代码
+fn lint_lua_files(plugin_dir: &Path, files: &[String], problems: &mut Vec<String>) {
+    let lua = mlua::Lua::new();
+    for file in files.iter().filter(|f| f.ends_with(".lua")) {
+        let bytes = match fs::read(plugin_dir.join(file)) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                problems.push(format!("读取 {} 失败: {}", file, e));
+                continue;
+            }
+        };
+        let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+        if bytes.starts_with(LUA_BYTECODE_SIGNATURE) {
+            problems.push(format!("{} 是预编译字节码，插件只能包含 Lua 源码", file));
+            continue;
+        }
+        if let Err(e) = lua
+            .load(bytes)
+            .set_name(format!("@{}", file))
+            .set_mode(mlua::ChunkMode::Text)
+            .into_function()
+        {
+            problems.push(format!("Lua 语法错误: {}", e));
+        }
+    }
+}
+
+fn write_package(plugin_dir: &Path, files: &[String], package_path: &Path) -> Result<(), String> {
+    let file = File::create(package_path)
+        .map_err(|e| format!("创建 {} 失败: {}", package_path.display(), e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    // 固定时间戳、权限与压缩级别，保证相同输入得到相同输出
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .compression_level(Some(6))
+        .last_modified_time(zip::DateTime::default())
+        .unix_permissions(0o644);
+
+    for relative in files {
+        let content = fs::read(plugin_dir.join(relative))
+            .map_err(|e| format!("读取 {} 失败: {}", relative, e))?;
+        zip.start_file(relative.as_str(), options)
+            .map_err(|e| format!("写入 {} 失败: {}", relative, e))?;
+        zip.write_all(&content)
+            .map_err(|e| format!("写入 {} 失败: {}", relative, e))?;
+    }
+    zip.finish()
+        .map_err(|e| format!("写入 {} 失败: {}", package_path.display(), e))?;
+    Ok(())
+}
+
+/// 校验并打包插件目录，输出 <id>-<version>.zip 与同名 .sha256 文件
+pub fn package_plugin(plugin_dir: &Path, output_dir: &Path) -> Result<PluginPackageResult, String> {
+    let manifest = PluginLoader::load_manifest(plugin_dir)?;
+
+    let package_name = format!("{}-{}.zip", manifest.id, manifest.version);
+    let checksum_name = format!("{}.sha256", package_name);
+
+    let mut warnings = Vec::new();
+    let mut files = list_files(plugin_dir, &mut warnings)?;
+    // 输出目录位于插件目录内时，排除此前生成的各版本包和校验文件，目录里的其他文件照常打包
+    if let Ok(relative) = output_dir.strip_prefix(plugin_dir) {
+        let prefix = relative.to_string_lossy().replace('\\', "/");
+        let package_prefix = format!("{}-", manifest.id);
+        files.retain(|f| {
+            let name = if prefix.is_empty() {
+                f.as_str()
+            } else {
+                match f.strip_prefix(&format!("{}/", prefix)) {
+                    Some(name) => name,
+                    None => return true,
+                }
+            };
+            let is_previous_output = !name.contains('/')
+                && (name.ends_with(".zip.sha256")
+                    || (name.starts_with(&package_prefix) && name.ends_with(".zip")));
+            !is_previous_output
+        });
+    }
+
+    let mut problems = validate_package(&manifest, &files);
+    lint_lua_files(plugin_dir, &files, &mut problems);
+    if !problems.is_empty() {
+        return Err(format!("插件打包校验失败:\n- {}", problems.join("\n- ")));
+    }
+
+    fs::create_dir_all(output_dir)
+        .map_err(|e| format!("创建目录 {} 失败: {}", output_dir.display(), e))?;
+    let package_path = output_dir.join(&package_name);
+    write_package(plugin_dir, &files, &package_path)?;
+
+    let bytes = fs::read(&package_path)
+        .map_err(|e| format!("读取 {} 失败: {}", package_path.display(), e))?;
+    let sha256 = Sha256::digest(&bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    fs::write(output_dir.join(&checksum_name), format!("{}  {}\n", sha256, package_name))
+        .map_err(|e| format!("写入 sha256 文件失败: {}", e))?;
+
+    Ok(PluginPackageResult {
+        plugin_id: manifest.id,
+        version: manifest.version,
+        package_path: package_path.to_string_lossy().to_string(),
+        sha256,
+        size: bytes.len() as u64,
+        files,
+        warnings,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const VALID_SETTINGS: &str = r#"[{"key":"mode","label":"Mode","type":"select","default":"a","options":[{"value":"a","label":"A"}]}]"#;
+
+    fn make_temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "sealantern_test_package_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn demo_manifest(settings: &str) -> String {
+        format!(
+            r#"{{"id":"demo","name":"Demo","version":"1.2.0","description":"d",
+                "author":{{"name":"a"}},"main":"main.lua","icon":"assets/img/icon.png",
+                "include":["assets/**/*.png"],"exports":["lib/util"],
+                "locales":{{"en-US":{{"name":"Demo"}}}},"settings":{}}}"#,
+            settings
+        )
+    }
+
+    /// 在 root/demo 下创建一个可以打包的插件，输出目录为 demo/dist
+    fn make_demo_plugin(root: &Path) -> PathBuf {
+        let plugin_dir = root.join("demo");
+        fs::create_dir_all(plugin_dir.join("lib")).unwrap();
+        fs::create_dir_all(plugin_dir.join("assets/img")).unwrap();
+        fs::create_dir_all(plugin_dir.join("dist")).unwrap();
+        fs::write(plugin_dir.join("manifest.json"), demo_manifest(VALID_SETTINGS)).unwrap();
+        fs::write(plugin_dir.join("main.lua"), "local u = require('lib.util') return u").unwrap();
+        fs::write(plugin_dir.join("lib/util.lua"), "return {}").unwrap();
+        fs::write(plugin_dir.join("dist/bundle.lua"), "return 1").unwrap();
+        fs::write(plugin_dir.join("assets/img/icon.png"), [0x89, b'P', b'N', b'G']).unwrap();
+        plugin_dir
+    }
+
+    #[test]
+    fn test_package_skips_vcs_and_editor_files() {
+        let root = make_temp_dir("exclude");
+        let plugin_dir = make_demo_plugin(&root);
+        fs::create_dir_all(plugin_dir.join(".git")).unwrap();
+        fs::write(plugin_dir.join(".git/HEAD"), "ref").unwrap();
+        fs::write(plugin_dir.join("main.lua~"), "backup").unwrap();
+
+        let result = package_plugin(&plugin_dir, &plugin_dir.join("dist")).unwrap();
+        assert_eq!(
+            result.files,
+            vec![
+                "assets/img/icon.png",
+                "dist/bundle.lua",
+                "lib/util.lua",
+                "main.lua",
+                "manifest.json"
+            ]
+        );
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_package_skips_previous_packages_in_output_dir() {
+        let root = make_temp_dir("previous");
+        let plugin_dir = make_demo_plugin(&root);
+        fs::write(plugin_dir.join("dist/demo-1.1.0.zip"), "old").unwrap();
+        fs::write(plugin_dir.join("dist/demo-1.1.0.zip.sha256"), "old").unwrap();
+
+        let output = plugin_dir.join("dist");
+        package_plugin(&plugin_dir, &output).unwrap();
+        let result = package_plugin(&plugin_dir, &output).unwrap();
+        assert!(result
+            .files
+            .iter()
+            .all(|f| !f.ends_with(".zip") && !f.ends_with(".sha256")));
+        assert!(result.files.contains(&"dist/bundle.lua".to_string()));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_package_is_deterministic_and_writes_checksum() {
+        let root = make_temp_dir("deterministic");
+        let plugin_dir = make_demo_plugin(&root);
+        let output = plugin_dir.join("dist");
+
+        let first = package_plugin(&plugin_dir, &output).unwrap();
+        let first_bytes = fs::read(&first.package_path).unwrap();
+        let second = package_plugin(&plugin_dir, &output).unwrap();
+        assert_eq!(first.files, second.files);
+        assert_eq!(first.sha256, second.sha256);
+        assert_eq!(first_bytes, fs::read(&second.package_path).unwrap());
+
+        let sha_file = fs::read_to_string(output.join("demo-1.2.0.zip.sha256")).unwrap();
+        assert!(sha_file.starts_with(&first.sha256));
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_package_reports_lua_syntax_errors_and_invalid_settings() {
+        let root = make_temp_dir("invalid");
+        let plugin_dir = make_demo_plugin(&root);
+        fs::write(plugin_dir.join("lib/util.lua"), "return {").unwrap();
+        fs::write(
+            plugin_dir.join("manifest.json"),
+            demo_manifest(r#"[{"key":"n","label":"N","type":"number","default":"x"}]"#),
+        )
+        .unwrap();
+
+        let err = package_plugin(&plugin_dir, &plugin_dir.join("dist")).unwrap_err();
+        assert!(err.contains("lib/util.lua"), "{}", err);
+        assert!(err.contains("settings.n"), "{}", err);
+        assert!(!plugin_dir.join("dist/demo-1.2.0.zip").exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_match_include_supports_globs_and_directories() {
+        let files = vec!["a/b/c.png".to_string(), "a/d.png".to_string(), "e.png".to_string()];
+        assert_eq!(match_include("a/*.png", &files).unwrap(), vec!["a/d.png"]);
+        assert_eq!(match_include("a/**/*.png", &files).unwrap().len(), 2);
+        assert_eq!(match_include("a/", &files).unwrap().len(), 2);
+        assert!(match_include("../x", &files).is_err());
+    }
+}
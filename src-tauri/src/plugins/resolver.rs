@@ -0,0 +1,537 @@
+use crate::models::plugin::{MarketPluginInfo, PluginDependency, PluginInfo, PluginState, SemVer};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// 依赖解析：
+// 1) 从目标插件出发沿 dependencies（以及可选的 optional_dependencies）展开，
+//    每个依赖优先使用已安装版本，不满足版本要求时改用市场索引中的版本
+// 2) 所有插件对同一依赖的版本要求必须同时满足，否则报告冲突；循环依赖直接报错
+// 3) 安装计划按拓扑顺序排列（依赖在前、目标插件在后），执行时依次安装/升级再依次启用
+// 4) 无法满足的可选依赖只记录在 skipped 中，不影响计划
+// 5) 执行时每一步都会重新解析，新计划中出现预览时没有的插件或版本就拒绝继续
+
+/// 解析时排除候选来源的最大轮数，每轮至少排除一个候选，正常情况下远达不到
+const MAX_RESOLVE_ROUNDS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlanAction {
+    Install,
+    Upgrade,
+    Enable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub plugin_id: String,
+    pub name: String,
+    pub action: PlanAction,
+    pub current_version: Option<String>,
+    pub target_version: String,
+    /// 引入该插件的插件 id，目标插件本身为空
+    pub required_by: Vec<String>,
+    /// 只被可选依赖引入
+    pub optional: bool,
+    /// 安装 / 升级所用的市场条目
+    #[serde(skip)]
+    pub market: Option<MarketPluginInfo>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedDependency {
+    pub plugin_id: String,
+    pub required_by: Vec<String>,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyPlan {
+    pub plugin_id: String,
+    /// 按执行顺序排列；已启用且满足要求的插件不出现在计划中
+    pub steps: Vec<PlanStep>,
+    pub skipped: Vec<SkippedDependency>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Source {
+    Installed,
+    Market,
+}
+
+#[derive(Debug, Clone)]
+struct Constraint {
+    from: String,
+    requirement: Option<String>,
+    required: bool,
+}
+
+#[derive(Default)]
+struct Walk {
+    chosen: BTreeMap<String, Source>,
+    constraints: BTreeMap<String, Vec<Constraint>>,
+    edges: BTreeMap<String, Vec<String>>,
+}
+
+fn version_satisfies(version: &str, requirement: Option<&str>) -> bool {
+    match requirement {
+        Some(req) => SemVer::parse(version).is_some_and(|v| v.satisfies(req)),
+        None => true,
+    }
+}
+
+pub struct DependencyResolver<'a> {
+    installed: &'a HashMap<String, PluginInfo>,
+    market: HashMap<&'a str, &'a MarketPluginInfo>,
+    include_optional: bool,
+}
+
+impl<'a> DependencyResolver<'a> {
+    pub fn new(
+        installed: &'a HashMap<String, PluginInfo>,
+        market: &'a [MarketPluginInfo],
+        include_optional: bool,
+    ) -> Self {
+        Self {
+            installed,
+            market: market.iter().map(|p| (p.id.as_str(), p)).collect(),
+            include_optional,
+        }
+    }
+
+    fn version(&self, id: &str, source: Source) -> Option<&str> {
+        match source {
+            Source::Installed => self.installed.get(id).map(|p| p.manifest.version.as_str()),
+            Source::Market => self.market.get(id).and_then(|p| p.version.as_deref()),
+        }
+    }
+
+    fn dependencies(&self, id: &str, source: Source) -> Vec<(&PluginDependency, bool)> {
+        let (required, optional): (&[PluginDependency], &[PluginDependency]) = match source {
+            Source::Installed => match self.installed.get(id) {
+                Some(p) => (&p.manifest.dependencies, &p.manifest.optional_dependencies),
+                None => (&[], &[]),
+            },
+            Source::Market => match self.market.get(id) {
+                Some(p) => (&p.dependencies, &p.optional_dependencies),
+                None => (&[], &[]),
+            },
+        };
+        let optional = if self.include_optional { optional } else { &[] };
+        required
+            .iter()
+            .map(|d| (d, true))
+            .chain(optional.iter().map(|d| (d, false)))
+            .collect()
+    }
+
+    fn satisfies_all(&self, id: &str, source: Source, constraints: &[Constraint]) -> bool {
+        self.version(id, source).is_some_and(|version| {
+            constraints
+                .iter()
+                .all(|c| version_satisfies(version, c.requirement.as_deref()))
+        })
+    }
+
+    fn pick(
+        &self,
+        id: &str,
+        constraints: &[Constraint],
+        banned: &BTreeSet<(String, Source)>,
+    ) -> Option<Source> {
+        [Source::Installed, Source::Market]
+            .into_iter()
+            .find(|source| {
+                !banned.contains(&(id.to_string(), *source))
+                    && self.satisfies_all(id, *source, constraints)
+            })
+    }
+
+    fn walk(&self, root: &str, root_source: Source, banned: &BTreeSet<(String, Source)>) -> Walk {
+        let mut walk = Walk::default();
+        walk.chosen.insert(root.to_string(), root_source);
+        let mut stack = vec![root.to_string()];
+
+        while let Some(id) = stack.pop() {
+            let source = walk.chosen[&id];
+            for (dep, required) in self.dependencies(&id, source) {
+                let dep_id = dep.id().to_string();
+                walk.edges
+                    .entry(id.clone())
+                    .or_default()
+                    .push(dep_id.clone());
+                let constraints = walk.constraints.entry(dep_id.clone()).or_default();
+                constraints.push(Constraint {
+                    from: id.clone(),
+                    requirement: dep.version_requirement().map(str::to_string),
+                    required,
+                });
+                if walk.chosen.contains_key(&dep_id) {
+                    continue;
+                }
+                if let Some(source) = self.pick(&dep_id, constraints, banned) {
+                    walk.chosen.insert(dep_id.clone(), source);
+                    stack.push(dep_id);
+                }
+            }
+        }
+        walk
+    }
+
+    /// 深度优先后序遍历得到拓扑顺序，同时检测循环依赖
+    fn topological_order(walk: &Walk, root: &str) -> Result<Vec<String>, String> {
+        fn visit(
+            id: &str,
+            walk: &Walk,
+            path: &mut Vec<String>,
+            done: &mut BTreeSet<String>,
+            order: &mut Vec<String>,
+        ) -> Result<(), String> {
+            if done.contains(id) {
+                return Ok(());
+            }
+            if let Some(start) = path.iter().position(|p| p == id) {
+                let mut cycle = path[start..].to_vec();
+                cycle.push(id.to_string());
+                return Err(format!("检测到循环依赖：{}", cycle.join(" -> ")));
+            }
+            path.push(id.to_string());
+            for dep in walk.edges.get(id).into_iter().flatten() {
+                if walk.chosen.contains_key(dep) {
+                    visit(dep, walk, path, done, order)?;
+                }
+            }
+            path.pop();
+            done.insert(id.to_string());
+            order.push(id.to_string());
+            Ok(())
+        }
+
+        let mut order = Vec::new();
+        visit(root, walk, &mut Vec::new(), &mut BTreeSet::new(), &mut order)?;
+        Ok(order)
+    }
+
+    fn describe_conflict(&self, id: &str, constraints: &[Constraint]) -> String {
+        let requirements = constraints
+            .iter()
+            .map(|c| format!("{} 要求 {}", c.from, c.requirement.as_deref().unwrap_or("任意版本")))
+            .collect::<Vec<_>>()
+            .join("，");
+        let installed = self.version(id, Source::Installed);
+        let market = self.version(id, Source::Market);
+        if installed.is_none() && market.is_none() {
+            return format!("依赖 '{}' 未安装，市场中也找不到（{}）", id, requirements);
+        }
+        format!(
+            "依赖 '{}' 的版本要求无法同时满足：{}（已安装 {}，市场版本 {}）",
+            id,
+            requirements,
+            installed.unwrap_or("无"),
+            market.unwrap_or("无")
+        )
+    }
+
+    pub fn resolve(&self, plugin_id: &str) -> Result<DependencyPlan, String> {
+        let root_source = if self.installed.contains_key(plugin_id) {
+            Source::Installed
+        } else if self.market.contains_key(plugin_id) {
+            Source::Market
+        } else {
+            return Err(format!("插件 '{}' 未安装，市场中也找不到", plugin_id));
+        };
+
+        // 先到先得地为每个依赖选择来源，之后发现不满足全部要求的就排除该来源重新展开
+        let mut banned = BTreeSet::new();
+        let mut resolved = None;
+        for _ in 0..MAX_RESOLVE_ROUNDS {
+            let walk = self.walk(plugin_id, root_source, &banned);
+            let order = Self::topological_order(&walk, plugin_id)?;
+            let violated: Vec<(String, Source)> = walk
+                .chosen
+                .iter()
+                .filter(|(id, source)| {
+                    id.as_str() != plugin_id
+                        && !self.satisfies_all(id, **source, &walk.constraints[id.as_str()])
+                })
+                .map(|(id, source)| (id.clone(), *source))
+                .collect();
+            if violated.is_empty() {
+                resolved = Some((walk, order));
+                break;
+            }
+            banned.extend(violated);
+        }
+        let (walk, order) = resolved.ok_or_else(|| "依赖关系过于复杂，无法完成解析".to_string())?;
+
+        let mut skipped = Vec::new();
+        for (id, constraints) in &walk.constraints {
+            if walk.chosen.contains_key(id) {
+                continue;
+            }
+            let reason = self.describe_conflict(id, constraints);
+            if constraints.iter().any(|c| c.required) {
+                return Err(reason);
+            }
+            skipped.push(SkippedDependency {
+                plugin_id: id.clone(),
+                required_by: constraints.iter().map(|c| c.from.clone()).collect(),
+                reason,
+            });
+        }
+
+        let mut steps = Vec::new();
+        for id in order {
+            let source = walk.chosen[&id];
+            let installed = self.installed.get(&id);
+            let action = match (source, installed) {
+                (Source::Market, Some(_)) => PlanAction::Upgrade,
+                (Source::Market, None) => PlanAction::Install,
+                (Source::Installed, Some(info)) if !matches!(info.state, PluginState::Enabled) => {
+                    PlanAction::Enable
+                }
+                _ => continue,
+            };
+            let constraints = walk.constraints.get(&id).map(Vec::as_slice).unwrap_or(&[]);
+            let market = self.market.get(id.as_str()).map(|p| (*p).clone());
+            let name = installed
+                .map(|p| p.manifest.name.clone())
+                .unwrap_or_else(|| {
+                    market
+                        .as_ref()
+                        .and_then(|m| m.name.as_str().map(str::to_string))
+                        .unwrap_or_else(|| id.clone())
+                });
+            steps.push(PlanStep {
+                current_version: installed.map(|p| p.manifest.version.clone()),
+                target_version: self.version(&id, source).unwrap_or_default().to_string(),
+                required_by: constraints.iter().map(|c| c.from.clone()).collect(),
+                optional: !constraints.is_empty() && constraints.iter().all(|c| !c.required),
+                market: if source == Source::Market {
+                    market
+                } else {
+                    None
+                },
+                plugin_id: id,
+                name,
+                action,
+            });
+        }
+
+        Ok(DependencyPlan {
+            plugin_id: plugin_id.to_string(),
+            steps,
+            skipped,
+        })
+    }
+}
+
+/// 重新解析出的步骤必须都在预览过的计划中：安装 / 升级的版本一致，装好后转为启用的插件也在计划里
+pub fn check_plan_previewed(
+    expected: &DependencyPlan,
+    plan: &DependencyPlan,
+) -> Result<(), String> {
+    for step in &plan.steps {
+        let previewed = expected.steps.iter().any(|e| {
+            e.plugin_id == step.plugin_id
+                && (step.action == PlanAction::Enable
+                    || (e.action == step.action && e.target_version == step.target_version))
+        });
+        if !previewed {
+            return Err(format!("依赖计划已变化（插件 '{}'），请重新预览后再执行", step.plugin_id));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installed(
+        id: &str,
+        version: &str,
+        state: PluginState,
+        deps: serde_json::Value,
+    ) -> PluginInfo {
+        PluginInfo {
+            manifest: serde_json::from_value(serde_json::json!({
+                "id": id, "name": id, "version": version, "description": "d",
+                "author": { "name": "a" }, "main": "main.lua", "dependencies": deps,
+            }))
+            .unwrap(),
+            state,
+            path: String::new(),
+            missing_dependencies: Vec::new(),
+            signature: Default::default(),
+        }
+    }
+
+    fn market(id: &str, version: &str, deps: serde_json::Value) -> MarketPluginInfo {
+        serde_json::from_value(serde_json::json!({
+            "id": id, "name": id, "version": version, "dependencies": deps,
+        }))
+        .unwrap()
+    }
+
+    /// app（已禁用）依赖 lib >=2.0.0 与 util；lib 已安装 1.0.0 且已启用
+    fn app_with_outdated_lib() -> HashMap<String, PluginInfo> {
+        let mut plugins = HashMap::new();
+        plugins.insert(
+            "app".to_string(),
+            installed(
+                "app",
+                "1.0.0",
+                PluginState::Disabled,
+                serde_json::json!([{ "id": "lib", "version": ">=2.0.0" }, "util"]),
+            ),
+        );
+        plugins.insert(
+            "lib".to_string(),
+            installed("lib", "1.0.0", PluginState::Enabled, serde_json::json!([])),
+        );
+        plugins
+    }
+
+    fn step_actions(plan: &DependencyPlan) -> Vec<(&str, PlanAction)> {
+        plan.steps
+            .iter()
+            .map(|s| (s.plugin_id.as_str(), s.action))
+            .collect()
+    }
+
+    fn step(plugin_id: &str, action: PlanAction, target_version: &str) -> PlanStep {
+        PlanStep {
+            plugin_id: plugin_id.to_string(),
+            name: plugin_id.to_string(),
+            action,
+            current_version: None,
+            target_version: target_version.to_string(),
+            required_by: Vec::new(),
+            optional: false,
+            market: None,
+        }
+    }
+
+    fn plan(steps: Vec<PlanStep>) -> DependencyPlan {
+        DependencyPlan {
+            plugin_id: "app".to_string(),
+            steps,
+            skipped: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_plan_orders_dependencies_before_dependents() {
+        let plugins = app_with_outdated_lib();
+        let index = vec![
+            market("lib", "2.1.0", serde_json::json!(["core"])),
+            market("core", "1.0.0", serde_json::json!([])),
+            market("util", "0.3.0", serde_json::json!([{ "id": "core", "version": "^1.0.0" }])),
+        ];
+
+        let plan = DependencyResolver::new(&plugins, &index, false)
+            .resolve("app")
+            .unwrap();
+        assert_eq!(
+            step_actions(&plan),
+            vec![
+                ("core", PlanAction::Install),
+                ("lib", PlanAction::Upgrade),
+                ("util", PlanAction::Install),
+                ("app", PlanAction::Enable),
+            ]
+        );
+        assert_eq!(plan.steps[1].current_version.as_deref(), Some("1.0.0"));
+        assert_eq!(plan.steps[1].target_version, "2.1.0");
+    }
+
+    #[test]
+    fn test_resolve_plan_rejects_conflicting_ranges() {
+        let plugins = app_with_outdated_lib();
+        let index = vec![
+            market("lib", "2.1.0", serde_json::json!([{ "id": "core", "version": ">=2.0.0" }])),
+            market("core", "1.0.0", serde_json::json!([])),
+            market("util", "0.3.0", serde_json::json!([{ "id": "core", "version": "^1.0.0" }])),
+        ];
+
+        let err = DependencyResolver::new(&plugins, &index, false)
+            .resolve("app")
+            .unwrap_err();
+        assert!(err.contains("core") && err.contains("无法同时满足"), "{}", err);
+    }
+
+    #[test]
+    fn test_resolve_plan_detects_cycles() {
+        let plugins = app_with_outdated_lib();
+        let index = vec![
+            market("lib", "2.1.0", serde_json::json!(["util"])),
+            market("util", "0.3.0", serde_json::json!(["lib"])),
+        ];
+
+        let err = DependencyResolver::new(&plugins, &index, false)
+            .resolve("app")
+            .unwrap_err();
+        assert!(err.contains("循环依赖"), "{}", err);
+    }
+
+    #[test]
+    fn test_resolve_plan_follows_optional_dependencies_only_when_requested() {
+        let mut plugins = HashMap::new();
+        let mut app = installed("app", "1.0.0", PluginState::Enabled, serde_json::json!([]));
+        app.manifest.optional_dependencies = serde_json::from_value(serde_json::json!([
+            "extra",
+            { "id": "missing", "version": ">=1.0.0" },
+        ]))
+        .unwrap();
+        plugins.insert("app".to_string(), app);
+        let index = vec![market("extra", "1.2.0", serde_json::json!([]))];
+
+        let plan = DependencyResolver::new(&plugins, &index, false)
+            .resolve("app")
+            .unwrap();
+        assert!(plan.steps.is_empty() && plan.skipped.is_empty());
+
+        let plan = DependencyResolver::new(&plugins, &index, true)
+            .resolve("app")
+            .unwrap();
+        assert_eq!(step_actions(&plan), vec![("extra", PlanAction::Install)]);
+        assert!(plan.steps[0].optional);
+        assert_eq!(plan.skipped.len(), 1);
+        assert_eq!(plan.skipped[0].plugin_id, "missing");
+        assert_eq!(plan.skipped[0].required_by, vec!["app".to_string()]);
+    }
+
+    #[test]
+    fn test_check_plan_previewed_accepts_previewed_steps_and_new_enables() {
+        let preview = plan(vec![
+            step("core", PlanAction::Install, "1.0.0"),
+            step("app", PlanAction::Install, "2.0.0"),
+        ]);
+        // 依赖装好后重新解析，只剩下对计划内插件的启用
+        let replanned = plan(vec![
+            step("core", PlanAction::Enable, "1.0.0"),
+            step("app", PlanAction::Install, "2.0.0"),
+        ]);
+        assert!(check_plan_previewed(&preview, &replanned).is_ok());
+        assert!(check_plan_previewed(&preview, &plan(Vec::new())).is_ok());
+    }
+
+    #[test]
+    fn test_check_plan_previewed_rejects_changed_plans() {
+        let preview = plan(vec![
+            step("core", PlanAction::Install, "1.0.0"),
+            step("app", PlanAction::Install, "2.0.0"),
+        ]);
+
+        let new_version = plan(vec![step("core", PlanAction::Install, "1.1.0")]);
+        let err = check_plan_previewed(&preview, &new_version).unwrap_err();
+        assert!(err.contains("'core'"), "{}", err);
+
+        let new_action = plan(vec![step("core", PlanAction::Upgrade, "1.0.0")]);
+        assert!(check_plan_previewed(&preview, &new_action).is_err());
+
+        let new_plugin = plan(vec![step("extra", PlanAction::Enable, "1.0.0")]);
+        let err = check_plan_previewed(&preview, &new_plugin).unwrap_err();
+        assert!(err.contains("'extra'"), "{}", err);
+    }
+}
@@ -17,13 +17,16 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to init _SL_APIS: {}", e))?;
 
         let plugin_id = self.plugin_id.clone();
+        let permissions = self.permissions.clone();
         let registry = self.api_registry.clone();
 
         let pid = plugin_id.clone();
         let reg = registry.clone();
+        let perms = permissions.clone();
         let register_fn = self
             .lua
             .create_function(move |lua, (name, func): (String, Function)| {
+                perms.require("api")?;
                 let globals = lua.globals();
                 let apis: Table = globals.get("_SL_APIS")?;
                 apis.set(name.clone(), func)?;
@@ -38,9 +41,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set api.register: {}", e))?;
 
         let reg = registry.clone();
+        let perms = permissions.clone();
         let has_fn = self
             .lua
             .create_function(move |_, (target_plugin, api_name): (String, String)| {
+                perms.require("api")?;
                 Ok(reg.has_api(&target_plugin, &api_name))
             })
             .map_err(|e| format!("Failed to create api.has: {}", e))?;
@@ -49,9 +54,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set api.has: {}", e))?;
 
         let reg = registry.clone();
+        let perms = permissions.clone();
         let list_fn = self
             .lua
             .create_function(move |lua, target_plugin: String| {
+                perms.require("api")?;
                 let apis = reg.list_apis(&target_plugin);
                 let table = lua.create_table()?;
                 for (i, api) in apis.iter().enumerate() {
@@ -65,9 +72,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set api.list: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let call_fn = self
             .lua
             .create_function(move |lua, args: MultiValue| {
+                perms.require("api")?;
                 let mut args_iter = args.into_iter();
 
                 let target_plugin: String = match args_iter.next() {
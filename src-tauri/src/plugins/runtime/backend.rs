@@ -0,0 +1,50 @@
+use crate::models::server::{ServerInstance, ServerStatus};
+
+// sl.server / sl.console 访问服务器的入口：
+// 1) 默认实现直接转给全局 ServerManager 与日志管道
+// 2) 无界面测试框架注入自己的实现，插件在没有真实服务器的情况下也能调用这些 API
+
+pub trait ServerBackend: Send + Sync {
+    fn list_servers(&self) -> Vec<ServerInstance>;
+
+    fn server_status(&self, server_id: &str) -> ServerStatus;
+
+    fn running_server_ids(&self) -> Vec<String>;
+
+    fn send_command(&self, server_id: &str, command: &str) -> Result<(), String>;
+
+    /// 指定服务器的全部日志行，按时间先后排列
+    fn logs(&self, server_id: &str) -> Vec<String>;
+
+    fn all_logs(&self) -> Vec<(String, Vec<String>)>;
+}
+
+pub struct ManagerServerBackend;
+
+impl ServerBackend for ManagerServerBackend {
+    fn list_servers(&self) -> Vec<ServerInstance> {
+        crate::services::global::server_manager().get_server_list()
+    }
+
+    fn server_status(&self, server_id: &str) -> ServerStatus {
+        crate::services::global::server_manager()
+            .get_server_status(server_id)
+            .status
+    }
+
+    fn running_server_ids(&self) -> Vec<String> {
+        crate::services::global::server_manager().get_running_server_ids()
+    }
+
+    fn send_command(&self, server_id: &str, command: &str) -> Result<(), String> {
+        crate::services::global::server_manager().send_command(server_id, command)
+    }
+
+    fn logs(&self, server_id: &str) -> Vec<String> {
+        crate::services::server_log_pipeline::get_logs(server_id, 0, None)
+    }
+
+    fn all_logs(&self) -> Vec<(String, Vec<String>)> {
+        crate::services::server_log_pipeline::get_all_logs()
+    }
+}
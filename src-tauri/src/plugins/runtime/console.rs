@@ -4,7 +4,6 @@ use mlua::Table;
 impl PluginRuntime {
     pub(super) fn setup_console_namespace(&self, sl: &Table) -> Result<(), String> {
         use crate::plugins::api::emit_permission_log;
-        use crate::services::global::server_manager;
 
         let console_table = self
             .lua
@@ -12,6 +11,7 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to create console table: {}", e))?;
 
         let plugin_id = self.plugin_id.clone();
+        let permissions = self.permissions.clone();
 
         const ALLOWED_COMMANDS: &[&str] = &[
             "tell",
@@ -90,16 +90,19 @@ impl PluginRuntime {
         }
 
         let pid = plugin_id.clone();
+        let backend = self.server_backend.clone();
+        let perms = permissions.clone();
         let send_fn = self
             .lua
             .create_function(move |_, (server_id, command): (String, String)| {
-                let servers = server_manager().get_server_list();
+                perms.require("console")?;
+                let servers = backend.list_servers();
                 if !servers.iter().any(|s| s.id == server_id) {
                     return Err(mlua::Error::runtime(format!("服务器不存在: {}", server_id)));
                 }
 
-                let status = server_manager().get_server_status(&server_id);
-                if status.status != crate::models::server::ServerStatus::Running {
+                if backend.server_status(&server_id) != crate::models::server::ServerStatus::Running
+                {
                     return Err(mlua::Error::runtime("服务器未运行"));
                 }
 
@@ -114,7 +117,7 @@ impl PluginRuntime {
                     &format!("[{}] {}", server_id, command),
                 );
 
-                server_manager()
+                backend
                     .send_command(&server_id, &command)
                     .map_err(|e| mlua::Error::runtime(format!("发送命令失败: {}", e)))?;
 
@@ -125,17 +128,20 @@ impl PluginRuntime {
             .set("send", send_fn)
             .map_err(|e| format!("Failed to set console.send: {}", e))?;
 
+        let backend = self.server_backend.clone();
+        let perms = permissions.clone();
         let get_logs_fn = self
             .lua
             .create_function(move |lua, (server_id, count): (String, Option<usize>)| {
-                let servers = server_manager().get_server_list();
+                perms.require("console")?;
+                let servers = backend.list_servers();
                 if !servers.iter().any(|s| s.id == server_id) {
                     return Err(mlua::Error::runtime(format!("服务器不存在: {}", server_id)));
                 }
 
                 let count = count.unwrap_or(100).min(1000);
 
-                let all_logs = crate::services::server_log_pipeline::get_logs(&server_id, 0, None);
+                let all_logs = backend.logs(&server_id);
 
                 let start = if all_logs.len() > count {
                     all_logs.len() - count
@@ -158,16 +164,18 @@ impl PluginRuntime {
             .set("get_logs", get_logs_fn)
             .map_err(|e| format!("Failed to set console.get_logs: {}", e))?;
 
+        let backend = self.server_backend.clone();
+        let perms = permissions.clone();
         let get_status_fn = self
             .lua
             .create_function(move |_, server_id: String| {
-                let servers = server_manager().get_server_list();
+                perms.require("console")?;
+                let servers = backend.list_servers();
                 if !servers.iter().any(|s| s.id == server_id) {
                     return Err(mlua::Error::runtime(format!("服务器不存在: {}", server_id)));
                 }
 
-                let status = server_manager().get_server_status(&server_id);
-                let status_str = match status.status {
+                let status_str = match backend.server_status(&server_id) {
                     crate::models::server::ServerStatus::Running => "running",
                     crate::models::server::ServerStatus::Stopped => "stopped",
                     crate::models::server::ServerStatus::Starting => "starting",
@@ -0,0 +1,593 @@
+use super::{PermissionSet, PluginRuntime};
+use mlua::{Function, Lua, MultiValue, Table, Value};
+use rusqlite::hooks::{AuthAction, AuthContext, Authorization};
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{params, Connection, ErrorCode};
+use serde_json::{Map, Value as JsonValue};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// 插件专属 SQLite 数据库（plugin_data/<id>/plugin.db）：
+// 1) sl.db 提供参数化查询与事务，sl.storage 的键值也存放在同一数据库的 _sl_kv 表中
+// 2) 首次打开时把旧的 storage.json 导入 _sl_kv，随后改名为 storage.json.migrated
+// 3) 通过 max_page_count 限制数据库大小；插件语句执行期间由 SQLite authorizer 拒绝 PRAGMA、ATTACH / DETACH
+//    与事务控制，检查作用于 SQLite 实际编译的语句（VACUUM 内部的 ATTACH 同样被拒绝），插件无法绕过配额或访问其他文件
+// 4) 连接按需打开，插件 cleanup 时关闭，避免 Windows 上删除插件数据时文件被占用
+
+const DB_FILE: &str = "plugin.db";
+const LEGACY_STORAGE_FILE: &str = "storage.json";
+const MAX_DB_SIZE: u64 = 64 * 1024 * 1024;
+const MAX_QUERY_ROWS: usize = 10_000;
+const MAX_VALUE_SIZE: usize = 1_048_576;
+
+pub(crate) struct PluginDatabase {
+    db_path: PathBuf,
+    legacy_storage_path: PathBuf,
+    conn: Mutex<Option<Connection>>,
+    /// 正在执行插件提供的 SQL；内部的 PRAGMA 与 sl.db.transaction 的事务语句不受 authorizer 限制
+    running_plugin_sql: Arc<AtomicBool>,
+}
+
+impl PluginDatabase {
+    pub(crate) fn new(data_dir: &Path) -> Self {
+        Self {
+            db_path: data_dir.join(DB_FILE),
+            legacy_storage_path: data_dir.join(LEGACY_STORAGE_FILE),
+            conn: Mutex::new(None),
+            running_plugin_sql: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    fn with_conn<T>(
+        &self,
+        f: impl FnOnce(&mut Connection) -> Result<T, String>,
+    ) -> Result<T, String> {
+        let mut guard = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.is_none() {
+            *guard = Some(self.open()?);
+        }
+        match guard.as_mut() {
+            Some(conn) => f(conn),
+            None => Err("插件数据库未打开".to_string()),
+        }
+    }
+
+    fn open(&self) -> Result<Connection, String> {
+        let mut conn =
+            Connection::open(&self.db_path).map_err(|e| format!("打开插件数据库失败: {}", e))?;
+        conn.busy_timeout(Duration::from_millis(2000))
+            .map_err(|e| format!("设置数据库参数失败: {}", e))?;
+        conn.pragma_update(None, "journal_mode", "WAL")
+            .map_err(|e| format!("设置数据库参数失败: {}", e))?;
+        let page_size: u64 = conn
+            .pragma_query_value(None, "page_size", |row| row.get(0))
+            .map_err(|e| format!("读取数据库参数失败: {}", e))?;
+        conn.pragma_update(None, "max_page_count", MAX_DB_SIZE / page_size.max(512))
+            .map_err(|e| format!("设置数据库配额失败: {}", e))?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS _sl_kv (key TEXT PRIMARY KEY NOT NULL, value TEXT NOT NULL)",
+        )
+        .map_err(|e| format!("初始化插件数据库失败: {}", e))?;
+
+        self.migrate_legacy_storage(&mut conn)?;
+        let running_plugin_sql = Arc::clone(&self.running_plugin_sql);
+        conn.authorizer(Some(move |ctx: AuthContext<'_>| {
+            if running_plugin_sql.load(Ordering::SeqCst) && is_forbidden_action(&ctx.action) {
+                Authorization::Deny
+            } else {
+                Authorization::Allow
+            }
+        }));
+        Ok(conn)
+    }
+
+    /// 执行插件提供的 SQL，期间 authorizer 生效（覆盖编译与执行两个阶段）
+    fn with_plugin_sql<T>(
+        &self,
+        f: impl FnOnce(&mut Connection) -> Result<T, String>,
+    ) -> Result<T, String> {
+        self.with_conn(|conn| {
+            self.running_plugin_sql.store(true, Ordering::SeqCst);
+            let result = f(conn);
+            self.running_plugin_sql.store(false, Ordering::SeqCst);
+            result
+        })
+    }
+
+    fn migrate_legacy_storage(&self, conn: &mut Connection) -> Result<(), String> {
+        let Ok(content) = fs::read_to_string(&self.legacy_storage_path) else {
+            return Ok(());
+        };
+        let data: Map<String, JsonValue> = match serde_json::from_str(&content) {
+            Ok(data) => data,
+            Err(e) => {
+                eprintln!(
+                    "[WARN] Failed to parse legacy storage {}: {}",
+                    self.legacy_storage_path.display(),
+                    e
+                );
+                return Ok(());
+            }
+        };
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| format!("迁移 storage.json 失败: {}", e))?;
+        for (key, value) in &data {
+            // 数据库中已有的键以数据库为准，迁移中断后重试不会覆盖新数据
+            tx.execute(
+                "INSERT OR IGNORE INTO _sl_kv (key, value) VALUES (?1, ?2)",
+                params![key, value.to_string()],
+            )
+            .map_err(|e| format!("迁移 storage.json 失败: {}", map_sql_error(e)))?;
+        }
+        tx.commit()
+            .map_err(|e| format!("迁移 storage.json 失败: {}", e))?;
+
+        let migrated = self.legacy_storage_path.with_extension("json.migrated");
+        fs::rename(&self.legacy_storage_path, &migrated)
+            .map_err(|e| format!("重命名 storage.json 失败: {}", e))
+    }
+
+    pub(crate) fn close(&self) {
+        self.conn.lock().unwrap_or_else(|e| e.into_inner()).take();
+    }
+
+    pub(super) fn kv_get(&self, key: &str) -> Result<Option<JsonValue>, String> {
+        self.with_conn(|conn| {
+            let value: Option<String> = conn
+                .query_row("SELECT value FROM _sl_kv WHERE key = ?1", [key], |row| row.get(0))
+                .map(Some)
+                .or_else(|e| match e {
+                    rusqlite::Error::QueryReturnedNoRows => Ok(None),
+                    e => Err(map_sql_error(e)),
+                })?;
+            Ok(value.and_then(|v| serde_json::from_str(&v).ok()))
+        })
+    }
+
+    pub(super) fn kv_set(&self, key: &str, value: &JsonValue) -> Result<(), String> {
+        let value = value.to_string();
+        if value.len() > MAX_VALUE_SIZE {
+            return Err("Storage value exceeds 1MB limit".to_string());
+        }
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO _sl_kv (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )
+            .map(|_| ())
+            .map_err(map_sql_error)
+        })
+    }
+
+    pub(super) fn kv_remove(&self, key: &str) -> Result<(), String> {
+        self.with_conn(|conn| {
+            conn.execute("DELETE FROM _sl_kv WHERE key = ?1", [key])
+                .map(|_| ())
+                .map_err(map_sql_error)
+        })
+    }
+
+    pub(super) fn kv_keys(&self) -> Result<Vec<String>, String> {
+        self.with_conn(|conn| {
+            let mut stmt = conn
+                .prepare("SELECT key FROM _sl_kv ORDER BY key")
+                .map_err(map_sql_error)?;
+            let keys = stmt
+                .query_map([], |row| row.get(0))
+                .and_then(|rows| rows.collect::<Result<Vec<String>, _>>())
+                .map_err(map_sql_error)?;
+            Ok(keys)
+        })
+    }
+
+    fn size(&self) -> Result<u64, String> {
+        self.with_conn(|conn| {
+            let page_count: u64 = conn
+                .pragma_query_value(None, "page_count", |row| row.get(0))
+                .map_err(map_sql_error)?;
+            let page_size: u64 = conn
+                .pragma_query_value(None, "page_size", |row| row.get(0))
+                .map_err(map_sql_error)?;
+            Ok(page_count * page_size)
+        })
+    }
+}
+
+fn map_sql_error(e: rusqlite::Error) -> String {
+    match &e {
+        rusqlite::Error::SqliteFailure(err, _) if err.code == ErrorCode::DiskFull => {
+            format!("插件数据库超出配额（{} MB）", MAX_DB_SIZE / 1024 / 1024)
+        }
+        rusqlite::Error::SqliteFailure(err, _)
+            if err.code == ErrorCode::AuthorizationForStatementDenied =>
+        {
+            "sl.db 不允许执行 PRAGMA、ATTACH / DETACH、VACUUM 或事务控制语句，事务请使用 sl.db.transaction"
+                .to_string()
+        }
+        _ => format!("SQL 执行失败: {}", e),
+    }
+}
+
+/// 只能通过 sl.db.transaction 管理事务，其余动作可能修改配额或访问数据库以外的文件
+fn is_forbidden_action(action: &AuthAction<'_>) -> bool {
+    matches!(
+        action,
+        AuthAction::Pragma { .. }
+            | AuthAction::Attach { .. }
+            | AuthAction::Detach { .. }
+            | AuthAction::Transaction { .. }
+            | AuthAction::Savepoint { .. }
+    )
+}
+
+fn lua_to_sql(value: &Value) -> mlua::Result<SqlValue> {
+    match value {
+        Value::Nil => Ok(SqlValue::Null),
+        Value::Boolean(b) => Ok(SqlValue::Integer(*b as i64)),
+        Value::Integer(i) => Ok(SqlValue::Integer(*i)),
+        Value::Number(n) => Ok(SqlValue::Real(*n)),
+        Value::String(s) => Ok(match s.to_str() {
+            Ok(text) => SqlValue::Text(text.to_string()),
+            Err(_) => SqlValue::Blob(s.as_bytes().to_vec()),
+        }),
+        other => Err(mlua::Error::runtime(format!("不支持的 SQL 参数类型: {}", other.type_name()))),
+    }
+}
+
+fn sql_to_lua(lua: &Lua, value: ValueRef) -> mlua::Result<Value> {
+    Ok(match value {
+        ValueRef::Null => Value::Nil,
+        ValueRef::Integer(i) => Value::Integer(i),
+        ValueRef::Real(f) => Value::Number(f),
+        ValueRef::Text(t) | ValueRef::Blob(t) => Value::String(lua.create_string(t)?),
+    })
+}
+
+/// 参数表为数组时按位置绑定 ?，否则按名称绑定 :name / @name / $name
+fn bind_params(stmt: &mut rusqlite::Statement, params: Option<Table>) -> mlua::Result<()> {
+    let Some(params) = params else {
+        return Ok(());
+    };
+    let to_err = |e: rusqlite::Error| mlua::Error::runtime(map_sql_error(e));
+
+    let len = params.raw_len();
+    if len > 0 {
+        if len != stmt.parameter_count() {
+            return Err(mlua::Error::runtime(format!(
+                "SQL 需要 {} 个参数，实际提供了 {} 个",
+                stmt.parameter_count(),
+                len
+            )));
+        }
+        for i in 1..=len {
+            let value: Value = params.raw_get(i)?;
+            stmt.raw_bind_parameter(i, lua_to_sql(&value)?)
+                .map_err(to_err)?;
+        }
+        return Ok(());
+    }
+
+    for pair in params.pairs::<String, Value>() {
+        let (name, value) = pair?;
+        let name = if name.starts_with([':', '@', '$']) {
+            name
+        } else {
+            format!(":{}", name)
+        };
+        let index = stmt
+            .parameter_index(&name)
+            .map_err(to_err)?
+            .ok_or_else(|| mlua::Error::runtime(format!("SQL 中不存在参数 {}", name)))?;
+        stmt.raw_bind_parameter(index, lua_to_sql(&value)?)
+            .map_err(to_err)?;
+    }
+    Ok(())
+}
+
+fn db_exec(db: &PluginDatabase, sql: &str, params: Option<Table>) -> mlua::Result<MultiValue> {
+    let (changes, last_id) = db
+        .with_plugin_sql(|conn| {
+            let mut stmt = conn.prepare(sql).map_err(map_sql_error)?;
+            bind_params(&mut stmt, params).map_err(|e| e.to_string())?;
+            let changes = stmt.raw_execute().map_err(map_sql_error)?;
+            Ok((changes, conn.last_insert_rowid()))
+        })
+        .map_err(mlua::Error::runtime)?;
+    Ok(MultiValue::from_vec(vec![
+        Value::Integer(changes as i64),
+        Value::Integer(last_id),
+    ]))
+}
+
+fn db_query(
+    lua: &Lua,
+    db: &PluginDatabase,
+    sql: &str,
+    params: Option<Table>,
+) -> mlua::Result<Table> {
+    let result = lua.create_table()?;
+    db.with_plugin_sql(|conn| {
+        let mut stmt = conn.prepare(sql).map_err(map_sql_error)?;
+        bind_params(&mut stmt, params).map_err(|e| e.to_string())?;
+        let columns: Vec<String> = stmt.column_names().iter().map(|c| c.to_string()).collect();
+        let mut rows = stmt.raw_query();
+        let mut count = 0;
+        while let Some(row) = rows.next().map_err(map_sql_error)? {
+            count += 1;
+            if count > MAX_QUERY_ROWS {
+                return Err(format!("查询结果超过 {} 行，请使用 LIMIT 分页", MAX_QUERY_ROWS));
+            }
+            let row_table = lua.create_table().map_err(|e| e.to_string())?;
+            for (i, column) in columns.iter().enumerate() {
+                let value = row.get_ref(i).map_err(map_sql_error)?;
+                row_table
+                    .set(column.as_str(), sql_to_lua(lua, value).map_err(|e| e.to_string())?)
+                    .map_err(|e| e.to_string())?;
+            }
+            result.raw_push(row_table).map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    })
+    .map_err(mlua::Error::runtime)?;
+    Ok(result)
+}
+
+/// 在事务中执行回调：正常返回时提交，出错时回滚并把错误继续抛给插件
+fn db_transaction(db: &PluginDatabase, callback: Function) -> mlua::Result<MultiValue> {
+    db.with_conn(|conn| {
+        if !conn.is_autocommit() {
+            return Err("sl.db.transaction 不支持嵌套".to_string());
+        }
+        conn.execute_batch("BEGIN IMMEDIATE").map_err(map_sql_error)
+    })
+    .map_err(mlua::Error::runtime)?;
+
+    match callback.call::<MultiValue>(()) {
+        Ok(values) => {
+            db.with_conn(|conn| conn.execute_batch("COMMIT").map_err(map_sql_error))
+                .map_err(|e| {
+                    let _ =
+                        db.with_conn(|conn| conn.execute_batch("ROLLBACK").map_err(map_sql_error));
+                    mlua::Error::runtime(e)
+                })?;
+            Ok(values)
+        }
+        Err(e) => {
+            let _ = db.with_conn(|conn| conn.execute_batch("ROLLBACK").map_err(map_sql_error));
+            Err(e)
+        }
+    }
+}
+
+fn build_db_table(
+    lua: &Lua,
+    db: Arc<PluginDatabase>,
+    permissions: PermissionSet,
+) -> mlua::Result<Table> {
+    let table = lua.create_table()?;
+
+    let exec_db = Arc::clone(&db);
+    let perms = permissions.clone();
+    table.set(
+        "exec",
+        lua.create_function(move |_, (sql, params): (String, Option<Table>)| {
+            perms.require("storage")?;
+            db_exec(&exec_db, &sql, params)
+        })?,
+    )?;
+
+    let query_db = Arc::clone(&db);
+    let perms = permissions.clone();
+    table.set(
+        "query",
+        lua.create_function(move |lua, (sql, params): (String, Option<Table>)| {
+            perms.require("storage")?;
+            db_query(lua, &query_db, &sql, params)
+        })?,
+    )?;
+
+    let query_one_db = Arc::clone(&db);
+    let perms = permissions.clone();
+    table.set(
+        "query_one",
+        lua.create_function(move |lua, (sql, params): (String, Option<Table>)| {
+            perms.require("storage")?;
+            let rows = db_query(lua, &query_one_db, &sql, params)?;
+            rows.raw_get::<Value>(1)
+        })?,
+    )?;
+
+    let tx_db = Arc::clone(&db);
+    let perms = permissions.clone();
+    table.set(
+        "transaction",
+        lua.create_function(move |_, callback: Function| {
+            perms.require("storage")?;
+            db_transaction(&tx_db, callback)
+        })?,
+    )?;
+
+    let size_db = Arc::clone(&db);
+    let perms = permissions.clone();
+    table.set(
+        "size",
+        lua.create_function(move |_, ()| {
+            perms.require("storage")?;
+            let used = size_db.size().map_err(mlua::Error::runtime)?;
+            Ok((used, MAX_DB_SIZE))
+        })?,
+    )?;
+
+    Ok(table)
+}
+
+impl PluginRuntime {
+    pub(super) fn setup_db_namespace(&self, sl: &Table) -> Result<(), String> {
+        let db = build_db_table(&self.lua, Arc::clone(&self.database), self.permissions.clone())
+            .map_err(|e| format!("Failed to create db table: {}", e))?;
+        sl.set("db", db)
+            .map_err(|e| format!("Failed to set sl.db: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "sealantern_test_db_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// 创建带 storage 权限的 sl.db，挂在全局变量 db 上
+    fn lua_with_db(dir: &Path) -> (Lua, Arc<PluginDatabase>) {
+        let lua = Lua::new();
+        let db = Arc::new(PluginDatabase::new(dir));
+        let table =
+            build_db_table(&lua, Arc::clone(&db), PermissionSet::new(vec!["storage".to_string()]))
+                .unwrap();
+        lua.globals().set("db", table).unwrap();
+        (lua, db)
+    }
+
+    #[test]
+    fn test_legacy_storage_is_migrated_into_kv_table() {
+        let dir = make_temp_dir("migrate");
+        fs::write(dir.join(LEGACY_STORAGE_FILE), r#"{"coins": 42, "name": "Steve"}"#).unwrap();
+
+        let db = PluginDatabase::new(&dir);
+        assert_eq!(db.kv_get("coins").unwrap(), Some(serde_json::json!(42)));
+        assert_eq!(db.kv_keys().unwrap(), vec!["coins".to_string(), "name".to_string()]);
+        assert!(!dir.join(LEGACY_STORAGE_FILE).exists());
+        assert!(dir.join("storage.json.migrated").exists());
+        db.close();
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_kv_values_are_stored_as_json_in_the_database() {
+        let dir = make_temp_dir("kv");
+        let (lua, db) = lua_with_db(&dir);
+        db.kv_set("coins", &serde_json::json!(43)).unwrap();
+        db.kv_set("gone", &serde_json::json!(true)).unwrap();
+        db.kv_remove("gone").unwrap();
+        assert_eq!(db.kv_get("gone").unwrap(), None);
+        assert_eq!(db.kv_keys().unwrap(), vec!["coins".to_string()]);
+        assert!(db
+            .kv_set("big", &serde_json::json!("x".repeat(MAX_VALUE_SIZE)))
+            .is_err());
+
+        lua.load(
+            r#"assert(db.query_one("SELECT value FROM _sl_kv WHERE key = ?", { "coins" }).value == "43")"#,
+        )
+        .exec()
+        .unwrap();
+        db.close();
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_exec_and_query_bind_positional_and_named_params() {
+        let dir = make_temp_dir("params");
+        let (lua, db) = lua_with_db(&dir);
+        lua.load(
+            r#"
+            db.exec("CREATE TABLE stats (player TEXT PRIMARY KEY, kills INTEGER)")
+            db.exec("INSERT INTO stats VALUES (?, ?)", { "Alex", 3 })
+            local changes, id = db.exec("INSERT INTO stats VALUES (:p, :k)", { p = "Steve", k = 7 })
+            assert(changes == 1 and id == 2)
+
+            local rows = db.query("SELECT * FROM stats WHERE kills > ? ORDER BY kills", { 1 })
+            assert(#rows == 2 and rows[2].player == "Steve" and rows[2].kills == 7)
+            assert(db.query_one("SELECT * FROM stats WHERE player = @p", { ["@p"] = "Alex" }).kills == 3)
+            assert(db.query_one("SELECT * FROM stats WHERE kills > 100") == nil)
+
+            assert(not pcall(db.exec, "INSERT INTO stats VALUES (?, ?)", { "Bob" }))
+            assert(not pcall(db.query, "SELECT * FROM stats WHERE player = :nope", { p = "Alex" }))
+            "#,
+        )
+        .exec()
+        .unwrap();
+        db.close();
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_transaction_commits_on_success_and_rolls_back_on_error() {
+        let dir = make_temp_dir("transaction");
+        let (lua, db) = lua_with_db(&dir);
+        lua.load(
+            r#"
+            db.exec("CREATE TABLE stats (player TEXT PRIMARY KEY, kills INTEGER)")
+            db.exec("INSERT INTO stats VALUES ('Alex', 3), ('Steve', 7)")
+
+            local ok = pcall(db.transaction, function()
+                db.exec("UPDATE stats SET kills = 100")
+                error("abort")
+            end)
+            assert(not ok)
+            assert(db.query_one("SELECT max(kills) AS k FROM stats").k == 7)
+
+            db.transaction(function() db.exec("DELETE FROM stats WHERE player = 'Alex'") end)
+            assert(db.query_one("SELECT count(*) AS n FROM stats").n == 1)
+
+            local ok, err = pcall(db.transaction, function()
+                db.transaction(function() end)
+            end)
+            assert(not ok and tostring(err):find("不支持嵌套", 1, true), tostring(err))
+            "#,
+        )
+        .exec()
+        .unwrap();
+        db.close();
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_authorizer_rejects_statements_that_escape_the_database() {
+        let dir = make_temp_dir("authorizer");
+        let (lua, db) = lua_with_db(&dir);
+        lua.load(
+            r#"
+            for _, sql in ipairs({
+                "PRAGMA max_page_count = 1000000",
+                "  /* comment */ -- line\n pragma main.max_page_count = 1000000",
+                "ATTACH DATABASE 'other.db' AS o",
+                "DETACH DATABASE main",
+                "VACUUM",
+                "VACUUM INTO 'copy.db'",
+                "BEGIN",
+                "COMMIT",
+                "SAVEPOINT s",
+            }) do
+                local ok, err = pcall(db.exec, sql)
+                assert(not ok, sql)
+                assert(tostring(err):find("sl.db 不允许执行", 1, true), tostring(err))
+            end
+
+            db.transaction(function() db.exec("CREATE TABLE t (v INTEGER)") end)
+            db.exec("INSERT INTO t VALUES (1)")
+            assert(db.query_one("SELECT count(*) AS n FROM t").n == 1)
+            "#,
+        )
+        .exec()
+        .unwrap();
+
+        assert!(db.size().unwrap() > 0);
+        assert!(!dir.join("other.db").exists());
+        assert!(!dir.join("copy.db").exists());
+        db.close();
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -15,11 +15,14 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to create element table: {}", e))?;
 
         let plugin_id = self.plugin_id.clone();
+        let permissions = self.permissions.clone();
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let get_text_fn = self
             .lua
             .create_function(move |lua, selector: mlua::String| {
+                perms.require("element")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                 let _ = emit_permission_log(&pid, "api_call", "sl.element.get_text", &selector);
 
@@ -58,9 +61,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set element.get_text: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let get_value_fn = self
             .lua
             .create_function(move |lua, selector: mlua::String| {
+                perms.require("element")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                 let _ = emit_permission_log(&pid, "api_call", "sl.element.get_value", &selector);
 
@@ -99,9 +104,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set element.get_value: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let get_attribute_fn = self
             .lua
             .create_function(move |lua, (selector, attr): (mlua::String, mlua::String)| {
+                perms.require("element")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                 let attr = String::from_utf8_lossy(&attr.as_bytes()).into_owned();
                 let _ = emit_permission_log(
@@ -146,9 +153,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set element.get_attribute: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let get_attributes_fn = self
             .lua
             .create_function(move |lua, selector: mlua::String| {
+                perms.require("element")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                 let _ =
                     emit_permission_log(&pid, "api_call", "sl.element.get_attributes", &selector);
@@ -188,9 +197,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set element.get_attributes: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let click_fn = self
             .lua
             .create_function(move |_, selector: mlua::String| {
+                perms.require("element")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                 let _ = emit_permission_log(&pid, "api_call", "sl.element.click", &selector);
                 let data = serde_json::json!({}).to_string();
@@ -208,9 +219,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set element.click: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let set_value_fn = self
             .lua
             .create_function(move |_, (selector, value): (mlua::String, mlua::String)| {
+                perms.require("element")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                 let value = String::from_utf8_lossy(&value.as_bytes()).into_owned();
                 let _ = emit_permission_log(
@@ -234,9 +247,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set element.set_value: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let check_fn = self
             .lua
             .create_function(move |_, (selector, checked): (mlua::String, bool)| {
+                perms.require("element")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                 let _ = emit_permission_log(
                     &pid,
@@ -259,9 +274,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set element.check: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let select_fn = self
             .lua
             .create_function(move |_, (selector, value): (mlua::String, mlua::String)| {
+                perms.require("element")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                 let value = String::from_utf8_lossy(&value.as_bytes()).into_owned();
                 let _ = emit_permission_log(
@@ -285,9 +302,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set element.select: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let focus_fn = self
             .lua
             .create_function(move |_, selector: mlua::String| {
+                perms.require("element")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                 let _ = emit_permission_log(&pid, "api_call", "sl.element.focus", &selector);
                 let data = serde_json::json!({}).to_string();
@@ -305,9 +324,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set element.focus: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let blur_fn = self
             .lua
             .create_function(move |_, selector: mlua::String| {
+                perms.require("element")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                 let _ = emit_permission_log(&pid, "api_call", "sl.element.blur", &selector);
                 let data = serde_json::json!({}).to_string();
@@ -326,9 +347,11 @@ impl PluginRuntime {
 
         let lua_weak = self.lua.clone();
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let on_change_fn = self
             .lua
             .create_function(move |_, (selector, callback): (mlua::String, mlua::Function)| {
+                perms.require("element")?;
                 let selector_str = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                 let _ =
                     emit_permission_log(&pid, "api_call", "sl.element.on_change", &selector_str);
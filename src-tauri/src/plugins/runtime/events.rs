@@ -0,0 +1,257 @@
+use super::helpers::lua_value_from_json;
+use super::PluginRuntime;
+use crate::plugins::api::ServerEvent;
+use mlua::{Function, Table, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+const EVENT_CALLBACKS_KEY: &str = "_sl_event_callbacks";
+
+/// 可订阅的事件及所需权限
+const SUPPORTED_EVENTS: &[(&str, &str)] = &[
+    ("server.log", "console"),
+    ("player.join", "console"),
+    ("player.leave", "console"),
+    ("player.chat", "console"),
+    ("server.starting", "server"),
+    ("server.ready", "server"),
+    ("server.stopped", "server"),
+    ("server.crashed", "server"),
+];
+
+/// 高频事件每个插件每秒最多分发的次数，超出部分丢弃
+const MAX_HIGH_FREQUENCY_EVENTS_PER_SECOND: u32 = 100;
+const MAX_SUBSCRIPTIONS_PER_PLUGIN: usize = 64;
+
+fn is_high_frequency(event: &str) -> bool {
+    matches!(event, "server.log" | "player.chat")
+}
+
+struct EventSubscription {
+    event: String,
+    server_id: Option<String>,
+}
+
+pub(crate) struct EventState {
+    subscriptions: HashMap<u64, EventSubscription>,
+    next_id: u64,
+    window_start: Instant,
+    window_count: u32,
+    dropped: u64,
+}
+
+impl EventState {
+    pub(crate) fn new() -> Self {
+        Self {
+            subscriptions: HashMap::new(),
+            next_id: 1,
+            window_start: Instant::now(),
+            window_count: 0,
+            dropped: 0,
+        }
+    }
+
+    /// 返回 (是否允许分发, 上个窗口被丢弃的数量)
+    fn take_rate_slot(&mut self) -> (bool, u64) {
+        let mut reported = 0;
+        if self.window_start.elapsed() >= Duration::from_secs(1) {
+            reported = std::mem::take(&mut self.dropped);
+            self.window_start = Instant::now();
+            self.window_count = 0;
+        }
+        if self.window_count >= MAX_HIGH_FREQUENCY_EVENTS_PER_SECOND {
+            self.dropped += 1;
+            return (false, reported);
+        }
+        self.window_count += 1;
+        (true, reported)
+    }
+}
+
+impl PluginRuntime {
+    pub(super) fn setup_events_namespace(&self, sl: &Table) -> Result<(), String> {
+        use crate::plugins::api::emit_permission_log;
+
+        let events_table = self
+            .lua
+            .create_table()
+            .map_err(|e| format!("Failed to create events table: {}", e))?;
+
+        let callbacks = self
+            .lua
+            .create_table()
+            .map_err(|e| format!("Failed to create event callbacks table: {}", e))?;
+        self.lua
+            .set_named_registry_value(EVENT_CALLBACKS_KEY, callbacks)
+            .map_err(|e| format!("Failed to store event callbacks: {}", e))?;
+
+        let plugin_id = self.plugin_id.clone();
+        let permissions = self.permissions.clone();
+        let state = self.event_state.clone();
+        let on_fn = self
+            .lua
+            .create_function(move |lua, (event, filter, callback): (String, Value, Value)| {
+                let (server_id, callback) = match (filter, callback) {
+                    (Value::Function(f), _) => (None, f),
+                    (Value::String(s), Value::Function(f)) => (Some(s.to_str()?.to_string()), f),
+                    (Value::Table(t), Value::Function(f)) => {
+                        (t.get::<Option<String>>("server_id")?, f)
+                    }
+                    _ => {
+                        return Err(mlua::Error::runtime(
+                            "用法: sl.events.on(event, [server_id], callback)",
+                        ))
+                    }
+                };
+
+                let required = SUPPORTED_EVENTS
+                    .iter()
+                    .find(|(name, _)| *name == event)
+                    .map(|(_, permission)| *permission)
+                    .ok_or_else(|| mlua::Error::runtime(format!("不支持的事件: {}", event)))?;
+                if !permissions.has(required) {
+                    return Err(mlua::Error::runtime(format!(
+                        "权限不足: 订阅 '{}' 事件需要在 manifest.json 中声明 '{}' 权限",
+                        event, required
+                    )));
+                }
+
+                let _ = emit_permission_log(&plugin_id, "api_call", "sl.events.on", &event);
+
+                let id = {
+                    let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                    if state.subscriptions.len() >= MAX_SUBSCRIPTIONS_PER_PLUGIN {
+                        return Err(mlua::Error::runtime(format!(
+                            "事件订阅数量超过上限 {}",
+                            MAX_SUBSCRIPTIONS_PER_PLUGIN
+                        )));
+                    }
+                    let id = state.next_id;
+                    state.next_id += 1;
+                    state
+                        .subscriptions
+                        .insert(id, EventSubscription { event, server_id });
+                    id
+                };
+
+                let callbacks: Table = lua.named_registry_value(EVENT_CALLBACKS_KEY)?;
+                callbacks.set(id, callback)?;
+                Ok(id)
+            })
+            .map_err(|e| format!("Failed to create events.on: {}", e))?;
+        events_table
+            .set("on", on_fn)
+            .map_err(|e| format!("Failed to set events.on: {}", e))?;
+
+        let state = self.event_state.clone();
+        let off_fn = self
+            .lua
+            .create_function(move |lua, id: u64| {
+                let removed = state
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .subscriptions
+                    .remove(&id)
+                    .is_some();
+                if removed {
+                    let callbacks: Table = lua.named_registry_value(EVENT_CALLBACKS_KEY)?;
+                    callbacks.set(id, Value::Nil)?;
+                }
+                Ok(removed)
+            })
+            .map_err(|e| format!("Failed to create events.off: {}", e))?;
+        events_table
+            .set("off", off_fn)
+            .map_err(|e| format!("Failed to set events.off: {}", e))?;
+
+        sl.set("events", events_table)
+            .map_err(|e| format!("Failed to set sl.events: {}", e))?;
+
+        Ok(())
+    }
+
+    /// 将服务器事件分发给本插件中匹配的订阅
+    pub fn dispatch_server_event(&self, event: &ServerEvent) -> Result<(), String> {
+        // 订阅后权限可能已被撤销
+        let permitted = SUPPORTED_EVENTS
+            .iter()
+            .find(|(name, _)| *name == event.event)
+            .is_some_and(|(_, permission)| self.permissions.has(permission));
+        if !permitted {
+            return Ok(());
+        }
+
+        let matched: Vec<u64> = {
+            let mut state = self.event_state.lock().unwrap_or_else(|e| e.into_inner());
+            let matched: Vec<u64> = state
+                .subscriptions
+                .iter()
+                .filter(|(_, sub)| {
+                    sub.event == event.event
+                        && sub
+                            .server_id
+                            .as_ref()
+                            .is_none_or(|id| *id == event.server_id)
+                })
+                .map(|(id, _)| *id)
+                .collect();
+            if matched.is_empty() {
+                return Ok(());
+            }
+
+            if is_high_frequency(&event.event) {
+                let (allowed, dropped) = state.take_rate_slot();
+                if dropped > 0 {
+                    let _ = crate::plugins::api::emit_log_event(
+                        &self.plugin_id,
+                        "warn",
+                        &format!("事件过于频繁，上一秒丢弃了 {} 个事件", dropped),
+                    );
+                }
+                if !allowed {
+                    return Ok(());
+                }
+            }
+            matched
+        };
+
+        let callbacks: Table = self
+            .lua
+            .named_registry_value(EVENT_CALLBACKS_KEY)
+            .map_err(|e| format!("获取事件回调失败: {}", e))?;
+        let payload = lua_value_from_json(&self.lua, &event.payload, 0)
+            .map_err(|e| format!("转换事件数据失败: {}", e))?;
+
+        let mut errors = Vec::new();
+        for id in matched {
+            if let Ok(callback) = callbacks.get::<Function>(id) {
+                if let Err(e) = self.with_budget(super::limits::CALL_TIME_BUDGET, || {
+                    callback
+                        .call::<()>(payload.clone())
+                        .map_err(|e| e.to_string())
+                }) {
+                    errors.push(format!("{}: {}", event.event, e));
+                }
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors.join("; "))
+        }
+    }
+
+    pub(super) fn clear_event_subscriptions(&self) {
+        self.event_state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .subscriptions
+            .clear();
+        if let Ok(table) = self.lua.create_table() {
+            let _ = self
+                .lua
+                .set_named_registry_value(EVENT_CALLBACKS_KEY, table);
+        }
+    }
+}
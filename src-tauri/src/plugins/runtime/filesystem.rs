@@ -25,11 +25,7 @@ impl PluginRuntime {
         let read_fn = self
             .lua
             .create_function(move |_, path: String| {
-                if !perms.iter().any(|p| p == "fs") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'fs' permission required",
-                    ));
-                }
+                perms.require("fs")?;
                 let full_path = validate_path_static(&dir, &path)?;
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.fs.read", &path);
@@ -54,11 +50,7 @@ impl PluginRuntime {
         let read_binary_fn = self
             .lua
             .create_function(move |_, path: String| {
-                if !perms.iter().any(|p| p == "fs") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'fs' permission required",
-                    ));
-                }
+                perms.require("fs")?;
                 let full_path = validate_path_static(&dir, &path)?;
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.fs.read_binary", &path);
@@ -84,11 +76,7 @@ impl PluginRuntime {
         let write_fn = self
             .lua
             .create_function(move |_, (path, content): (String, String)| {
-                if !perms.iter().any(|p| p == "fs") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'fs' permission required",
-                    ));
-                }
+                perms.require("fs")?;
 
                 if content.len() > 10 * 1024 * 1024 {
                     return Err(mlua::Error::runtime("Content too large (max 10MB)"));
@@ -115,11 +103,7 @@ impl PluginRuntime {
         let exists_fn = self
             .lua
             .create_function(move |_, path: String| {
-                if !perms.iter().any(|p| p == "fs") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'fs' permission required",
-                    ));
-                }
+                perms.require("fs")?;
                 let full_path = validate_path_static(&dir, &path)?;
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.fs.exists", &path);
@@ -136,11 +120,7 @@ impl PluginRuntime {
         let list_fn = self
             .lua
             .create_function(move |lua, path: String| {
-                if !perms.iter().any(|p| p == "fs") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'fs' permission required",
-                    ));
-                }
+                perms.require("fs")?;
                 let full_path = validate_path_static(&dir, &path)?;
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.fs.list", &path);
@@ -168,11 +148,7 @@ impl PluginRuntime {
         let mkdir_fn = self
             .lua
             .create_function(move |_, path: String| {
-                if !perms.iter().any(|p| p == "fs") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'fs' permission required",
-                    ));
-                }
+                perms.require("fs")?;
                 let full_path = validate_path_static(&dir, &path)?;
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.fs.mkdir", &path);
@@ -190,11 +166,7 @@ impl PluginRuntime {
         let remove_fn = self
             .lua
             .create_function(move |_, path: String| {
-                if !perms.iter().any(|p| p == "fs") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'fs' permission required",
-                    ));
-                }
+                perms.require("fs")?;
                 let full_path = validate_path_static(&dir, &path)?;
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.fs.remove", &path);
@@ -217,11 +189,7 @@ impl PluginRuntime {
         let info_fn = self
             .lua
             .create_function(move |lua, path: String| {
-                if !perms.iter().any(|p| p == "fs") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'fs' permission required",
-                    ));
-                }
+                perms.require("fs")?;
                 let full_path = validate_path_static(&dir, &path)?;
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.fs.info", &path);
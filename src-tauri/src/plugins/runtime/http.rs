@@ -1,8 +1,34 @@
 use super::helpers::json_value_from_lua;
+use super::net::{self, SharedNetState};
 use super::PluginRuntime;
+use crate::plugins::api::NetEventKind;
 use mlua::{Lua, MultiValue, Result as LuaResult, Table, Value};
-
-fn is_ssrf_url(url: &str) -> bool {
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::io::Read;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// sl.http 的网络范围限制：
+// 1) manifest.allowed_hosts 声明可访问的主机（api.example.com、*.example.com）或 URL 前缀
+//    （https://api.example.com/v1/），未声明时保持旧行为，允许访问任意公网地址
+// 2) 每次重定向都重新校验 SSRF 与允许列表；DNS 解析结果同样校验，防止域名重绑定到内网
+// 3) 每个插件保留最近的请求记录（方法、URL、状态、上下行字节数、耗时）
+// 4) sl.http.request 支持 PATCH / HEAD 等方法；提供回调时在后台线程流式读取，数据块经
+//    net::emit 交回插件线程，避免长连接占用插件线程与执行预算
+
+const MAX_RESPONSE_SIZE: u64 = 5 * 1024 * 1024;
+const DEFAULT_TIMEOUT_SECS: u64 = 30;
+const MAX_TIMEOUT_SECS: u64 = 60;
+const MAX_REDIRECTS: usize = 5;
+const MAX_LOG_ENTRIES_PER_PLUGIN: usize = 200;
+const MAX_STREAM_TIMEOUT_SECS: u64 = 3600;
+const MAX_STREAM_SIZE: u64 = 100 * 1024 * 1024;
+const STREAM_CHUNK_SIZE: usize = 16 * 1024;
+
+pub(super) fn is_ssrf_url(url: &str) -> bool {
     let parsed = match url::Url::parse(url) {
         Ok(u) => u,
         Err(_) => return true,
@@ -18,297 +44,585 @@ fn is_ssrf_url(url: &str) -> bool {
         None => return true,
     };
 
-    if host == "::1" || host == "[::1]" {
+    if host == "localhost" {
         return true;
     }
 
-    if host == "localhost" {
-        return true;
+    if let Ok(addr) = host
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse::<IpAddr>()
+    {
+        return is_private_ip(&addr);
     }
 
-    if let Ok(addr) = host.parse::<std::net::Ipv4Addr>() {
-        let octets = addr.octets();
+    false
+}
 
-        if octets[0] == 127 {
-            return true;
+/// 回环、内网、链路本地、运营商 NAT 与未指定地址；用于校验 DNS 解析后的实际地址，
+/// 防止域名重绑定绕过 is_ssrf_url
+pub(super) fn is_private_ip(ip: &IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(addr) => {
+            let octets = addr.octets();
+            addr.is_loopback()
+                || addr.is_private()
+                || addr.is_link_local()
+                || addr.is_unspecified()
+                || addr.is_broadcast()
+                // 0.0.0.0/8 在 Linux 与 macOS 上会连到本机
+                || octets[0] == 0
+                // 100.64.0.0/10 运营商级 NAT
+                || (octets[0] == 100 && (octets[1] & 0xc0) == 64)
         }
+        IpAddr::V6(addr) => match addr.to_ipv4_mapped() {
+            Some(mapped) => is_private_ip(&IpAddr::V4(mapped)),
+            None => {
+                addr.is_loopback()
+                    || addr.is_unspecified()
+                    || addr.is_unicast_link_local()
+                    || addr.is_unique_local()
+            }
+        },
+    }
+}
 
-        if octets[0] == 10 {
-            return true;
-        }
+/// reqwest 的 DNS 解析器：解析结果中有内网或本地地址时拒绝连接，与 WebSocket 的校验保持一致
+struct PublicOnlyResolver;
+
+impl reqwest::dns::Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if let Some(addr) = addrs.iter().find(|addr| is_private_ip(&addr.ip())) {
+                return Err(format!("SSRF: '{}' 解析到内网或本地地址 {}", host, addr.ip()).into());
+            }
+            let addrs: reqwest::dns::Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
 
-        if octets[0] == 172 && octets[1] >= 16 && octets[1] <= 31 {
-            return true;
-        }
+/// 插件声明的网络访问范围，为空表示不限制主机
+#[derive(Clone, Default)]
+pub(crate) struct HostScope {
+    patterns: Arc<Vec<String>>,
+}
 
-        if octets[0] == 192 && octets[1] == 168 {
-            return true;
+impl HostScope {
+    pub(crate) fn new(patterns: Vec<String>) -> Self {
+        Self {
+            patterns: Arc::new(
+                patterns
+                    .into_iter()
+                    .map(|p| p.trim().to_lowercase())
+                    .collect(),
+            ),
         }
     }
 
-    false
+    pub(crate) fn allows(&self, url: &url::Url) -> bool {
+        self.patterns.is_empty() || self.patterns.iter().any(|p| pattern_matches(p, url))
+    }
 }
 
-impl PluginRuntime {
-    pub(super) fn setup_http_namespace(&self, sl: &Table) -> Result<(), String> {
-        use crate::plugins::api::emit_permission_log;
+fn pattern_matches(pattern: &str, url: &url::Url) -> bool {
+    let Some(host) = url.host_str().map(|h| h.to_lowercase()) else {
+        return false;
+    };
 
-        let http_table = self
-            .lua
-            .create_table()
-            .map_err(|e| format!("Failed to create http table: {}", e))?;
+    if pattern.contains("://") {
+        let Ok(prefix) = url::Url::parse(pattern.trim_end_matches('*')) else {
+            return false;
+        };
+        return prefix.scheme() == url.scheme()
+            && prefix.host_str() == Some(host.as_str())
+            && prefix.port_or_known_default() == url.port_or_known_default()
+            && url.path().starts_with(prefix.path());
+    }
 
-        let plugin_id = self.plugin_id.clone();
-        let permissions = self.permissions.clone();
+    match pattern.strip_prefix("*.") {
+        Some(domain) => host.ends_with(&format!(".{}", domain)),
+        None => host == pattern,
+    }
+}
 
-        const MAX_RESPONSE_SIZE: u64 = 5 * 1024 * 1024;
+/// 校验 manifest 中 allowed_hosts 的单个条目
+pub(crate) fn validate_host_pattern(pattern: &str) -> Result<(), String> {
+    let pattern = pattern.trim();
+    if pattern.is_empty() {
+        return Err("allowed_hosts 中不能包含空条目".to_string());
+    }
+    if pattern.contains("://") {
+        let parsed = url::Url::parse(pattern.trim_end_matches('*'))
+            .map_err(|e| format!("allowed_hosts 条目 '{}' 不是有效的 URL: {}", pattern, e))?;
+        if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+            return Err(format!("allowed_hosts 条目 '{}' 必须是 http(s) 地址", pattern));
+        }
+        return Ok(());
+    }
+    let host = pattern.strip_prefix("*.").unwrap_or(pattern);
+    if host.is_empty()
+        || host.contains('*')
+        || !host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '-')
+    {
+        return Err(format!(
+            "allowed_hosts 条目 '{}' 无效，只支持主机名、*.域名 或 http(s) URL 前缀",
+            pattern
+        ));
+    }
+    Ok(())
+}
 
-        let pid = plugin_id.clone();
-        let perms = permissions.clone();
-        let get_fn = self
-            .lua
-            .create_function(move |lua, args: MultiValue| {
-                if !perms.iter().any(|p| p == "network") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'network' permission required",
-                    ));
-                }
-                let url: String = args
-                    .front()
-                    .and_then(|v| match v {
-                        Value::String(s) => s.to_str().ok().map(|s| s.to_string()),
-                        _ => None,
-                    })
-                    .ok_or_else(|| {
-                        mlua::Error::runtime("sl.http.get 第一个参数必须是 URL 字符串")
-                    })?;
-
-                let _ = emit_permission_log(&pid, "api_call", "sl.http.get", &url);
-
-                if is_ssrf_url(&url) {
-                    return Err(mlua::Error::runtime(
-                        "SSRF: 不允许访问内网、本地或非 HTTP(S) 地址",
-                    ));
-                }
+fn load_allowed_hosts(plugin_dir: &std::path::Path) -> Vec<String> {
+    crate::plugins::loader::PluginLoader::load_manifest(plugin_dir)
+        .map(|m| m.allowed_hosts)
+        .unwrap_or_default()
+}
 
-                let (headers, timeout) = parse_http_options(args.get(1))?;
+#[derive(Debug, Clone, Serialize)]
+pub struct HttpRequestLogEntry {
+    pub timestamp: u64,
+    pub method: String,
+    pub url: String,
+    pub final_url: Option<String>,
+    pub status: Option<u16>,
+    pub request_bytes: u64,
+    pub response_bytes: u64,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+}
 
-                let client = reqwest::blocking::Client::builder()
-                    .timeout(std::time::Duration::from_secs(timeout))
-                    .build()
-                    .map_err(|e| mlua::Error::runtime(format!("创建 HTTP 客户端失败: {}", e)))?;
+impl HttpRequestLogEntry {
+    pub(super) fn new(method: &str, url: &str) -> Self {
+        Self {
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            method: method.to_string(),
+            url: url.to_string(),
+            final_url: None,
+            status: None,
+            request_bytes: 0,
+            response_bytes: 0,
+            duration_ms: 0,
+            error: None,
+        }
+    }
+}
 
-                let mut req = client.get(&url);
-                for (k, v) in &headers {
-                    req = req.header(k.as_str(), v.as_str());
-                }
+static HTTP_REQUEST_LOG: OnceLock<Mutex<HashMap<String, VecDeque<HttpRequestLogEntry>>>> =
+    OnceLock::new();
+
+pub(super) fn record_request(plugin_id: &str, entry: HttpRequestLogEntry) {
+    let mut log = HTTP_REQUEST_LOG
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let entries = log.entry(plugin_id.to_string()).or_default();
+    if entries.len() >= MAX_LOG_ENTRIES_PER_PLUGIN {
+        entries.pop_front();
+    }
+    entries.push_back(entry);
+}
 
-                match req.send() {
-                    Ok(resp) => build_response_table(lua, resp, MAX_RESPONSE_SIZE),
-                    Err(e) => {
-                        let nil = Value::Nil;
-                        let err = Value::String(lua.create_string(format!("{}", e))?);
-                        Ok(MultiValue::from_vec(vec![nil, err]))
-                    }
-                }
-            })
-            .map_err(|e| format!("Failed to create http.get: {}", e))?;
-        http_table
-            .set("get", get_fn)
-            .map_err(|e| format!("Failed to set http.get: {}", e))?;
+/// 插件最近的网络请求记录，最新的在前
+pub fn plugin_http_log(plugin_id: &str) -> Vec<HttpRequestLogEntry> {
+    let Some(log) = HTTP_REQUEST_LOG.get() else {
+        return Vec::new();
+    };
+    log.lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(plugin_id)
+        .map(|entries| entries.iter().rev().cloned().collect())
+        .unwrap_or_default()
+}
 
-        let pid = plugin_id.clone();
-        let perms = permissions.clone();
-        let post_fn = self
-            .lua
-            .create_function(move |lua, args: MultiValue| {
-                if !perms.iter().any(|p| p == "network") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'network' permission required",
-                    ));
-                }
-                let url: String = args
-                    .front()
-                    .and_then(|v| match v {
-                        Value::String(s) => s.to_str().ok().map(|s| s.to_string()),
-                        _ => None,
-                    })
-                    .ok_or_else(|| {
-                        mlua::Error::runtime("sl.http.post 第一个参数必须是 URL 字符串")
-                    })?;
-
-                let _ = emit_permission_log(&pid, "api_call", "sl.http.post", &url);
-
-                if is_ssrf_url(&url) {
-                    return Err(mlua::Error::runtime(
-                        "SSRF: 不允许访问内网、本地或非 HTTP(S) 地址",
-                    ));
-                }
+#[derive(Clone, Copy)]
+enum HttpMethod {
+    Get,
+    Post,
+    Put,
+    Delete,
+}
 
-                let (body_str, is_json) = lua_body_to_string(args.get(1))?;
-                let (mut headers, timeout) = parse_http_options(args.get(2))?;
+impl HttpMethod {
+    fn name(self) -> &'static str {
+        match self {
+            HttpMethod::Get => "get",
+            HttpMethod::Post => "post",
+            HttpMethod::Put => "put",
+            HttpMethod::Delete => "delete",
+        }
+    }
 
-                if is_json
-                    && !headers
-                        .iter()
-                        .any(|(k, _)| k.to_lowercase() == "content-type")
-                {
-                    headers.push(("Content-Type".to_string(), "application/json".to_string()));
-                }
+    fn has_body(self) -> bool {
+        matches!(self, HttpMethod::Post | HttpMethod::Put)
+    }
 
-                let client = reqwest::blocking::Client::builder()
-                    .timeout(std::time::Duration::from_secs(timeout))
-                    .build()
-                    .map_err(|e| mlua::Error::runtime(format!("创建 HTTP 客户端失败: {}", e)))?;
+    fn method(self) -> reqwest::Method {
+        match self {
+            HttpMethod::Get => reqwest::Method::GET,
+            HttpMethod::Post => reqwest::Method::POST,
+            HttpMethod::Put => reqwest::Method::PUT,
+            HttpMethod::Delete => reqwest::Method::DELETE,
+        }
+    }
+}
 
-                let mut req = client.post(&url).body(body_str);
-                for (k, v) in &headers {
-                    req = req.header(k.as_str(), v.as_str());
-                }
+/// sl.http.request 支持的方法
+fn parse_request_method(method: &str) -> Result<reqwest::Method, mlua::Error> {
+    match method.to_uppercase().as_str() {
+        "GET" => Ok(reqwest::Method::GET),
+        "POST" => Ok(reqwest::Method::POST),
+        "PUT" => Ok(reqwest::Method::PUT),
+        "DELETE" => Ok(reqwest::Method::DELETE),
+        "PATCH" => Ok(reqwest::Method::PATCH),
+        "HEAD" => Ok(reqwest::Method::HEAD),
+        "OPTIONS" => Ok(reqwest::Method::OPTIONS),
+        other => Err(mlua::Error::runtime(format!("不支持的 HTTP 方法: {}", other))),
+    }
+}
 
-                match req.send() {
-                    Ok(resp) => build_response_table(lua, resp, MAX_RESPONSE_SIZE),
-                    Err(e) => {
-                        let nil = Value::Nil;
-                        let err = Value::String(lua.create_string(format!("{}", e))?);
-                        Ok(MultiValue::from_vec(vec![nil, err]))
-                    }
-                }
+impl PluginRuntime {
+    pub(super) fn setup_http_namespace(&self, sl: &Table) -> Result<(), String> {
+        let http_table = self
+            .lua
+            .create_table()
+            .map_err(|e| format!("Failed to create http table: {}", e))?;
+
+        let scope = HostScope::new(load_allowed_hosts(&self.plugin_dir));
+
+        for method in [HttpMethod::Get, HttpMethod::Post, HttpMethod::Put, HttpMethod::Delete] {
+            let plugin_id = self.plugin_id.clone();
+            let perms = self.permissions.clone();
+            let scope = scope.clone();
+            let request_fn = self
+                .lua
+                .create_function(move |lua, args: MultiValue| {
+                    perms.require("network")?;
+                    perform_request(lua, &plugin_id, &scope, method, args)
+                })
+                .map_err(|e| format!("Failed to create http.{}: {}", method.name(), e))?;
+            http_table
+                .set(method.name(), request_fn)
+                .map_err(|e| format!("Failed to set http.{}: {}", method.name(), e))?;
+        }
+
+        let plugin_id = self.plugin_id.clone();
+        let perms = self.permissions.clone();
+        let request_scope = scope.clone();
+        let state = self.net_state.clone();
+        let request_fn = self
+            .lua
+            .create_function(move |lua, opts: Table| {
+                perms.require("network")?;
+                perform_generic_request(lua, &plugin_id, &request_scope, &state, opts)
             })
-            .map_err(|e| format!("Failed to create http.post: {}", e))?;
+            .map_err(|e| format!("Failed to create http.request: {}", e))?;
         http_table
-            .set("post", post_fn)
-            .map_err(|e| format!("Failed to set http.post: {}", e))?;
+            .set("request", request_fn)
+            .map_err(|e| format!("Failed to set http.request: {}", e))?;
 
-        let pid = plugin_id.clone();
-        let perms = permissions.clone();
-        let put_fn = self
+        let state = self.net_state.clone();
+        let cancel_fn = self
             .lua
-            .create_function(move |lua, args: MultiValue| {
-                if !perms.iter().any(|p| p == "network") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'network' permission required",
-                    ));
-                }
-                let url: String = args
-                    .front()
-                    .and_then(|v| match v {
-                        Value::String(s) => s.to_str().ok().map(|s| s.to_string()),
-                        _ => None,
-                    })
-                    .ok_or_else(|| {
-                        mlua::Error::runtime("sl.http.put 第一个参数必须是 URL 字符串")
-                    })?;
-
-                let _ = emit_permission_log(&pid, "api_call", "sl.http.put", &url);
-
-                if is_ssrf_url(&url) {
-                    return Err(mlua::Error::runtime(
-                        "SSRF: 不允许访问内网、本地或非 HTTP(S) 地址",
-                    ));
-                }
+            .create_function(move |_, handle_id: u64| Ok(net::cancel_handle(&state, handle_id)))
+            .map_err(|e| format!("Failed to create http.cancel: {}", e))?;
+        http_table
+            .set("cancel", cancel_fn)
+            .map_err(|e| format!("Failed to set http.cancel: {}", e))?;
 
-                let (body_str, is_json) = lua_body_to_string(args.get(1))?;
-                let (mut headers, timeout) = parse_http_options(args.get(2))?;
+        sl.set("http", http_table)
+            .map_err(|e| format!("Failed to set sl.http: {}", e))?;
 
-                if is_json
-                    && !headers
-                        .iter()
-                        .any(|(k, _)| k.to_lowercase() == "content-type")
-                {
-                    headers.push(("Content-Type".to_string(), "application/json".to_string()));
-                }
+        self.setup_ws_namespace(sl, scope)
+    }
+}
 
-                let client = reqwest::blocking::Client::builder()
-                    .timeout(std::time::Duration::from_secs(timeout))
-                    .build()
-                    .map_err(|e| mlua::Error::runtime(format!("创建 HTTP 客户端失败: {}", e)))?;
+fn perform_request(
+    lua: &Lua,
+    plugin_id: &str,
+    scope: &HostScope,
+    method: HttpMethod,
+    args: MultiValue,
+) -> LuaResult<MultiValue> {
+    let api_name = format!("sl.http.{}", method.name());
+    let url: String = args
+        .front()
+        .and_then(|v| match v {
+            Value::String(s) => s.to_str().ok().map(|s| s.to_string()),
+            _ => None,
+        })
+        .ok_or_else(|| mlua::Error::runtime(format!("{} 第一个参数必须是 URL 字符串", api_name)))?;
+
+    let (body, is_json, options) = if method.has_body() {
+        let (body, is_json) = lua_body_to_string(args.get(1))?;
+        (body, is_json, args.get(2))
+    } else {
+        (String::new(), false, args.get(1))
+    };
+    let (headers, timeout) = parse_http_options(options)?;
+
+    let prepared = prepare_request(
+        plugin_id,
+        scope,
+        &api_name,
+        method.method(),
+        url,
+        body,
+        is_json,
+        headers,
+        Some(Duration::from_secs(timeout)),
+    )?;
+    execute_buffered(lua, plugin_id, prepared)
+}
 
-                let mut req = client.put(&url).body(body_str);
-                for (k, v) in &headers {
-                    req = req.header(k.as_str(), v.as_str());
-                }
+/// sl.http.request{ method, url, headers, body, timeout, on_response, on_chunk, on_done }
+/// 提供任一回调时以流式方式在后台执行并立即返回请求 id，否则与 sl.http.get 等一样同步返回响应
+fn perform_generic_request(
+    lua: &Lua,
+    plugin_id: &str,
+    scope: &HostScope,
+    state: &SharedNetState,
+    opts: Table,
+) -> LuaResult<MultiValue> {
+    let api_name = "sl.http.request";
+    let url: String = opts
+        .get::<Option<String>>("url")?
+        .ok_or_else(|| mlua::Error::runtime("sl.http.request 缺少 url 字段"))?;
+    let method = parse_request_method(
+        &opts
+            .get::<Option<String>>("method")?
+            .unwrap_or_else(|| "GET".to_string()),
+    )?;
+    let body_value: Value = opts.get("body")?;
+    let (body, is_json) = lua_body_to_string(Some(&body_value))?;
+
+    let options = Value::Table(opts.clone());
+    let (headers, timeout) = parse_http_options(Some(&options))?;
+
+    let callbacks = net::collect_callbacks(lua, &opts, &["on_response", "on_chunk", "on_done"])?;
+    let streaming = callbacks.pairs::<Value, Value>().next().is_some();
+    if !streaming {
+        let prepared = prepare_request(
+            plugin_id,
+            scope,
+            api_name,
+            method,
+            url,
+            body,
+            is_json,
+            headers,
+            Some(Duration::from_secs(timeout)),
+        )?;
+        return execute_buffered(lua, plugin_id, prepared);
+    }
 
-                match req.send() {
-                    Ok(resp) => build_response_table(lua, resp, MAX_RESPONSE_SIZE),
-                    Err(e) => {
-                        let nil = Value::Nil;
-                        let err = Value::String(lua.create_string(format!("{}", e))?);
-                        Ok(MultiValue::from_vec(vec![nil, err]))
-                    }
-                }
-            })
-            .map_err(|e| format!("Failed to create http.put: {}", e))?;
-        http_table
-            .set("put", put_fn)
-            .map_err(|e| format!("Failed to set http.put: {}", e))?;
+    // 长连接（SSE、日志流等）默认不限总时长，显式指定时放宽到 MAX_STREAM_TIMEOUT_SECS
+    let stream_timeout = opts
+        .get::<Option<u64>>("timeout")?
+        .filter(|t| *t > 0)
+        .map(|t| Duration::from_secs(t.min(MAX_STREAM_TIMEOUT_SECS)));
+    let prepared = prepare_request(
+        plugin_id,
+        scope,
+        api_name,
+        method,
+        url,
+        body,
+        is_json,
+        headers,
+        stream_timeout,
+    )?;
+
+    let (handle_id, cancel) = net::register_handle(lua, state, callbacks, None)?;
+    let thread_plugin_id = plugin_id.to_string();
+    std::thread::Builder::new()
+        .name(format!("plugin-http-stream-{}", handle_id))
+        .spawn(move || run_stream(thread_plugin_id, handle_id, prepared, cancel))
+        .map_err(|e| mlua::Error::runtime(format!("启动请求线程失败: {}", e)))?;
+
+    Ok(MultiValue::from_vec(vec![Value::Integer(handle_id as i64)]))
+}
 
-        let pid = plugin_id.clone();
-        let perms = permissions.clone();
-        let delete_fn = self
-            .lua
-            .create_function(move |lua, args: MultiValue| {
-                if !perms.iter().any(|p| p == "network") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'network' permission required",
-                    ));
-                }
-                let url: String = args
-                    .front()
-                    .and_then(|v| match v {
-                        Value::String(s) => s.to_str().ok().map(|s| s.to_string()),
-                        _ => None,
-                    })
-                    .ok_or_else(|| {
-                        mlua::Error::runtime("sl.http.delete 第一个参数必须是 URL 字符串")
-                    })?;
-
-                let _ = emit_permission_log(&pid, "api_call", "sl.http.delete", &url);
-
-                if is_ssrf_url(&url) {
-                    return Err(mlua::Error::runtime(
-                        "SSRF: 不允许访问内网、本地或非 HTTP(S) 地址",
-                    ));
-                }
+struct PreparedRequest {
+    request: reqwest::blocking::RequestBuilder,
+    entry: HttpRequestLogEntry,
+}
 
-                let (headers, timeout) = parse_http_options(args.get(1))?;
+/// 校验 SSRF 与允许列表并构造请求；timeout 为 None 时不限制总时长
+#[allow(clippy::too_many_arguments)]
+fn prepare_request(
+    plugin_id: &str,
+    scope: &HostScope,
+    api_name: &str,
+    method: reqwest::Method,
+    url: String,
+    body: String,
+    is_json: bool,
+    mut headers: Vec<(String, String)>,
+    timeout: Option<Duration>,
+) -> LuaResult<PreparedRequest> {
+    use crate::plugins::api::emit_permission_log;
+
+    let _ = emit_permission_log(plugin_id, "api_call", api_name, &url);
+
+    if is_ssrf_url(&url) {
+        return Err(mlua::Error::runtime("SSRF: 不允许访问内网、本地或非 HTTP(S) 地址"));
+    }
+    let parsed = url::Url::parse(&url).map_err(|e| mlua::Error::runtime(e.to_string()))?;
+    if !scope.allows(&parsed) {
+        let _ = emit_permission_log(plugin_id, "denied", api_name, &url);
+        return Err(mlua::Error::runtime(format!(
+            "网络访问被拒绝: '{}' 不在 manifest.json 的 allowed_hosts 中",
+            parsed.host_str().unwrap_or_default()
+        )));
+    }
 
-                let client = reqwest::blocking::Client::builder()
-                    .timeout(std::time::Duration::from_secs(timeout))
-                    .build()
-                    .map_err(|e| mlua::Error::runtime(format!("创建 HTTP 客户端失败: {}", e)))?;
+    if is_json
+        && !headers
+            .iter()
+            .any(|(k, _)| k.to_lowercase() == "content-type")
+    {
+        headers.push(("Content-Type".to_string(), "application/json".to_string()));
+    }
 
-                let mut req = client.delete(&url);
-                for (k, v) in &headers {
-                    req = req.header(k.as_str(), v.as_str());
-                }
+    let redirect_scope = scope.clone();
+    let mut builder = reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .dns_resolver(Arc::new(PublicOnlyResolver))
+        .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("重定向次数过多")
+            } else if is_ssrf_url(attempt.url().as_str()) {
+                attempt.error("SSRF: 重定向目标为内网、本地或非 HTTP(S) 地址")
+            } else if !redirect_scope.allows(attempt.url()) {
+                attempt.error("重定向目标不在 allowed_hosts 中")
+            } else {
+                attempt.follow()
+            }
+        }));
+    if timeout.is_none() {
+        builder = builder.connect_timeout(Duration::from_secs(DEFAULT_TIMEOUT_SECS));
+    }
+    let client = builder
+        .build()
+        .map_err(|e| mlua::Error::runtime(format!("创建 HTTP 客户端失败: {}", e)))?;
+
+    let mut entry = HttpRequestLogEntry::new(method.as_str(), &url);
+    entry.request_bytes = body.len() as u64;
+    let mut request = client.request(method, &url);
+    if !body.is_empty() {
+        request = request.body(body);
+    }
+    for (k, v) in &headers {
+        request = request.header(k.as_str(), v.as_str());
+    }
 
-                match req.send() {
-                    Ok(resp) => build_response_table(lua, resp, MAX_RESPONSE_SIZE),
-                    Err(e) => {
-                        let nil = Value::Nil;
-                        let err = Value::String(lua.create_string(format!("{}", e))?);
-                        Ok(MultiValue::from_vec(vec![nil, err]))
-                    }
-                }
-            })
-            .map_err(|e| format!("Failed to create http.delete: {}", e))?;
-        http_table
-            .set("delete", delete_fn)
-            .map_err(|e| format!("Failed to set http.delete: {}", e))?;
+    Ok(PreparedRequest { request, entry })
+}
 
-        sl.set("http", http_table)
-            .map_err(|e| format!("Failed to set sl.http: {}", e))?;
+fn execute_buffered(
+    lua: &Lua,
+    plugin_id: &str,
+    prepared: PreparedRequest,
+) -> LuaResult<MultiValue> {
+    let PreparedRequest { request, mut entry } = prepared;
+    let started = Instant::now();
+    // HEAD 响应的 Content-Length 描述的是对应 GET 的大小，不能据此拒绝
+    let is_head = entry.method == "HEAD";
+    let result = request.send().map_err(|e| e.to_string()).and_then(|resp| {
+        entry.final_url = Some(resp.url().to_string());
+        entry.status = Some(resp.status().as_u16());
+        read_response(resp, MAX_RESPONSE_SIZE, !is_head)
+    });
+    entry.duration_ms = started.elapsed().as_millis() as u64;
+
+    let values = match result {
+        Ok((status, headers, body)) => {
+            entry.response_bytes = body.len() as u64;
+            build_response_table(lua, status, headers, &body)?
+        }
+        Err(e) => {
+            entry.error = Some(e.clone());
+            MultiValue::from_vec(vec![Value::Nil, Value::String(lua.create_string(e)?)])
+        }
+    };
+    record_request(plugin_id, entry);
+    Ok(values)
+}
 
-        Ok(())
-    }
+/// 流式请求线程：依次发出 Response、若干 Chunk，最后总会发出一次 Done
+fn run_stream(
+    plugin_id: String,
+    handle_id: u64,
+    prepared: PreparedRequest,
+    cancel: Arc<AtomicBool>,
+) {
+    let PreparedRequest { request, mut entry } = prepared;
+    let started = Instant::now();
+    let mut total = 0u64;
+    let pending = Arc::new(AtomicUsize::new(0));
+
+    let result = request
+        .send()
+        .map_err(|e| e.to_string())
+        .and_then(|mut resp| {
+            entry.final_url = Some(resp.url().to_string());
+            entry.status = Some(resp.status().as_u16());
+            let headers = resp
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    value
+                        .to_str()
+                        .ok()
+                        .map(|v| (name.as_str().to_string(), v.to_string()))
+                })
+                .collect();
+            let status = resp.status().as_u16();
+            net::emit(&plugin_id, handle_id, NetEventKind::Response { status, headers });
+
+            let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+            loop {
+                if cancel.load(Ordering::SeqCst) {
+                    return Err("请求已取消".to_string());
+                }
+                let n = match resp.read(&mut buf) {
+                    Ok(0) => return Ok(()),
+                    Ok(n) => n,
+                    Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(format!("读取响应体失败: {}", e)),
+                };
+                total += n as u64;
+                if total > MAX_STREAM_SIZE {
+                    return Err(format!("响应体过大，限制 {} 字节", MAX_STREAM_SIZE));
+                }
+                let chunk = NetEventKind::Chunk(buf[..n].to_vec());
+                net::emit_data(&plugin_id, handle_id, &pending, &cancel, chunk);
+            }
+        });
+
+    let error = result.err();
+    let done = NetEventKind::Done { bytes: total, error: error.clone() };
+    net::emit_final(&plugin_id, handle_id, &pending, &cancel, done);
+    entry.response_bytes = total;
+    entry.duration_ms = started.elapsed().as_millis() as u64;
+    entry.error = error;
+    record_request(&plugin_id, entry);
 }
 
 fn parse_http_options(
     options: Option<&Value>,
 ) -> Result<(Vec<(String, String)>, u64), mlua::Error> {
     let mut headers = Vec::new();
-    let mut timeout = 30u64;
+    let mut timeout = DEFAULT_TIMEOUT_SECS;
 
     if let Some(Value::Table(opts)) = options {
         if let Ok(Value::Table(h)) = opts.get::<Value>("headers") {
@@ -318,8 +632,8 @@ fn parse_http_options(
         }
 
         if let Ok(t) = opts.get::<u64>("timeout") {
-            if t > 0 && t <= 300 {
-                timeout = t;
+            if t > 0 {
+                timeout = t.min(MAX_TIMEOUT_SECS);
             }
         }
     }
@@ -344,52 +658,204 @@ fn lua_body_to_string(body: Option<&Value>) -> Result<(String, bool), mlua::Erro
     }
 }
 
-fn build_response_table(
-    lua: &Lua,
+type ResponseParts = (u16, Vec<(String, String)>, Vec<u8>);
+
+/// 读取响应体，超过上限立即中止，不依赖 Content-Length
+fn read_response(
     resp: reqwest::blocking::Response,
     max_size: u64,
-) -> LuaResult<MultiValue> {
+    check_content_length: bool,
+) -> Result<ResponseParts, String> {
     let status = resp.status().as_u16();
-
-    let headers_table = lua.create_table()?;
-    for (name, value) in resp.headers().iter() {
-        if let Ok(v) = value.to_str() {
-            headers_table.set(name.as_str().to_string(), v.to_string())?;
+    let headers = resp
+        .headers()
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|v| (name.as_str().to_string(), v.to_string()))
+        })
+        .collect();
+
+    if let Some(len) = resp.content_length().filter(|_| check_content_length) {
+        if len > max_size {
+            return Err(format!("响应体过大: {} 字节，限制 {} 字节", len, max_size));
         }
     }
 
-    if let Some(len) = resp.content_length() {
-        if len > max_size {
-            let nil = Value::Nil;
-            let err = Value::String(
-                lua.create_string(format!("响应体过大: {} 字节，限制 {} 字节", len, max_size))?,
-            );
-            return Ok(MultiValue::from_vec(vec![nil, err]));
-        }
+    let mut body = Vec::new();
+    resp.take(max_size + 1)
+        .read_to_end(&mut body)
+        .map_err(|e| format!("读取响应体失败: {}", e))?;
+    if body.len() as u64 > max_size {
+        return Err(format!("响应体过大，限制 {} 字节", max_size));
     }
 
-    let body_bytes = resp
-        .bytes()
-        .map_err(|e| mlua::Error::runtime(format!("读取响应体失败: {}", e)))?;
+    Ok((status, headers, body))
+}
 
-    if body_bytes.len() as u64 > max_size {
-        let nil = Value::Nil;
-        let err = Value::String(lua.create_string(format!(
-            "响应体过大: {} 字节，限制 {} 字节",
-            body_bytes.len(),
-            max_size
-        ))?);
-        return Ok(MultiValue::from_vec(vec![nil, err]));
+fn build_response_table(
+    lua: &Lua,
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: &[u8],
+) -> LuaResult<MultiValue> {
+    let headers_table = lua.create_table()?;
+    for (name, value) in headers {
+        headers_table.set(name, value)?;
     }
 
-    let body_str = String::from_utf8_lossy(&body_bytes).to_string();
-
     let response_table = lua.create_table()?;
     response_table.set("status", status)?;
-    response_table.set("body", body_str)?;
+    response_table.set("body", String::from_utf8_lossy(body).to_string())?;
     response_table.set("headers", headers_table)?;
 
-    let resp_val = Value::Table(response_table);
-    let nil = Value::Nil;
-    Ok(MultiValue::from_vec(vec![resp_val, nil]))
+    Ok(MultiValue::from_vec(vec![Value::Table(response_table), Value::Nil]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allows(patterns: &[&str], url: &str) -> bool {
+        HostScope::new(patterns.iter().map(|p| p.to_string()).collect())
+            .allows(&url::Url::parse(url).unwrap())
+    }
+
+    /// 本地服务器：读完请求头后一次性返回整个响应体
+    fn spawn_body_server(body: Vec<u8>) -> u16 {
+        use std::io::Write;
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut chunk = [0u8; 1024];
+            while !request.windows(4).any(|w| w == b"\r\n\r\n") {
+                let n = stream.read(&mut chunk).unwrap();
+                request.extend_from_slice(&chunk[..n]);
+            }
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                body.len()
+            );
+            let _ = stream.write_all(head.as_bytes());
+            let _ = stream.write_all(&body);
+        });
+        port
+    }
+
+    #[test]
+    fn streams_chunks_and_pauses_when_plugin_falls_behind() {
+        let body: Vec<u8> = (0..2 * 1024 * 1024u32).map(|i| (i % 251) as u8).collect();
+        let port = spawn_body_server(body.clone());
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let tx = Mutex::new(tx);
+        crate::plugins::api::set_net_event_handler(Arc::new(move |event| {
+            if event.plugin_id == "stream-test" {
+                let _ = tx.lock().unwrap().send(event);
+            }
+            Ok(())
+        }));
+
+        let url = format!("http://127.0.0.1:{}/stream", port);
+        let prepared = PreparedRequest {
+            request: reqwest::blocking::Client::new().get(&url),
+            entry: HttpRequestLogEntry::new("GET", &url),
+        };
+        let cancel = Arc::new(AtomicBool::new(false));
+        std::thread::spawn(move || run_stream("stream-test".to_string(), 1, prepared, cancel));
+
+        // 插件线程不处理事件时，后台线程在待处理上限处停下
+        let mut held = Vec::new();
+        while held.len() <= net::MAX_PENDING_EVENTS_PER_HANDLE {
+            held.push(rx.recv_timeout(Duration::from_secs(5)).unwrap());
+        }
+        assert!(rx.recv_timeout(Duration::from_millis(300)).is_err());
+        assert!(matches!(held[0].kind, NetEventKind::Response { status: 200, .. }));
+
+        let mut received = Vec::new();
+        for event in held.drain(..).skip(1) {
+            let NetEventKind::Chunk(data) = &event.kind else {
+                panic!("unexpected event")
+            };
+            received.extend_from_slice(data);
+        }
+        loop {
+            match rx.recv_timeout(Duration::from_secs(5)).unwrap().kind {
+                NetEventKind::Chunk(data) => received.extend_from_slice(&data),
+                NetEventKind::Done { bytes, error } => {
+                    assert_eq!(error, None);
+                    assert_eq!(bytes, body.len() as u64);
+                    break;
+                }
+                other => panic!("unexpected event: {:?}", other),
+            }
+        }
+        assert_eq!(received, body);
+    }
+
+    #[test]
+    fn private_ip_covers_reserved_ranges() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "0.0.0.0",
+            "0.1.2.3",
+            "169.254.169.254",
+            "100.64.0.1",
+            "100.127.255.254",
+            "::",
+            "::1",
+            "fe80::1",
+            "fc00::1",
+            "fd12:3456::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(is_private_ip(&ip.parse().unwrap()), "{}", ip);
+        }
+        for ip in ["8.8.8.8", "100.128.0.1", "2606:4700::1111"] {
+            assert!(!is_private_ip(&ip.parse().unwrap()), "{}", ip);
+        }
+        assert!(is_ssrf_url("http://0.0.0.0:25575/"));
+        assert!(is_ssrf_url("http://[fe80::1]/"));
+        assert!(!is_ssrf_url("https://example.com/"));
+    }
+
+    #[test]
+    fn resolver_rejects_names_resolving_to_private_addresses() {
+        use reqwest::dns::Resolve;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let name: reqwest::dns::Name = "localhost".parse().unwrap();
+        let result = runtime.block_on(PublicOnlyResolver.resolve(name));
+        assert!(result.is_err_and(|e| e.to_string().contains("SSRF")));
+    }
+
+    #[test]
+    fn host_scope_matches_hosts_wildcards_and_prefixes() {
+        assert!(allows(&[], "https://anything.example.org/"));
+        assert!(allows(&["api.example.com"], "https://API.example.com/v1"));
+        assert!(!allows(&["api.example.com"], "https://api.example.com.evil.net/"));
+        assert!(allows(&["*.example.com"], "https://cdn.example.com/a.png"));
+        assert!(!allows(&["*.example.com"], "https://example.com/"));
+        assert!(allows(&["https://api.example.com/v1/*"], "https://api.example.com/v1/users"));
+        assert!(!allows(&["https://api.example.com/v1/"], "https://api.example.com/v2/users"));
+        assert!(!allows(&["https://api.example.com/v1/"], "http://api.example.com/v1/users"));
+    }
+
+    #[test]
+    fn validates_host_patterns() {
+        assert!(validate_host_pattern("*.example.com").is_ok());
+        assert!(validate_host_pattern("https://api.example.com/v1/").is_ok());
+        assert!(validate_host_pattern("ftp://example.com").is_err());
+        assert!(validate_host_pattern("exa*mple.com").is_err());
+        assert!(validate_host_pattern(" ").is_err());
+    }
 }
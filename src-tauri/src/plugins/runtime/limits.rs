@@ -0,0 +1,110 @@
+use super::PluginRuntime;
+use mlua::{HookTriggers, VmState};
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+
+// 插件 Lua VM 的资源限制：
+// 1) 内存上限：超过后分配失败，当前调用以 memory error 结束
+// 2) 时间预算：每次从 Rust 进入 Lua 的调用都有截止时间，指令钩子定期检查，超时即中止
+// 3) 看门狗：累计违规达到上限后，请求宿主自动禁用插件，并通过权限日志上报
+
+pub(crate) const PLUGIN_MEMORY_LIMIT: usize = 64 * 1024 * 1024;
+/// 普通回调（事件、计时器、API、右键菜单）的时间预算
+pub(crate) const CALL_TIME_BUDGET: Duration = Duration::from_secs(5);
+/// 加载主文件与生命周期回调的时间预算
+pub(crate) const LIFECYCLE_TIME_BUDGET: Duration = Duration::from_secs(10);
+const HOOK_INSTRUCTION_INTERVAL: u32 = 10_000;
+const MAX_VIOLATIONS: u32 = 3;
+
+#[derive(Default)]
+pub(crate) struct ExecutionBudget {
+    deadline: Option<Instant>,
+    exceeded: bool,
+}
+
+impl PluginRuntime {
+    pub(super) fn install_resource_limits(&self) -> Result<(), String> {
+        self.lua
+            .set_memory_limit(PLUGIN_MEMORY_LIMIT)
+            .map_err(|e| format!("Failed to set memory limit: {}", e))?;
+
+        let budget = self.execution_budget.clone();
+        self.lua.set_hook(
+            HookTriggers::new().every_nth_instruction(HOOK_INSTRUCTION_INTERVAL),
+            move |_, _| {
+                let mut budget = budget.lock().unwrap_or_else(|e| e.into_inner());
+                match budget.deadline {
+                    Some(deadline) if Instant::now() >= deadline => {
+                        budget.exceeded = true;
+                        Err(mlua::Error::runtime("执行时间超出预算，插件回调已被中止"))
+                    }
+                    _ => Ok(VmState::Continue),
+                }
+            },
+        );
+
+        Ok(())
+    }
+
+    /// 在时间预算内执行一次 Lua 调用；嵌套调用沿用外层的截止时间
+    pub(crate) fn with_budget<T>(
+        &self,
+        budget: Duration,
+        f: impl FnOnce() -> Result<T, String>,
+    ) -> Result<T, String> {
+        let is_outermost = {
+            let mut state = self
+                .execution_budget
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            if state.deadline.is_none() {
+                state.deadline = Some(Instant::now() + budget);
+                state.exceeded = false;
+                true
+            } else {
+                false
+            }
+        };
+
+        let result = f();
+
+        if is_outermost {
+            let exceeded = {
+                let mut state = self
+                    .execution_budget
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner());
+                state.deadline = None;
+                std::mem::take(&mut state.exceeded)
+            };
+
+            if let Err(e) = &result {
+                if exceeded {
+                    self.record_violation("cpu_time", e);
+                } else if e.contains("memory error") || e.contains("not enough memory") {
+                    self.record_violation("memory", e);
+                }
+            }
+        }
+
+        result
+    }
+
+    fn record_violation(&self, kind: &str, detail: &str) {
+        use crate::plugins::api::{emit_permission_log, emit_plugin_watchdog};
+
+        let count = self.violations.fetch_add(1, Ordering::SeqCst) + 1;
+        let _ = emit_permission_log(
+            &self.plugin_id,
+            "violation",
+            kind,
+            &format!("第 {}/{} 次违规: {}", count, MAX_VIOLATIONS, detail),
+        );
+
+        if count == MAX_VIOLATIONS {
+            let reason = format!("资源限制违规次数达到 {} 次，插件已被自动禁用", MAX_VIOLATIONS);
+            let _ = emit_permission_log(&self.plugin_id, "violation", "auto_disable", &reason);
+            let _ = emit_plugin_watchdog(&self.plugin_id, &reason);
+        }
+    }
+}
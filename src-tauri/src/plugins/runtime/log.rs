@@ -1,5 +1,17 @@
 use super::PluginRuntime;
-use mlua::Table;
+use mlua::{Lua, Table};
+
+/// 调用 sl.log.* 的 Lua 代码位置，如 "main.lua:12"
+fn caller_location(lua: &Lua) -> Option<String> {
+    let debug = lua.inspect_stack(1)?;
+    let line = debug.curr_line();
+    let source = debug.source().short_src?.into_owned();
+    Some(if line > 0 {
+        format!("{}:{}", source, line)
+    } else {
+        source
+    })
+}
 
 impl PluginRuntime {
     pub(super) fn setup_log_namespace(
@@ -7,7 +19,7 @@ impl PluginRuntime {
         sl: &Table,
         has_log_permission: bool,
     ) -> Result<(), String> {
-        use crate::plugins::api::emit_log_event;
+        use crate::plugins::api::emit_log_event_at;
 
         let log = self
             .lua
@@ -20,11 +32,12 @@ impl PluginRuntime {
             let pid = plugin_id.clone();
             let debug_fn = self
                 .lua
-                .create_function(move |_, msg: mlua::String| {
+                .create_function(move |lua, msg: mlua::String| {
                     let msg_str = String::from_utf8_lossy(&msg.as_bytes()).to_string();
                     println!("[DEBUG] [{}] {}", pid, msg_str);
 
-                    let _ = emit_log_event(&pid, "debug", &msg_str);
+                    let _ =
+                        emit_log_event_at(&pid, "debug", &msg_str, caller_location(lua).as_deref());
                     Ok(())
                 })
                 .map_err(|e| format!("Failed to create log.debug: {}", e))?;
@@ -42,11 +55,11 @@ impl PluginRuntime {
         let pid = plugin_id.clone();
         let info_fn = self
             .lua
-            .create_function(move |_, msg: mlua::String| {
+            .create_function(move |lua, msg: mlua::String| {
                 let msg_str = String::from_utf8_lossy(&msg.as_bytes()).to_string();
                 println!("[INFO] [{}] {}", pid, msg_str);
 
-                let _ = emit_log_event(&pid, "info", &msg_str);
+                let _ = emit_log_event_at(&pid, "info", &msg_str, caller_location(lua).as_deref());
                 Ok(())
             })
             .map_err(|e| format!("Failed to create log.info: {}", e))?;
@@ -56,11 +69,11 @@ impl PluginRuntime {
         let pid = plugin_id.clone();
         let warn_fn = self
             .lua
-            .create_function(move |_, msg: mlua::String| {
+            .create_function(move |lua, msg: mlua::String| {
                 let msg_str = String::from_utf8_lossy(&msg.as_bytes()).to_string();
                 println!("[WARN] [{}] {}", pid, msg_str);
 
-                let _ = emit_log_event(&pid, "warn", &msg_str);
+                let _ = emit_log_event_at(&pid, "warn", &msg_str, caller_location(lua).as_deref());
                 Ok(())
             })
             .map_err(|e| format!("Failed to create log.warn: {}", e))?;
@@ -70,11 +83,11 @@ impl PluginRuntime {
         let pid = plugin_id.clone();
         let error_fn = self
             .lua
-            .create_function(move |_, msg: mlua::String| {
+            .create_function(move |lua, msg: mlua::String| {
                 let msg_str = String::from_utf8_lossy(&msg.as_bytes()).to_string();
                 println!("[ERROR] [{}] {}", pid, msg_str);
 
-                let _ = emit_log_event(&pid, "error", &msg_str);
+                let _ = emit_log_event_at(&pid, "error", &msg_str, caller_location(lua).as_deref());
                 Ok(())
             })
             .map_err(|e| format!("Failed to create log.error: {}", e))?;
@@ -1,29 +1,97 @@
 mod api_bridge;
+mod backend;
 mod console;
+mod database;
 mod element;
+mod events;
 mod filesystem;
 pub(crate) mod helpers;
 mod http;
 mod i18n;
+mod limits;
 mod log;
+mod modules;
+mod net;
 mod plugins_api;
 mod process;
 mod server;
 mod storage;
 mod system;
+mod timer;
 mod ui;
+mod websocket;
+mod worker;
 
 use crate::plugins::api::ApiRegistry;
+pub use backend::{ManagerServerBackend, ServerBackend};
 use helpers::{json_value_from_lua, lua_value_from_json};
+pub(crate) use http::validate_host_pattern;
+pub use http::{plugin_http_log, HttpRequestLogEntry};
 use mlua::{Function, MultiValue, Table, Value};
+pub(crate) use modules::normalize_module_name;
 pub use process::{kill_all_processes, new_process_registry, ProcessRegistry};
 use serde_json::Value as JsonValue;
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::sync::{
-    atomic::{AtomicBool, Ordering},
-    Arc, Mutex,
+    atomic::{AtomicBool, AtomicU32, Ordering},
+    Arc, Mutex, RwLock,
 };
+pub use worker::PluginWorker;
+
+/// 由权限控制的 sl.* 命名空间，授予或撤销时按权限名单独重建
+const NAMESPACE_PERMISSIONS: &[&str] = &[
+    "log",
+    "storage",
+    "fs",
+    "api",
+    "ui",
+    "element",
+    "server",
+    "console",
+    "system",
+    "network",
+    "execute_program",
+    "plugin_folder_access",
+];
+
+/// 插件当前生效的权限；闭包持有同一份引用，运行期间撤销权限会立即生效
+#[derive(Clone, Default)]
+pub struct PermissionSet(Arc<RwLock<Vec<String>>>);
+
+impl PermissionSet {
+    pub fn new(permissions: Vec<String>) -> Self {
+        Self(Arc::new(RwLock::new(permissions)))
+    }
+
+    pub fn has(&self, permission: &str) -> bool {
+        self.0
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .any(|p| p == permission)
+    }
+
+    /// sl.* 函数调用时检查权限；撤销后已保存的函数引用同样会失败
+    pub fn require(&self, permission: &str) -> mlua::Result<()> {
+        if self.has(permission) {
+            Ok(())
+        } else {
+            Err(mlua::Error::runtime(format!(
+                "权限不足: 需要在 manifest.json 中声明并授予 '{}' 权限",
+                permission
+            )))
+        }
+    }
+
+    fn set(&self, permission: &str, granted: bool) {
+        let mut permissions = self.0.write().unwrap_or_else(|e| e.into_inner());
+        permissions.retain(|p| p != permission);
+        if granted {
+            permissions.push(permission.to_string());
+        }
+    }
+}
 
 pub struct PluginRuntime {
     pub(super) lua: mlua::Lua,
@@ -32,14 +100,26 @@ pub struct PluginRuntime {
     pub(super) data_dir: PathBuf,
     loaded: AtomicBool,
 
-    pub(super) permissions: Vec<String>,
+    pub(super) permissions: PermissionSet,
 
     pub(super) api_registry: ApiRegistry,
 
-    pub(super) storage_lock: Arc<Mutex<()>>,
+    pub(super) server_backend: Arc<dyn ServerBackend>,
+
+    pub(super) database: Arc<database::PluginDatabase>,
 
     pub(super) process_registry: ProcessRegistry,
 
+    pub(super) event_state: Arc<Mutex<events::EventState>>,
+
+    pub(super) timer_state: Arc<Mutex<timer::TimerState>>,
+
+    pub(super) net_state: Arc<Mutex<net::NetState>>,
+
+    pub(super) execution_budget: Arc<Mutex<limits::ExecutionBudget>>,
+
+    violations: AtomicU32,
+
     #[allow(dead_code)]
     element_callbacks: Arc<Mutex<std::collections::HashMap<u64, mlua::RegistryKey>>>,
 }
@@ -51,6 +131,25 @@ impl PluginRuntime {
         data_dir: &Path,
         api_registry: ApiRegistry,
         permissions: Vec<String>,
+    ) -> Result<Self, String> {
+        Self::with_server_backend(
+            plugin_id,
+            plugin_dir,
+            data_dir,
+            api_registry,
+            permissions,
+            Arc::new(ManagerServerBackend),
+        )
+    }
+
+    /// 使用指定的服务器后端创建运行时，sl.server 与 sl.console 都经由它访问服务器
+    pub fn with_server_backend(
+        plugin_id: &str,
+        plugin_dir: &Path,
+        data_dir: &Path,
+        api_registry: ApiRegistry,
+        permissions: Vec<String>,
+        server_backend: Arc<dyn ServerBackend>,
     ) -> Result<Self, String> {
         let lua = mlua::Lua::new_with(
             mlua::StdLib::TABLE
@@ -70,15 +169,22 @@ impl PluginRuntime {
             plugin_dir: plugin_dir.to_path_buf(),
             data_dir: data_dir.to_path_buf(),
             loaded: AtomicBool::new(false),
-            permissions,
+            permissions: PermissionSet::new(permissions),
             api_registry,
-            storage_lock: Arc::new(Mutex::new(())),
+            server_backend,
+            database: Arc::new(database::PluginDatabase::new(data_dir)),
             process_registry: new_process_registry(),
+            event_state: Arc::new(Mutex::new(events::EventState::new())),
+            timer_state: Arc::new(Mutex::new(timer::TimerState::new())),
+            net_state: Arc::new(Mutex::new(net::NetState::new())),
+            execution_budget: Arc::new(Mutex::new(limits::ExecutionBudget::default())),
+            violations: AtomicU32::new(0),
             element_callbacks: Arc::new(Mutex::new(std::collections::HashMap::new())),
         };
 
         runtime.setup_sandbox(plugin_id, plugin_dir)?;
         runtime.setup_sl_namespace()?;
+        runtime.install_resource_limits()?;
 
         Ok(runtime)
     }
@@ -88,18 +194,6 @@ impl PluginRuntime {
         self.loaded.load(Ordering::SeqCst)
     }
 
-    #[allow(dead_code)]
-    pub(super) fn check_permission(&self, permission: &str) -> mlua::Result<()> {
-        if self.permissions.iter().any(|p| p == permission) {
-            Ok(())
-        } else {
-            Err(mlua::Error::runtime(format!(
-                "Permission denied: '{}' permission is required for this operation",
-                permission
-            )))
-        }
-    }
-
     fn setup_sandbox(&self, plugin_id: &str, plugin_dir: &Path) -> Result<(), String> {
         let globals = self.lua.globals();
 
@@ -117,6 +211,8 @@ impl PluginRuntime {
 
         self.remove_dangerous_globals()?;
 
+        self.setup_require()?;
+
         Ok(())
     }
 
@@ -176,82 +272,73 @@ impl PluginRuntime {
             .create_table()
             .map_err(|e| format!("Failed to create sl table: {}", e))?;
 
-        let has_log_permission = self.permissions.iter().any(|p| p == "log");
-        self.setup_log_namespace(&sl, has_log_permission)?;
+        self.setup_net_callbacks()?;
 
-        if self.permissions.iter().any(|p| p == "storage") {
-            self.setup_storage_namespace(&sl)?;
-        } else {
-            self.setup_permission_denied_module(&sl, "storage")?;
+        for permission in NAMESPACE_PERMISSIONS {
+            self.setup_permission_namespace(&sl, permission)?;
         }
 
-        if self.permissions.iter().any(|p| p == "fs") {
-            self.setup_fs_namespace(&sl)?;
-        } else {
-            self.setup_permission_denied_module(&sl, "fs")?;
-        }
+        self.setup_i18n_namespace(&sl)?;
 
-        if self.permissions.iter().any(|p| p == "api") {
-            self.setup_api_namespace(&sl)?;
-        } else {
-            self.setup_permission_denied_module(&sl, "api")?;
-        }
+        // 具体事件在订阅时按 server / console 权限校验
+        self.setup_events_namespace(&sl)?;
 
-        if self.permissions.iter().any(|p| p == "ui") {
-            self.setup_ui_namespace(&sl)?;
-        } else {
-            self.setup_permission_denied_module(&sl, "ui")?;
-        }
+        self.setup_timer_namespace(&sl)?;
 
-        if self.permissions.iter().any(|p| p == "element") {
-            self.setup_element_namespace(&sl)?;
-        } else {
-            self.setup_permission_denied_module(&sl, "element")?;
-        }
+        globals
+            .set("sl", sl)
+            .map_err(|e| format!("Failed to set sl global: {}", e))?;
 
-        if self.permissions.iter().any(|p| p == "server") {
-            self.setup_server_namespace(&sl)?;
-        } else {
-            self.setup_permission_denied_module(&sl, "server")?;
-        }
+        Ok(())
+    }
 
-        if self.permissions.iter().any(|p| p == "console") {
-            self.setup_console_namespace(&sl)?;
-        } else {
-            self.setup_permission_denied_module(&sl, "console")?;
+    /// 按当前权限构建 sl.* 中对应的命名空间，没有权限时替换为拒绝访问的占位模块
+    fn setup_permission_namespace(&self, sl: &Table, permission: &str) -> Result<(), String> {
+        let granted = self.permissions.has(permission);
+        if permission == "log" {
+            return self.setup_log_namespace(sl, granted);
         }
-
-        if self.permissions.iter().any(|p| p == "system") {
-            self.setup_system_namespace(&sl)?;
-        } else {
-            self.setup_permission_denied_module(&sl, "system")?;
+        if !granted {
+            return self.setup_permission_denied_module(sl, permission);
         }
 
-        if self.permissions.iter().any(|p| p == "network") {
-            self.setup_http_namespace(&sl)?;
-        } else {
-            self.setup_permission_denied_module(&sl, "network")?;
+        match permission {
+            "storage" => self.setup_storage_namespace(sl),
+            "fs" => self.setup_fs_namespace(sl),
+            "api" => self.setup_api_namespace(sl),
+            "ui" => self.setup_ui_namespace(sl),
+            "element" => self.setup_element_namespace(sl),
+            "server" => self.setup_server_namespace(sl),
+            "console" => self.setup_console_namespace(sl),
+            "system" => self.setup_system_namespace(sl),
+            "network" => self.setup_http_namespace(sl),
+            "execute_program" => {
+                self.setup_process_namespace(sl, Arc::clone(&self.process_registry))
+            }
+            "plugin_folder_access" => self.setup_plugins_namespace(sl),
+            _ => Ok(()),
         }
+    }
 
-        if self.permissions.iter().any(|p| p == "execute_program") {
-            self.setup_process_namespace(&sl, Arc::clone(&self.process_registry))?;
-        } else {
-            self.setup_permission_denied_module(&sl, "execute_program")?;
+    /// 运行期间授予或撤销单项权限，并重建受影响的命名空间
+    pub fn set_permission_granted(&self, permission: &str, granted: bool) -> Result<(), String> {
+        self.permissions.set(permission, granted);
+        if permission == "network" && !granted {
+            self.clear_net_handles();
         }
-
-        if self.permissions.iter().any(|p| p == "plugin_folder_access") {
-            self.setup_plugins_namespace(&sl)?;
-        } else {
-            self.setup_permission_denied_module(&sl, "plugin_folder_access")?;
+        if permission == "storage" && !granted {
+            self.database.close();
         }
 
-        self.setup_i18n_namespace(&sl)?;
-
-        globals
-            .set("sl", sl)
-            .map_err(|e| format!("Failed to set sl global: {}", e))?;
-
-        Ok(())
+        if !NAMESPACE_PERMISSIONS.contains(&permission) {
+            return Ok(());
+        }
+        let sl: Table = self
+            .lua
+            .globals()
+            .get("sl")
+            .map_err(|e| format!("Failed to get sl table: {}", e))?;
+        self.setup_permission_namespace(&sl, permission)
     }
 
     fn setup_permission_denied_module(&self, sl: &Table, module_name: &str) -> Result<(), String> {
@@ -308,12 +395,18 @@ impl PluginRuntime {
         let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
         let content = String::from_utf8_lossy(bytes).into_owned();
 
-        let result: Value = self
-            .lua
-            .load(&content)
-            .set_name(path.to_string_lossy())
-            .eval()
-            .map_err(|e| format!("Failed to execute {:?}: {}", path, e))?;
+        // 以 "@相对路径" 命名代码块，语法与运行时错误会显示为 main.lua:12: ... 的形式
+        let chunk_name = path.strip_prefix(&self.plugin_dir).unwrap_or(path);
+        let chunk_name = format!("@{}", chunk_name.to_string_lossy().replace('\\', "/"));
+
+        let result: Value = self.with_budget(limits::LIFECYCLE_TIME_BUDGET, || {
+            self.lua
+                .load(&content)
+                .set_name(chunk_name)
+                .set_mode(mlua::ChunkMode::Text)
+                .eval()
+                .map_err(|e| format!("Failed to execute {:?}: {}", path, e))
+        })?;
 
         if let Value::Table(table) = result {
             let globals = self.lua.globals();
@@ -331,15 +424,19 @@ impl PluginRuntime {
 
         if let Ok(plugin_table) = globals.get::<Table>("plugin") {
             if let Ok(func) = plugin_table.get::<Function>(event) {
-                func.call::<()>(())
-                    .map_err(|e| format!("Failed to call plugin.{}: {}", event, e))?;
+                self.with_budget(limits::LIFECYCLE_TIME_BUDGET, || {
+                    func.call::<()>(())
+                        .map_err(|e| format!("Failed to call plugin.{}: {}", event, e))
+                })?;
                 return Ok(());
             }
         }
 
         if let Ok(func) = globals.get::<Function>(event) {
-            func.call::<()>(())
-                .map_err(|e| format!("Failed to call {}: {}", event, e))?;
+            self.with_budget(limits::LIFECYCLE_TIME_BUDGET, || {
+                func.call::<()>(())
+                    .map_err(|e| format!("Failed to call {}: {}", event, e))
+            })?;
             return Ok(());
         }
 
@@ -351,26 +448,72 @@ impl PluginRuntime {
 
         if let Ok(plugin_table) = globals.get::<Table>("plugin") {
             if let Ok(func) = plugin_table.get::<Function>(event) {
-                func.call::<()>(arg.to_string())
-                    .map_err(|e| format!("Failed to call plugin.{}: {}", event, e))?;
+                self.with_budget(limits::LIFECYCLE_TIME_BUDGET, || {
+                    func.call::<()>(arg.to_string())
+                        .map_err(|e| format!("Failed to call plugin.{}: {}", event, e))
+                })?;
                 return Ok(());
             }
         }
 
         if let Ok(func) = globals.get::<Function>(event) {
-            func.call::<()>(arg.to_string())
-                .map_err(|e| format!("Failed to call {}: {}", event, e))?;
+            self.with_budget(limits::LIFECYCLE_TIME_BUDGET, || {
+                func.call::<()>(arg.to_string())
+                    .map_err(|e| format!("Failed to call {}: {}", event, e))
+            })?;
             return Ok(());
         }
 
         Ok(())
     }
 
+    /// 以 JSON 转换成的 Lua 值作为参数调用生命周期回调，插件未实现时忽略
+    pub fn call_lifecycle_with_json(&self, event: &str, arg: JsonValue) -> Result<(), String> {
+        let globals = self.lua.globals();
+        let Ok(func) = globals
+            .get::<Table>("plugin")
+            .and_then(|plugin_table| plugin_table.get::<Function>(event))
+            .or_else(|_| globals.get::<Function>(event))
+        else {
+            return Ok(());
+        };
+
+        let lua_arg =
+            lua_value_from_json(&self.lua, &arg, 0).map_err(|e| format!("参数转换失败: {}", e))?;
+        self.with_budget(limits::CALL_TIME_BUDGET, || {
+            func.call::<()>(lua_arg)
+                .map_err(|e| format!("Failed to call plugin.{}: {}", event, e))
+        })
+    }
+
+    /// 执行 manifest 中声明的命令：调用 plugin.onCommand(id, args)
+    pub fn call_command(&self, command_id: &str, args: JsonValue) -> Result<(), String> {
+        let globals = self.lua.globals();
+        let func = globals
+            .get::<Table>("plugin")
+            .and_then(|plugin_table| plugin_table.get::<Function>("onCommand"))
+            .or_else(|_| globals.get::<Function>("onCommand"))
+            .map_err(|_| format!("插件 '{}' 未实现 onCommand", self.plugin_id))?;
+
+        let lua_args =
+            lua_value_from_json(&self.lua, &args, 0).map_err(|e| format!("参数转换失败: {}", e))?;
+
+        self.with_budget(limits::CALL_TIME_BUDGET, || {
+            func.call::<()>((command_id.to_string(), lua_args))
+                .map_err(|e| format!("执行命令 '{}' 失败: {}", command_id, e))
+        })
+    }
+
     pub fn cleanup(&self) {
         use crate::plugins::api::emit_i18n_event;
         use crate::services::global::i18n_service;
         use crate::services::i18n::LocaleCallbackToken;
 
+        self.clear_event_subscriptions();
+        self.clear_timers();
+        self.clear_net_handles();
+        crate::plugins::logs::close(&self.plugin_id);
+
         let registry_key = format!("_locale_callback_token_{}", self.plugin_id);
         if let Ok(token_id) = self.lua.named_registry_value::<usize>(&registry_key) {
             let i18n = i18n_service();
@@ -415,9 +558,10 @@ impl PluginRuntime {
             lua_args.push(lua_val);
         }
 
-        let result: Value = func
-            .call(MultiValue::from_vec(lua_args))
-            .map_err(|e| format!("调用 API '{}' 失败: {}", api_name, e))?;
+        let result: Value = self.with_budget(limits::CALL_TIME_BUDGET, || {
+            func.call(MultiValue::from_vec(lua_args))
+                .map_err(|e| format!("调用 API '{}' 失败: {}", api_name, e))
+        })?;
 
         json_value_from_lua(&result, 0).map_err(|e| format!("结果转换失败: {}", e))
     }
@@ -428,9 +572,11 @@ impl PluginRuntime {
             .lua
             .named_registry_value(&registry_key)
             .map_err(|e| format!("获取右键菜单隐藏回调函数失败: {}", e))?;
-        callback
-            .call::<()>(())
-            .map_err(|e| format!("调用右键菜单隐藏回调失败: {}", e))?;
+        self.with_budget(limits::CALL_TIME_BUDGET, || {
+            callback
+                .call::<()>(())
+                .map_err(|e| format!("调用右键菜单隐藏回调失败: {}", e))
+        })?;
         Ok(())
     }
 
@@ -451,9 +597,11 @@ impl PluginRuntime {
         let target_lua = lua_value_from_json(&self.lua, &target_data, 0)
             .map_err(|e| format!("转换 target_data 失败: {}", e))?;
 
-        let result: Value = callback
-            .call((context.to_string(), target_lua, x, y))
-            .map_err(|e| format!("调用右键菜单显示回调失败: {}", e))?;
+        let result: Value = self.with_budget(limits::CALL_TIME_BUDGET, || {
+            callback
+                .call((context.to_string(), target_lua, x, y))
+                .map_err(|e| format!("调用右键菜单显示回调失败: {}", e))
+        })?;
 
         let mut dynamic_items = Vec::new();
         if let Value::Table(tbl) = result {
@@ -484,9 +632,11 @@ impl PluginRuntime {
         let target_lua = lua_value_from_json(&self.lua, &target_data, 0)
             .map_err(|e| format!("转换 target_data 失败: {}", e))?;
 
-        callback
-            .call::<()>((context.to_string(), item_id.to_string(), target_lua))
-            .map_err(|e| format!("调用右键菜单回调失败: {}", e))?;
+        self.with_budget(limits::CALL_TIME_BUDGET, || {
+            callback
+                .call::<()>((context.to_string(), item_id.to_string(), target_lua))
+                .map_err(|e| format!("调用右键菜单回调失败: {}", e))
+        })?;
 
         Ok(())
     }
@@ -531,4 +681,126 @@ mod tests {
 
         let _ = fs::remove_dir_all(&temp_dir);
     }
+
+    #[test]
+    fn test_revoking_permission_rebuilds_namespace() {
+        let temp_dir = env::temp_dir().join("sl_test_revoke");
+        let data_dir = temp_dir.join("data");
+        let runtime = PluginRuntime::new(
+            "test-revoke",
+            &temp_dir,
+            &data_dir,
+            new_api_registry(),
+            vec!["storage".to_string()],
+        )
+        .unwrap();
+
+        let result: LuaResult<Value> = runtime
+            .lua
+            .load("captured_set = sl.storage.set; captured_query = sl.db.query; return sl.storage.get('k')")
+            .eval();
+        assert!(result.is_ok());
+
+        runtime.set_permission_granted("storage", false).unwrap();
+        let result: LuaResult<Value> = runtime.lua.load("return sl.storage.get('k')").eval();
+        assert!(result.is_err());
+        // 撤销前保存下来的函数引用同样失效
+        let result: LuaResult<()> = runtime.lua.load("captured_set('k', 1)").exec();
+        assert!(result.is_err());
+        let result: LuaResult<Value> = runtime.lua.load("return captured_query('SELECT 1')").eval();
+        assert!(result.is_err());
+
+        runtime.set_permission_granted("storage", true).unwrap();
+        let result: LuaResult<Value> = runtime.lua.load("return sl.storage.get('k')").eval();
+        assert!(result.is_ok());
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_timer_fires_once_and_cancel() {
+        let temp_dir = env::temp_dir().join("sl_test_timer");
+        let data_dir = temp_dir.join("data");
+        let api_registry = new_api_registry();
+
+        let runtime =
+            PluginRuntime::new("test-timer", &temp_dir, &data_dir, api_registry, vec![]).unwrap();
+
+        let once_id: u64 = runtime
+            .lua
+            .load("fired = 0; return sl.timer.after(10000, function() fired = fired + 1 end)")
+            .eval()
+            .unwrap();
+        runtime.fire_timer(once_id).unwrap();
+        runtime.fire_timer(once_id).unwrap();
+        let fired: i64 = runtime.lua.globals().get("fired").unwrap();
+        assert_eq!(fired, 1);
+
+        let every_id: u64 = runtime
+            .lua
+            .load("return sl.timer.every(10000, function() end)")
+            .eval()
+            .unwrap();
+        let cancelled: bool = runtime
+            .lua
+            .load(format!("return sl.timer.cancel({})", every_id))
+            .eval()
+            .unwrap();
+        assert!(cancelled);
+
+        runtime.cleanup();
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_repeating_timer_survives_lost_fires() {
+        let temp_dir = env::temp_dir().join("sl_test_timer_lost");
+        let data_dir = temp_dir.join("data");
+        let runtime =
+            PluginRuntime::new("test-timer-lost", &temp_dir, &data_dir, new_api_registry(), vec![])
+                .unwrap();
+
+        // 触发全部丢弃，回调一次也不执行
+        let fires = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = Arc::clone(&fires);
+        crate::plugins::api::set_timer_fired_handler(Arc::new(move |plugin_id, _| {
+            if plugin_id == "test-timer-lost" {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            }
+            Ok(())
+        }));
+
+        runtime
+            .lua
+            .load("sl.timer.every(50, function() end)")
+            .exec()
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(400));
+        assert!(fires.load(std::sync::atomic::Ordering::SeqCst) >= 3);
+
+        runtime.cleanup();
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
+    #[test]
+    fn test_runaway_loop_is_aborted() {
+        let temp_dir = env::temp_dir().join("sl_test_budget");
+        let data_dir = temp_dir.join("data");
+        let api_registry = new_api_registry();
+
+        let runtime =
+            PluginRuntime::new("test-budget", &temp_dir, &data_dir, api_registry, vec![]).unwrap();
+        runtime
+            .lua
+            .load("function onEnable() while true do end end")
+            .exec()
+            .unwrap();
+
+        let result = runtime.with_budget(std::time::Duration::from_millis(50), || {
+            runtime.call_lifecycle("onEnable")
+        });
+        assert!(result.is_err());
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
 }
@@ -0,0 +1,355 @@
+use super::helpers::safe_canonicalize_check;
+use super::PluginRuntime;
+use crate::plugins::loader::PluginLoader;
+use mlua::{ChunkMode, Lua, Table, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+// 沙箱化的 require：
+// 1) require("lib.util") 只在插件目录内查找 lib/util.lua 或 lib/util/init.lua，
+//    与 sl.fs 使用同一套 safe_canonicalize_check，符号链接也无法逃出插件目录
+// 2) 已加载的模块按运行时缓存，重复 require 返回同一个值；循环 require 直接报错
+// 3) 只接受文本代码，拒绝预编译的字节码
+// 4) require("@<依赖插件 id>/模块") 可以加载 manifest 中声明的依赖插件在 exports 中导出的模块，
+//    代码在当前插件的沙箱和权限下执行
+
+const LOADED_MODULES_KEY: &str = "_sl_loaded_modules";
+const LUA_BYTECODE_SIGNATURE: &[u8] = b"\x1bLua";
+
+/// 标记正在加载中的模块，用于识别循环 require
+#[derive(Clone, Copy)]
+struct Loading;
+
+impl mlua::UserData for Loading {}
+
+/// 把 "lib.util"、"lib/util"、"lib/util.lua" 统一成 "lib/util"
+pub(crate) fn normalize_module_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    let name = name.strip_suffix(".lua").unwrap_or(name);
+    let normalized = name.replace('.', "/");
+    let valid = !normalized.is_empty()
+        && normalized.split('/').all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        });
+    if !valid {
+        return Err(format!(
+            "模块名 '{}' 无效，只能包含字母、数字、'_'、'-'，并以 '.' 或 '/' 分隔",
+            name
+        ));
+    }
+    Ok(normalized)
+}
+
+fn resolve_module(base_dir: &Path, module: &str) -> Result<PathBuf, String> {
+    let candidates =
+        [base_dir.join(format!("{}.lua", module)), base_dir.join(module).join("init.lua")];
+    for candidate in candidates {
+        if candidate.is_file() {
+            return safe_canonicalize_check(base_dir, &candidate);
+        }
+    }
+    Err(format!("找不到模块 '{}'", module))
+}
+
+/// 在插件目录的同级目录中查找依赖插件，返回其目录与导出的模块
+fn find_dependency(
+    plugin_dir: &Path,
+    dependency_id: &str,
+) -> Result<(PathBuf, Vec<String>), String> {
+    let plugins_dir = plugin_dir
+        .parent()
+        .ok_or_else(|| "无法确定插件目录".to_string())?;
+    for dir in PluginLoader::discover_plugins(plugins_dir)? {
+        let Ok(manifest) = PluginLoader::load_manifest(&dir) else {
+            continue;
+        };
+        if manifest.id == dependency_id {
+            let exports = manifest
+                .exports
+                .iter()
+                .filter_map(|e| normalize_module_name(e).ok())
+                .collect();
+            return Ok((dir, exports));
+        }
+    }
+    Err(format!("依赖插件 '{}' 未安装", dependency_id))
+}
+
+struct ModuleResolver {
+    plugin_dir: PathBuf,
+    /// manifest 中声明的必须与可选依赖
+    dependencies: Vec<String>,
+    dependency_cache: Mutex<HashMap<String, (PathBuf, Vec<String>)>>,
+}
+
+impl ModuleResolver {
+    /// 返回缓存键、模块文件与代码块名称
+    fn resolve(&self, name: &str) -> Result<(String, PathBuf, String), String> {
+        let Some(rest) = name.trim().strip_prefix('@') else {
+            let module = normalize_module_name(name)?;
+            let path = resolve_module(&self.plugin_dir, &module)?;
+            let chunk_name = path
+                .strip_prefix(self.plugin_dir.canonicalize().unwrap_or_default())
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .unwrap_or_else(|_| format!("{}.lua", module));
+            return Ok((module, path, format!("@{}", chunk_name)));
+        };
+
+        let (dependency_id, module) = rest
+            .split_once('/')
+            .ok_or_else(|| format!("依赖模块 '{}' 格式应为 @插件id/模块", name))?;
+        if !self.dependencies.iter().any(|d| d == dependency_id) {
+            return Err(format!(
+                "插件 '{}' 不在 manifest.json 的 dependencies 或 optional_dependencies 中",
+                dependency_id
+            ));
+        }
+        let module = normalize_module_name(module)?;
+
+        let (dir, exports) = {
+            let mut cache = self
+                .dependency_cache
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            if !cache.contains_key(dependency_id) {
+                let found = find_dependency(&self.plugin_dir, dependency_id)?;
+                cache.insert(dependency_id.to_string(), found);
+            }
+            cache[dependency_id].clone()
+        };
+        if !exports.contains(&module) {
+            return Err(format!("插件 '{}' 没有导出模块 '{}'", dependency_id, module));
+        }
+
+        let path = resolve_module(&dir, &module)?;
+        Ok((
+            format!("@{}/{}", dependency_id, module),
+            path,
+            format!("@{}/{}.lua", dependency_id, module),
+        ))
+    }
+}
+
+fn load_module(lua: &Lua, resolver: &ModuleResolver, name: &str) -> mlua::Result<Value> {
+    let (key, path, chunk_name) = resolver.resolve(name).map_err(mlua::Error::runtime)?;
+
+    let loaded: Table = lua.named_registry_value(LOADED_MODULES_KEY)?;
+    match loaded.raw_get::<Value>(key.as_str())? {
+        Value::Nil => {}
+        Value::UserData(ud) if ud.is::<Loading>() => {
+            return Err(mlua::Error::runtime(format!("模块 '{}' 存在循环 require", key)));
+        }
+        value => return Ok(value),
+    }
+
+    let bytes = fs::read(&path)
+        .map_err(|e| mlua::Error::runtime(format!("读取模块 '{}' 失败: {}", key, e)))?;
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(&bytes);
+    if bytes.starts_with(LUA_BYTECODE_SIGNATURE) {
+        return Err(mlua::Error::runtime(format!("模块 '{}' 是预编译字节码，不允许加载", key)));
+    }
+
+    loaded.raw_set(key.as_str(), Loading)?;
+    let result = lua
+        .load(bytes)
+        .set_name(chunk_name)
+        .set_mode(ChunkMode::Text)
+        .call::<Value>(key.as_str());
+    let value = match result {
+        Ok(Value::Nil) => Value::Boolean(true),
+        Ok(value) => value,
+        Err(e) => {
+            loaded.raw_set(key.as_str(), Value::Nil)?;
+            return Err(e);
+        }
+    };
+    loaded.raw_set(key.as_str(), value.clone())?;
+    Ok(value)
+}
+
+impl PluginRuntime {
+    pub(super) fn setup_require(&self) -> Result<(), String> {
+        let loaded = self
+            .lua
+            .create_table()
+            .map_err(|e| format!("Failed to create module cache: {}", e))?;
+        self.lua
+            .set_named_registry_value(LOADED_MODULES_KEY, loaded)
+            .map_err(|e| format!("Failed to store module cache: {}", e))?;
+
+        let dependencies = PluginLoader::load_manifest(&self.plugin_dir)
+            .map(|m| {
+                m.dependencies
+                    .iter()
+                    .chain(m.optional_dependencies.iter())
+                    .map(|d| d.id().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        let resolver = Arc::new(ModuleResolver {
+            plugin_dir: self.plugin_dir.clone(),
+            dependencies,
+            dependency_cache: Mutex::new(HashMap::new()),
+        });
+
+        let require_fn = self
+            .lua
+            .create_function(move |lua, name: String| load_module(lua, &resolver, &name))
+            .map_err(|e| format!("Failed to create require: {}", e))?;
+        self.lua
+            .globals()
+            .set("require", require_fn)
+            .map_err(|e| format!("Failed to set require: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::api::new_api_registry;
+
+    fn make_temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "sealantern_test_require_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_manifest(dir: &Path, id: &str, extra: &str) {
+        fs::write(
+            dir.join("manifest.json"),
+            format!(
+                r#"{{"id":"{}","name":"n","version":"1.0.0","description":"d",
+                    "author":{{"name":"a"}},"main":"main.lua"{}}}"#,
+                id, extra
+            ),
+        )
+        .unwrap();
+    }
+
+    /// 在 <root>/app 中创建插件 app 的运行时，它依赖同级目录中的 lib-plugin（导出 api 模块）
+    fn make_app_runtime(root: &Path, files: &[(&str, &[u8])]) -> PluginRuntime {
+        let plugin_dir = root.join("app");
+        let lib_dir = root.join("lib-plugin");
+        fs::create_dir_all(&plugin_dir).unwrap();
+        fs::create_dir_all(&lib_dir).unwrap();
+        write_manifest(&plugin_dir, "app", r#","dependencies":["lib-plugin"]"#);
+        write_manifest(&lib_dir, "lib-plugin", r#","exports":["api"]"#);
+        fs::write(lib_dir.join("api.lua"), "return { name = 'lib' }").unwrap();
+        fs::write(lib_dir.join("internal.lua"), "return {}").unwrap();
+        for (path, content) in files {
+            let path = plugin_dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        }
+        PluginRuntime::new("app", &plugin_dir, &root.join("data"), new_api_registry(), vec![])
+            .unwrap()
+    }
+
+    #[test]
+    fn test_require_caches_modules_and_accepts_slash_paths() {
+        let root = make_temp_dir("cache");
+        let runtime = make_app_runtime(
+            &root,
+            &[(
+                "util/strings/init.lua",
+                b"COUNT = (COUNT or 0) + 1 return { upper = string.upper }",
+            )],
+        );
+        runtime
+            .lua
+            .load(
+                r#"
+                local a = require("util.strings")
+                local b = require("util/strings")
+                assert(a == b and COUNT == 1 and a.upper("x") == "X")
+                "#,
+            )
+            .exec()
+            .unwrap();
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_require_loads_only_exported_dependency_modules() {
+        let root = make_temp_dir("exports");
+        let runtime = make_app_runtime(&root, &[]);
+        runtime
+            .lua
+            .load(
+                r#"
+                assert(require("@lib-plugin/api").name == "lib")
+                assert(not pcall(require, "@lib-plugin/internal"))
+                assert(not pcall(require, "@other-plugin/api"))
+                "#,
+            )
+            .exec()
+            .unwrap();
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_require_rejects_paths_outside_plugin() {
+        let root = make_temp_dir("escape");
+        let runtime = make_app_runtime(&root, &[]);
+        runtime
+            .lua
+            .load(r#"assert(not pcall(require, "../lib-plugin/api"))"#)
+            .exec()
+            .unwrap();
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_require_detects_cycles() {
+        let root = make_temp_dir("cycle");
+        let runtime = make_app_runtime(
+            &root,
+            &[
+                ("cycle_a.lua", b"return require('cycle_b')"),
+                ("cycle_b.lua", b"return require('cycle_a')"),
+            ],
+        );
+        let err = runtime
+            .lua
+            .load(r#"require("cycle_a")"#)
+            .exec()
+            .unwrap_err();
+        assert!(err.to_string().contains("循环 require"), "{}", err);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_require_rejects_bytecode() {
+        let root = make_temp_dir("bytecode");
+        let runtime = make_app_runtime(&root, &[("compiled.lua", b"\x1bLua\x54\x00")]);
+        runtime
+            .lua
+            .load(r#"assert(not pcall(require, "compiled"))"#)
+            .exec()
+            .unwrap();
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_require_reports_missing_modules() {
+        let root = make_temp_dir("missing");
+        let runtime = make_app_runtime(&root, &[]);
+        let err = runtime
+            .lua
+            .load(r#"require("missing")"#)
+            .exec()
+            .unwrap_err();
+        assert!(err.to_string().contains("找不到模块 'missing'"), "{}", err);
+        let _ = fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,265 @@
+use super::PluginRuntime;
+use crate::plugins::api::{NetEvent, NetEventKind, NetEventTicket};
+use mlua::{Lua, MultiValue, Table, Value};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+
+// sl.ws 与流式 sl.http.request 共用的长连接句柄：
+// 1) 网络 I/O 在独立线程进行，事件经 api::emit_net_event 交回插件自己的工作线程执行回调
+// 2) 回调保存在注册表中，句柄结束（close / done）后一并移除
+// 3) 插件 cleanup 或撤销 network 权限时关闭全部句柄，之后到达的事件直接丢弃
+// 4) 每个句柄未处理的数据事件有上限，插件线程追不上时后台线程暂停读取，由 TCP 反压对端
+// 5) 错误与结束事件经工作线程的不丢弃队列送达，发出前先等之前的数据事件处理完，保持顺序
+
+const NET_CALLBACKS_KEY: &str = "_sl_net_callbacks";
+const MAX_HANDLES_PER_PLUGIN: usize = 16;
+pub(super) const MAX_PENDING_EVENTS_PER_HANDLE: usize = 64;
+const BACKPRESSURE_POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+static NEXT_HANDLE_ID: AtomicU64 = AtomicU64::new(1);
+
+/// 发给 WebSocket 连接线程的指令
+pub(super) enum WsCommand {
+    Text(String),
+    Binary(Vec<u8>),
+    Close(u16, String),
+}
+
+struct NetHandle {
+    cancel: Arc<AtomicBool>,
+    commands: Option<mpsc::Sender<WsCommand>>,
+}
+
+pub(crate) struct NetState {
+    handles: HashMap<u64, NetHandle>,
+}
+
+impl NetState {
+    pub(crate) fn new() -> Self {
+        Self { handles: HashMap::new() }
+    }
+}
+
+pub(super) type SharedNetState = Arc<Mutex<NetState>>;
+
+/// 登记一个新句柄并保存其回调，返回句柄 id 与取消标记
+pub(super) fn register_handle(
+    lua: &Lua,
+    state: &SharedNetState,
+    callbacks: Table,
+    commands: Option<mpsc::Sender<WsCommand>>,
+) -> mlua::Result<(u64, Arc<AtomicBool>)> {
+    let handle_id = NEXT_HANDLE_ID.fetch_add(1, Ordering::Relaxed);
+    let cancel = Arc::new(AtomicBool::new(false));
+    {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.handles.len() >= MAX_HANDLES_PER_PLUGIN {
+            return Err(mlua::Error::runtime(format!(
+                "网络连接数量超过上限 {}",
+                MAX_HANDLES_PER_PLUGIN
+            )));
+        }
+        state
+            .handles
+            .insert(handle_id, NetHandle { cancel: Arc::clone(&cancel), commands });
+    }
+
+    let registry: Table = lua.named_registry_value(NET_CALLBACKS_KEY)?;
+    registry.set(handle_id, callbacks)?;
+    Ok((handle_id, cancel))
+}
+
+/// 请求结束句柄；实际的 close / done 事件仍会送达回调
+pub(super) fn cancel_handle(state: &SharedNetState, handle_id: u64) -> bool {
+    let state = state.lock().unwrap_or_else(|e| e.into_inner());
+    match state.handles.get(&handle_id) {
+        Some(handle) => {
+            handle.cancel.store(true, Ordering::SeqCst);
+            true
+        }
+        None => false,
+    }
+}
+
+pub(super) fn send_command(state: &SharedNetState, handle_id: u64, command: WsCommand) -> bool {
+    let state = state.lock().unwrap_or_else(|e| e.into_inner());
+    state
+        .handles
+        .get(&handle_id)
+        .and_then(|handle| handle.commands.as_ref())
+        .is_some_and(|sender| sender.send(command).is_ok())
+}
+
+/// 从后台线程发出事件，发送失败只记录日志
+pub(super) fn emit(plugin_id: &str, handle_id: u64, kind: NetEventKind) {
+    send_event(plugin_id, handle_id, kind, None);
+}
+
+/// 发出数据事件（消息、响应分块）；待处理的事件达到上限时先等待插件线程处理，句柄取消后不再发送
+pub(super) fn emit_data(
+    plugin_id: &str,
+    handle_id: u64,
+    pending: &Arc<AtomicUsize>,
+    cancel: &AtomicBool,
+    kind: NetEventKind,
+) {
+    while pending.load(Ordering::SeqCst) >= MAX_PENDING_EVENTS_PER_HANDLE {
+        if cancel.load(Ordering::SeqCst) {
+            return;
+        }
+        std::thread::sleep(BACKPRESSURE_POLL_INTERVAL);
+    }
+    send_event(plugin_id, handle_id, kind, Some(NetEventTicket::new(pending)));
+}
+
+/// 发出错误或结束事件；先等待已发出的数据事件处理完（或被丢弃），避免结束事件越过数据事件
+pub(super) fn emit_final(
+    plugin_id: &str,
+    handle_id: u64,
+    pending: &AtomicUsize,
+    cancel: &AtomicBool,
+    kind: NetEventKind,
+) {
+    while pending.load(Ordering::SeqCst) > 0 && !cancel.load(Ordering::SeqCst) {
+        std::thread::sleep(BACKPRESSURE_POLL_INTERVAL);
+    }
+    send_event(plugin_id, handle_id, kind, None);
+}
+
+fn send_event(plugin_id: &str, handle_id: u64, kind: NetEventKind, ticket: Option<NetEventTicket>) {
+    let event = NetEvent {
+        plugin_id: plugin_id.to_string(),
+        handle_id,
+        kind,
+        ticket,
+    };
+    if let Err(e) = crate::plugins::api::emit_net_event(event) {
+        eprintln!("[WARN] plugin '{}' net event {} dropped: {}", plugin_id, handle_id, e);
+    }
+}
+
+/// 从 opts 中挑出指定名称的回调函数，未提供的回调忽略
+pub(super) fn collect_callbacks(lua: &Lua, opts: &Table, names: &[&str]) -> mlua::Result<Table> {
+    let callbacks = lua.create_table()?;
+    for name in names {
+        match opts.get::<Value>(*name)? {
+            Value::Function(f) => callbacks.set(*name, f)?,
+            Value::Nil => {}
+            _ => return Err(mlua::Error::runtime(format!("{} 必须是函数", name))),
+        }
+    }
+    Ok(callbacks)
+}
+
+impl PluginRuntime {
+    pub(super) fn setup_net_callbacks(&self) -> Result<(), String> {
+        let callbacks = self
+            .lua
+            .create_table()
+            .map_err(|e| format!("Failed to create net callbacks table: {}", e))?;
+        self.lua
+            .set_named_registry_value(NET_CALLBACKS_KEY, callbacks)
+            .map_err(|e| format!("Failed to store net callbacks: {}", e))
+    }
+
+    /// 在插件线程上执行网络事件回调；已关闭的句柄直接忽略
+    pub fn dispatch_net_event(&self, event: &NetEvent) -> Result<(), String> {
+        let known = {
+            let mut state = self.net_state.lock().unwrap_or_else(|e| e.into_inner());
+            if event.kind.is_terminal() {
+                state.handles.remove(&event.handle_id).is_some()
+            } else {
+                state.handles.contains_key(&event.handle_id)
+            }
+        };
+        if !known {
+            return Ok(());
+        }
+
+        let registry: Table = self
+            .lua
+            .named_registry_value(NET_CALLBACKS_KEY)
+            .map_err(|e| format!("获取网络回调失败: {}", e))?;
+        let callbacks: Option<Table> = registry.get(event.handle_id).ok();
+        if event.kind.is_terminal() {
+            let _ = registry.set(event.handle_id, Value::Nil);
+        }
+        let Some(callbacks) = callbacks else {
+            return Ok(());
+        };
+
+        let lua = &self.lua;
+        let to_lua = |e: mlua::Error| format!("构造网络回调参数失败: {}", e);
+        let (name, args): (&str, MultiValue) = match &event.kind {
+            NetEventKind::Open => ("on_open", MultiValue::new()),
+            NetEventKind::Message { data, binary } => (
+                "on_message",
+                MultiValue::from_vec(vec![
+                    Value::String(lua.create_string(data).map_err(to_lua)?),
+                    Value::Boolean(*binary),
+                ]),
+            ),
+            NetEventKind::Close { code, reason } => (
+                "on_close",
+                MultiValue::from_vec(vec![
+                    Value::Integer(*code as i64),
+                    Value::String(lua.create_string(reason).map_err(to_lua)?),
+                ]),
+            ),
+            NetEventKind::Error(message) => (
+                "on_error",
+                MultiValue::from_vec(vec![Value::String(
+                    lua.create_string(message).map_err(to_lua)?,
+                )]),
+            ),
+            NetEventKind::Response { status, headers } => {
+                let table = lua.create_table().map_err(to_lua)?;
+                for (name, value) in headers {
+                    table.set(name.as_str(), value.as_str()).map_err(to_lua)?;
+                }
+                (
+                    "on_response",
+                    MultiValue::from_vec(vec![Value::Integer(*status as i64), Value::Table(table)]),
+                )
+            }
+            NetEventKind::Chunk(data) => (
+                "on_chunk",
+                MultiValue::from_vec(vec![Value::String(lua.create_string(data).map_err(to_lua)?)]),
+            ),
+            NetEventKind::Done { bytes, error } => {
+                let error = match error {
+                    Some(e) => Value::String(lua.create_string(e).map_err(to_lua)?),
+                    None => Value::Nil,
+                };
+                ("on_done", MultiValue::from_vec(vec![Value::Integer(*bytes as i64), error]))
+            }
+        };
+
+        let Ok(Value::Function(callback)) = callbacks.get::<Value>(name) else {
+            return Ok(());
+        };
+        self.with_budget(super::limits::CALL_TIME_BUDGET, || {
+            callback
+                .call::<()>(args)
+                .map_err(|e| format!("网络回调 {} 执行失败: {}", name, e))
+        })
+    }
+
+    /// 关闭插件的全部网络句柄，后台线程看到取消标记后自行退出
+    pub(super) fn clear_net_handles(&self) {
+        let handles: Vec<NetHandle> = self
+            .net_state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .handles
+            .drain()
+            .map(|(_, handle)| handle)
+            .collect();
+        for handle in handles {
+            handle.cancel.store(true, Ordering::SeqCst);
+        }
+        let _ = self.setup_net_callbacks();
+    }
+}
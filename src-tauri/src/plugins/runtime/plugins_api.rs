@@ -14,6 +14,7 @@ impl PluginRuntime {
 
         let plugin_dir = self.plugin_dir.clone();
         let plugin_id = self.plugin_id.clone();
+        let permissions = self.permissions.clone();
 
         let plugins_root = plugin_dir
             .parent()
@@ -24,9 +25,11 @@ impl PluginRuntime {
 
         let root = plugins_root.clone();
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let list_fn = self
             .lua
             .create_function(move |lua, ()| {
+                perms.require("plugin_folder_access")?;
                 let _ = emit_permission_log(&pid, "api_call", "sl.plugins.list", "");
 
                 let result = lua.create_table()?;
@@ -85,9 +88,11 @@ impl PluginRuntime {
 
         let root = plugins_root.clone();
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let get_manifest_fn = self
             .lua
             .create_function(move |lua, target_id: String| {
+                perms.require("plugin_folder_access")?;
                 let _ =
                     emit_permission_log(&pid, "api_call", "sl.plugins.get_manifest", &target_id);
 
@@ -115,9 +120,11 @@ impl PluginRuntime {
 
         let root = plugins_root.clone();
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let read_file_fn = self
             .lua
             .create_function(move |_, (target_id, relative_path): (String, String)| {
+                perms.require("plugin_folder_access")?;
                 let _ = emit_permission_log(
                     &pid,
                     "api_call",
@@ -156,10 +163,12 @@ impl PluginRuntime {
 
         let root = plugins_root.clone();
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let write_file_fn = self
             .lua
             .create_function(
                 move |_, (target_id, relative_path, content): (String, String, String)| {
+                    perms.require("plugin_folder_access")?;
                     let _ = emit_permission_log(
                         &pid,
                         "api_call",
@@ -202,9 +211,11 @@ impl PluginRuntime {
 
         let root = plugins_root.clone();
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let file_exists_fn = self
             .lua
             .create_function(move |_, (target_id, relative_path): (String, String)| {
+                perms.require("plugin_folder_access")?;
                 let _ = emit_permission_log(
                     &pid,
                     "api_call",
@@ -229,9 +240,11 @@ impl PluginRuntime {
 
         let root = plugins_root.clone();
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let list_files_fn = self
             .lua
             .create_function(move |lua, (target_id, relative_path): (String, String)| {
+                perms.require("plugin_folder_access")?;
                 let _ = emit_permission_log(
                     &pid,
                     "api_call",
@@ -61,11 +61,7 @@ impl PluginRuntime {
                         Option<Vec<String>>,
                         Option<Table>,
                     )| {
-                        if !perms.iter().any(|p| p == "execute_program") {
-                            return Err(mlua::Error::runtime(
-                                "Permission denied: 'execute_program' permission required",
-                            ));
-                        }
+                        perms.require("execute_program")?;
 
                         let program_path = validate_path_static(&dir, &program)?;
 
@@ -175,9 +171,11 @@ impl PluginRuntime {
 
         let pid = plugin_id.clone();
         let registry = Arc::clone(&process_registry);
+        let perms = permissions.clone();
         let kill_fn = self
             .lua
             .create_function(move |_, target_pid: u32| {
+                perms.require("execute_program")?;
                 let _ = emit_permission_log(
                     &pid,
                     "api_call",
@@ -212,9 +210,11 @@ impl PluginRuntime {
 
         let pid = plugin_id.clone();
         let registry = Arc::clone(&process_registry);
+        let perms = permissions.clone();
         let get_fn = self
             .lua
             .create_function(move |lua, target_pid: u32| {
+                perms.require("execute_program")?;
                 let _ = emit_permission_log(
                     &pid,
                     "api_call",
@@ -259,9 +259,11 @@ impl PluginRuntime {
 
         let pid = plugin_id.clone();
         let registry = Arc::clone(&process_registry);
+        let perms = permissions.clone();
         let list_fn = self
             .lua
             .create_function(move |lua, ()| {
+                perms.require("execute_program")?;
                 let _ = emit_permission_log(&pid, "api_call", "sl.process.list", "");
 
                 let mut procs = registry.lock().unwrap_or_else(|e| {
@@ -301,9 +303,11 @@ impl PluginRuntime {
 
         let pid = plugin_id.clone();
         let registry = Arc::clone(&process_registry);
+        let perms = permissions.clone();
         let read_output_fn = self
             .lua
             .create_function(move |lua, target_pid: u32| {
+                perms.require("execute_program")?;
                 let _ = emit_permission_log(
                     &pid,
                     "api_call",
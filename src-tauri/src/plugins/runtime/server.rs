@@ -6,8 +6,6 @@ use std::path::PathBuf;
 
 impl PluginRuntime {
     pub(super) fn setup_server_namespace(&self, sl: &Table) -> Result<(), String> {
-        use crate::services::global::server_manager;
-
         let server_table = self
             .lua
             .create_table()
@@ -15,17 +13,15 @@ impl PluginRuntime {
 
         const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024;
         let permissions = self.permissions.clone();
+        let server_backend = self.server_backend.clone();
 
         let perms = permissions.clone();
+        let backend = server_backend.clone();
         let list_fn = self
             .lua
             .create_function(move |lua, ()| {
-                if !perms.iter().any(|p| p == "server") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'server' permission required",
-                    ));
-                }
-                let servers = server_manager().get_server_list();
+                perms.require("server")?;
+                let servers = backend.list_servers();
                 let result = lua.create_table()?;
                 for (i, server) in servers.iter().enumerate() {
                     let entry = lua.create_table()?;
@@ -44,15 +40,12 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set server.list: {}", e))?;
 
         let perms = permissions.clone();
+        let backend = server_backend.clone();
         let get_path_fn =
             self.lua
                 .create_function(move |_, server_id: String| {
-                    if !perms.iter().any(|p| p == "server") {
-                        return Err(mlua::Error::runtime(
-                            "Permission denied: 'server' permission required",
-                        ));
-                    }
-                    let servers = server_manager().get_server_list();
+                    perms.require("server")?;
+                    let servers = backend.list_servers();
                     let server = servers.iter().find(|s| s.id == server_id).ok_or_else(|| {
                         mlua::Error::runtime(format!("服务器不存在: {}", server_id))
                     })?;
@@ -64,15 +57,12 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set server.get_path: {}", e))?;
 
         let perms = permissions.clone();
+        let backend = server_backend.clone();
         let read_file_fn =
             self.lua
                 .create_function(move |_, (server_id, relative_path): (String, String)| {
-                    if !perms.iter().any(|p| p == "server") {
-                        return Err(mlua::Error::runtime(
-                            "Permission denied: 'server' permission required",
-                        ));
-                    }
-                    let servers = server_manager().get_server_list();
+                    perms.require("server")?;
+                    let servers = backend.list_servers();
                     let server = servers.iter().find(|s| s.id == server_id).ok_or_else(|| {
                         mlua::Error::runtime(format!("服务器不存在: {}", server_id))
                     })?;
@@ -95,16 +85,13 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set server.read_file: {}", e))?;
 
         let perms = permissions.clone();
+        let backend = server_backend.clone();
         let write_file_fn = self
             .lua
             .create_function(
                 move |_, (server_id, relative_path, content): (String, String, String)| {
-                    if !perms.iter().any(|p| p == "server") {
-                        return Err(mlua::Error::runtime(
-                            "Permission denied: 'server' permission required",
-                        ));
-                    }
-                    let servers = server_manager().get_server_list();
+                    perms.require("server")?;
+                    let servers = backend.list_servers();
                     let server = servers.iter().find(|s| s.id == server_id).ok_or_else(|| {
                         mlua::Error::runtime(format!("服务器不存在: {}", server_id))
                     })?;
@@ -128,15 +115,12 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set server.write_file: {}", e))?;
 
         let perms = permissions.clone();
+        let backend = server_backend.clone();
         let list_dir_fn =
             self.lua
                 .create_function(move |lua, (server_id, relative_path): (String, String)| {
-                    if !perms.iter().any(|p| p == "server") {
-                        return Err(mlua::Error::runtime(
-                            "Permission denied: 'server' permission required",
-                        ));
-                    }
-                    let servers = server_manager().get_server_list();
+                    perms.require("server")?;
+                    let servers = backend.list_servers();
                     let server = servers.iter().find(|s| s.id == server_id).ok_or_else(|| {
                         mlua::Error::runtime(format!("服务器不存在: {}", server_id))
                     })?;
@@ -175,15 +159,12 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set server.list_dir: {}", e))?;
 
         let perms = permissions.clone();
+        let backend = server_backend.clone();
         let exists_fn =
             self.lua
                 .create_function(move |_, (server_id, relative_path): (String, String)| {
-                    if !perms.iter().any(|p| p == "server") {
-                        return Err(mlua::Error::runtime(
-                            "Permission denied: 'server' permission required",
-                        ));
-                    }
-                    let servers = server_manager().get_server_list();
+                    perms.require("server")?;
+                    let servers = backend.list_servers();
                     let server = servers.iter().find(|s| s.id == server_id).ok_or_else(|| {
                         mlua::Error::runtime(format!("服务器不存在: {}", server_id))
                     })?;
@@ -199,6 +180,7 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set server.exists: {}", e))?;
 
         let perms = permissions.clone();
+        let backend = server_backend.clone();
         let logs_table = self
             .lua
             .create_table()
@@ -207,19 +189,15 @@ impl PluginRuntime {
         let get_logs_fn = self
             .lua
             .create_function(move |lua, (server_id, count): (String, Option<usize>)| {
-                if !perms.iter().any(|p| p == "server") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'server' permission required",
-                    ));
-                }
+                perms.require("server")?;
 
-                let servers = server_manager().get_server_list();
+                let servers = backend.list_servers();
                 if !servers.iter().any(|s| s.id == server_id) {
                     return Err(mlua::Error::runtime(format!("服务器不存在: {}", server_id)));
                 }
 
                 let count = count.unwrap_or(100).min(1000);
-                let all_logs = crate::services::server_log_pipeline::get_logs(&server_id, 0, None);
+                let all_logs = backend.logs(&server_id);
 
                 let start = if all_logs.len() > count {
                     all_logs.len() - count
@@ -240,19 +218,16 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set server.logs.get: {}", e))?;
 
         let perms = permissions.clone();
+        let backend = server_backend.clone();
         let get_all_logs_fn = self
             .lua
             .create_function(move |lua, count: Option<usize>| {
-                if !perms.iter().any(|p| p == "server") {
-                    return Err(mlua::Error::runtime(
-                        "Permission denied: 'server' permission required",
-                    ));
-                }
+                perms.require("server")?;
 
                 let count = count.unwrap_or(100).min(1000);
 
-                let running_ids = server_manager().get_running_server_ids();
-                let logs_map = crate::services::server_log_pipeline::get_all_logs();
+                let running_ids = backend.running_server_ids();
+                let logs_map = backend.all_logs();
 
                 let result = lua.create_table()?;
                 let mut i = 1;
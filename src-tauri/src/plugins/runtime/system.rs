@@ -12,11 +12,14 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to create system table: {}", e))?;
 
         let plugin_id = self.plugin_id.clone();
+        let permissions = self.permissions.clone();
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let get_os_fn = self
             .lua
             .create_function(move |_, ()| {
+                perms.require("system")?;
                 let _ = emit_permission_log(&pid, "api_call", "sl.system.get_os", "");
                 let os = std::env::consts::OS;
                 Ok(os.to_string())
@@ -27,9 +30,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set system.get_os: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let get_arch_fn = self
             .lua
             .create_function(move |_, ()| {
+                perms.require("system")?;
                 let _ = emit_permission_log(&pid, "api_call", "sl.system.get_arch", "");
                 Ok(std::env::consts::ARCH.to_string())
             })
@@ -39,9 +44,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set system.get_arch: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let get_app_version_fn = self
             .lua
             .create_function(move |_, ()| {
+                perms.require("system")?;
                 let _ = emit_permission_log(&pid, "api_call", "sl.system.get_app_version", "");
                 Ok(env!("CARGO_PKG_VERSION").to_string())
             })
@@ -51,9 +58,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set system.get_app_version: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let get_memory_fn = self
             .lua
             .create_function(move |lua, ()| {
+                perms.require("system")?;
                 let _ = emit_permission_log(&pid, "api_call", "sl.system.get_memory", "");
                 let mut sys = System::new();
                 sys.refresh_memory();
@@ -74,9 +83,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set system.get_memory: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let get_cpu_fn = self
             .lua
             .create_function(move |lua, ()| {
+                perms.require("system")?;
                 let _ = emit_permission_log(&pid, "api_call", "sl.system.get_cpu", "");
                 let mut sys = System::new();
                 sys.refresh_cpu_all();
@@ -0,0 +1,247 @@
+use super::PluginRuntime;
+use mlua::{Function, Table, Value};
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+// sl.timer：
+// 1) 全局一个调度线程按到期时间排序，到期后通过 api::emit_timer_fired 交回对应插件的运行时执行回调
+// 2) 计时器 id 全局递增，插件重载后旧的调度项不会误触发新计时器
+// 3) 插件 cleanup 时清空自身计时器，并从调度队列中移除
+// 4) 重复计时器在调度线程发出触发时就排好下一次，不依赖回调是否执行；
+//    插件线程对同一计时器未执行的触发只保留一次，回调耗时超过间隔时不会堆积
+
+const TIMER_CALLBACKS_KEY: &str = "_sl_timer_callbacks";
+const MAX_TIMERS_PER_PLUGIN: usize = 64;
+const MIN_TIMER_DELAY_MS: u64 = 10;
+const MIN_TIMER_INTERVAL_MS: u64 = 50;
+
+static NEXT_TIMER_ID: AtomicU64 = AtomicU64::new(1);
+
+struct TimerEntry {
+    interval: Option<Duration>,
+}
+
+pub(crate) struct TimerState {
+    timers: HashMap<u64, TimerEntry>,
+}
+
+impl TimerState {
+    pub(crate) fn new() -> Self {
+        Self { timers: HashMap::new() }
+    }
+}
+
+/// 到期时间、计时器 id、所属插件、重复间隔
+type ScheduledTimer = (Instant, u64, String, Option<Duration>);
+
+struct TimerScheduler {
+    queue: Mutex<BinaryHeap<Reverse<ScheduledTimer>>>,
+    wakeup: Condvar,
+}
+
+static TIMER_SCHEDULER: OnceLock<&'static TimerScheduler> = OnceLock::new();
+
+fn timer_scheduler() -> &'static TimerScheduler {
+    TIMER_SCHEDULER.get_or_init(|| {
+        let scheduler: &'static TimerScheduler = Box::leak(Box::new(TimerScheduler {
+            queue: Mutex::new(BinaryHeap::new()),
+            wakeup: Condvar::new(),
+        }));
+        std::thread::spawn(move || run_timer_scheduler(scheduler));
+        scheduler
+    })
+}
+
+fn run_timer_scheduler(scheduler: &'static TimerScheduler) {
+    let mut queue = scheduler.queue.lock().unwrap_or_else(|e| e.into_inner());
+    loop {
+        let next_due = queue.peek().map(|Reverse((due, _, _, _))| *due);
+        match next_due {
+            None => {
+                queue = scheduler
+                    .wakeup
+                    .wait(queue)
+                    .unwrap_or_else(|e| e.into_inner());
+            }
+            Some(due) if due > Instant::now() => {
+                queue = scheduler
+                    .wakeup
+                    .wait_timeout(queue, due - Instant::now())
+                    .unwrap_or_else(|e| e.into_inner())
+                    .0;
+            }
+            Some(_) => {
+                let Some(Reverse((_, timer_id, plugin_id, interval))) = queue.pop() else {
+                    continue;
+                };
+                // 在持有队列锁时排好下一次，取消与 cleanup 随后都能把它移除
+                if let Some(interval) = interval {
+                    let next =
+                        (Instant::now() + interval, timer_id, plugin_id.clone(), Some(interval));
+                    queue.push(Reverse(next));
+                }
+                // 投递触发期间释放队列锁，回调里可以继续创建或取消计时器
+                drop(queue);
+                if let Err(e) = crate::plugins::api::emit_timer_fired(&plugin_id, timer_id) {
+                    eprintln!("[WARN] plugin '{}' timer {} failed: {}", plugin_id, timer_id, e);
+                }
+                queue = scheduler.queue.lock().unwrap_or_else(|e| e.into_inner());
+            }
+        }
+    }
+}
+
+fn schedule_timer(plugin_id: &str, timer_id: u64, delay: Duration, interval: Option<Duration>) {
+    let scheduler = timer_scheduler();
+    let mut queue = scheduler.queue.lock().unwrap_or_else(|e| e.into_inner());
+    queue.push(Reverse((Instant::now() + delay, timer_id, plugin_id.to_string(), interval)));
+    scheduler.wakeup.notify_one();
+}
+
+fn unschedule_timer(timer_id: u64) {
+    let Some(scheduler) = TIMER_SCHEDULER.get() else {
+        return;
+    };
+    let mut queue = scheduler.queue.lock().unwrap_or_else(|e| e.into_inner());
+    queue.retain(|Reverse((_, id, _, _))| *id != timer_id);
+}
+
+fn unschedule_plugin_timers(plugin_id: &str) {
+    let Some(scheduler) = TIMER_SCHEDULER.get() else {
+        return;
+    };
+    let mut queue = scheduler.queue.lock().unwrap_or_else(|e| e.into_inner());
+    queue.retain(|Reverse((_, _, owner, _))| owner != plugin_id);
+}
+
+impl PluginRuntime {
+    pub(super) fn setup_timer_namespace(&self, sl: &Table) -> Result<(), String> {
+        let timer_table = self
+            .lua
+            .create_table()
+            .map_err(|e| format!("Failed to create timer table: {}", e))?;
+
+        let callbacks = self
+            .lua
+            .create_table()
+            .map_err(|e| format!("Failed to create timer callbacks table: {}", e))?;
+        self.lua
+            .set_named_registry_value(TIMER_CALLBACKS_KEY, callbacks)
+            .map_err(|e| format!("Failed to store timer callbacks: {}", e))?;
+
+        for (name, repeating) in [("after", false), ("every", true)] {
+            let plugin_id = self.plugin_id.clone();
+            let state = self.timer_state.clone();
+            let create_fn = self
+                .lua
+                .create_function(move |lua, (delay_ms, callback): (u64, Function)| {
+                    let min_delay = if repeating {
+                        MIN_TIMER_INTERVAL_MS
+                    } else {
+                        MIN_TIMER_DELAY_MS
+                    };
+                    let delay = Duration::from_millis(delay_ms.max(min_delay));
+
+                    let timer_id = NEXT_TIMER_ID.fetch_add(1, Ordering::Relaxed);
+                    let interval = repeating.then_some(delay);
+                    {
+                        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+                        if state.timers.len() >= MAX_TIMERS_PER_PLUGIN {
+                            return Err(mlua::Error::runtime(format!(
+                                "计时器数量超过上限 {}",
+                                MAX_TIMERS_PER_PLUGIN
+                            )));
+                        }
+                        state.timers.insert(timer_id, TimerEntry { interval });
+                    }
+
+                    let callbacks: Table = lua.named_registry_value(TIMER_CALLBACKS_KEY)?;
+                    callbacks.set(timer_id, callback)?;
+                    schedule_timer(&plugin_id, timer_id, delay, interval);
+                    Ok(timer_id)
+                })
+                .map_err(|e| format!("Failed to create timer.{}: {}", name, e))?;
+            timer_table
+                .set(name, create_fn)
+                .map_err(|e| format!("Failed to set timer.{}: {}", name, e))?;
+        }
+
+        let state = self.timer_state.clone();
+        let cancel_fn = self
+            .lua
+            .create_function(move |lua, timer_id: u64| {
+                let removed = state
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .timers
+                    .remove(&timer_id)
+                    .is_some();
+                if removed {
+                    unschedule_timer(timer_id);
+                    let callbacks: Table = lua.named_registry_value(TIMER_CALLBACKS_KEY)?;
+                    callbacks.set(timer_id, Value::Nil)?;
+                }
+                Ok(removed)
+            })
+            .map_err(|e| format!("Failed to create timer.cancel: {}", e))?;
+        timer_table
+            .set("cancel", cancel_fn)
+            .map_err(|e| format!("Failed to set timer.cancel: {}", e))?;
+
+        sl.set("timer", timer_table)
+            .map_err(|e| format!("Failed to set sl.timer: {}", e))?;
+
+        Ok(())
+    }
+
+    /// 由调度线程在计时器到期时调用；已取消的计时器直接忽略
+    pub fn fire_timer(&self, timer_id: u64) -> Result<(), String> {
+        let repeating = {
+            let mut state = self.timer_state.lock().unwrap_or_else(|e| e.into_inner());
+            match state.timers.get(&timer_id) {
+                None => return Ok(()),
+                Some(entry) => {
+                    let repeating = entry.interval.is_some();
+                    if !repeating {
+                        state.timers.remove(&timer_id);
+                    }
+                    repeating
+                }
+            }
+        };
+
+        let callbacks: Table = self
+            .lua
+            .named_registry_value(TIMER_CALLBACKS_KEY)
+            .map_err(|e| format!("获取计时器回调失败: {}", e))?;
+        let callback: Function = callbacks
+            .get(timer_id)
+            .map_err(|e| format!("计时器 {} 回调不存在: {}", timer_id, e))?;
+        if !repeating {
+            let _ = callbacks.set(timer_id, Value::Nil);
+        }
+
+        self.with_budget(super::limits::CALL_TIME_BUDGET, || {
+            callback
+                .call::<()>(())
+                .map_err(|e| format!("计时器回调执行失败: {}", e))
+        })
+    }
+
+    pub(super) fn clear_timers(&self) {
+        self.timer_state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .timers
+            .clear();
+        unschedule_plugin_timers(&self.plugin_id);
+        if let Ok(table) = self.lua.create_table() {
+            let _ = self
+                .lua
+                .set_named_registry_value(TIMER_CALLBACKS_KEY, table);
+        }
+    }
+}
@@ -11,11 +11,14 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to create ui table: {}", e))?;
 
         let plugin_id = self.plugin_id.clone();
+        let permissions = self.permissions.clone();
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let inject_fn = self
             .lua
             .create_function(move |_, (element_id, html): (mlua::String, mlua::String)| {
+                perms.require("ui")?;
                 let element_id = String::from_utf8_lossy(&element_id.as_bytes()).into_owned();
                 let html = String::from_utf8_lossy(&html.as_bytes()).into_owned();
 
@@ -34,9 +37,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.inject_html: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let remove_fn = self
             .lua
             .create_function(move |_, element_id: mlua::String| {
+                perms.require("ui")?;
                 let element_id = String::from_utf8_lossy(&element_id.as_bytes()).into_owned();
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.ui.remove_html", &element_id);
@@ -54,9 +59,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.remove_html: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let update_fn = self
             .lua
             .create_function(move |_, (element_id, html): (mlua::String, mlua::String)| {
+                perms.require("ui")?;
                 let element_id = String::from_utf8_lossy(&element_id.as_bytes()).into_owned();
                 let html = String::from_utf8_lossy(&html.as_bytes()).into_owned();
 
@@ -75,9 +82,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.update_html: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let register_context_menu_fn = self
             .lua
             .create_function(move |_, (context, items): (String, Table)| {
+                perms.require("ui")?;
                 use crate::plugins::api::emit_context_menu_event;
 
                 let valid_contexts =
@@ -132,9 +141,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.register_context_menu: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let unregister_context_menu_fn = self
             .lua
             .create_function(move |_, context: String| {
+                perms.require("ui")?;
                 use crate::plugins::api::emit_context_menu_event;
 
                 let valid_contexts =
@@ -161,9 +172,11 @@ impl PluginRuntime {
 
         let lua_weak = self.lua.clone();
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let on_context_menu_click_fn = self
             .lua
             .create_function(move |_, callback: Function| {
+                perms.require("ui")?;
                 let registry_key = format!("_context_menu_callback_{}", pid);
                 lua_weak
                     .set_named_registry_value(&registry_key, callback)
@@ -177,9 +190,11 @@ impl PluginRuntime {
 
         let lua_weak = self.lua.clone();
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let on_context_menu_show_fn = self
             .lua
             .create_function(move |_, callback: Function| {
+                perms.require("ui")?;
                 let registry_key = format!("_context_menu_show_callback_{}", pid);
                 lua_weak
                     .set_named_registry_value(&registry_key, callback)
@@ -193,9 +208,11 @@ impl PluginRuntime {
 
         let lua_weak = self.lua.clone();
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let on_context_menu_hide_fn = self
             .lua
             .create_function(move |_, callback: Function| {
+                perms.require("ui")?;
                 let registry_key = format!("_context_menu_hide_callback_{}", pid);
                 lua_weak
                     .set_named_registry_value(&registry_key, callback)
@@ -208,9 +225,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.on_context_menu_hide: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let inject_css_fn = self
             .lua
             .create_function(move |_, (style_id, css): (mlua::String, mlua::String)| {
+                perms.require("ui")?;
                 let style_id = String::from_utf8_lossy(&style_id.as_bytes()).into_owned();
                 let css = String::from_utf8_lossy(&css.as_bytes()).into_owned();
                 let _ = emit_permission_log(&pid, "api_call", "sl.ui.inject_css", &style_id);
@@ -228,9 +247,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.inject_css: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let remove_css_fn = self
             .lua
             .create_function(move |_, style_id: mlua::String| {
+                perms.require("ui")?;
                 let style_id = String::from_utf8_lossy(&style_id.as_bytes()).into_owned();
                 let _ = emit_permission_log(&pid, "api_call", "sl.ui.remove_css", &style_id);
                 match emit_ui_event(&pid, "remove_css", &style_id, "") {
@@ -247,6 +268,7 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.remove_css: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let toast_fn = self
             .lua
             .create_function(
@@ -256,6 +278,7 @@ impl PluginRuntime {
                     mlua::String,
                     Option<u32>,
                 )| {
+                    perms.require("ui")?;
                     let toast_type = String::from_utf8_lossy(&toast_type.as_bytes()).into_owned();
                     let message = String::from_utf8_lossy(&message.as_bytes()).into_owned();
                     let dur = duration.unwrap_or(3000);
@@ -281,9 +304,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.toast: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let register_sidebar_fn = self
             .lua
             .create_function(move |_, config: Table| {
+                perms.require("ui")?;
                 use crate::plugins::api::emit_sidebar_event;
 
                 let label: String = config
@@ -306,9 +331,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.register_sidebar: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let unregister_sidebar_fn = self
             .lua
             .create_function(move |_, ()| {
+                perms.require("ui")?;
                 use crate::plugins::api::emit_sidebar_event;
 
                 match emit_sidebar_event(&pid, "unregister", "", "") {
@@ -325,9 +352,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.unregister_sidebar: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let hide_fn = self
             .lua
             .create_function(move |_, selector: mlua::String| {
+                perms.require("ui")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.ui.hide", &selector);
@@ -345,9 +374,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.hide: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let show_fn = self
             .lua
             .create_function(move |_, selector: mlua::String| {
+                perms.require("ui")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.ui.show", &selector);
@@ -365,9 +396,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.show: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let disable_fn = self
             .lua
             .create_function(move |_, selector: mlua::String| {
+                perms.require("ui")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.ui.disable", &selector);
@@ -385,9 +418,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.disable: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let enable_fn = self
             .lua
             .create_function(move |_, selector: mlua::String| {
+                perms.require("ui")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.ui.enable", &selector);
@@ -405,10 +440,12 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.enable: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let insert_fn = self
             .lua
             .create_function(
                 move |_, (placement, selector, html): (mlua::String, mlua::String, mlua::String)| {
+                    perms.require("ui")?;
                     let placement = String::from_utf8_lossy(&placement.as_bytes()).into_owned();
                     let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                     let html = String::from_utf8_lossy(&html.as_bytes()).into_owned();
@@ -443,9 +480,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.insert: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let remove_selector_fn = self
             .lua
             .create_function(move |_, selector: mlua::String| {
+                perms.require("ui")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
 
                 let _ = emit_permission_log(&pid, "api_call", "sl.ui.remove", &selector);
@@ -463,9 +502,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.remove: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let set_style_fn = self
             .lua
             .create_function(move |_, (selector, styles): (mlua::String, Table)| {
+                perms.require("ui")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
 
                 let mut style_map = serde_json::Map::new();
@@ -492,10 +533,12 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.set_style: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let set_attribute_fn = self
             .lua
             .create_function(
                 move |_, (selector, attr, value): (mlua::String, mlua::String, mlua::String)| {
+                    perms.require("ui")?;
                     let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                     let attr = String::from_utf8_lossy(&attr.as_bytes()).into_owned();
                     let value = String::from_utf8_lossy(&value.as_bytes()).into_owned();
@@ -527,9 +570,11 @@ impl PluginRuntime {
             .map_err(|e| format!("Failed to set ui.set_attribute: {}", e))?;
 
         let pid = plugin_id.clone();
+        let perms = permissions.clone();
         let query_fn = self
             .lua
             .create_function(move |_, selector: mlua::String| {
+                perms.require("ui")?;
                 let selector = String::from_utf8_lossy(&selector.as_bytes()).into_owned();
                 match emit_ui_event(&pid, "query", &selector, "") {
                     Ok(()) => Ok(true),
@@ -553,9 +598,11 @@ impl PluginRuntime {
 
             let pid = plugin_id.clone();
 
+            let perms = permissions.clone();
             let list_fn = self
                 .lua
                 .create_function(move |lua, page_filter: Option<mlua::String>| {
+                    perms.require("ui")?;
                     let filter =
                         page_filter.map(|s| String::from_utf8_lossy(&s.as_bytes()).into_owned());
                     let entries = crate::plugins::api::component_mirror_list(filter.as_deref());
@@ -574,7 +621,9 @@ impl PluginRuntime {
                 .map_err(|e| format!("Failed to set ui.component.list: {}", e))?;
 
             let pid2 = pid.clone();
+            let perms = permissions.clone();
             let get_fn = self.lua.create_function(move |_, (cid, prop): (mlua::String, mlua::String)| {
+                perms.require("ui")?;
                 let cid = String::from_utf8_lossy(&cid.as_bytes()).into_owned();
                 let prop = String::from_utf8_lossy(&prop.as_bytes()).into_owned();
                 let payload = serde_json::json!({ "action": "get", "component_id": cid, "prop": prop, "plugin_id": pid2 });
@@ -585,7 +634,9 @@ impl PluginRuntime {
                 .set("get", get_fn)
                 .map_err(|e| format!("Failed to set ui.component.get: {}", e))?;
             let pid2 = pid.clone();
+            let perms = permissions.clone();
             let set_fn = self.lua.create_function(move |_, (cid, prop, val): (mlua::String, mlua::String, mlua::Value)| {
+                perms.require("ui")?;
                 let cid = String::from_utf8_lossy(&cid.as_bytes()).into_owned();
                 let prop = String::from_utf8_lossy(&prop.as_bytes()).into_owned();
                 let json_val = match val {
@@ -604,7 +655,9 @@ impl PluginRuntime {
                 .map_err(|e| format!("Failed to set ui.component.set: {}", e))?;
 
             let pid2 = pid.clone();
+            let perms = permissions.clone();
             let call_fn = self.lua.create_function(move |_, (cid, method): (mlua::String, mlua::String)| {
+                perms.require("ui")?;
                 let cid = String::from_utf8_lossy(&cid.as_bytes()).into_owned();
                 let method = String::from_utf8_lossy(&method.as_bytes()).into_owned();
                 let payload = serde_json::json!({ "action": "call", "component_id": cid, "method": method, "plugin_id": pid2 });
@@ -616,7 +669,9 @@ impl PluginRuntime {
                 .map_err(|e| format!("Failed to set ui.component.call: {}", e))?;
 
             let pid2 = pid.clone();
+            let perms = permissions.clone();
             let on_fn = self.lua.create_function(move |_, (cid, event): (mlua::String, mlua::String)| {
+                perms.require("ui")?;
                 let cid = String::from_utf8_lossy(&cid.as_bytes()).into_owned();
                 let event = String::from_utf8_lossy(&event.as_bytes()).into_owned();
                 let payload = serde_json::json!({ "action": "on", "component_id": cid, "prop": event, "plugin_id": pid2 });
@@ -628,6 +683,7 @@ impl PluginRuntime {
                 .map_err(|e| format!("Failed to set ui.component.on: {}", e))?;
 
             let pid2 = pid.clone();
+            let perms = permissions.clone();
             let create_fn = self
                 .lua
                 .create_function(
@@ -637,6 +693,7 @@ impl PluginRuntime {
                         mlua::String,
                         mlua::Table,
                     )| {
+                        perms.require("ui")?;
                         let component_type =
                             String::from_utf8_lossy(&component_type.as_bytes()).into_owned();
                         let component_id =
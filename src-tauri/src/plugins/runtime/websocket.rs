@@ -0,0 +1,776 @@
+use super::http::{is_private_ip, is_ssrf_url, record_request, HostScope, HttpRequestLogEntry};
+use super::net::{self, SharedNetState, WsCommand};
+use super::PluginRuntime;
+use crate::plugins::api::NetEventKind;
+use base64::Engine;
+use mlua::{Table, Value};
+use sha1::{Digest, Sha1};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+// sl.ws：最小化的 RFC 6455 客户端
+// 1) 每个连接一个线程，独占套接字；短读超时轮询，期间处理插件发来的发送 / 关闭指令
+// 2) 收到的消息通过 net::emit 交回插件线程执行 on_message 等回调
+// 3) 与 sl.http 相同，连接前校验 SSRF 与 manifest.allowed_hosts（ws/wss 分别按 http/https 匹配）
+// 4) 域名解析后再校验一次实际地址，连接只使用这个已校验的地址，防止 DNS 重绑定
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+const WRITE_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(5);
+const MAX_HANDSHAKE_SIZE: usize = 16 * 1024;
+const MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+const WS_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+enum WsStream {
+    Plain(TcpStream),
+    Tls(Box<native_tls::TlsStream<TcpStream>>),
+}
+
+impl WsStream {
+    fn tcp(&self) -> &TcpStream {
+        match self {
+            WsStream::Plain(s) => s,
+            WsStream::Tls(s) => s.get_ref(),
+        }
+    }
+}
+
+impl Read for WsStream {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            WsStream::Plain(s) => s.read(buf),
+            WsStream::Tls(s) => s.read(buf),
+        }
+    }
+}
+
+impl Write for WsStream {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            WsStream::Plain(s) => s.write(buf),
+            WsStream::Tls(s) => s.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            WsStream::Plain(s) => s.flush(),
+            WsStream::Tls(s) => s.flush(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub(super) enum WsMessage {
+    Text(String),
+    Binary(Vec<u8>),
+    Close(u16, String),
+}
+
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+fn accept_key(key: &str) -> String {
+    let digest = Sha1::digest(format!("{}{}", key, WS_GUID).as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(digest)
+}
+
+fn random_bytes() -> [u8; 16] {
+    *uuid::Uuid::new_v4().as_bytes()
+}
+
+fn encode_frame(opcode: u8, payload: &[u8], mask: Option<[u8; 4]>) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(payload.len() + 14);
+    frame.push(0x80 | opcode);
+    let mask_bit = if mask.is_some() { 0x80 } else { 0 };
+    match payload.len() {
+        len if len < 126 => frame.push(mask_bit | len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(mask_bit | 126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(mask_bit | 127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+    }
+    match mask {
+        Some(mask) => {
+            frame.extend_from_slice(&mask);
+            frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        }
+        None => frame.extend_from_slice(payload),
+    }
+    frame
+}
+
+/// 从缓冲区取出一个完整帧，数据不足时返回 None
+fn parse_frame(buffer: &mut Vec<u8>) -> Result<Option<Frame>, String> {
+    if buffer.len() < 2 {
+        return Ok(None);
+    }
+    let fin = buffer[0] & 0x80 != 0;
+    let opcode = buffer[0] & 0x0f;
+    let masked = buffer[1] & 0x80 != 0;
+    let mut offset = 2;
+    let len = match buffer[1] & 0x7f {
+        126 => {
+            if buffer.len() < 4 {
+                return Ok(None);
+            }
+            offset = 4;
+            u16::from_be_bytes([buffer[2], buffer[3]]) as u64
+        }
+        127 => {
+            if buffer.len() < 10 {
+                return Ok(None);
+            }
+            offset = 10;
+            let mut bytes = [0u8; 8];
+            bytes.copy_from_slice(&buffer[2..10]);
+            u64::from_be_bytes(bytes)
+        }
+        len => len as u64,
+    };
+    if len > MAX_MESSAGE_SIZE as u64 {
+        return Err(format!("WebSocket 消息过大，限制 {} 字节", MAX_MESSAGE_SIZE));
+    }
+    let len = len as usize;
+
+    let mask = if masked {
+        if buffer.len() < offset + 4 {
+            return Ok(None);
+        }
+        let mask = [buffer[offset], buffer[offset + 1], buffer[offset + 2], buffer[offset + 3]];
+        offset += 4;
+        Some(mask)
+    } else {
+        None
+    };
+
+    if buffer.len() < offset + len {
+        return Ok(None);
+    }
+    let mut payload: Vec<u8> = buffer.drain(..offset + len).skip(offset).collect();
+    if let Some(mask) = mask {
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+    }
+    Ok(Some(Frame { fin, opcode, payload }))
+}
+
+fn finish_message(binary: bool, data: Vec<u8>) -> Result<WsMessage, String> {
+    if binary {
+        Ok(WsMessage::Binary(data))
+    } else {
+        String::from_utf8(data)
+            .map(WsMessage::Text)
+            .map_err(|_| "收到的文本消息不是有效的 UTF-8".to_string())
+    }
+}
+
+pub(super) struct WsClient {
+    stream: WsStream,
+    buffer: Vec<u8>,
+    fragment: Option<(bool, Vec<u8>)>,
+    close_sent: bool,
+}
+
+impl WsClient {
+    /// 解析连接地址；不做 SSRF 校验，调用方负责
+    pub(super) fn resolve(url: &url::Url) -> Result<SocketAddr, String> {
+        let host = url.host_str().ok_or("WebSocket 地址缺少主机名")?;
+        let port = url
+            .port_or_known_default()
+            .ok_or("WebSocket 地址缺少端口")?;
+        (host, port)
+            .to_socket_addrs()
+            .map_err(|e| format!("解析 {} 失败: {}", host, e))?
+            .next()
+            .ok_or_else(|| format!("解析 {} 失败: 没有可用地址", host))
+    }
+
+    /// 连接到已解析的地址并完成握手
+    pub(super) fn connect(
+        url: &url::Url,
+        addr: SocketAddr,
+        headers: &[(String, String)],
+    ) -> Result<Self, String> {
+        let host = url.host_str().ok_or("WebSocket 地址缺少主机名")?;
+        let tcp = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+            .map_err(|e| format!("连接 {} 失败: {}", addr, e))?;
+        tcp.set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+            .and_then(|_| tcp.set_write_timeout(Some(WRITE_TIMEOUT)))
+            .and_then(|_| tcp.set_nodelay(true))
+            .map_err(|e| format!("设置套接字参数失败: {}", e))?;
+
+        let stream = match url.scheme() {
+            "ws" => WsStream::Plain(tcp),
+            "wss" => {
+                let connector = native_tls::TlsConnector::new()
+                    .map_err(|e| format!("创建 TLS 连接器失败: {}", e))?;
+                let tls = connector
+                    .connect(host, tcp)
+                    .map_err(|e| format!("TLS 握手失败: {}", e))?;
+                WsStream::Tls(Box::new(tls))
+            }
+            scheme => return Err(format!("不支持的 WebSocket 协议: {}", scheme)),
+        };
+
+        let mut client = Self {
+            stream,
+            buffer: Vec::new(),
+            fragment: None,
+            close_sent: false,
+        };
+        client.handshake(url, host, headers)?;
+        client
+            .stream
+            .tcp()
+            .set_read_timeout(Some(POLL_INTERVAL))
+            .map_err(|e| format!("设置套接字参数失败: {}", e))?;
+        Ok(client)
+    }
+
+    fn handshake(
+        &mut self,
+        url: &url::Url,
+        host: &str,
+        headers: &[(String, String)],
+    ) -> Result<(), String> {
+        let key = base64::engine::general_purpose::STANDARD.encode(random_bytes());
+        let host_header = match url.port() {
+            Some(port) => format!("{}:{}", host, port),
+            None => host.to_string(),
+        };
+        let mut request = format!(
+            "GET {} HTTP/1.1\r\nHost: {}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\
+             Sec-WebSocket-Key: {}\r\nSec-WebSocket-Version: 13\r\n",
+            &url[url::Position::BeforePath..url::Position::AfterQuery],
+            host_header,
+            key
+        );
+        for (name, value) in headers {
+            if [name, value].iter().any(|s| s.contains(['\r', '\n'])) {
+                return Err(format!("请求头 '{}' 包含非法字符", name));
+            }
+            request.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        request.push_str("\r\n");
+        self.stream
+            .write_all(request.as_bytes())
+            .map_err(|e| format!("发送握手请求失败: {}", e))?;
+
+        let header_end = loop {
+            if let Some(pos) = self.buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                break pos + 4;
+            }
+            if self.buffer.len() > MAX_HANDSHAKE_SIZE {
+                return Err("握手响应过大".to_string());
+            }
+            let mut chunk = [0u8; 4096];
+            let n = self
+                .stream
+                .read(&mut chunk)
+                .map_err(|e| format!("读取握手响应失败: {}", e))?;
+            if n == 0 {
+                return Err("服务器在握手期间关闭了连接".to_string());
+            }
+            self.buffer.extend_from_slice(&chunk[..n]);
+        };
+
+        let response: Vec<u8> = self.buffer.drain(..header_end).collect();
+        let response = String::from_utf8_lossy(&response);
+        let mut lines = response.split("\r\n");
+        let status_line = lines.next().unwrap_or_default();
+        if status_line.split_whitespace().nth(1) != Some("101") {
+            return Err(format!("WebSocket 握手失败: {}", status_line));
+        }
+        let accept = lines
+            .filter_map(|line| line.split_once(':'))
+            .find(|(name, _)| name.trim().eq_ignore_ascii_case("sec-websocket-accept"))
+            .map(|(_, value)| value.trim().to_string());
+        if accept.as_deref() != Some(accept_key(&key).as_str()) {
+            return Err("WebSocket 握手失败: Sec-WebSocket-Accept 不匹配".to_string());
+        }
+        Ok(())
+    }
+
+    fn send_frame(&mut self, opcode: u8, payload: &[u8]) -> Result<(), String> {
+        let random = random_bytes();
+        let mask = [random[0], random[1], random[2], random[3]];
+        self.stream
+            .write_all(&encode_frame(opcode, payload, Some(mask)))
+            .and_then(|_| self.stream.flush())
+            .map_err(|e| format!("发送 WebSocket 帧失败: {}", e))
+    }
+
+    pub(super) fn send_text(&mut self, text: &str) -> Result<(), String> {
+        self.send_frame(OP_TEXT, text.as_bytes())
+    }
+
+    pub(super) fn send_binary(&mut self, data: &[u8]) -> Result<(), String> {
+        self.send_frame(OP_BINARY, data)
+    }
+
+    pub(super) fn send_close(&mut self, code: u16, reason: &str) -> Result<(), String> {
+        if self.close_sent {
+            return Ok(());
+        }
+        self.close_sent = true;
+        let mut payload = code.to_be_bytes().to_vec();
+        payload.extend_from_slice(reason.as_bytes());
+        payload.truncate(125);
+        self.send_frame(OP_CLOSE, &payload)
+    }
+
+    /// 读取下一条消息；在读超时内没有完整消息时返回 None
+    pub(super) fn poll(&mut self) -> Result<Option<WsMessage>, String> {
+        loop {
+            if let Some(message) = self.take_message()? {
+                return Ok(Some(message));
+            }
+            let mut chunk = [0u8; 16 * 1024];
+            match self.stream.read(&mut chunk) {
+                Ok(0) => return Err("连接已被服务器关闭".to_string()),
+                Ok(n) => self.buffer.extend_from_slice(&chunk[..n]),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    return Ok(None)
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(format!("读取 WebSocket 数据失败: {}", e)),
+            }
+        }
+    }
+
+    fn take_message(&mut self) -> Result<Option<WsMessage>, String> {
+        while let Some(frame) = parse_frame(&mut self.buffer)? {
+            match frame.opcode {
+                OP_PING => self.send_frame(OP_PONG, &frame.payload)?,
+                OP_PONG => {}
+                OP_CLOSE => {
+                    let (code, reason) = if frame.payload.len() >= 2 {
+                        (
+                            u16::from_be_bytes([frame.payload[0], frame.payload[1]]),
+                            String::from_utf8_lossy(&frame.payload[2..]).to_string(),
+                        )
+                    } else {
+                        (1005, String::new())
+                    };
+                    let _ = self.send_close(code, "");
+                    return Ok(Some(WsMessage::Close(code, reason)));
+                }
+                OP_TEXT | OP_BINARY => {
+                    if self.fragment.is_some() {
+                        return Err("分片消息尚未结束时收到了新消息".to_string());
+                    }
+                    let binary = frame.opcode == OP_BINARY;
+                    if frame.fin {
+                        return finish_message(binary, frame.payload).map(Some);
+                    }
+                    self.fragment = Some((binary, frame.payload));
+                }
+                OP_CONTINUATION => {
+                    let (binary, mut data) =
+                        self.fragment.take().ok_or("收到了没有起始帧的分片")?;
+                    data.extend_from_slice(&frame.payload);
+                    if data.len() > MAX_MESSAGE_SIZE {
+                        return Err(format!("WebSocket 消息过大，限制 {} 字节", MAX_MESSAGE_SIZE));
+                    }
+                    if frame.fin {
+                        return finish_message(binary, data).map(Some);
+                    }
+                    self.fragment = Some((binary, data));
+                }
+                opcode => return Err(format!("未知的 WebSocket 帧类型: {:#x}", opcode)),
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// 连接线程：握手、收发消息，结束时总会发出一次 Close 事件
+fn run_connection(
+    plugin_id: String,
+    handle_id: u64,
+    url: url::Url,
+    headers: Vec<(String, String)>,
+    commands: mpsc::Receiver<WsCommand>,
+    cancel: Arc<AtomicBool>,
+) {
+    let started = Instant::now();
+    let mut entry = HttpRequestLogEntry::new("WS", url.as_str());
+    let mut sent = 0u64;
+    let mut received = 0u64;
+    let pending = Arc::new(AtomicUsize::new(0));
+
+    let connected = WsClient::resolve(&url)
+        .and_then(|addr| check_resolved_addr(&url, addr))
+        .and_then(|addr| WsClient::connect(&url, addr, &headers));
+    let (code, reason) = match connected {
+        Err(e) => {
+            let kind = NetEventKind::Error(e.clone());
+            net::emit_final(&plugin_id, handle_id, &pending, &cancel, kind);
+            entry.error = Some(e.clone());
+            (1006, e)
+        }
+        Ok(mut client) => {
+            entry.status = Some(101);
+            net::emit(&plugin_id, handle_id, NetEventKind::Open);
+            let mut close_deadline: Option<Instant> = None;
+
+            loop {
+                if close_deadline.is_none() {
+                    let mut closing = cancel.load(Ordering::SeqCst);
+                    let mut outcome = Ok(());
+                    while !closing && outcome.is_ok() {
+                        outcome = match commands.try_recv() {
+                            Ok(WsCommand::Text(text)) => {
+                                sent += text.len() as u64;
+                                client.send_text(&text)
+                            }
+                            Ok(WsCommand::Binary(data)) => {
+                                sent += data.len() as u64;
+                                client.send_binary(&data)
+                            }
+                            Ok(WsCommand::Close(code, reason)) => {
+                                closing = true;
+                                client.send_close(code, &reason)
+                            }
+                            Err(mpsc::TryRecvError::Empty) => break,
+                            // 插件运行时已销毁
+                            Err(mpsc::TryRecvError::Disconnected) => {
+                                closing = true;
+                                Ok(())
+                            }
+                        };
+                    }
+                    if let Err(e) = outcome {
+                        let kind = NetEventKind::Error(e.clone());
+                        net::emit_final(&plugin_id, handle_id, &pending, &cancel, kind);
+                        entry.error = Some(e.clone());
+                        break (1006, e);
+                    }
+                    if closing {
+                        let _ = client.send_close(1000, "");
+                        close_deadline = Some(Instant::now() + CLOSE_TIMEOUT);
+                    }
+                }
+                if close_deadline.is_some_and(|deadline| Instant::now() > deadline) {
+                    break (1006, "等待关闭握手超时".to_string());
+                }
+
+                match client.poll() {
+                    Ok(None) => {}
+                    Ok(Some(WsMessage::Close(code, reason))) => break (code, reason),
+                    Ok(Some(WsMessage::Text(text))) => {
+                        received += text.len() as u64;
+                        let data = text.into_bytes();
+                        let kind = NetEventKind::Message { data, binary: false };
+                        net::emit_data(&plugin_id, handle_id, &pending, &cancel, kind);
+                    }
+                    Ok(Some(WsMessage::Binary(data))) => {
+                        received += data.len() as u64;
+                        let kind = NetEventKind::Message { data, binary: true };
+                        net::emit_data(&plugin_id, handle_id, &pending, &cancel, kind);
+                    }
+                    Err(e) => {
+                        let kind = NetEventKind::Error(e.clone());
+                        net::emit_final(&plugin_id, handle_id, &pending, &cancel, kind);
+                        entry.error = Some(e.clone());
+                        break (1006, e);
+                    }
+                }
+            }
+        }
+    };
+
+    let kind = NetEventKind::Close { code, reason };
+    net::emit_final(&plugin_id, handle_id, &pending, &cancel, kind);
+    entry.request_bytes = sent;
+    entry.response_bytes = received;
+    entry.duration_ms = started.elapsed().as_millis() as u64;
+    record_request(&plugin_id, entry);
+}
+
+/// ws/wss 地址按对应的 http/https 地址做 SSRF 与允许列表校验
+fn check_ws_url(url: &str, scope: &HostScope) -> Result<url::Url, String> {
+    let parsed = url::Url::parse(url).map_err(|e| format!("无效的 WebSocket 地址: {}", e))?;
+    let http_scheme = match parsed.scheme() {
+        "ws" => "http",
+        "wss" => "https",
+        _ => return Err("WebSocket 地址必须以 ws:// 或 wss:// 开头".to_string()),
+    };
+    let mut as_http = parsed.clone();
+    as_http
+        .set_scheme(http_scheme)
+        .map_err(|_| "无效的 WebSocket 地址".to_string())?;
+    if is_ssrf_url(as_http.as_str()) {
+        return Err("SSRF: 不允许访问内网、本地或非 HTTP(S) 地址".to_string());
+    }
+    if !scope.allows(&as_http) {
+        return Err(format!(
+            "网络访问被拒绝: '{}' 不在 manifest.json 的 allowed_hosts 中",
+            parsed.host_str().unwrap_or_default()
+        ));
+    }
+    Ok(parsed)
+}
+
+/// 解析出的地址落在回环或内网时拒绝连接
+fn check_resolved_addr(url: &url::Url, addr: SocketAddr) -> Result<SocketAddr, String> {
+    if is_private_ip(&addr.ip()) {
+        return Err(format!(
+            "SSRF: '{}' 解析到内网或本地地址 {}",
+            url.host_str().unwrap_or_default(),
+            addr.ip()
+        ));
+    }
+    Ok(addr)
+}
+
+impl PluginRuntime {
+    pub(super) fn setup_ws_namespace(&self, sl: &Table, scope: HostScope) -> Result<(), String> {
+        use crate::plugins::api::emit_permission_log;
+
+        let ws_table = self
+            .lua
+            .create_table()
+            .map_err(|e| format!("Failed to create ws table: {}", e))?;
+
+        let plugin_id = self.plugin_id.clone();
+        let perms = self.permissions.clone();
+        let state = self.net_state.clone();
+        let connect_fn = self
+            .lua
+            .create_function(move |lua, (url, opts): (String, Option<Table>)| {
+                perms.require("network")?;
+                let _ = emit_permission_log(&plugin_id, "api_call", "sl.ws.connect", &url);
+                let parsed = check_ws_url(&url, &scope).map_err(|e| {
+                    let _ = emit_permission_log(&plugin_id, "denied", "sl.ws.connect", &url);
+                    mlua::Error::runtime(e)
+                })?;
+
+                let opts = match opts {
+                    Some(opts) => opts,
+                    None => lua.create_table()?,
+                };
+                let mut headers = Vec::new();
+                if let Ok(Value::Table(h)) = opts.get::<Value>("headers") {
+                    for (k, v) in h.pairs::<String, String>().flatten() {
+                        headers.push((k, v));
+                    }
+                }
+                let callbacks = net::collect_callbacks(
+                    lua,
+                    &opts,
+                    &["on_open", "on_message", "on_close", "on_error"],
+                )?;
+
+                let (sender, receiver) = mpsc::channel();
+                let (handle_id, cancel) =
+                    net::register_handle(lua, &state, callbacks, Some(sender))?;
+                let thread_plugin_id = plugin_id.clone();
+                std::thread::Builder::new()
+                    .name(format!("plugin-ws-{}", handle_id))
+                    .spawn(move || {
+                        run_connection(
+                            thread_plugin_id,
+                            handle_id,
+                            parsed,
+                            headers,
+                            receiver,
+                            cancel,
+                        )
+                    })
+                    .map_err(|e| mlua::Error::runtime(format!("启动 WebSocket 线程失败: {}", e)))?;
+
+                build_connection_table(lua, &state, handle_id)
+            })
+            .map_err(|e| format!("Failed to create ws.connect: {}", e))?;
+        ws_table
+            .set("connect", connect_fn)
+            .map_err(|e| format!("Failed to set ws.connect: {}", e))?;
+
+        sl.set("ws", ws_table)
+            .map_err(|e| format!("Failed to set sl.ws: {}", e))?;
+        Ok(())
+    }
+}
+
+/// 返回给插件的连接对象：conn.id、conn:send(data, is_binary)、conn:close(code, reason)
+fn build_connection_table(
+    lua: &mlua::Lua,
+    state: &SharedNetState,
+    handle_id: u64,
+) -> mlua::Result<Table> {
+    let conn = lua.create_table()?;
+    conn.set("id", handle_id)?;
+
+    let send_state = state.clone();
+    conn.set(
+        "send",
+        lua.create_function(
+            move |_, (_this, data, binary): (Table, mlua::String, Option<bool>)| {
+                let bytes = data.as_bytes().to_vec();
+                if bytes.len() > MAX_MESSAGE_SIZE {
+                    return Err(mlua::Error::runtime(format!(
+                        "WebSocket 消息过大，限制 {} 字节",
+                        MAX_MESSAGE_SIZE
+                    )));
+                }
+                let command = if binary.unwrap_or(false) {
+                    WsCommand::Binary(bytes)
+                } else {
+                    let text = String::from_utf8(bytes)
+                        .map_err(|_| mlua::Error::runtime("文本消息必须是有效的 UTF-8"))?;
+                    WsCommand::Text(text)
+                };
+                Ok(net::send_command(&send_state, handle_id, command))
+            },
+        )?,
+    )?;
+
+    let close_state = state.clone();
+    conn.set(
+        "close",
+        lua.create_function(
+            move |_, (_this, code, reason): (Table, Option<u16>, Option<String>)| {
+                let command = WsCommand::Close(code.unwrap_or(1000), reason.unwrap_or_default());
+                Ok(net::send_command(&close_state, handle_id, command))
+            },
+        )?,
+    )?;
+
+    Ok(conn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// 本地回显服务器：完成握手后原样回送文本 / 二进制消息，收到关闭帧时回应并退出
+    fn spawn_echo_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buffer = Vec::new();
+            let mut chunk = [0u8; 4096];
+            let header_end = loop {
+                let n = stream.read(&mut chunk).unwrap();
+                buffer.extend_from_slice(&chunk[..n]);
+                if let Some(pos) = buffer.windows(4).position(|w| w == b"\r\n\r\n") {
+                    break pos + 4;
+                }
+            };
+            let request = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+            buffer.drain(..header_end);
+            let key = request
+                .lines()
+                .filter_map(|line| line.split_once(':'))
+                .find(|(name, _)| name.eq_ignore_ascii_case("sec-websocket-key"))
+                .map(|(_, value)| value.trim().to_string())
+                .unwrap();
+            let response = format!(
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Accept: {}\r\n\r\n",
+                accept_key(&key)
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+
+            loop {
+                while let Some(frame) = parse_frame(&mut buffer).unwrap() {
+                    // 服务器发出的帧不加掩码；分两段发送以覆盖分片重组
+                    if frame.opcode == OP_PONG {
+                        continue;
+                    }
+                    if frame.opcode == OP_CLOSE {
+                        stream
+                            .write_all(&encode_frame(OP_CLOSE, &frame.payload, None))
+                            .unwrap();
+                        return;
+                    }
+                    let (head, tail) = frame.payload.split_at(frame.payload.len() / 2);
+                    let mut first = encode_frame(frame.opcode, head, None);
+                    first[0] &= 0x7f;
+                    stream.write_all(&first).unwrap();
+                    stream
+                        .write_all(&encode_frame(OP_PING, b"hb", None))
+                        .unwrap();
+                    stream
+                        .write_all(&encode_frame(OP_CONTINUATION, tail, None))
+                        .unwrap();
+                }
+                let n = stream.read(&mut chunk).unwrap();
+                if n == 0 {
+                    return;
+                }
+                buffer.extend_from_slice(&chunk[..n]);
+            }
+        });
+        port
+    }
+
+    fn next_message(client: &mut WsClient) -> WsMessage {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while Instant::now() < deadline {
+            if let Some(message) = client.poll().unwrap() {
+                return message;
+            }
+        }
+        panic!("timed out waiting for message");
+    }
+
+    #[test]
+    fn echoes_messages_through_local_server() {
+        let port = spawn_echo_server();
+        let url = url::Url::parse(&format!("ws://127.0.0.1:{}/echo?x=1", port)).unwrap();
+        let addr = WsClient::resolve(&url).unwrap();
+        assert!(check_resolved_addr(&url, addr).is_err());
+        let mut client =
+            WsClient::connect(&url, addr, &[("X-Token".to_string(), "t".to_string())]).unwrap();
+
+        client.send_text("hello, 海灯").unwrap();
+        assert_eq!(next_message(&mut client), WsMessage::Text("hello, 海灯".to_string()));
+
+        let payload: Vec<u8> = (0..70_000u32).map(|i| (i % 251) as u8).collect();
+        client.send_binary(&payload).unwrap();
+        assert_eq!(next_message(&mut client), WsMessage::Binary(payload));
+
+        client.send_close(1000, "bye").unwrap();
+        assert_eq!(next_message(&mut client), WsMessage::Close(1000, "bye".to_string()));
+    }
+
+    #[test]
+    fn rejects_private_and_out_of_scope_ws_urls() {
+        let scope = HostScope::new(vec!["gateway.example.com".to_string()]);
+        assert!(check_ws_url("ws://127.0.0.1:8080/", &HostScope::default()).is_err());
+        assert!(check_ws_url("ws://0.0.0.0:25575/", &HostScope::default()).is_err());
+        assert!(check_ws_url("ws://[fd00::1]/", &HostScope::default()).is_err());
+        assert!(check_ws_url("wss://other.example.com/", &scope).is_err());
+        assert!(check_ws_url("https://gateway.example.com/", &scope).is_err());
+        assert!(check_ws_url("wss://gateway.example.com/?v=10", &scope).is_ok());
+    }
+}
@@ -0,0 +1,405 @@
+use super::{PluginRuntime, ProcessRegistry};
+use crate::plugins::api::{ApiRegistry, NetEvent, NetEventKind, ServerEvent};
+use serde_json::Value as JsonValue;
+use std::collections::{HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread::{self, ThreadId};
+use std::time::Duration;
+
+// 每个插件的 Lua VM 运行在独立的工作线程上：
+// 1) 宿主通过消息队列投递任务，需要结果的调用经通道返回并带有超时
+// 2) 事件、计时器、页面切换等通知只投递不等待，慢插件不会拖住其他插件或 UI
+// 3) 句柄被移除后队列关闭，线程处理完剩余任务即退出
+// 4) 队列有上限，插件处理不过来时服务器事件等可错过的通知直接丢弃，需要结果的调用立即返回错误
+// 5) 计时器触发与网络句柄的结束事件不能丢：走单独的不丢弃队列，每个任务执行后优先处理；
+//    同一计时器尚未执行的触发只保留一次
+
+/// 生命周期调用（加载、onLoad/onEnable/onDisable 等）的等待上限
+const LIFECYCLE_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+/// API 调用与右键菜单回调的等待上限
+const API_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+/// 每个插件待处理任务的上限
+const JOB_QUEUE_CAPACITY: usize = 1024;
+
+type Job = Box<dyn FnOnce(&PluginRuntime) + Send>;
+
+/// 不丢弃的任务；数量由计时器与网络句柄的上限约束
+#[derive(Default)]
+struct ReliableJobs {
+    jobs: VecDeque<Job>,
+    pending_timers: HashSet<u64>,
+}
+
+type SharedReliableJobs = Arc<Mutex<ReliableJobs>>;
+
+fn run_reliable_jobs(reliable: &SharedReliableJobs, runtime: &PluginRuntime) {
+    loop {
+        let job = reliable
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .jobs
+            .pop_front();
+        match job {
+            Some(job) => job(runtime),
+            None => return,
+        }
+    }
+}
+
+pub struct PluginWorker {
+    plugin_id: String,
+    sender: mpsc::SyncSender<Job>,
+    reliable: SharedReliableJobs,
+    thread_id: ThreadId,
+    pub process_registry: ProcessRegistry,
+}
+
+impl PluginWorker {
+    /// 启动工作线程并在线程内创建运行时，创建失败时返回错误
+    pub fn spawn(
+        plugin_id: &str,
+        plugin_dir: &Path,
+        data_dir: &Path,
+        api_registry: ApiRegistry,
+        permissions: Vec<String>,
+    ) -> Result<Self, String> {
+        let (sender, receiver) = mpsc::sync_channel::<Job>(JOB_QUEUE_CAPACITY);
+        let (ready_tx, ready_rx) = mpsc::sync_channel::<Result<ProcessRegistry, String>>(1);
+        let reliable = SharedReliableJobs::default();
+        let worker_reliable = Arc::clone(&reliable);
+
+        let id = plugin_id.to_string();
+        let plugin_dir = plugin_dir.to_path_buf();
+        let data_dir = data_dir.to_path_buf();
+        let handle = thread::Builder::new()
+            .name(format!("plugin-{}", plugin_id))
+            .spawn(move || {
+                let runtime = match PluginRuntime::new(
+                    &id,
+                    &plugin_dir,
+                    &data_dir,
+                    api_registry,
+                    permissions,
+                ) {
+                    Ok(runtime) => runtime,
+                    Err(e) => {
+                        let _ = ready_tx.send(Err(e));
+                        return;
+                    }
+                };
+                let _ = ready_tx.send(Ok(runtime.process_registry.clone()));
+                drop(ready_tx);
+
+                // 不丢弃的任务入队后会投递一个空任务唤醒线程，主队列已满时则由下一个任务带出
+                for job in receiver {
+                    run_reliable_jobs(&worker_reliable, &runtime);
+                    job(&runtime);
+                }
+            })
+            .map_err(|e| format!("创建插件工作线程失败: {}", e))?;
+
+        let process_registry = ready_rx
+            .recv()
+            .map_err(|_| format!("插件 '{}' 工作线程意外退出", plugin_id))??;
+
+        Ok(Self {
+            plugin_id: plugin_id.to_string(),
+            sender,
+            reliable,
+            thread_id: handle.thread().id(),
+            process_registry,
+        })
+    }
+
+    /// 在工作线程上执行并等待结果
+    pub fn call<T, F>(&self, timeout: Duration, f: F) -> Result<T, String>
+    where
+        T: Send + 'static,
+        F: FnOnce(&PluginRuntime) -> Result<T, String> + Send + 'static,
+    {
+        // 插件在自己的线程里同步调用自己会永远等不到结果
+        if thread::current().id() == self.thread_id {
+            return Err(format!("插件 '{}' 不能同步调用自身", self.plugin_id));
+        }
+
+        let (result_tx, result_rx) = mpsc::sync_channel(1);
+        self.sender
+            .try_send(Box::new(move |runtime| {
+                let _ = result_tx.send(f(runtime));
+            }))
+            .map_err(|e| match e {
+                mpsc::TrySendError::Full(_) => {
+                    format!("插件 '{}' 的任务队列已满", self.plugin_id)
+                }
+                mpsc::TrySendError::Disconnected(_) => {
+                    format!("插件 '{}' 的工作线程已退出", self.plugin_id)
+                }
+            })?;
+
+        match result_rx.recv_timeout(timeout) {
+            Ok(result) => result,
+            Err(mpsc::RecvTimeoutError::Timeout) => {
+                Err(format!("插件 '{}' 调用超时（{} 秒）", self.plugin_id, timeout.as_secs()))
+            }
+            Err(mpsc::RecvTimeoutError::Disconnected) => {
+                Err(format!("插件 '{}' 的工作线程已退出", self.plugin_id))
+            }
+        }
+    }
+
+    /// 投递任务但不等待结果
+    pub fn post<F>(&self, f: F)
+    where
+        F: FnOnce(&PluginRuntime) + Send + 'static,
+    {
+        match self.sender.try_send(Box::new(f)) {
+            Ok(()) => {}
+            Err(mpsc::TrySendError::Full(_)) => {
+                eprintln!("[WARN] plugin '{}' job queue is full, dropping job", self.plugin_id);
+            }
+            Err(mpsc::TrySendError::Disconnected(_)) => {
+                eprintln!("[WARN] plugin '{}' worker has exited", self.plugin_id);
+            }
+        }
+    }
+
+    /// 投递不能丢失的任务，队列已满时照样排队
+    fn post_reliable<F>(&self, f: F)
+    where
+        F: FnOnce(&PluginRuntime) + Send + 'static,
+    {
+        self.reliable
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .jobs
+            .push_back(Box::new(f));
+        self.wake();
+    }
+
+    /// 唤醒空闲的工作线程；主队列已满说明线程正忙，处理完当前任务就会执行不丢弃的任务
+    fn wake(&self) {
+        if let Err(mpsc::TrySendError::Disconnected(_)) = self.sender.try_send(Box::new(|_| {})) {
+            eprintln!("[WARN] plugin '{}' worker has exited", self.plugin_id);
+        }
+    }
+
+    pub fn load_file(&self, path: &Path) -> Result<(), String> {
+        let path: PathBuf = path.to_path_buf();
+        self.call(LIFECYCLE_CALL_TIMEOUT, move |runtime| runtime.load_file(&path))
+    }
+
+    pub fn call_lifecycle(&self, event: &str) -> Result<(), String> {
+        let event = event.to_string();
+        self.call(LIFECYCLE_CALL_TIMEOUT, move |runtime| runtime.call_lifecycle(&event))
+    }
+
+    pub fn call_lifecycle_with_arg(&self, event: &str, arg: &str) -> Result<(), String> {
+        let event = event.to_string();
+        let arg = arg.to_string();
+        self.call(LIFECYCLE_CALL_TIMEOUT, move |runtime| {
+            runtime.call_lifecycle_with_arg(&event, &arg)
+        })
+    }
+
+    /// 通知型生命周期回调（页面切换、服务器就绪等），失败只记录日志
+    pub fn post_lifecycle_with_arg(&self, event: &str, arg: &str) {
+        let event = event.to_string();
+        let arg = arg.to_string();
+        let plugin_id = self.plugin_id.clone();
+        self.post(move |runtime| {
+            if let Err(e) = runtime.call_lifecycle_with_arg(&event, &arg) {
+                eprintln!("[WARN] plugin '{}' {} failed: {}", plugin_id, event, e);
+                let _ = crate::plugins::api::emit_log_event(
+                    &plugin_id,
+                    "error",
+                    &format!("Failed to call {}: {}", event, e),
+                );
+            }
+        });
+    }
+
+    pub fn post_lifecycle_with_json(&self, event: &str, arg: JsonValue) {
+        let event = event.to_string();
+        let plugin_id = self.plugin_id.clone();
+        self.post(move |runtime| {
+            if let Err(e) = runtime.call_lifecycle_with_json(&event, arg) {
+                eprintln!("[WARN] plugin '{}' {} failed: {}", plugin_id, event, e);
+                let _ = crate::plugins::api::emit_log_event(
+                    &plugin_id,
+                    "error",
+                    &format!("Failed to call {}: {}", event, e),
+                );
+            }
+        });
+    }
+
+    pub fn cleanup(&self) {
+        if let Err(e) = self.call(LIFECYCLE_CALL_TIMEOUT, |runtime| {
+            runtime.cleanup();
+            Ok(())
+        }) {
+            eprintln!("[WARN] plugin '{}' cleanup failed: {}", self.plugin_id, e);
+        }
+    }
+
+    pub fn call_registered_api(
+        &self,
+        api_name: &str,
+        args: Vec<JsonValue>,
+    ) -> Result<JsonValue, String> {
+        let api_name = api_name.to_string();
+        self.call(API_CALL_TIMEOUT, move |runtime| runtime.call_registered_api(&api_name, args))
+    }
+
+    pub fn call_command(&self, command_id: &str, args: JsonValue) -> Result<(), String> {
+        let command_id = command_id.to_string();
+        self.call(API_CALL_TIMEOUT, move |runtime| runtime.call_command(&command_id, args))
+    }
+
+    pub fn post_context_menu_hide(&self) {
+        self.post(|runtime| {
+            let _ = runtime.call_context_menu_hide_callback();
+        });
+    }
+
+    pub fn post_context_menu_show(&self, context: &str, target_data: JsonValue, x: f64, y: f64) {
+        let context = context.to_string();
+        self.post(move |runtime| {
+            let _ = runtime.call_context_menu_show_callback(&context, target_data, x, y);
+        });
+    }
+
+    pub fn call_context_menu_callback(
+        &self,
+        context: &str,
+        item_id: &str,
+        target_data: JsonValue,
+    ) -> Result<(), String> {
+        let context = context.to_string();
+        let item_id = item_id.to_string();
+        self.call(API_CALL_TIMEOUT, move |runtime| {
+            runtime.call_context_menu_callback(&context, &item_id, target_data)
+        })
+    }
+
+    pub fn set_permission_granted(&self, permission: &str, granted: bool) -> Result<(), String> {
+        let permission = permission.to_string();
+        self.call(API_CALL_TIMEOUT, move |runtime| {
+            runtime.set_permission_granted(&permission, granted)
+        })
+    }
+
+    pub fn dispatch_server_event(&self, event: ServerEvent) {
+        let plugin_id = self.plugin_id.clone();
+        self.post(move |runtime| {
+            if let Err(e) = runtime.dispatch_server_event(&event) {
+                eprintln!("[WARN] plugin '{}' event handler failed: {}", plugin_id, e);
+            }
+        });
+    }
+
+    /// 计时器触发不丢弃；上一次触发尚未执行时合并为一次
+    pub fn fire_timer(&self, timer_id: u64) {
+        {
+            let mut reliable = self.reliable.lock().unwrap_or_else(|e| e.into_inner());
+            if !reliable.pending_timers.insert(timer_id) {
+                return;
+            }
+            let plugin_id = self.plugin_id.clone();
+            let pending = Arc::clone(&self.reliable);
+            reliable.jobs.push_back(Box::new(move |runtime| {
+                pending
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .pending_timers
+                    .remove(&timer_id);
+                if let Err(e) = runtime.fire_timer(timer_id) {
+                    eprintln!("[WARN] plugin '{}' timer {} failed: {}", plugin_id, timer_id, e);
+                    let _ = crate::plugins::api::emit_log_event(&plugin_id, "error", &e);
+                }
+            }));
+        }
+        self.wake();
+    }
+
+    /// 数据事件可能因队列已满被丢弃；错误与结束事件不丢弃，保证句柄与回调被清理
+    pub fn dispatch_net_event(&self, event: NetEvent) {
+        let plugin_id = self.plugin_id.clone();
+        let reliable = event.kind.is_terminal() || matches!(event.kind, NetEventKind::Error(_));
+        // 任务被丢弃时 ticket 随闭包一起释放，后台线程不会一直等待
+        let job = move |runtime: &PluginRuntime| {
+            let result = runtime.dispatch_net_event(&event);
+            drop(event.ticket);
+            if let Err(e) = result {
+                eprintln!("[WARN] plugin '{}' net callback failed: {}", plugin_id, e);
+                let _ = crate::plugins::api::emit_log_event(&plugin_id, "error", &e);
+            }
+        };
+        if reliable {
+            self.post_reliable(job);
+        } else {
+            self.post(job);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plugins::api::new_api_registry;
+
+    #[test]
+    fn timer_fires_survive_a_full_queue_and_are_merged() {
+        let temp_dir = std::env::temp_dir().join("sl_test_worker_queue");
+        let worker = PluginWorker::spawn(
+            "test-worker-queue",
+            &temp_dir,
+            &temp_dir.join("data"),
+            new_api_registry(),
+            vec![],
+        )
+        .unwrap();
+        let timer_id: u64 = worker
+            .call(API_CALL_TIMEOUT, |runtime| {
+                runtime
+                    .lua
+                    .load(
+                        "fired = 0; return sl.timer.every(60000, function() fired = fired + 1 end)",
+                    )
+                    .eval()
+                    .map_err(|e| e.to_string())
+            })
+            .unwrap();
+
+        // 阻塞工作线程并塞满主队列
+        let (release_tx, release_rx) = mpsc::channel::<()>();
+        worker.post(move |_| {
+            let _ = release_rx.recv();
+        });
+        for _ in 0..JOB_QUEUE_CAPACITY + 1 {
+            worker.post(|_| {});
+        }
+        worker.fire_timer(timer_id);
+        worker.fire_timer(timer_id);
+        release_tx.send(()).unwrap();
+
+        let fired: i64 = loop {
+            let result = worker.call(API_CALL_TIMEOUT, |runtime| {
+                runtime
+                    .lua
+                    .globals()
+                    .get("fired")
+                    .map_err(|e| e.to_string())
+            });
+            match result {
+                Ok(fired) => break fired,
+                Err(_) => thread::sleep(Duration::from_millis(10)),
+            }
+        };
+        assert_eq!(fired, 1);
+
+        worker.cleanup();
+        let _ = std::fs::remove_dir_all(&temp_dir);
+    }
+}
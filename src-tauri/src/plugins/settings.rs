@@ -0,0 +1,238 @@
+use crate::models::plugin::PluginSettingField;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+// 插件设置：
+// 1) 设置保存在插件目录的 settings.json，写入前按 manifest.settings 校验类型、可选值、长度与数值范围
+// 2) 未填写的项用 default 补齐；manifest 未声明的 key 原样保留（主题预设等会写入额外的 key）
+// 3) 插件更新时把旧版本目录里的设置迁移到新版本：按 renamed_from 改名，删除新版本不再声明的项，不合法的值回退到默认值
+// 4) 保存后变化的项以 { key = { old = ..., new = ... } } 传给 plugin.onSettingsChanged(diff)
+
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// 读取插件目录下的设置，文件不存在时返回空表
+pub fn read_settings(plugin_dir: &Path) -> Result<Map<String, Value>, String> {
+    let path = plugin_dir.join(SETTINGS_FILE);
+    if !path.exists() {
+        return Ok(Map::new());
+    }
+    let content =
+        fs::read_to_string(&path).map_err(|e| format!("Failed to read settings file: {}", e))?;
+    match serde_json::from_str(&content)
+        .map_err(|e| format!("Failed to parse settings file: {}", e))?
+    {
+        Value::Object(map) => Ok(map),
+        _ => Err("settings.json 的内容不是 JSON 对象".to_string()),
+    }
+}
+
+pub fn write_settings(plugin_dir: &Path, settings: &Map<String, Value>) -> Result<(), String> {
+    let content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    fs::write(plugin_dir.join(SETTINGS_FILE), content)
+        .map_err(|e| format!("Failed to write settings file: {}", e))
+}
+
+/// null 与空的下拉选择都视为未填写，按默认值处理
+fn is_unset(field: &PluginSettingField, value: &Value) -> bool {
+    value.is_null() || (field.field_type == "select" && value.as_str() == Some(""))
+}
+
+fn check_value(field: &PluginSettingField, value: &Value) -> Result<(), String> {
+    match field.field_type.as_str() {
+        "string" | "textarea" | "color" => {
+            let text = value.as_str().ok_or("应为字符串")?;
+            if let Some(max) = field.maxlength {
+                if text.chars().count() > max as usize {
+                    return Err(format!("长度不能超过 {} 个字符", max));
+                }
+            }
+        }
+        "number" => {
+            let number = value.as_f64().ok_or("应为数字")?;
+            if let Some(min) = field.min.filter(|min| number < *min) {
+                return Err(format!("不能小于 {}", min));
+            }
+            if let Some(max) = field.max.filter(|max| number > *max) {
+                return Err(format!("不能大于 {}", max));
+            }
+        }
+        "boolean" | "checkbox" if !value.is_boolean() => {
+            return Err("应为布尔值".to_string());
+        }
+        "select" => {
+            let selected = value.as_str().ok_or("应为字符串")?;
+            let options = field.options.as_deref().unwrap_or_default();
+            if !options.is_empty() && !options.iter().any(|o| o.value == selected) {
+                return Err(format!("'{}' 不是可选的值", selected));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// 去掉不符合声明的值并补齐默认值，用于读取已保存的设置和迁移
+pub fn normalize_settings(
+    schema: &[PluginSettingField],
+    mut settings: Map<String, Value>,
+) -> Map<String, Value> {
+    for field in schema {
+        let valid = settings
+            .get(&field.key)
+            .is_some_and(|value| !is_unset(field, value) && check_value(field, value).is_ok());
+        if !valid {
+            settings.remove(&field.key);
+            if let Some(default) = &field.default {
+                settings.insert(field.key.clone(), default.clone());
+            }
+        }
+    }
+    settings
+}
+
+/// 校验前端提交的设置，任何声明过的项不合法都整体拒绝；未填写的项用默认值补齐
+pub fn validate_settings(
+    schema: &[PluginSettingField],
+    settings: Value,
+) -> Result<Map<String, Value>, String> {
+    let Value::Object(mut settings) = settings else {
+        return Err("插件设置必须是 JSON 对象".to_string());
+    };
+    for field in schema {
+        match settings.get(&field.key) {
+            Some(value) if !is_unset(field, value) => {
+                check_value(field, value).map_err(|e| format!("设置项 '{}' {}", field.label, e))?
+            }
+            _ => {
+                settings.remove(&field.key);
+                if let Some(default) = &field.default {
+                    settings.insert(field.key.clone(), default.clone());
+                }
+            }
+        }
+    }
+    Ok(settings)
+}
+
+/// 把旧版本的设置迁移到新版本的声明上
+pub fn migrate_settings(
+    old_schema: &[PluginSettingField],
+    new_schema: &[PluginSettingField],
+    mut settings: Map<String, Value>,
+) -> Map<String, Value> {
+    let declared = |key: &str| new_schema.iter().any(|f| f.key == key);
+
+    for field in new_schema {
+        for old_key in &field.renamed_from {
+            if declared(old_key) {
+                continue;
+            }
+            if let Some(value) = settings.remove(old_key) {
+                settings.entry(field.key.clone()).or_insert(value);
+            }
+        }
+    }
+    for field in old_schema {
+        if !declared(&field.key) {
+            settings.remove(&field.key);
+        }
+    }
+    normalize_settings(new_schema, settings)
+}
+
+/// 新旧设置中值不同的项：key → { old, new }，不存在的一侧为 null
+pub fn settings_diff(old: &Map<String, Value>, new: &Map<String, Value>) -> Map<String, Value> {
+    old.keys()
+        .chain(new.keys().filter(|key| !old.contains_key(*key)))
+        .filter(|key| old.get(*key) != new.get(*key))
+        .map(|key| {
+            let change = serde_json::json!({
+                "old": old.get(key).cloned().unwrap_or(Value::Null),
+                "new": new.get(key).cloned().unwrap_or(Value::Null),
+            });
+            (key.clone(), change)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema(value: Value) -> Vec<PluginSettingField> {
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_validate_migrate_and_diff() {
+        let fields = schema(json!([
+            { "key": "name", "label": "Name", "type": "string", "maxlength": 4, "default": "sl" },
+            { "key": "interval", "label": "Interval", "type": "number", "min": 1, "max": 60,
+              "renamed_from": ["period"] },
+            { "key": "mode", "label": "Mode", "type": "select", "default": "a",
+              "options": [{ "value": "a", "label": "A" }, { "value": "b", "label": "B" }] },
+            { "key": "on", "label": "On", "type": "boolean" }
+        ]));
+
+        let ok =
+            validate_settings(&fields, json!({ "interval": 5, "on": true, "extra": 1 })).unwrap();
+        assert_eq!(ok["name"], "sl");
+        assert_eq!(ok["mode"], "a");
+        assert_eq!(ok["extra"], 1);
+
+        assert!(validate_settings(&fields, json!({ "name": "toolong" })).is_err());
+        assert!(validate_settings(&fields, json!({ "interval": 0 })).is_err());
+        assert!(validate_settings(&fields, json!({ "interval": "5" })).is_err());
+        assert!(validate_settings(&fields, json!({ "mode": "c" })).is_err());
+        assert!(validate_settings(&fields, json!({ "on": "true" })).is_err());
+        assert!(validate_settings(&fields, json!([1, 2])).is_err());
+
+        let old_fields = schema(json!([
+            { "key": "period", "label": "Period", "type": "number" },
+            { "key": "legacy", "label": "Legacy", "type": "string" },
+            { "key": "mode", "label": "Mode", "type": "select" }
+        ]));
+        let old = json!({ "period": 30, "legacy": "x", "mode": "z", "preset": "dark" });
+        let migrated = migrate_settings(&old_fields, &fields, old.as_object().cloned().unwrap());
+        assert_eq!(migrated["interval"], 30);
+        assert_eq!(migrated["mode"], "a");
+        assert_eq!(migrated["name"], "sl");
+        assert_eq!(migrated["preset"], "dark");
+        assert!(!migrated.contains_key("period") && !migrated.contains_key("legacy"));
+
+        let mut changed = migrated.clone();
+        changed.insert("interval".to_string(), json!(10));
+        changed.remove("preset");
+        let diff = settings_diff(&migrated, &changed);
+        assert_eq!(diff.len(), 2);
+        assert_eq!(diff["interval"], json!({ "old": 30, "new": 10 }));
+        assert_eq!(diff["preset"], json!({ "old": "dark", "new": null }));
+    }
+
+    #[test]
+    fn test_form_default_payload_is_accepted() {
+        let fields = schema(json!([
+            { "key": "name", "label": "Name", "type": "string" },
+            { "key": "interval", "label": "Interval", "type": "number", "min": 5, "max": 60 },
+            { "key": "mode", "label": "Mode", "type": "select",
+              "options": [{ "value": "a", "label": "A" }] },
+            { "key": "level", "label": "Level", "type": "select", "default": "b",
+              "options": [{ "value": "a", "label": "A" }, { "value": "b", "label": "B" }] },
+            { "key": "on", "label": "On", "type": "checkbox" }
+        ]));
+
+        // 设置表单未改动时提交的内容：数值取 min，下拉框可能仍是空字符串
+        let form = json!({ "name": "", "interval": 5, "mode": "", "level": "", "on": false });
+        let saved = validate_settings(&fields, form).unwrap();
+        assert!(!saved.contains_key("mode"));
+        assert_eq!(saved["level"], "b");
+        assert_eq!(saved["interval"], 5);
+
+        let normalized = normalize_settings(&fields, saved);
+        assert!(!normalized.contains_key("mode"));
+        assert_eq!(normalized["level"], "b");
+    }
+}
@@ -0,0 +1,136 @@
+use crate::models::plugin::{PluginSignature, SignatureStatus};
+use crate::models::settings::TrustedPublisher;
+use base64::Engine;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// 插件包签名校验：
+// 1) 市场条目可以携带 sha256 与 ed25519 签名，签名对象是整个插件 ZIP 的原始字节
+// 2) 校验在解压前完成；提供了签名却无法被任何受信任发布者公钥验证时直接拒绝安装
+// 3) 没有签名的插件可以安装，但状态记为 unsigned，申请高危权限时需要用户明确确认
+
+const SIGNATURE_STORE_FILE: &str = "plugin_signatures.json";
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 校验下载的插件包，返回应记录的签名状态
+pub fn verify_package(
+    bytes: &[u8],
+    expected_sha256: Option<&str>,
+    signature: Option<&str>,
+    trusted: &[TrustedPublisher],
+) -> Result<PluginSignature, String> {
+    let actual_sha256 = sha256_hex(bytes);
+    if let Some(expected) = expected_sha256 {
+        if !expected.trim().eq_ignore_ascii_case(&actual_sha256) {
+            return Err(format!(
+                "插件包校验失败：sha256 不匹配（期望 {}，实际 {}）",
+                expected.trim(),
+                actual_sha256
+            ));
+        }
+    }
+
+    let Some(signature) = signature.map(str::trim).filter(|s| !s.is_empty()) else {
+        return Ok(PluginSignature {
+            status: SignatureStatus::Unsigned,
+            publisher: None,
+            sha256: Some(actual_sha256),
+            critical_confirmed: false,
+        });
+    };
+
+    let engine = base64::engine::general_purpose::STANDARD;
+    let signature = engine
+        .decode(signature)
+        .map_err(|e| format!("插件签名格式无效: {}", e))?;
+
+    for publisher in trusted {
+        let Ok(key) = engine.decode(publisher.public_key.trim()) else {
+            eprintln!("[WARN] Invalid public key for trusted publisher '{}'", publisher.name);
+            continue;
+        };
+        if UnparsedPublicKey::new(&ED25519, &key)
+            .verify(bytes, &signature)
+            .is_ok()
+        {
+            return Ok(PluginSignature {
+                status: SignatureStatus::Verified,
+                publisher: Some(publisher.name.clone()),
+                sha256: Some(actual_sha256),
+                critical_confirmed: false,
+            });
+        }
+    }
+
+    Err("插件签名校验失败：签名无法被任何受信任的发布者公钥验证".to_string())
+}
+
+pub fn load_signature_store(data_dir: &Path) -> Option<HashMap<String, PluginSignature>> {
+    let content = fs::read_to_string(data_dir.join(SIGNATURE_STORE_FILE)).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(store) => Some(store),
+        Err(e) => {
+            eprintln!("[WARN] Failed to parse plugin signature store: {}", e);
+            Some(HashMap::new())
+        }
+    }
+}
+
+pub fn save_signature_store(data_dir: &Path, store: &HashMap<String, PluginSignature>) {
+    if let Err(e) = fs::create_dir_all(data_dir) {
+        eprintln!("[WARN] Failed to create plugin data directory: {}", e);
+        return;
+    }
+    match serde_json::to_string_pretty(store) {
+        Ok(json) => {
+            if let Err(e) = fs::write(data_dir.join(SIGNATURE_STORE_FILE), json) {
+                eprintln!("[WARN] Failed to save plugin signature store: {}", e);
+            }
+        }
+        Err(e) => eprintln!("[WARN] Failed to serialize plugin signature store: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::SystemRandom;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    #[test]
+    fn verifies_signed_package_and_rejects_tampering() {
+        let rng = SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let engine = base64::engine::general_purpose::STANDARD;
+
+        let package = b"PK\x03\x04 plugin payload".to_vec();
+        let signature = engine.encode(key_pair.sign(&package).as_ref());
+        let trusted = vec![TrustedPublisher {
+            name: "SeaLantern".to_string(),
+            public_key: engine.encode(key_pair.public_key().as_ref()),
+        }];
+
+        let sha256 = sha256_hex(&package);
+        let verified = verify_package(&package, Some(&sha256), Some(&signature), &trusted).unwrap();
+        assert_eq!(verified.status, SignatureStatus::Verified);
+        assert_eq!(verified.publisher.as_deref(), Some("SeaLantern"));
+
+        let mut tampered = package.clone();
+        tampered.push(0);
+        assert!(verify_package(&tampered, None, Some(&signature), &trusted).is_err());
+        assert!(verify_package(&tampered, Some(&sha256), None, &trusted).is_err());
+
+        let unsigned = verify_package(&package, None, None, &trusted).unwrap();
+        assert_eq!(unsigned.status, SignatureStatus::Unsigned);
+    }
+}
@@ -0,0 +1,263 @@
+use crate::models::plugin::{get_permission_danger_level, PermissionDangerLevel, SignatureStatus};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// 插件更新：
+// 1) 更新包先解压到 plugin_staging/<id>，原版本目录整体移到 plugin_backups/<id> 作为上一版本保留
+// 2) 插件运行中时先禁用（连同依赖它的插件），换好目录后重新启用，启用时调用 onMigrate(fromVersion)
+// 3) load_file、onMigrate 或 onEnable 失败时自动换回上一版本并重新启用
+// 4) 每个插件可设置更新策略 off / notify / auto，auto 只对新旧版本都未申请 Critical 权限、
+//    且新版本签名状态不低于当前版本的插件生效
+
+const UPDATE_STORE_FILE: &str = "plugin_updates.json";
+const BACKUP_DIR_NAME: &str = "plugin_backups";
+const STAGING_DIR_NAME: &str = "plugin_staging";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdatePolicy {
+    Off,
+    #[default]
+    Notify,
+    Auto,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct UpdateStoreData {
+    #[serde(default)]
+    policies: HashMap<String, UpdatePolicy>,
+    /// 已换上新版本但尚未成功执行 onMigrate 的插件：插件 id → 更新前的版本
+    #[serde(default)]
+    pending_migrations: HashMap<String, String>,
+}
+
+pub struct UpdateStore {
+    data_dir: PathBuf,
+    data: UpdateStoreData,
+}
+
+impl UpdateStore {
+    pub fn load(data_dir: &Path) -> Self {
+        let data = fs::read_to_string(data_dir.join(UPDATE_STORE_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { data_dir: data_dir.to_path_buf(), data }
+    }
+
+    fn save(&self) {
+        let path = self.data_dir.join(UPDATE_STORE_FILE);
+        match serde_json::to_string_pretty(&self.data) {
+            Ok(content) => {
+                if let Err(e) = fs::write(&path, content) {
+                    eprintln!("[WARN] Failed to save plugin update store: {}", e);
+                }
+            }
+            Err(e) => eprintln!("[WARN] Failed to serialize plugin update store: {}", e),
+        }
+    }
+
+    pub fn policy(&self, plugin_id: &str) -> UpdatePolicy {
+        self.data
+            .policies
+            .get(plugin_id)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_policy(&mut self, plugin_id: &str, policy: UpdatePolicy) {
+        self.data.policies.insert(plugin_id.to_string(), policy);
+        self.save();
+    }
+
+    pub fn pending_migration(&self, plugin_id: &str) -> Option<String> {
+        self.data.pending_migrations.get(plugin_id).cloned()
+    }
+
+    pub fn set_pending_migration(&mut self, plugin_id: &str, from_version: Option<&str>) {
+        match from_version {
+            Some(version) => {
+                self.data
+                    .pending_migrations
+                    .insert(plugin_id.to_string(), version.to_string());
+            }
+            None => {
+                if self.data.pending_migrations.remove(plugin_id).is_none() {
+                    return;
+                }
+            }
+        }
+        self.save();
+    }
+
+    pub fn remove_plugin(&mut self, plugin_id: &str) {
+        self.data.policies.remove(plugin_id);
+        self.data.pending_migrations.remove(plugin_id);
+        self.save();
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginUpdateResult {
+    pub plugin_id: String,
+    pub from_version: String,
+    pub to_version: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginUpdateFailure {
+    pub plugin_id: String,
+    pub error: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct AutoUpdateReport {
+    /// 已自动更新的插件
+    pub updated: Vec<PluginUpdateResult>,
+    /// 有新版本但策略为 notify，或因高危权限、签名状态降低需要手动更新的插件
+    pub available: Vec<crate::models::plugin::PluginUpdateInfo>,
+    pub failed: Vec<PluginUpdateFailure>,
+}
+
+pub fn has_critical_permission(permissions: &[String]) -> bool {
+    permissions
+        .iter()
+        .any(|p| get_permission_danger_level(p) == PermissionDangerLevel::Critical)
+}
+
+/// 自动更新不能降低签名状态：已验证签名的插件只能自动更新为同样验证通过的包
+pub fn keeps_signature_trust(current: SignatureStatus, update: SignatureStatus) -> bool {
+    matches!(
+        (current, update),
+        (SignatureStatus::Unsigned, _) | (_, SignatureStatus::Verified)
+    )
+}
+
+/// 插件上一版本的保存位置，与 plugins 目录同级
+pub fn backup_dir(plugins_dir: &Path, plugin_id: &str) -> PathBuf {
+    plugins_dir.with_file_name(BACKUP_DIR_NAME).join(plugin_id)
+}
+
+/// 更新包的解压位置，与 plugins 目录同级，避免被扫描为插件
+pub fn staging_dir(plugins_dir: &Path, plugin_id: &str) -> PathBuf {
+    plugins_dir.with_file_name(STAGING_DIR_NAME).join(plugin_id)
+}
+
+pub fn remove_dir_if_exists(dir: &Path) -> Result<(), String> {
+    if dir.exists() {
+        fs::remove_dir_all(dir).map_err(|e| format!("删除目录 {} 失败: {}", dir.display(), e))?;
+    }
+    Ok(())
+}
+
+/// 移动目录，目标已存在时先删除；跨卷 rename 失败时退回复制后删除
+pub fn move_dir(from: &Path, to: &Path) -> Result<(), String> {
+    remove_dir_if_exists(to)?;
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("创建目录 {} 失败: {}", parent.display(), e))?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    copy_dir(from, to)?;
+    fs::remove_dir_all(from).map_err(|e| format!("删除目录 {} 失败: {}", from.display(), e))
+}
+
+fn copy_dir(from: &Path, to: &Path) -> Result<(), String> {
+    fs::create_dir_all(to).map_err(|e| format!("创建目录 {} 失败: {}", to.display(), e))?;
+    let entries =
+        fs::read_dir(from).map_err(|e| format!("读取目录 {} 失败: {}", from.display(), e))?;
+    for entry in entries.flatten() {
+        let source = entry.path();
+        let target = to.join(entry.file_name());
+        if source.is_dir() {
+            copy_dir(&source, &target)?;
+        } else {
+            fs::copy(&source, &target)
+                .map_err(|e| format!("复制文件 {} 失败: {}", source.display(), e))?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "sealantern_test_updater_{}_{}",
+            name,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_update_policy_defaults_to_notify_and_persists() {
+        let root = make_temp_dir("policy");
+        let mut store = UpdateStore::load(&root);
+        assert_eq!(store.policy("demo"), UpdatePolicy::Notify);
+        store.set_policy("demo", UpdatePolicy::Auto);
+        assert_eq!(UpdateStore::load(&root).policy("demo"), UpdatePolicy::Auto);
+
+        store.remove_plugin("demo");
+        assert_eq!(UpdateStore::load(&root).policy("demo"), UpdatePolicy::Notify);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_pending_migration_persists_until_cleared() {
+        let root = make_temp_dir("migration");
+        let mut store = UpdateStore::load(&root);
+        store.set_pending_migration("demo", Some("1.0.0"));
+        assert_eq!(
+            UpdateStore::load(&root)
+                .pending_migration("demo")
+                .as_deref(),
+            Some("1.0.0")
+        );
+
+        store.set_pending_migration("demo", None);
+        assert_eq!(UpdateStore::load(&root).pending_migration("demo"), None);
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_move_dir_replaces_backup_next_to_plugins_dir() {
+        let root = make_temp_dir("move");
+        let plugins_dir = root.join("plugins");
+        fs::create_dir_all(plugins_dir.join("demo/lib")).unwrap();
+        fs::write(plugins_dir.join("demo/lib/a.lua"), "return 1").unwrap();
+
+        let backup = backup_dir(&plugins_dir, "demo");
+        assert_eq!(backup, root.join("plugin_backups").join("demo"));
+        fs::create_dir_all(backup.join("stale")).unwrap();
+
+        move_dir(&plugins_dir.join("demo"), &backup).unwrap();
+        assert!(!plugins_dir.join("demo").exists());
+        assert!(backup.join("lib/a.lua").is_file());
+        assert!(!backup.join("stale").exists());
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_keeps_signature_trust() {
+        use SignatureStatus::{Unsigned, Verified};
+        assert!(keeps_signature_trust(Verified, Verified));
+        assert!(keeps_signature_trust(Unsigned, Verified));
+        assert!(keeps_signature_trust(Unsigned, Unsigned));
+        assert!(!keeps_signature_trust(Verified, Unsigned));
+    }
+
+    #[test]
+    fn test_has_critical_permission() {
+        assert!(has_critical_permission(&["log".to_string(), "execute_program".to_string()]));
+        assert!(!has_critical_permission(&["log".to_string(), "fs".to_string()]));
+    }
+}
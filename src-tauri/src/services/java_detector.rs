@@ -1,10 +1,13 @@
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 #[cfg(target_os = "windows")]
 use std::path::PathBuf;
 use std::process::Command;
+use std::sync::{Mutex, OnceLock};
+use std::time::UNIX_EPOCH;
 
 // 静默执行命令
 #[cfg(target_os = "windows")]
@@ -41,17 +44,92 @@ const MAX_SCAN_DEPTH: u32 = 5;
 #[cfg(not(target_os = "windows"))]
 const MAX_SCAN_DEPTH: u32 = 4;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+// 检测缓存：以可执行文件路径 + 修改时间 + 大小为键，命中时不再执行 java -version
+const DETECTION_CACHE_FILE: &str = "java_detection_cache.json";
+const MAX_PROBE_THREADS: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
 pub struct JavaInfo {
     pub path: String,
     pub version: String,
     pub vendor: String,
     pub is_64bit: bool,
     pub major_version: u32,
+    /// release 文件中的 IMPLEMENTOR，例如 "Eclipse Adoptium"
+    #[serde(default)]
+    pub implementor: Option<String>,
+    #[serde(default)]
+    pub is_jdk: bool,
+    /// 架构，例如 x86_64 / aarch64，无法识别时为空
+    #[serde(default)]
+    pub arch: String,
+    /// 是否位于 SeaLantern 管理的 runtimes/ 目录
+    #[serde(default)]
+    pub is_managed: bool,
+    /// 虚拟机类型：hotspot / graalvm / openj9
+    #[serde(default)]
+    pub flavour: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DetectionCache {
+    #[serde(default)]
+    entries: HashMap<String, DetectionCacheEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DetectionCacheEntry {
+    modified: u64,
+    size: u64,
+    info: JavaInfo,
+}
+
+static DETECTION_CACHE: OnceLock<Mutex<DetectionCache>> = OnceLock::new();
+
+fn detection_cache() -> &'static Mutex<DetectionCache> {
+    DETECTION_CACHE.get_or_init(|| Mutex::new(load_detection_cache()))
+}
+
+fn detection_cache_path() -> std::path::PathBuf {
+    std::path::PathBuf::from(crate::utils::path::get_or_create_app_data_dir())
+        .join(DETECTION_CACHE_FILE)
+}
+
+fn load_detection_cache() -> DetectionCache {
+    fs::read_to_string(detection_cache_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// 写回缓存文件，同时清理已不存在的 Java
+fn save_detection_cache() {
+    let Ok(mut cache) = detection_cache().lock() else {
+        return;
+    };
+    cache.entries.retain(|path, _| Path::new(path).exists());
+    match serde_json::to_string_pretty(&*cache) {
+        Ok(content) => {
+            if let Err(e) = fs::write(detection_cache_path(), content) {
+                eprintln!("[WARN] 写入 Java 检测缓存失败: {}", e);
+            }
+        }
+        Err(e) => eprintln!("[WARN] 序列化 Java 检测缓存失败: {}", e),
+    }
+}
+
+fn file_stamp(path: &str) -> Option<(u64, u64)> {
+    let metadata = fs::metadata(path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_millis() as u64;
+    Some((modified, metadata.len()))
 }
 
 pub fn detect_java_installations() -> Vec<JavaInfo> {
-    let mut results = Vec::new();
     let candidate_paths = get_candidate_paths();
 
     #[cfg(target_os = "windows")]
@@ -63,20 +141,57 @@ pub fn detect_java_installations() -> Vec<JavaInfo> {
         paths
     };
 
-    for path in candidate_paths {
-        if let Some(info) = check_java(&path) {
-            if !results.iter().any(|r: &JavaInfo| r.path == info.path) {
-                results.push(info);
-            }
-        }
-    }
+    // 先解析为真实路径并去重，避免同一个 Java 经由符号链接被重复探测
+    let mut seen = HashSet::new();
+    let resolved_paths: Vec<String> = candidate_paths
+        .iter()
+        .filter_map(|path| resolve_java_path(path))
+        .filter(|path| seen.insert(path.clone()))
+        .collect();
+
+    let mut results = probe_java_paths(&resolved_paths);
+    save_detection_cache();
 
     results.sort_by(|a, b| b.major_version.cmp(&a.major_version));
     results
 }
 
 pub fn validate_java(path: &str) -> Result<JavaInfo, String> {
-    check_java(path).ok_or_else(|| format!("无法验证 Java 路径: {}", path))
+    let info = resolve_java_path(path)
+        .and_then(|resolved| check_resolved_java(&resolved))
+        .ok_or_else(|| format!("无法验证 Java 路径: {}", path))?;
+    save_detection_cache();
+    Ok(info)
+}
+
+/// 并行探测，每个线程处理一段候选路径
+fn probe_java_paths(paths: &[String]) -> Vec<JavaInfo> {
+    if paths.is_empty() {
+        return Vec::new();
+    }
+    let workers = std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(4)
+        .clamp(1, MAX_PROBE_THREADS);
+    let chunk_size = paths.len().div_ceil(workers);
+
+    std::thread::scope(|scope| {
+        let handles: Vec<_> = paths
+            .chunks(chunk_size)
+            .map(|chunk| {
+                scope.spawn(move || {
+                    chunk
+                        .iter()
+                        .filter_map(|path| check_resolved_java(path))
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_default())
+            .collect()
+    })
 }
 
 fn get_candidate_paths() -> Vec<String> {
@@ -180,7 +295,66 @@ fn deep_scan_recursive(dir: &Path, paths: &mut Vec<String>, depth: u32) {
     }
 }
 
-fn check_java(path: &str) -> Option<JavaInfo> {
+/// 将候选路径解析为规范化的绝对路径，"java" 从 PATH 中查找
+fn resolve_java_path(path: &str) -> Option<String> {
+    let path = if path == "java" {
+        resolve_path_from_env(path)?
+    } else {
+        path.to_string()
+    };
+    let p = fs::canonicalize(&path).ok()?;
+    #[cfg(target_os = "windows")]
+    {
+        let path_str = p.to_string_lossy();
+        if let Some(stripped) = path_str.strip_prefix(r"\\?\") {
+            Some(stripped.to_string())
+        } else {
+            Some(path_str.into_owned())
+        }
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        Some(p.to_string_lossy().into_owned())
+    }
+}
+
+/// 优先使用缓存；可执行文件的修改时间或大小变化后重新探测
+fn check_resolved_java(path: &str) -> Option<JavaInfo> {
+    let stamp = file_stamp(path);
+    let cached = stamp.and_then(|(modified, size)| {
+        let cache = detection_cache().lock().ok()?;
+        cache
+            .entries
+            .get(path)
+            .filter(|entry| entry.modified == modified && entry.size == size)
+            .map(|entry| entry.info.clone())
+    });
+
+    let mut info = match cached {
+        Some(info) => info,
+        None => {
+            let info = probe_java(path)?;
+            if let (Some((modified, size)), Ok(mut cache)) = (stamp, detection_cache().lock()) {
+                cache.entries.insert(
+                    path.to_string(),
+                    DetectionCacheEntry { modified, size, info: info.clone() },
+                );
+            }
+            info
+        }
+    };
+    // runtimes 目录可能被移动或删除，托管标记不放进缓存
+    info.is_managed = is_managed_runtime(path);
+    Some(info)
+}
+
+fn is_managed_runtime(path: &str) -> bool {
+    let runtimes_dir = crate::services::java_runtime_catalogue::runtimes_dir();
+    let runtimes_dir = fs::canonicalize(&runtimes_dir).unwrap_or(runtimes_dir);
+    Path::new(path).starts_with(&runtimes_dir)
+}
+
+fn probe_java(path: &str) -> Option<JavaInfo> {
     let output = command_output(path, &["-version"])?;
     let stderr = String::from_utf8_lossy(&output.stderr);
     let stdout = String::from_utf8_lossy(&output.stdout);
@@ -205,34 +379,90 @@ fn check_java(path: &str) -> Option<JavaInfo> {
         "Oracle".to_string()
     };
 
-    let resolved = if path == "java" {
-        resolve_path_from_env(path)?
-    } else {
-        let p = fs::canonicalize(path).ok()?;
-        #[cfg(target_os = "windows")]
-        {
-            let path_str = p.to_string_lossy();
-            if let Some(stripped) = path_str.strip_prefix(r"\\?\") {
-                stripped.to_string()
-            } else {
-                path_str.into_owned()
-            }
-        }
-        #[cfg(not(target_os = "windows"))]
-        {
-            p.to_string_lossy().into_owned()
-        }
+    let java_exe = Path::new(path);
+    let release = read_release_file(java_exe);
+    let is_jdk = match release.get("IMAGE_TYPE") {
+        Some(image_type) => image_type.eq_ignore_ascii_case("jdk"),
+        None => java_exe
+            .parent()
+            .map(|bin| {
+                bin.join(if cfg!(target_os = "windows") {
+                    "javac.exe"
+                } else {
+                    "javac"
+                })
+                .exists()
+            })
+            .unwrap_or(false),
     };
 
     Some(JavaInfo {
-        path: resolved,
+        path: path.to_string(),
         version,
         vendor,
         is_64bit,
         major_version,
+        implementor: release.get("IMPLEMENTOR").cloned(),
+        is_jdk,
+        arch: release
+            .get("OS_ARCH")
+            .map(|arch| normalize_arch(arch))
+            .unwrap_or_default(),
+        is_managed: false,
+        flavour: detect_flavour(&combined, &release).to_string(),
     })
 }
 
+/// 读取 JAVA_HOME 下的 release 文件；Java 8 的 jdk/jre/bin/java 需要再往上一级查找
+fn read_release_file(java_exe: &Path) -> HashMap<String, String> {
+    let Some(home) = java_exe.parent().and_then(|bin| bin.parent()) else {
+        return HashMap::new();
+    };
+    let mut release_path = home.join("release");
+    if !release_path.exists() && home.file_name().is_some_and(|n| n == "jre") {
+        if let Some(parent) = home.parent() {
+            release_path = parent.join("release");
+        }
+    }
+    fs::read_to_string(release_path)
+        .map(|content| parse_release(&content))
+        .unwrap_or_default()
+}
+
+fn parse_release(content: &str) -> HashMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once('=')?;
+            Some((key.trim().to_string(), value.trim().trim_matches('"').to_string()))
+        })
+        .collect()
+}
+
+fn detect_flavour(version_output: &str, release: &HashMap<String, String>) -> &'static str {
+    let output = version_output.to_lowercase();
+    if output.contains("graalvm") || release.contains_key("GRAALVM_VERSION") {
+        "graalvm"
+    } else if output.contains("openj9")
+        || release
+            .get("JVM_VARIANT")
+            .is_some_and(|variant| variant.eq_ignore_ascii_case("openj9"))
+    {
+        "openj9"
+    } else {
+        "hotspot"
+    }
+}
+
+fn normalize_arch(arch: &str) -> String {
+    match arch.trim().to_lowercase().as_str() {
+        "amd64" | "x64" | "x86_64" => "x86_64".to_string(),
+        "arm64" | "aarch64" => "aarch64".to_string(),
+        "i386" | "i586" | "i686" | "x86" => "x86".to_string(),
+        other => other.to_string(),
+    }
+}
+
 fn parse_major_version(version: &str) -> u32 {
     let parts: Vec<&str> = version.split('.').collect();
     let first: u32 = parts.first().and_then(|s| s.parse().ok()).unwrap_or(0);
@@ -316,3 +546,36 @@ fn command_output(program: &str, args: &[&str]) -> Option<std::process::Output>
 
     command.output().ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_release_strips_quotes() {
+        let release = parse_release(
+            "IMPLEMENTOR=\"Eclipse Adoptium\"\nJAVA_VERSION=\"21.0.4\"\nOS_ARCH=\"amd64\"\n",
+        );
+        assert_eq!(release.get("IMPLEMENTOR").unwrap(), "Eclipse Adoptium");
+        assert_eq!(release.get("JAVA_VERSION").unwrap(), "21.0.4");
+        assert_eq!(normalize_arch(release.get("OS_ARCH").unwrap()), "x86_64");
+    }
+
+    #[test]
+    fn detect_flavour_from_output_and_release() {
+        let empty = HashMap::new();
+        assert_eq!(
+            detect_flavour("OpenJDK 64-Bit Server VM (build 21.0.4+7-LTS)", &empty),
+            "hotspot"
+        );
+        assert_eq!(detect_flavour("Eclipse OpenJ9 VM (build openj9-0.46.0)", &empty), "openj9");
+        let graal = parse_release("GRAALVM_VERSION=\"23.1.2\"");
+        assert_eq!(detect_flavour("Java HotSpot(TM) 64-Bit", &graal), "graalvm");
+    }
+
+    #[test]
+    fn parse_major_version_handles_legacy_scheme() {
+        assert_eq!(parse_major_version("1.8.0_412"), 8);
+        assert_eq!(parse_major_version("21.0.4"), 21);
+    }
+}
@@ -22,9 +22,11 @@ struct DownloadProgress {
 pub async fn download_and_install_java<R: tauri::Runtime>(
     url: String,
     version_name: String,
+    expected_sha256: String,
     window: Window<R>,
     cancel_flag: Arc<AtomicBool>,
 ) -> Result<String, String> {
+    crate::services::java_runtime_catalogue::validate_runtime_name(&version_name)?;
     let app_dir = crate::utils::path::get_app_data_dir();
 
     let runtimes_dir = app_dir.join("runtimes");
@@ -111,6 +113,9 @@ pub async fn download_and_install_java<R: tauri::Runtime>(
         return Err("用户取消下载".to_string());
     }
 
+    // 校验压缩包完整性，哈希不一致时直接放弃，避免解压被篡改或损坏的运行时
+    verify_sha256(&data, &expected_sha256)?;
+
     // 2. Extract
     let _ = window.emit(
         "java-install-progress",
@@ -269,3 +274,16 @@ fn extract_tar_gz(data: &[u8], target_dir: &Path, cancel_flag: &AtomicBool) -> R
 fn bytes_to_mb(bytes: u64) -> String {
     format!("{:.2}MB", bytes as f64 / 1024.0 / 1024.0)
 }
+
+fn verify_sha256(data: &[u8], expected: &str) -> Result<(), String> {
+    use sha2::{Digest, Sha256};
+    let actual = format!("{:x}", Sha256::digest(data));
+    if !actual.eq_ignore_ascii_case(expected.trim()) {
+        return Err(format!(
+            "Java 安装包 sha256 校验失败：期望 {}，实际 {}",
+            expected.trim(),
+            actual
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,291 @@
+use std::str::FromStr;
+
+use serde::Serialize;
+
+use crate::services::java_detector::JavaInfo;
+use crate::services::server_installer::CoreType;
+
+// 启动前的 Java 版本兼容性检查：
+// 1) 优先读取 Mojang 版本详情中的 javaVersion.majorVersion（经 server_core_catalogue 缓存）
+// 2) 代理端、Bedrock 以及离线时使用内置表兜底
+// 3) 从已检测的 Java 中挑选匹配项，找不到时提示需要下载的主版本
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct JavaRequirement {
+    pub min_major: u32,
+    /// 建议的最高主版本，仅用于提示（老版本 Forge 系核心在新 Java 上无法启动）
+    pub max_major: Option<u32>,
+    /// "mojang" 或 "builtin"
+    pub source: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JavaCompatibility {
+    Compatible,
+    TooOld,
+    TooNew,
+    Unknown,
+    NotApplicable,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JavaCompatibilityReport {
+    pub core_type: String,
+    pub mc_version: String,
+    pub requirement: Option<JavaRequirement>,
+    pub current_major: Option<u32>,
+    pub status: JavaCompatibility,
+    pub suggested: Option<JavaInfo>,
+    /// 本机没有合适的 Java 时，建议通过 Java 安装功能下载的主版本
+    pub download_major: Option<u32>,
+}
+
+impl JavaCompatibilityReport {
+    /// 生成面向用户的提示信息，兼容时返回 None
+    pub fn message(&self) -> Option<String> {
+        let requirement = self.requirement.as_ref()?;
+        let current = self.current_major?;
+        let mut message = match self.status {
+            JavaCompatibility::TooOld => format!(
+                "{} {} 需要 Java {}+，当前 Java 版本为 {}",
+                self.core_type, self.mc_version, requirement.min_major, current
+            ),
+            JavaCompatibility::TooNew => format!(
+                "{} {} 建议使用 Java {} 及以下，当前 Java 版本为 {}，可能无法正常启动",
+                self.core_type,
+                self.mc_version,
+                requirement.max_major.unwrap_or(requirement.min_major),
+                current
+            ),
+            _ => return None,
+        };
+
+        if let Some(suggested) = &self.suggested {
+            message.push_str(&format!(
+                "；可改用已检测到的 Java {}: {}",
+                suggested.major_version, suggested.path
+            ));
+        } else if let Some(major) = self.download_major {
+            message
+                .push_str(&format!("；未检测到合适的 Java，可在 Java 管理中下载 Java {}", major));
+        }
+        Some(message)
+    }
+}
+
+/// 查询某个核心 + MC 版本的 Java 要求；Bedrock 等非 Java 核心返回 None
+pub fn java_requirement_for(core_type: &str, mc_version: &str) -> Option<JavaRequirement> {
+    let core = CoreType::from_str(core_type.trim()).unwrap_or(CoreType::Unknown);
+    if let Some(requirement) = proxy_or_non_java_requirement(core) {
+        return requirement;
+    }
+
+    let mc_version = mc_version.trim();
+    if mc_version.is_empty() {
+        return None;
+    }
+
+    let max_major = legacy_max_java_major(core, mc_version);
+    // 启动服务器时调用，只读缓存的 Mojang 清单，缓存缺失时使用内置表
+    if let Some(min_major) =
+        crate::services::server_core_catalogue::cached_mojang_java_major_version(mc_version)
+    {
+        return Some(JavaRequirement {
+            min_major,
+            max_major,
+            source: "mojang".to_string(),
+        });
+    }
+
+    builtin_java_major(mc_version).map(|min_major| JavaRequirement {
+        min_major,
+        max_major,
+        source: "builtin".to_string(),
+    })
+}
+
+/// 代理端与非 Java 核心的固定要求；返回 None 表示需要按 MC 版本查询
+fn proxy_or_non_java_requirement(core: CoreType) -> Option<Option<JavaRequirement>> {
+    let builtin = |min_major| {
+        Some(Some(JavaRequirement {
+            min_major,
+            max_major: None,
+            source: "builtin".to_string(),
+        }))
+    };
+    match core {
+        CoreType::Bedrock => Some(None),
+        CoreType::Velocity => builtin(17),
+        CoreType::Bungeecord | CoreType::Lightfall | CoreType::Travertine | CoreType::Nukkitx => {
+            builtin(8)
+        }
+        _ => None,
+    }
+}
+
+fn parse_mc_version(mc_version: &str) -> Option<(u32, u32, u32)> {
+    let mut parts = mc_version
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|part| !part.is_empty())
+        .map(|part| part.parse::<u32>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().flatten().unwrap_or(0);
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+/// 离线兜底表，与 Mojang 清单中正式版的 javaVersion 保持一致
+fn builtin_java_major(mc_version: &str) -> Option<u32> {
+    let (major, minor, patch) = parse_mc_version(mc_version)?;
+    if major != 1 {
+        // 26.x 起改为年份版本号
+        return Some(if major >= 26 { 25 } else { 21 });
+    }
+    Some(match (minor, patch) {
+        (m, _) if m >= 21 => 21,
+        (20, p) if p >= 5 => 21,
+        (m, _) if m >= 18 => 17,
+        (17, _) => 16,
+        _ => 8,
+    })
+}
+
+fn legacy_max_java_major(core: CoreType, mc_version: &str) -> Option<u32> {
+    let is_forge_family = matches!(
+        core,
+        CoreType::Forge | CoreType::Mohist | CoreType::Catserver | CoreType::Spongeforge
+    );
+    match parse_mc_version(mc_version)? {
+        (1, minor, _) if is_forge_family && minor < 13 => Some(8),
+        _ => None,
+    }
+}
+
+pub fn evaluate_java(requirement: &JavaRequirement, current_major: u32) -> JavaCompatibility {
+    if current_major < requirement.min_major {
+        JavaCompatibility::TooOld
+    } else if requirement.max_major.is_some_and(|max| current_major > max) {
+        JavaCompatibility::TooNew
+    } else {
+        JavaCompatibility::Compatible
+    }
+}
+
+/// 从候选列表中挑选满足要求且主版本最低的 Java（越接近要求越稳妥）
+pub fn suggest_java(requirement: &JavaRequirement, candidates: &[JavaInfo]) -> Option<JavaInfo> {
+    candidates
+        .iter()
+        .filter(|java| {
+            evaluate_java(requirement, java.major_version) == JavaCompatibility::Compatible
+        })
+        .min_by_key(|java| (java.major_version, !java.is_64bit))
+        .cloned()
+}
+
+/// 汇总检查结果；current_major 为 None 表示无法识别当前 Java 版本
+pub fn check_java_compatibility(
+    core_type: &str,
+    mc_version: &str,
+    current_major: Option<u32>,
+) -> JavaCompatibilityReport {
+    let requirement = java_requirement_for(core_type, mc_version);
+    let status = match (&requirement, current_major) {
+        (None, _) => JavaCompatibility::NotApplicable,
+        (Some(_), None) => JavaCompatibility::Unknown,
+        (Some(requirement), Some(current)) => evaluate_java(requirement, current),
+    };
+
+    let (suggested, download_major) = match (&requirement, status) {
+        (Some(requirement), JavaCompatibility::TooOld | JavaCompatibility::TooNew) => {
+            let suggested = suggest_java(requirement, &known_java_installations());
+            let download_major = if suggested.is_none() {
+                Some(requirement.max_major.unwrap_or(requirement.min_major))
+            } else {
+                None
+            };
+            (suggested, download_major)
+        }
+        _ => (None, None),
+    };
+
+    JavaCompatibilityReport {
+        core_type: core_type.to_string(),
+        mc_version: mc_version.to_string(),
+        requirement,
+        current_major,
+        status,
+        suggested,
+        download_major,
+    }
+}
+
+fn known_java_installations() -> Vec<JavaInfo> {
+    // 过滤掉已被卸载或移动的 Java，避免推荐失效的路径
+    let cached: Vec<JavaInfo> = crate::services::global::settings_manager()
+        .get()
+        .cached_java_list
+        .into_iter()
+        .filter(|java| std::path::Path::new(&java.path).exists())
+        .collect();
+    if !cached.is_empty() {
+        return cached;
+    }
+    crate::services::java_detector::detect_java_installations()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn java(major: u32) -> JavaInfo {
+        JavaInfo {
+            path: format!("/opt/java{}/bin/java", major),
+            version: format!("{}.0.1", major),
+            vendor: "Test".to_string(),
+            is_64bit: true,
+            major_version: major,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn builtin_table_matches_mojang_release_requirements() {
+        assert_eq!(builtin_java_major("1.12.2"), Some(8));
+        assert_eq!(builtin_java_major("1.17.1"), Some(16));
+        assert_eq!(builtin_java_major("1.18"), Some(17));
+        assert_eq!(builtin_java_major("1.20.4"), Some(17));
+        assert_eq!(builtin_java_major("1.20.5"), Some(21));
+        assert_eq!(builtin_java_major("1.21.4"), Some(21));
+        assert_eq!(builtin_java_major("snapshot"), None);
+    }
+
+    #[test]
+    fn evaluate_respects_min_and_max() {
+        let requirement = JavaRequirement {
+            min_major: 8,
+            max_major: Some(8),
+            source: "builtin".to_string(),
+        };
+        assert_eq!(evaluate_java(&requirement, 8), JavaCompatibility::Compatible);
+        assert_eq!(evaluate_java(&requirement, 17), JavaCompatibility::TooNew);
+        assert_eq!(evaluate_java(&requirement, 7), JavaCompatibility::TooOld);
+    }
+
+    #[test]
+    fn suggest_prefers_lowest_compatible_major() {
+        let requirement = JavaRequirement {
+            min_major: 17,
+            max_major: None,
+            source: "mojang".to_string(),
+        };
+        let candidates = vec![java(8), java(21), java(17)];
+        assert_eq!(
+            suggest_java(&requirement, &candidates)
+                .unwrap()
+                .major_version,
+            17
+        );
+        assert!(suggest_java(&requirement, &[java(8)]).is_none());
+    }
+}
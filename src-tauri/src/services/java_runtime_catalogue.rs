@@ -0,0 +1,467 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+// 托管 Java 运行时：
+// 1) 按主版本 + 当前系统/架构查询 Adoptium(Temurin) 与 Azul(Zulu) 的发行包及 sha256
+// 2) 列出 runtimes/ 下已安装的运行时，并统计引用它的服务器
+// 3) 删除运行时前确认没有服务器仍在使用
+const ADOPTIUM_API_BASE: &str = "https://api.adoptium.net/v3/assets/latest";
+const AZUL_API_BASE: &str = "https://api.azul.com/metadata/v1/zulu/packages";
+const JAVA_CATALOGUE_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JavaDistribution {
+    Temurin,
+    Zulu,
+}
+
+impl JavaDistribution {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JavaDistribution::Temurin => "temurin",
+            JavaDistribution::Zulu => "zulu",
+        }
+    }
+}
+
+impl std::str::FromStr for JavaDistribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "temurin" | "adoptium" => Ok(JavaDistribution::Temurin),
+            "zulu" | "azul" => Ok(JavaDistribution::Zulu),
+            other => Err(format!("不支持的 Java 发行版: {}", other)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JavaPackage {
+    pub distribution: JavaDistribution,
+    pub major_version: u32,
+    pub java_version: String,
+    pub image_type: String,
+    pub os: String,
+    pub arch: String,
+    pub file_name: String,
+    pub download_url: String,
+    pub sha256: Option<String>,
+    /// Azul 包的 package_uuid，用于查询 sha256
+    #[serde(skip)]
+    zulu_package_uuid: Option<String>,
+}
+
+impl JavaPackage {
+    /// 安装到 runtimes/ 下使用的目录名，只包含安全字符
+    pub fn runtime_name(&self) -> String {
+        sanitize_runtime_name(&format!(
+            "{}-{}-{}",
+            self.distribution.as_str(),
+            self.java_version,
+            self.image_type
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RuntimeUsage {
+    pub server_id: String,
+    pub server_name: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ManagedRuntime {
+    pub name: String,
+    pub path: String,
+    pub java_path: String,
+    pub java_version: Option<String>,
+    pub used_by: Vec<RuntimeUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumAsset {
+    binary: AdoptiumBinary,
+    version: AdoptiumVersion,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumBinary {
+    package: AdoptiumPackage,
+    #[serde(default)]
+    image_type: String,
+    #[serde(default)]
+    os: String,
+    #[serde(default)]
+    architecture: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumPackage {
+    name: String,
+    link: String,
+    #[serde(default)]
+    checksum: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdoptiumVersion {
+    #[serde(default)]
+    semver: String,
+    #[serde(default)]
+    openjdk_version: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AzulPackage {
+    package_uuid: String,
+    name: String,
+    download_url: String,
+    #[serde(default)]
+    java_version: Vec<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AzulPackageDetail {
+    #[serde(default)]
+    sha256_hash: Option<String>,
+}
+
+fn current_os() -> &'static str {
+    match std::env::consts::OS {
+        "macos" => "mac",
+        other => other,
+    }
+}
+
+fn current_arch() -> &'static str {
+    match std::env::consts::ARCH {
+        "x86_64" => "x64",
+        "x86" => "x86",
+        "aarch64" => "aarch64",
+        "arm" => "arm",
+        other => other,
+    }
+}
+
+/// 与 java_installer 的解压逻辑保持一致：Windows 使用 zip，其余平台使用 tar.gz
+fn current_archive_type() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "zip"
+    } else {
+        "tar.gz"
+    }
+}
+
+fn catalogue_client() -> Result<reqwest::blocking::Client, String> {
+    reqwest::blocking::Client::builder()
+        .timeout(JAVA_CATALOGUE_REQUEST_TIMEOUT)
+        .user_agent(crate::utils::downloader::USER_AGENT_EXAMPLE)
+        .build()
+        .map_err(|e| format!("创建 Java 目录请求客户端失败: {}", e))
+}
+
+fn get_json<T: serde::de::DeserializeOwned>(
+    client: &reqwest::blocking::Client,
+    url: &str,
+) -> Result<T, String> {
+    let response = client
+        .get(url)
+        .send()
+        .map_err(|e| format!("请求 Java 发行信息失败: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Java 发行接口返回异常状态: {} ({})", status, url));
+    }
+    response
+        .json::<T>()
+        .map_err(|e| format!("解析 Java 发行信息失败 ({}): {}", url, e))
+}
+
+/// 查询指定主版本可用的 Java 发行包；任一来源成功即返回，全部失败时返回合并后的错误
+pub fn list_java_packages(major_version: u32) -> Result<Vec<JavaPackage>, String> {
+    let client = catalogue_client()?;
+    let mut packages = Vec::new();
+    let mut errors = Vec::new();
+
+    match list_temurin_packages(&client, major_version) {
+        Ok(mut found) => packages.append(&mut found),
+        Err(e) => errors.push(format!("Temurin: {}", e)),
+    }
+    match list_zulu_packages(&client, major_version) {
+        Ok(mut found) => packages.append(&mut found),
+        Err(e) => errors.push(format!("Zulu: {}", e)),
+    }
+
+    if packages.is_empty() && !errors.is_empty() {
+        return Err(errors.join("; "));
+    }
+    Ok(packages)
+}
+
+/// 选出指定发行版与主版本的安装包，优先 JRE，并补全 sha256
+pub fn resolve_java_package(
+    distribution: JavaDistribution,
+    major_version: u32,
+) -> Result<JavaPackage, String> {
+    let client = catalogue_client()?;
+    let mut packages = match distribution {
+        JavaDistribution::Temurin => list_temurin_packages(&client, major_version)?,
+        JavaDistribution::Zulu => list_zulu_packages(&client, major_version)?,
+    };
+    packages.sort_by_key(|p| p.image_type != "jre");
+    let mut package = packages.into_iter().next().ok_or_else(|| {
+        format!(
+            "{} 没有适用于 {}/{} 的 Java {}",
+            distribution.as_str(),
+            current_os(),
+            current_arch(),
+            major_version
+        )
+    })?;
+
+    if package.sha256.is_none() {
+        if let Some(package_uuid) = &package.zulu_package_uuid {
+            package.sha256 = fetch_zulu_sha256(&client, package_uuid)?;
+        }
+    }
+    if package.sha256.is_none() {
+        return Err(format!("{} 未提供 sha256，拒绝安装", package.file_name));
+    }
+    Ok(package)
+}
+
+fn list_temurin_packages(
+    client: &reqwest::blocking::Client,
+    major_version: u32,
+) -> Result<Vec<JavaPackage>, String> {
+    let mut packages = Vec::new();
+    for image_type in ["jre", "jdk"] {
+        let url = format!(
+            "{}/{}/hotspot?architecture={}&image_type={}&os={}&vendor=eclipse",
+            ADOPTIUM_API_BASE,
+            major_version,
+            current_arch(),
+            image_type,
+            current_os()
+        );
+        let assets: Vec<AdoptiumAsset> = get_json(client, &url)?;
+        packages.extend(
+            assets
+                .into_iter()
+                .filter(|asset| asset.binary.package.name.ends_with(current_archive_type()))
+                .map(|asset| JavaPackage {
+                    distribution: JavaDistribution::Temurin,
+                    major_version,
+                    java_version: if asset.version.semver.is_empty() {
+                        asset.version.openjdk_version
+                    } else {
+                        asset.version.semver
+                    },
+                    image_type: asset.binary.image_type,
+                    os: asset.binary.os,
+                    arch: asset.binary.architecture,
+                    file_name: asset.binary.package.name,
+                    download_url: asset.binary.package.link,
+                    sha256: asset.binary.package.checksum,
+                    zulu_package_uuid: None,
+                }),
+        );
+    }
+    Ok(packages)
+}
+
+fn list_zulu_packages(
+    client: &reqwest::blocking::Client,
+    major_version: u32,
+) -> Result<Vec<JavaPackage>, String> {
+    let azul_os = if current_os() == "mac" {
+        "macos"
+    } else {
+        current_os()
+    };
+    let mut packages = Vec::new();
+    for image_type in ["jre", "jdk"] {
+        let url = format!(
+            "{}/?java_version={}&os={}&arch={}&archive_type={}&java_package_type={}&javafx_bundled=false&latest=true&release_status=ga&availability_types=CA&page=1&page_size=5",
+            AZUL_API_BASE,
+            major_version,
+            azul_os,
+            current_arch(),
+            current_archive_type(),
+            image_type
+        );
+        let found: Vec<AzulPackage> = get_json(client, &url)?;
+        packages.extend(found.into_iter().map(|pkg| {
+            JavaPackage {
+                distribution: JavaDistribution::Zulu,
+                major_version,
+                java_version: pkg
+                    .java_version
+                    .iter()
+                    .map(|part| part.to_string())
+                    .collect::<Vec<_>>()
+                    .join("."),
+                image_type: image_type.to_string(),
+                os: azul_os.to_string(),
+                arch: current_arch().to_string(),
+                file_name: pkg.name,
+                download_url: pkg.download_url,
+                // Azul 列表接口不返回哈希，安装前通过详情接口补全
+                sha256: None,
+                zulu_package_uuid: Some(pkg.package_uuid),
+            }
+        }));
+    }
+    Ok(packages)
+}
+
+fn fetch_zulu_sha256(
+    client: &reqwest::blocking::Client,
+    package_uuid: &str,
+) -> Result<Option<String>, String> {
+    let detail: AzulPackageDetail =
+        get_json(client, &format!("{}/{}", AZUL_API_BASE, package_uuid))?;
+    Ok(detail.sha256_hash)
+}
+
+pub fn sanitize_runtime_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('.')
+        .to_string()
+}
+
+/// 校验运行时目录名，避免通过名称逃逸出 runtimes/
+pub fn validate_runtime_name(name: &str) -> Result<(), String> {
+    let trimmed = name.trim();
+    if trimmed.is_empty()
+        || trimmed.contains("..")
+        || trimmed.contains('/')
+        || trimmed.contains('\\')
+        || trimmed.starts_with("temp_")
+    {
+        return Err(format!("无效的运行时名称: {}", name));
+    }
+    Ok(())
+}
+
+pub fn runtimes_dir() -> PathBuf {
+    crate::utils::path::get_app_data_dir().join("runtimes")
+}
+
+fn runtime_java_bin(runtime_dir: &Path) -> PathBuf {
+    if cfg!(target_os = "windows") {
+        runtime_dir.join("bin").join("java.exe")
+    } else {
+        runtime_dir.join("bin").join("java")
+    }
+}
+
+fn read_release_java_version(runtime_dir: &Path) -> Option<String> {
+    let content = fs::read_to_string(runtime_dir.join("release")).ok()?;
+    content.lines().find_map(|line| {
+        line.strip_prefix("JAVA_VERSION=")
+            .map(|value| value.trim().trim_matches('"').to_string())
+    })
+}
+
+fn runtime_usages(runtime_dir: &Path) -> Vec<RuntimeUsage> {
+    let canonical_runtime = runtime_dir
+        .canonicalize()
+        .unwrap_or_else(|_| runtime_dir.to_path_buf());
+    crate::services::global::server_manager()
+        .get_server_list()
+        .into_iter()
+        .filter(|server| {
+            let java_path = Path::new(&server.java_path);
+            let canonical_java = java_path
+                .canonicalize()
+                .unwrap_or_else(|_| java_path.to_path_buf());
+            canonical_java.starts_with(&canonical_runtime)
+        })
+        .map(|server| RuntimeUsage {
+            server_id: server.id,
+            server_name: server.name,
+        })
+        .collect()
+}
+
+/// 列出 runtimes/ 下已安装的运行时
+pub fn list_managed_runtimes() -> Result<Vec<ManagedRuntime>, String> {
+    let dir = runtimes_dir();
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&dir).map_err(|e| format!("读取运行时目录失败: {}", e))?;
+    let mut runtimes = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !path.is_dir() || name.starts_with("temp_") {
+            continue;
+        }
+        let java_bin = runtime_java_bin(&path);
+        if !java_bin.exists() {
+            continue;
+        }
+        runtimes.push(ManagedRuntime {
+            name,
+            path: path.to_string_lossy().to_string(),
+            java_path: java_bin.to_string_lossy().to_string(),
+            java_version: read_release_java_version(&path),
+            used_by: runtime_usages(&path),
+        });
+    }
+    runtimes.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(runtimes)
+}
+
+/// 删除运行时；仍被服务器引用时拒绝删除
+pub fn remove_managed_runtime(name: &str) -> Result<(), String> {
+    validate_runtime_name(name)?;
+    let runtime_dir = runtimes_dir().join(name.trim());
+    if !runtime_dir.is_dir() {
+        return Err(format!("运行时不存在: {}", name));
+    }
+
+    let usages = runtime_usages(&runtime_dir);
+    if !usages.is_empty() {
+        let names: Vec<String> = usages.into_iter().map(|u| u.server_name).collect();
+        return Err(format!("运行时 {} 正在被以下服务器使用: {}", name, names.join(", ")));
+    }
+
+    fs::remove_dir_all(&runtime_dir).map_err(|e| format!("删除运行时失败: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_runtime_name_replaces_unsafe_characters() {
+        assert_eq!(sanitize_runtime_name("temurin-21.0.4+7-jre"), "temurin-21.0.4_7-jre");
+        assert_eq!(sanitize_runtime_name("../evil/x"), "_.._evil_x");
+    }
+
+    #[test]
+    fn validate_runtime_name_rejects_traversal() {
+        assert!(validate_runtime_name("temurin-21").is_ok());
+        assert!(validate_runtime_name("../x").is_err());
+        assert!(validate_runtime_name("a/b").is_err());
+        assert!(validate_runtime_name("temp_21").is_err());
+        assert!(validate_runtime_name("").is_err());
+    }
+}
@@ -0,0 +1,253 @@
+use serde::Serialize;
+
+use crate::models::server::ServerJvmPreset;
+
+// 内置 JVM 参数预设：
+// 1) 每个预设有 id + revision，服务器记录所选的 revision，预设调整后旧服务器仍按原版本渲染
+// 2) 单个参数可以限定 Java 主版本与堆大小范围，渲染时按实际 Java 过滤
+// 3) 整个预设声明支持的 Java 范围，启动前校验，避免旧 JDK 因未知参数无法启动
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JvmFlag {
+    pub arg: &'static str,
+    pub min_java: Option<u32>,
+    pub max_java: Option<u32>,
+    /// 仅当 -Xmx（MB）落在该范围时生效
+    pub min_heap_mb: Option<u32>,
+    pub max_heap_mb: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JvmFlagPreset {
+    pub id: &'static str,
+    pub revision: u32,
+    pub name: &'static str,
+    pub description: &'static str,
+    pub min_java: u32,
+    pub max_java: Option<u32>,
+    pub flags: Vec<JvmFlag>,
+}
+
+const fn flag(arg: &'static str) -> JvmFlag {
+    JvmFlag {
+        arg,
+        min_java: None,
+        max_java: None,
+        min_heap_mb: None,
+        max_heap_mb: None,
+    }
+}
+
+const fn java_range(arg: &'static str, min_java: Option<u32>, max_java: Option<u32>) -> JvmFlag {
+    JvmFlag {
+        arg,
+        min_java,
+        max_java,
+        min_heap_mb: None,
+        max_heap_mb: None,
+    }
+}
+
+const fn heap_range(
+    arg: &'static str,
+    min_heap_mb: Option<u32>,
+    max_heap_mb: Option<u32>,
+) -> JvmFlag {
+    JvmFlag {
+        arg,
+        min_java: None,
+        max_java: None,
+        min_heap_mb,
+        max_heap_mb,
+    }
+}
+
+/// Aikar 建议 12G 以上堆使用另一组 G1 分代参数
+const AIKAR_LARGE_HEAP_MB: u32 = 12 * 1024;
+
+fn aikar_g1_v1() -> JvmFlagPreset {
+    JvmFlagPreset {
+        id: "aikar-g1",
+        revision: 1,
+        name: "Aikar G1",
+        description: "Aikar 的 G1 调优参数，适用于大多数 Paper/Spigot 服务器",
+        min_java: 8,
+        max_java: None,
+        flags: vec![
+            flag("-XX:+UseG1GC"),
+            flag("-XX:+ParallelRefProcEnabled"),
+            flag("-XX:MaxGCPauseMillis=200"),
+            flag("-XX:+UnlockExperimentalVMOptions"),
+            flag("-XX:+DisableExplicitGC"),
+            flag("-XX:+AlwaysPreTouch"),
+            heap_range("-XX:G1NewSizePercent=30", None, Some(AIKAR_LARGE_HEAP_MB)),
+            heap_range("-XX:G1MaxNewSizePercent=40", None, Some(AIKAR_LARGE_HEAP_MB)),
+            heap_range("-XX:G1HeapRegionSize=8M", None, Some(AIKAR_LARGE_HEAP_MB)),
+            heap_range("-XX:G1ReservePercent=20", None, Some(AIKAR_LARGE_HEAP_MB)),
+            heap_range("-XX:InitiatingHeapOccupancyPercent=15", None, Some(AIKAR_LARGE_HEAP_MB)),
+            heap_range("-XX:G1NewSizePercent=40", Some(AIKAR_LARGE_HEAP_MB + 1), None),
+            heap_range("-XX:G1MaxNewSizePercent=50", Some(AIKAR_LARGE_HEAP_MB + 1), None),
+            heap_range("-XX:G1HeapRegionSize=16M", Some(AIKAR_LARGE_HEAP_MB + 1), None),
+            heap_range("-XX:G1ReservePercent=15", Some(AIKAR_LARGE_HEAP_MB + 1), None),
+            heap_range(
+                "-XX:InitiatingHeapOccupancyPercent=20",
+                Some(AIKAR_LARGE_HEAP_MB + 1),
+                None,
+            ),
+            flag("-XX:G1HeapWastePercent=5"),
+            flag("-XX:G1MixedGCCountTarget=4"),
+            flag("-XX:G1MixedGCLiveThresholdPercent=90"),
+            // JDK 20 起该参数已被移除
+            java_range("-XX:G1RSetUpdatingPauseTimePercent=5", None, Some(19)),
+            flag("-XX:SurvivorRatio=32"),
+            flag("-XX:+PerfDisableSharedMem"),
+            flag("-XX:MaxTenuringThreshold=1"),
+            flag("-Dusing.aikars.flags=https://mcflags.emc.gs"),
+            flag("-Daikars.new.flags=true"),
+        ],
+    }
+}
+
+fn zgc_generational_v1() -> JvmFlagPreset {
+    JvmFlagPreset {
+        id: "zgc-generational",
+        revision: 1,
+        name: "分代 ZGC",
+        description: "Java 21+ 的分代 ZGC，停顿极低，适合大内存服务器",
+        min_java: 21,
+        max_java: None,
+        flags: vec![
+            flag("-XX:+UseZGC"),
+            // Java 23 起分代模式为默认，24 起移除了该开关
+            java_range("-XX:+ZGenerational", Some(21), Some(22)),
+            flag("-XX:+AlwaysPreTouch"),
+            flag("-XX:+DisableExplicitGC"),
+            flag("-XX:+PerfDisableSharedMem"),
+        ],
+    }
+}
+
+fn low_memory_v1() -> JvmFlagPreset {
+    JvmFlagPreset {
+        id: "low-memory",
+        revision: 1,
+        name: "低内存",
+        description: "使用 Serial GC 并缩小线程栈与代码缓存，适合 2G 以下的小型服务器",
+        min_java: 8,
+        max_java: None,
+        flags: vec![
+            flag("-XX:+UseSerialGC"),
+            flag("-Xss512k"),
+            flag("-XX:ReservedCodeCacheSize=64m"),
+            flag("-XX:+DisableExplicitGC"),
+        ],
+    }
+}
+
+/// 所有内置预设的全部版本，同一 id 按 revision 升序排列
+pub fn builtin_presets() -> Vec<JvmFlagPreset> {
+    vec![aikar_g1_v1(), zgc_generational_v1(), low_memory_v1()]
+}
+
+/// 每个 id 的最新版本，供前端选择
+pub fn latest_presets() -> Vec<JvmFlagPreset> {
+    let mut latest: Vec<JvmFlagPreset> = Vec::new();
+    for preset in builtin_presets() {
+        match latest.iter_mut().find(|p| p.id == preset.id) {
+            Some(existing) if existing.revision < preset.revision => *existing = preset,
+            Some(_) => {}
+            None => latest.push(preset),
+        }
+    }
+    latest
+}
+
+/// 查找预设；revision 为 None 时返回最新版本
+pub fn find_preset(id: &str, revision: Option<u32>) -> Option<JvmFlagPreset> {
+    match revision {
+        Some(revision) => builtin_presets()
+            .into_iter()
+            .find(|p| p.id == id && p.revision == revision),
+        None => latest_presets().into_iter().find(|p| p.id == id),
+    }
+}
+
+pub fn resolve_server_preset(selected: &ServerJvmPreset) -> Result<JvmFlagPreset, String> {
+    find_preset(&selected.id, Some(selected.revision)).ok_or_else(|| {
+        format!("未知的 JVM 参数预设: {} (revision {})", selected.id, selected.revision)
+    })
+}
+
+pub fn validate_preset(preset: &JvmFlagPreset, java_major: u32) -> Result<(), String> {
+    if java_major < preset.min_java {
+        return Err(format!(
+            "JVM 参数预设「{}」需要 Java {}+，当前 Java 版本为 {}",
+            preset.name, preset.min_java, java_major
+        ));
+    }
+    if let Some(max_java) = preset.max_java {
+        if java_major > max_java {
+            return Err(format!(
+                "JVM 参数预设「{}」仅支持 Java {} 及以下，当前 Java 版本为 {}",
+                preset.name, max_java, java_major
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// 按 Java 主版本与最大堆渲染参数；无法识别 Java 版本时跳过所有限定了 Java 版本的参数
+pub fn render_preset(
+    preset: &JvmFlagPreset,
+    java_major: Option<u32>,
+    max_memory_mb: u32,
+) -> Vec<String> {
+    preset
+        .flags
+        .iter()
+        .filter(|flag| match java_major {
+            Some(major) => {
+                flag.min_java.is_none_or(|min| major >= min)
+                    && flag.max_java.is_none_or(|max| major <= max)
+            }
+            None => flag.min_java.is_none() && flag.max_java.is_none(),
+        })
+        .filter(|flag| {
+            flag.min_heap_mb.is_none_or(|min| max_memory_mb >= min)
+                && flag.max_heap_mb.is_none_or(|max| max_memory_mb <= max)
+        })
+        .map(|flag| flag.arg.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn zgc_generational_flag_depends_on_java_version() {
+        let preset = find_preset("zgc-generational", None).unwrap();
+        assert!(validate_preset(&preset, 17).is_err());
+        assert!(render_preset(&preset, Some(21), 4096).contains(&"-XX:+ZGenerational".to_string()));
+        assert!(!render_preset(&preset, Some(23), 4096).contains(&"-XX:+ZGenerational".to_string()));
+    }
+
+    #[test]
+    fn aikar_switches_flags_for_large_heaps() {
+        let preset = find_preset("aikar-g1", Some(1)).unwrap();
+        let small = render_preset(&preset, Some(17), 8192);
+        let large = render_preset(&preset, Some(21), 16384);
+        assert!(small.contains(&"-XX:G1HeapRegionSize=8M".to_string()));
+        assert!(large.contains(&"-XX:G1HeapRegionSize=16M".to_string()));
+        assert!(!large.contains(&"-XX:G1HeapRegionSize=8M".to_string()));
+        assert!(!large.contains(&"-XX:G1RSetUpdatingPauseTimePercent=5".to_string()));
+        assert!(render_preset(&preset, Some(8), 8192)
+            .contains(&"-XX:G1RSetUpdatingPauseTimePercent=5".to_string()));
+    }
+
+    #[test]
+    fn unknown_preset_is_rejected() {
+        let selected = ServerJvmPreset { id: "missing".to_string(), revision: 1 };
+        assert!(resolve_server_preset(&selected).is_err());
+    }
+}
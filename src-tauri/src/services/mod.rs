@@ -5,10 +5,14 @@ pub mod global;
 pub mod i18n;
 pub mod java_detector;
 pub mod java_installer;
+pub mod java_requirement;
+pub mod java_runtime_catalogue;
 pub mod join_manager;
+pub mod jvm_presets;
 pub mod mcs_plugin_manager;
 pub mod mod_manager;
 pub mod player_manager;
+pub mod server_core_catalogue;
 pub mod server_id_manager;
 pub mod server_installer;
 pub mod server_log_pipeline;
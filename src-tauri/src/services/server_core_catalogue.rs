@@ -0,0 +1,753 @@
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::Digest;
+
+use crate::services::server_installer::CoreType;
+
+// 服务端核心版本目录：
+// 1) 汇总 Paper API v2（paper/folia/velocity）、Purpur API、Mojang 版本清单与 Fabric meta
+// 2) 所有远端响应按 URL 缓存到 server_core_catalogue.json，过期后刷新，刷新失败时回退到旧缓存
+// 3) 按 CoreType + MC 版本查询可用构建，并提供带校验的下载
+// 4) 启动服务器时的 Java 要求只读缓存，缓存缺失或过期时在后台刷新，不阻塞启动
+const CATALOGUE_CACHE_FILE: &str = "server_core_catalogue.json";
+const CATALOGUE_CACHE_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+const CATALOGUE_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const CORE_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+const PAPER_API_BASE: &str = "https://api.papermc.io/v2/projects";
+const PURPUR_API_BASE: &str = "https://api.purpurmc.org/v2/purpur";
+const MOJANG_VERSION_MANIFEST_URL: &str =
+    "https://piston-meta.mojang.com/mc/game/version_manifest_v2.json";
+const FABRIC_META_BASE: &str = "https://meta.fabricmc.net/v2/versions";
+
+static CATALOGUE_CACHE_LOCK: Mutex<()> = Mutex::new(());
+static JAVA_VERSION_REFRESHING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Sha1,
+    Md5,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreChecksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoreBuild {
+    pub core_type: String,
+    pub mc_version: String,
+    /// 构建标识：Paper/Purpur 为构建号，Fabric 为 loader 版本，Vanilla 为 MC 版本本身
+    pub build: String,
+    pub file_name: String,
+    pub download_url: String,
+    pub checksum: Option<CoreChecksum>,
+    pub stable: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CatalogueCache {
+    #[serde(default)]
+    entries: HashMap<String, CatalogueCacheEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CatalogueCacheEntry {
+    fetched_at: u64,
+    body: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperProject {
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperBuilds {
+    #[serde(default)]
+    builds: Vec<PaperBuild>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperBuild {
+    build: u64,
+    #[serde(default)]
+    channel: String,
+    downloads: HashMap<String, PaperDownload>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PaperDownload {
+    name: String,
+    #[serde(default)]
+    sha256: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PurpurProject {
+    #[serde(default)]
+    versions: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PurpurVersion {
+    builds: PurpurBuildList,
+}
+
+#[derive(Debug, Deserialize)]
+struct PurpurBuildList {
+    #[serde(default)]
+    all: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PurpurBuildDetail {
+    #[serde(default)]
+    md5: Option<String>,
+    #[serde(default)]
+    result: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MojangManifest {
+    versions: Vec<MojangManifestVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MojangManifestVersion {
+    id: String,
+    #[serde(rename = "type")]
+    version_type: String,
+    url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MojangVersionDetail {
+    #[serde(default)]
+    downloads: HashMap<String, MojangDownload>,
+    #[serde(default, rename = "javaVersion")]
+    java_version: Option<MojangJavaVersion>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MojangJavaVersion {
+    #[serde(rename = "majorVersion")]
+    major_version: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct MojangDownload {
+    url: String,
+    #[serde(default)]
+    sha1: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FabricGameVersion {
+    version: String,
+    #[serde(default)]
+    stable: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct FabricLoaderEntry {
+    loader: FabricComponentVersion,
+}
+
+#[derive(Debug, Deserialize)]
+struct FabricComponentVersion {
+    version: String,
+    #[serde(default)]
+    stable: bool,
+}
+
+/// 返回目录支持的核心类型
+pub fn supported_core_types() -> Vec<CoreType> {
+    vec![
+        CoreType::Paper,
+        CoreType::Folia,
+        CoreType::Velocity,
+        CoreType::Purpur,
+        CoreType::Vanilla,
+        CoreType::VanillaSnapshot,
+        CoreType::Fabric,
+    ]
+}
+
+/// 列出某个核心可用的 MC 版本（新版本在前）
+pub fn list_core_mc_versions(core_type: CoreType) -> Result<Vec<String>, String> {
+    let mut versions = match core_type {
+        CoreType::Paper | CoreType::Folia | CoreType::Velocity => {
+            let project = paper_project_name(core_type)?;
+            let url = format!("{}/{}", PAPER_API_BASE, project);
+            fetch_cached_json::<PaperProject>(&url)?.versions
+        }
+        CoreType::Purpur => fetch_cached_json::<PurpurProject>(PURPUR_API_BASE)?.versions,
+        CoreType::Vanilla | CoreType::VanillaSnapshot => {
+            let wanted_type = mojang_version_type(core_type);
+            let manifest: MojangManifest = fetch_cached_json(MOJANG_VERSION_MANIFEST_URL)?;
+            // Mojang 清单本身就是新版本在前，直接返回避免按数字排序打乱快照顺序
+            return Ok(manifest
+                .versions
+                .into_iter()
+                .filter(|v| v.version_type == wanted_type)
+                .map(|v| v.id)
+                .collect());
+        }
+        CoreType::Fabric => {
+            let url = format!("{}/game", FABRIC_META_BASE);
+            let games: Vec<FabricGameVersion> = fetch_cached_json(&url)?;
+            return Ok(games
+                .into_iter()
+                .filter(|g| g.stable)
+                .map(|g| g.version)
+                .collect());
+        }
+        other => return Err(unsupported_core_error(other)),
+    };
+
+    versions.reverse();
+    Ok(versions)
+}
+
+/// 列出某个核心在指定 MC 版本下的全部构建（新构建在前）
+pub fn list_core_builds(core_type: CoreType, mc_version: &str) -> Result<Vec<CoreBuild>, String> {
+    let mc_version = mc_version.trim();
+    if mc_version.is_empty() {
+        return Err("查询核心构建时缺少 MC 版本".to_string());
+    }
+
+    match core_type {
+        CoreType::Paper | CoreType::Folia | CoreType::Velocity => {
+            list_paper_builds(core_type, mc_version)
+        }
+        CoreType::Purpur => list_purpur_builds(mc_version),
+        CoreType::Vanilla | CoreType::VanillaSnapshot => list_vanilla_builds(core_type, mc_version),
+        CoreType::Fabric => list_fabric_builds(mc_version),
+        other => Err(unsupported_core_error(other)),
+    }
+}
+
+/// 解析出要下载的构建：指定构建号时精确匹配，否则取最新稳定构建（没有稳定构建时取最新构建）
+pub fn resolve_core_build(
+    core_type: CoreType,
+    mc_version: &str,
+    build: Option<&str>,
+) -> Result<CoreBuild, String> {
+    let builds = list_core_builds(core_type, mc_version)?;
+    let selected = match build.map(str::trim).filter(|b| !b.is_empty()) {
+        Some(wanted) => builds
+            .into_iter()
+            .find(|b| b.build.eq_ignore_ascii_case(wanted))
+            .ok_or_else(|| {
+                format!(
+                    "未找到核心构建: core={}, version={}, build={}",
+                    core_type, mc_version, wanted
+                )
+            })?,
+        None => {
+            let stable_index = builds.iter().position(|b| b.stable).unwrap_or(0);
+            builds
+                .into_iter()
+                .nth(stable_index)
+                .ok_or_else(|| format!("核心 {} 在 MC {} 下没有可用构建", core_type, mc_version))?
+        }
+    };
+
+    if core_type == CoreType::Purpur && selected.checksum.is_none() {
+        return fill_purpur_checksum(selected);
+    }
+    Ok(selected)
+}
+
+/// 下载构建到目标路径并校验哈希；校验失败时删除已下载的文件
+pub fn download_core_build(build: &CoreBuild, target_path: &Path) -> Result<(), String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(CORE_DOWNLOAD_TIMEOUT)
+        .user_agent(crate::utils::downloader::USER_AGENT_EXAMPLE)
+        .build()
+        .map_err(|e| format!("创建核心下载客户端失败: {}", e))?;
+    let mut response = client
+        .get(&build.download_url)
+        .send()
+        .map_err(|e| format!("下载核心失败: {}", e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("下载核心返回异常状态: {} ({})", status, build.download_url));
+    }
+
+    if let Some(parent) = target_path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("创建核心下载目录失败: {}", e))?;
+    }
+    let mut file =
+        std::fs::File::create(target_path).map_err(|e| format!("创建核心文件失败: {}", e))?;
+    let mut hasher = build
+        .checksum
+        .as_ref()
+        .map(|checksum| ChecksumHasher::new(checksum.algorithm));
+
+    let mut buffer = [0u8; 64 * 1024];
+    let copy_result = loop {
+        let read = match response.read(&mut buffer) {
+            Ok(0) => break Ok(()),
+            Ok(read) => read,
+            Err(e) => break Err(format!("读取核心下载数据失败: {}", e)),
+        };
+        if let Some(hasher) = hasher.as_mut() {
+            hasher.update(&buffer[..read]);
+        }
+        if let Err(e) = file.write_all(&buffer[..read]) {
+            break Err(format!("写入核心文件失败: {}", e));
+        }
+    };
+    drop(file);
+
+    let verify_result = copy_result.and_then(|_| match (&build.checksum, hasher) {
+        (Some(expected), Some(hasher)) => {
+            let actual = hasher.finalize_hex();
+            if actual.eq_ignore_ascii_case(expected.value.trim()) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "核心文件校验失败: 期望 {:?} {}，实际 {}",
+                    expected.algorithm, expected.value, actual
+                ))
+            }
+        }
+        _ => verify_jar_archive(target_path),
+    });
+
+    if verify_result.is_err() {
+        let _ = std::fs::remove_file(target_path);
+    }
+    verify_result
+}
+
+fn list_paper_builds(core_type: CoreType, mc_version: &str) -> Result<Vec<CoreBuild>, String> {
+    check_mc_version_path_segment(mc_version)?;
+    let project = paper_project_name(core_type)?;
+    let url = format!("{}/{}/versions/{}/builds", PAPER_API_BASE, project, mc_version);
+    let payload: PaperBuilds = fetch_cached_json(&url)?;
+
+    let mut builds: Vec<CoreBuild> = payload
+        .builds
+        .into_iter()
+        .filter_map(|build| {
+            let download = build.downloads.get("application")?;
+            Some(CoreBuild {
+                core_type: core_type.as_str().to_string(),
+                mc_version: mc_version.to_string(),
+                build: build.build.to_string(),
+                file_name: download.name.clone(),
+                download_url: format!(
+                    "{}/{}/versions/{}/builds/{}/downloads/{}",
+                    PAPER_API_BASE, project, mc_version, build.build, download.name
+                ),
+                checksum: download.sha256.clone().map(|value| CoreChecksum {
+                    algorithm: ChecksumAlgorithm::Sha256,
+                    value,
+                }),
+                stable: build.channel.is_empty() || build.channel.eq_ignore_ascii_case("default"),
+            })
+        })
+        .collect();
+    builds.reverse();
+    Ok(builds)
+}
+
+fn list_purpur_builds(mc_version: &str) -> Result<Vec<CoreBuild>, String> {
+    check_mc_version_path_segment(mc_version)?;
+    let url = format!("{}/{}", PURPUR_API_BASE, mc_version);
+    let payload: PurpurVersion = fetch_cached_json(&url)?;
+
+    let mut builds: Vec<CoreBuild> = payload
+        .builds
+        .all
+        .into_iter()
+        .map(|build| CoreBuild {
+            core_type: CoreType::Purpur.as_str().to_string(),
+            mc_version: mc_version.to_string(),
+            file_name: format!("purpur-{}-{}.jar", mc_version, build),
+            download_url: format!("{}/{}/{}/download", PURPUR_API_BASE, mc_version, build),
+            build,
+            checksum: None,
+            stable: true,
+        })
+        .collect();
+    builds.reverse();
+    Ok(builds)
+}
+
+fn fill_purpur_checksum(mut build: CoreBuild) -> Result<CoreBuild, String> {
+    let url = format!("{}/{}/{}", PURPUR_API_BASE, build.mc_version, build.build);
+    let detail: PurpurBuildDetail = fetch_cached_json(&url)?;
+    if let Some(result) = detail.result.as_deref() {
+        if !result.eq_ignore_ascii_case("SUCCESS") {
+            return Err(format!("Purpur 构建 {} 状态异常: {}", build.build, result));
+        }
+    }
+    build.checksum = detail
+        .md5
+        .map(|value| CoreChecksum { algorithm: ChecksumAlgorithm::Md5, value });
+    Ok(build)
+}
+
+fn list_vanilla_builds(core_type: CoreType, mc_version: &str) -> Result<Vec<CoreBuild>, String> {
+    let manifest: MojangManifest = fetch_cached_json(MOJANG_VERSION_MANIFEST_URL)?;
+    let entry = manifest
+        .versions
+        .into_iter()
+        .find(|v| v.id == mc_version)
+        .ok_or_else(|| format!("Mojang 版本清单中不存在版本: {}", mc_version))?;
+    let detail: MojangVersionDetail = fetch_cached_json(&entry.url)?;
+    let server = detail
+        .downloads
+        .get("server")
+        .ok_or_else(|| format!("MC {} 没有提供官方服务端下载", mc_version))?;
+
+    Ok(vec![CoreBuild {
+        core_type: core_type.as_str().to_string(),
+        mc_version: mc_version.to_string(),
+        build: mc_version.to_string(),
+        file_name: format!("minecraft_server.{}.jar", mc_version),
+        download_url: server.url.clone(),
+        checksum: server.sha1.clone().map(|value| CoreChecksum {
+            algorithm: ChecksumAlgorithm::Sha1,
+            value,
+        }),
+        stable: entry.version_type == "release",
+    }])
+}
+
+/// 从 Mojang 版本详情读取该 MC 版本要求的最低 Java 主版本；清单中不存在该版本时返回 None
+pub fn mojang_java_major_version(mc_version: &str) -> Result<Option<u32>, String> {
+    let manifest: MojangManifest = fetch_cached_json(MOJANG_VERSION_MANIFEST_URL)?;
+    let Some(entry) = manifest
+        .versions
+        .into_iter()
+        .find(|v| v.id == mc_version.trim())
+    else {
+        return Ok(None);
+    };
+    let detail: MojangVersionDetail = fetch_cached_json(&entry.url)?;
+    Ok(detail.java_version.map(|java| java.major_version))
+}
+
+/// 只从缓存读取 MC 版本要求的最低 Java 主版本，不发起网络请求；
+/// 缓存缺失或过期时在后台刷新，供下次查询使用
+pub fn cached_mojang_java_major_version(mc_version: &str) -> Option<u32> {
+    let mc_version = mc_version.trim();
+    let cached = read_cached_json::<MojangManifest>(MOJANG_VERSION_MANIFEST_URL).and_then(
+        |(manifest, manifest_fresh)| {
+            let entry = manifest.versions.into_iter().find(|v| v.id == mc_version)?;
+            let (detail, detail_fresh) = read_cached_json::<MojangVersionDetail>(&entry.url)?;
+            Some((detail.java_version, manifest_fresh && detail_fresh))
+        },
+    );
+
+    let (java_version, fresh) = cached.unwrap_or((None, false));
+    if !fresh {
+        refresh_java_version_in_background(mc_version);
+    }
+    java_version.map(|java| java.major_version)
+}
+
+fn refresh_java_version_in_background(mc_version: &str) {
+    if JAVA_VERSION_REFRESHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let mc_version = mc_version.to_string();
+    std::thread::spawn(move || {
+        if let Err(e) = mojang_java_major_version(&mc_version) {
+            eprintln!("[WARN] 后台刷新 Mojang Java 版本要求失败: {}", e);
+        }
+        JAVA_VERSION_REFRESHING.store(false, Ordering::SeqCst);
+    });
+}
+
+fn list_fabric_builds(mc_version: &str) -> Result<Vec<CoreBuild>, String> {
+    check_mc_version_path_segment(mc_version)?;
+    let loaders: Vec<FabricLoaderEntry> =
+        fetch_cached_json(&format!("{}/loader/{}", FABRIC_META_BASE, mc_version))?;
+    let installers: Vec<FabricComponentVersion> =
+        fetch_cached_json(&format!("{}/installer", FABRIC_META_BASE))?;
+    let installer = installers
+        .iter()
+        .find(|i| i.stable)
+        .or_else(|| installers.first())
+        .ok_or_else(|| "Fabric meta 未返回可用的安装器版本".to_string())?;
+
+    // Fabric meta 不提供服务端 jar 的哈希，下载后仅校验 jar 结构
+    Ok(loaders
+        .into_iter()
+        .map(|entry| CoreBuild {
+            core_type: CoreType::Fabric.as_str().to_string(),
+            mc_version: mc_version.to_string(),
+            file_name: format!(
+                "fabric-server-mc.{}-loader.{}-launcher.{}.jar",
+                mc_version, entry.loader.version, installer.version
+            ),
+            download_url: format!(
+                "{}/loader/{}/{}/{}/server/jar",
+                FABRIC_META_BASE, mc_version, entry.loader.version, installer.version
+            ),
+            build: entry.loader.version,
+            checksum: None,
+            stable: entry.loader.stable,
+        })
+        .collect())
+}
+
+/// 版本号会直接拼进 Paper / Purpur / Fabric 接口的 URL 路径，只允许 [0-9A-Za-z._-] 且不能只由符号组成
+fn check_mc_version_path_segment(mc_version: &str) -> Result<(), String> {
+    let valid = mc_version.chars().any(|c| c.is_ascii_alphanumeric())
+        && mc_version
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("无效的 MC 版本: {}", mc_version))
+    }
+}
+
+fn paper_project_name(core_type: CoreType) -> Result<&'static str, String> {
+    match core_type {
+        CoreType::Paper => Ok("paper"),
+        CoreType::Folia => Ok("folia"),
+        CoreType::Velocity => Ok("velocity"),
+        other => Err(unsupported_core_error(other)),
+    }
+}
+
+fn mojang_version_type(core_type: CoreType) -> &'static str {
+    if core_type == CoreType::VanillaSnapshot {
+        "snapshot"
+    } else {
+        "release"
+    }
+}
+
+fn unsupported_core_error(core_type: CoreType) -> String {
+    format!("核心类型 {} 暂不支持在线查询版本", core_type)
+}
+
+/// 从字符串解析目录支持的核心类型
+pub fn parse_catalogue_core_type(core_type: &str) -> Result<CoreType, String> {
+    let parsed = CoreType::from_str(core_type.trim())?;
+    if !supported_core_types().contains(&parsed) {
+        return Err(unsupported_core_error(parsed));
+    }
+    Ok(parsed)
+}
+
+fn verify_jar_archive(path: &Path) -> Result<(), String> {
+    let file = std::fs::File::open(path).map_err(|e| format!("打开核心文件失败: {}", e))?;
+    let archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("下载的核心不是有效的 jar 文件: {}", e))?;
+    if archive.is_empty() {
+        return Err("下载的核心 jar 文件为空".to_string());
+    }
+    Ok(())
+}
+
+enum ChecksumHasher {
+    Sha256(sha2::Sha256),
+    Sha1(sha1::Sha1),
+    Md5(md5::Md5),
+}
+
+impl ChecksumHasher {
+    fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Sha256 => Self::Sha256(sha2::Sha256::new()),
+            ChecksumAlgorithm::Sha1 => Self::Sha1(sha1::Sha1::new()),
+            ChecksumAlgorithm::Md5 => Self::Md5(md5::Md5::new()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(h) => h.update(data),
+            Self::Sha1(h) => h.update(data),
+            Self::Md5(h) => h.update(data),
+        }
+    }
+
+    fn finalize_hex(self) -> String {
+        let bytes = match self {
+            Self::Sha256(h) => h.finalize().to_vec(),
+            Self::Sha1(h) => h.finalize().to_vec(),
+            Self::Md5(h) => h.finalize().to_vec(),
+        };
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+}
+
+fn catalogue_cache_path() -> PathBuf {
+    PathBuf::from(crate::utils::path::get_or_create_app_data_dir()).join(CATALOGUE_CACHE_FILE)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or(Duration::ZERO)
+        .as_secs()
+}
+
+fn fetch_cached_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T, String> {
+    let body = load_or_refresh_catalogue_entry(url)?;
+    serde_json::from_value(body).map_err(|e| format!("解析核心版本信息失败 ({}): {}", url, e))
+}
+
+/// 读取缓存中的响应（不论是否过期），同时返回是否仍在有效期内
+fn read_cached_json<T: serde::de::DeserializeOwned>(url: &str) -> Option<(T, bool)> {
+    let entry = {
+        let _guard = CATALOGUE_CACHE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        read_catalogue_cache(&catalogue_cache_path())
+            .entries
+            .remove(url)?
+    };
+    let fresh = is_cache_entry_fresh(entry.fetched_at, unix_now());
+    serde_json::from_value(entry.body)
+        .ok()
+        .map(|body| (body, fresh))
+}
+
+fn load_or_refresh_catalogue_entry(url: &str) -> Result<serde_json::Value, String> {
+    let cache_path = catalogue_cache_path();
+    let cached = {
+        let _guard = CATALOGUE_CACHE_LOCK
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        read_catalogue_cache(&cache_path).entries.remove(url)
+    };
+
+    if let Some(entry) = &cached {
+        if is_cache_entry_fresh(entry.fetched_at, unix_now()) {
+            return Ok(entry.body.clone());
+        }
+    }
+
+    // 网络请求期间不持有缓存锁，写回时重新读取缓存，避免覆盖其他请求写入的条目
+    match fetch_catalogue_json(url) {
+        Ok(body) => {
+            let _guard = CATALOGUE_CACHE_LOCK
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            let mut cache = read_catalogue_cache(&cache_path);
+            cache.entries.insert(
+                url.to_string(),
+                CatalogueCacheEntry {
+                    fetched_at: unix_now(),
+                    body: body.clone(),
+                },
+            );
+            if let Err(e) = write_catalogue_cache(&cache_path, &cache) {
+                eprintln!("[WARN] {}", e);
+            }
+            Ok(body)
+        }
+        Err(refresh_error) => match cached {
+            Some(stale) => {
+                eprintln!("[WARN] {}，使用过期的核心版本缓存", refresh_error);
+                Ok(stale.body)
+            }
+            None => Err(refresh_error),
+        },
+    }
+}
+
+fn is_cache_entry_fresh(fetched_at: u64, now: u64) -> bool {
+    now.saturating_sub(fetched_at) <= CATALOGUE_CACHE_TTL.as_secs()
+}
+
+fn read_catalogue_cache(path: &Path) -> CatalogueCache {
+    std::fs::read(path)
+        .ok()
+        .and_then(|body| serde_json::from_slice(&body).ok())
+        .unwrap_or_default()
+}
+
+fn write_catalogue_cache(path: &Path, cache: &CatalogueCache) -> Result<(), String> {
+    let body = serde_json::to_vec(cache).map_err(|e| format!("序列化核心版本缓存失败: {}", e))?;
+    std::fs::write(path, body).map_err(|e| format!("写入核心版本缓存失败: {}", e))
+}
+
+fn fetch_catalogue_json(url: &str) -> Result<serde_json::Value, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(CATALOGUE_REQUEST_TIMEOUT)
+        .user_agent(crate::utils::downloader::USER_AGENT_EXAMPLE)
+        .build()
+        .map_err(|e| format!("创建核心版本请求客户端失败: {}", e))?;
+    let response = client
+        .get(url)
+        .send()
+        .map_err(|e| format!("请求核心版本信息失败: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("核心版本接口返回异常状态: {} ({})", status, url));
+    }
+
+    response
+        .json::<serde_json::Value>()
+        .map_err(|e| format!("读取核心版本信息失败 ({}): {}", url, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cache_entry_freshness_respects_ttl() {
+        let now = 1_000_000;
+        assert!(is_cache_entry_fresh(now - 60, now));
+        assert!(!is_cache_entry_fresh(now - CATALOGUE_CACHE_TTL.as_secs() - 1, now));
+        assert!(is_cache_entry_fresh(now + 10, now));
+    }
+
+    #[test]
+    fn checksum_hasher_produces_lowercase_hex() {
+        let mut hasher = ChecksumHasher::new(ChecksumAlgorithm::Sha256);
+        hasher.update(b"abc");
+        assert_eq!(
+            hasher.finalize_hex(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+
+        let mut hasher = ChecksumHasher::new(ChecksumAlgorithm::Md5);
+        hasher.update(b"abc");
+        assert_eq!(hasher.finalize_hex(), "900150983cd24fb0d6963f7d28e17f72");
+    }
+
+    #[test]
+    fn mc_version_path_segment_rejects_url_syntax() {
+        assert!(check_mc_version_path_segment("1.21.1").is_ok());
+        assert!(check_mc_version_path_segment("24w14a").is_ok());
+        assert!(check_mc_version_path_segment("1.20.5-rc1").is_ok());
+        for bad in ["../../v2", "1.21/builds", "1.21?x=1", "1.21#", "1.21%2F", "1 .21", ".."] {
+            assert!(check_mc_version_path_segment(bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn parse_catalogue_core_type_rejects_unsupported() {
+        assert_eq!(parse_catalogue_core_type("paper").unwrap(), CoreType::Paper);
+        assert!(parse_catalogue_core_type("forge").is_err());
+    }
+}
@@ -49,6 +49,8 @@ use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use rusqlite::{params, Connection, TransactionBehavior};
 
+use crate::plugins::api::ServerEvent;
+
 const LATEST_LOG_DB_FILE: &str = "latest_log.db";
 
 pub type ServerLogEventHandler = Arc<dyn Fn(&str, &str) -> Result<(), String> + Send + Sync>;
@@ -56,6 +58,8 @@ pub type ServerLogEventHandler = Arc<dyn Fn(&str, &str) -> Result<(), String> +
 static SERVER_LOG_EVENT_HANDLER: OnceLock<ServerLogEventHandler> = OnceLock::new();
 static LOG_WRITERS: OnceLock<Mutex<HashMap<String, ServerLogWriter>>> = OnceLock::new();
 
+static PLAYER_LINE_PATTERNS: OnceLock<PlayerLinePatterns> = OnceLock::new();
+
 const LOG_BATCH_SIZE: usize = 128;
 const LOG_FLUSH_INTERVAL_MS: u64 = 50;
 
@@ -372,18 +376,101 @@ where
                     }
 
                     let _ = append_server_log(&server_id, &line);
+                    emit_plugin_line_events(&server_id, &line);
 
                     if line.contains("Done (") && line.contains(")! For help") {
                         super::global::server_manager().clear_starting(&server_id);
                         let _ = crate::plugins::api::emit_server_ready(&server_id);
+                        let _ = crate::plugins::api::emit_server_event(
+                            "server.ready",
+                            &server_id,
+                            serde_json::json!({ "server_id": server_id }),
+                        );
                     }
                 }
                 Err(_) => break,
             }
         }
+
+        // 输出流关闭通常意味着进程已退出，及时回收以便触发 stopped / crashed 事件
+        super::global::server_manager().reap_exited_process(&server_id);
     });
 }
 
+struct PlayerLinePatterns {
+    join: regex::Regex,
+    leave: regex::Regex,
+    chat: regex::Regex,
+}
+
+#[derive(Debug, PartialEq)]
+enum PlayerLineEvent {
+    Join(String),
+    Leave(String),
+    Chat(String, String),
+}
+
+fn player_line_patterns() -> &'static PlayerLinePatterns {
+    PLAYER_LINE_PATTERNS.get_or_init(|| PlayerLinePatterns {
+        join: regex::Regex::new(r"\]: (?P<name>[A-Za-z0-9_.]{1,32}) joined the game$")
+            .expect("invalid join pattern"),
+        leave: regex::Regex::new(r"\]: (?P<name>[A-Za-z0-9_.]{1,32}) left the game$")
+            .expect("invalid leave pattern"),
+        chat: regex::Regex::new(
+            r"\]: (?:\[Not Secure\] )?<(?P<name>[A-Za-z0-9_.]{1,32})> (?P<message>.*)$",
+        )
+        .expect("invalid chat pattern"),
+    })
+}
+
+fn parse_player_line(line: &str) -> Option<PlayerLineEvent> {
+    let patterns = player_line_patterns();
+    if let Some(caps) = patterns.chat.captures(line) {
+        return Some(PlayerLineEvent::Chat(caps["name"].to_string(), caps["message"].to_string()));
+    }
+    if let Some(caps) = patterns.join.captures(line) {
+        return Some(PlayerLineEvent::Join(caps["name"].to_string()));
+    }
+    patterns
+        .leave
+        .captures(line)
+        .map(|caps| PlayerLineEvent::Leave(caps["name"].to_string()))
+}
+
+/// 将控制台行转换为插件事件：server.log 以及可识别的玩家事件
+pub(crate) fn plugin_line_events(server_id: &str, line: &str) -> Vec<ServerEvent> {
+    use serde_json::json;
+
+    let event = |name: &str, payload: serde_json::Value| ServerEvent {
+        event: name.to_string(),
+        server_id: server_id.to_string(),
+        payload,
+    };
+    let mut events = vec![event("server.log", json!({ "server_id": server_id, "line": line }))];
+    match parse_player_line(line) {
+        Some(PlayerLineEvent::Join(player)) => {
+            events.push(event("player.join", json!({ "server_id": server_id, "player": player })));
+        }
+        Some(PlayerLineEvent::Leave(player)) => {
+            events.push(event("player.leave", json!({ "server_id": server_id, "player": player })));
+        }
+        Some(PlayerLineEvent::Chat(player, message)) => {
+            events.push(event(
+                "player.chat",
+                json!({ "server_id": server_id, "player": player, "message": message }),
+            ));
+        }
+        None => {}
+    }
+    events
+}
+
+fn emit_plugin_line_events(server_id: &str, line: &str) {
+    for event in plugin_line_events(server_id, line) {
+        let _ = crate::plugins::api::emit_server_event(&event.event, server_id, event.payload);
+    }
+}
+
 fn emit_server_log_line(server_id: &str, line: &str) {
     if let Some(handler) = SERVER_LOG_EVENT_HANDLER.get() {
         let _ = handler(server_id, line);
@@ -486,3 +573,25 @@ fn decode_console_bytes(bytes: &[u8]) -> String {
         String::from_utf8_lossy(bytes).into_owned()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_player_line_detects_join_leave_and_chat() {
+        assert_eq!(
+            parse_player_line("[12:00:00 INFO]: Steve joined the game"),
+            Some(PlayerLineEvent::Join("Steve".to_string()))
+        );
+        assert_eq!(
+            parse_player_line("[12:00:05] [Server thread/INFO]: Alex left the game"),
+            Some(PlayerLineEvent::Leave("Alex".to_string()))
+        );
+        assert_eq!(
+            parse_player_line("[12:00:10 INFO]: [Not Secure] <Steve> hello world"),
+            Some(PlayerLineEvent::Chat("Steve".to_string(), "hello world".to_string()))
+        );
+        assert_eq!(parse_player_line("[12:00:10 INFO]: Preparing spawn area"), None);
+    }
+}
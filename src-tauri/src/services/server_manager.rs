@@ -143,6 +143,44 @@ impl ServerManager {
         }
     }
 
+    /// 进程退出后通知插件：主动停止或正常退出视为 stopped，其余视为 crashed
+    fn notify_process_exit(&self, id: &str, exit_status: Option<std::process::ExitStatus>) {
+        let exit_code = exit_status.and_then(|status| status.code());
+        let expected = self.is_stopping(id) || exit_status.is_some_and(|status| status.success());
+        let event = if expected {
+            "server.stopped"
+        } else {
+            "server.crashed"
+        };
+        let _ = crate::plugins::api::emit_server_event(
+            event,
+            id,
+            serde_json::json!({ "server_id": id, "exit_code": exit_code }),
+        );
+    }
+
+    /// 输出流结束后在后台线程等待进程退出并回收，最多等待 5 秒，不占用控制台读取线程
+    pub fn reap_exited_process(&self, id: &str) {
+        let sid = id.to_string();
+        std::thread::spawn(move || {
+            let manager = super::global::server_manager();
+            for _ in 0..10 {
+                if !manager
+                    .processes
+                    .lock()
+                    .expect("processes lock poisoned")
+                    .contains_key(&sid)
+                {
+                    return;
+                }
+                if manager.get_server_status(&sid).status == ServerStatus::Stopped {
+                    return;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(500));
+            }
+        });
+    }
+
     pub fn request_stop_server(&self, id: &str) -> Result<(), String> {
         if self.is_stopping(id) {
             return Ok(());
@@ -164,6 +202,31 @@ impl ServerManager {
         Ok(())
     }
 
+    /// 后台停止服务器，停止成功后重新启动
+    pub fn request_restart_server(&self, id: &str) -> Result<(), String> {
+        if self.is_stopping(id) {
+            return Err("服务器正在停止中，请稍后再试".to_string());
+        }
+
+        self.mark_stopping(id);
+        let sid = id.to_string();
+        std::thread::spawn(move || {
+            let manager = super::global::server_manager();
+            let result = manager
+                .stop_server(&sid)
+                .and_then(|_| manager.start_server(&sid));
+            if let Err(err) = result {
+                let _ = server_log_pipeline::append_sealantern_log(
+                    &sid,
+                    &format!("[Sea Lantern] 重启失败: {}", err),
+                );
+                manager.clear_stopping(&sid);
+            }
+        });
+
+        Ok(())
+    }
+
     fn save(&self) {
         let servers = self.servers.lock().expect("servers lock poisoned");
         let data_dir = self.data_dir.lock().expect("data_dir lock poisoned");
@@ -179,6 +242,7 @@ impl ServerManager {
         server: &ServerInstance,
         settings: &crate::models::settings::AppSettings,
         console_encoding: ManagedConsoleEncoding,
+        java_major: Option<u32>,
     ) -> Vec<String> {
         let java_encoding = console_encoding.java_name();
         let mut args = vec![
@@ -189,6 +253,17 @@ impl ServerManager {
             format!("-Dsun.stderr.encoding={}", java_encoding),
         ];
 
+        if let Some(selected) = &server.jvm_preset {
+            match super::jvm_presets::resolve_server_preset(selected) {
+                Ok(preset) => args.extend(super::jvm_presets::render_preset(
+                    &preset,
+                    java_major,
+                    server.max_memory,
+                )),
+                Err(e) => eprintln!("[WARN] {}", e),
+            }
+        }
+
         let jvm = settings.default_jvm_args.trim();
         if !jvm.is_empty() {
             args.extend(jvm.split_whitespace().map(|arg| arg.to_string()));
@@ -203,8 +278,9 @@ impl ServerManager {
         server: &ServerInstance,
         settings: &crate::models::settings::AppSettings,
         console_encoding: ManagedConsoleEncoding,
+        java_major: Option<u32>,
     ) -> Result<(), String> {
-        let args = self.build_managed_jvm_args(server, settings, console_encoding);
+        let args = self.build_managed_jvm_args(server, settings, console_encoding, java_major);
         let user_jvm_args_path = std::path::Path::new(&server.path).join("user_jvm_args.txt");
         let content = if args.is_empty() {
             String::new()
@@ -243,6 +319,7 @@ impl ServerManager {
             max_memory: req.max_memory,
             min_memory: req.min_memory,
             jvm_args: Vec::new(),
+            jvm_preset: None,
             port: req.port,
             created_at: now,
             last_started_at: None,
@@ -349,6 +426,7 @@ impl ServerManager {
             max_memory: req.max_memory,
             min_memory: req.min_memory,
             jvm_args: Vec::new(),
+            jvm_preset: None,
             port,
             created_at: now,
             last_started_at: None,
@@ -540,6 +618,7 @@ impl ServerManager {
             max_memory: req.max_memory,
             min_memory: req.min_memory,
             jvm_args: Vec::new(),
+            jvm_preset: None,
             port,
             created_at: now,
             last_started_at: None,
@@ -645,6 +724,7 @@ impl ServerManager {
             max_memory: req.max_memory,
             min_memory: req.min_memory,
             jvm_args: Vec::new(),
+            jvm_preset: None,
             port,
             created_at: now,
             last_started_at: None,
@@ -677,14 +757,16 @@ impl ServerManager {
             let mut procs = self.processes.lock().expect("processes lock poisoned");
             if let Some(child) = procs.get_mut(id) {
                 match child.try_wait() {
-                    Ok(Some(_)) => {
+                    Ok(Some(exit_status)) => {
                         procs.remove(id);
                         server_log_pipeline::shutdown_writer(id);
+                        self.notify_process_exit(id, Some(exit_status));
                     } // Dead process, clean up
                     Ok(None) => return Err("服务器已在运行中".to_string()),
                     Err(_) => {
                         procs.remove(id);
                         server_log_pipeline::shutdown_writer(id);
+                        self.notify_process_exit(id, None);
                     }
                 }
             }
@@ -813,8 +895,36 @@ impl ServerManager {
             resolve_managed_console_encoding(startup_mode, startup_path_obj)
         };
 
+        let current_java_major = if startup_mode == "custom" {
+            None
+        } else {
+            detect_java_major_version(&server.java_path)
+        };
+
+        if current_java_major.is_some() {
+            let report = super::java_requirement::check_java_compatibility(
+                &server.core_type,
+                &server.mc_version,
+                current_java_major,
+            );
+            if let Some(message) = report.message() {
+                if report.status == super::java_requirement::JavaCompatibility::TooOld {
+                    return Err(message);
+                }
+                let _ = server_log_pipeline::append_sealantern_log(
+                    id,
+                    &format!("[Sea Lantern] 警告: {}", message),
+                );
+            }
+        }
+
+        if let (Some(selected), Some(major_version)) = (&server.jvm_preset, current_java_major) {
+            let preset = super::jvm_presets::resolve_server_preset(selected)?;
+            super::jvm_presets::validate_preset(&preset, major_version)?;
+        }
+
         if startup_mode == "bat" || startup_mode == "sh" || startup_mode == "ps1" {
-            if let Some(major_version) = detect_java_major_version(&server.java_path) {
+            if let Some(major_version) = current_java_major {
                 if major_version < 9 {
                     return Err(format!(
                         "当前 Java 版本 {} 不支持 @user_jvm_args.txt 参数文件语法，请改用 Java 9+（NeoForge 建议 Java 21）",
@@ -919,7 +1029,12 @@ impl ServerManager {
                 }
             }
             "bat" => {
-                self.write_user_jvm_args(&server, &settings, managed_console_encoding)?;
+                self.write_user_jvm_args(
+                    &server,
+                    &settings,
+                    managed_console_encoding,
+                    current_java_major,
+                )?;
 
                 #[cfg(target_os = "windows")]
                 {
@@ -945,7 +1060,12 @@ impl ServerManager {
                 }
             }
             "sh" => {
-                self.write_user_jvm_args(&server, &settings, managed_console_encoding)?;
+                self.write_user_jvm_args(
+                    &server,
+                    &settings,
+                    managed_console_encoding,
+                    current_java_major,
+                )?;
                 let mut sh_cmd = Command::new("sh");
                 sh_cmd.arg(&startup_filename);
                 sh_cmd.arg("nogui");
@@ -960,7 +1080,12 @@ impl ServerManager {
                 sh_cmd
             }
             "ps1" => {
-                self.write_user_jvm_args(&server, &settings, managed_console_encoding)?;
+                self.write_user_jvm_args(
+                    &server,
+                    &settings,
+                    managed_console_encoding,
+                    current_java_major,
+                )?;
                 #[cfg(target_os = "windows")]
                 {
                     let mut ps_cmd = Command::new("powershell");
@@ -991,8 +1116,12 @@ impl ServerManager {
                     .clone()
                     .ok_or_else(|| "Starter 安装器下载链接为空".to_string())?;
                 let mut starter_cmd = Command::new(&server.java_path);
-                for arg in self.build_managed_jvm_args(&server, &settings, managed_console_encoding)
-                {
+                for arg in self.build_managed_jvm_args(
+                    &server,
+                    &settings,
+                    managed_console_encoding,
+                    current_java_major,
+                ) {
                     starter_cmd.arg(arg);
                 }
                 starter_cmd.arg("-jar");
@@ -1004,8 +1133,12 @@ impl ServerManager {
             }
             "jar" => {
                 let mut jar_cmd = Command::new(&server.java_path);
-                for arg in self.build_managed_jvm_args(&server, &settings, managed_console_encoding)
-                {
+                for arg in self.build_managed_jvm_args(
+                    &server,
+                    &settings,
+                    managed_console_encoding,
+                    current_java_major,
+                ) {
                     jar_cmd.arg(arg);
                 }
                 jar_cmd.arg("-jar");
@@ -1015,8 +1148,12 @@ impl ServerManager {
             }
             _ => {
                 let mut jar_cmd = Command::new(&server.java_path);
-                for arg in self.build_managed_jvm_args(&server, &settings, managed_console_encoding)
-                {
+                for arg in self.build_managed_jvm_args(
+                    &server,
+                    &settings,
+                    managed_console_encoding,
+                    current_java_major,
+                ) {
                     jar_cmd.arg(arg);
                 }
                 jar_cmd.arg("-jar");
@@ -1059,6 +1196,11 @@ impl ServerManager {
             .expect("processes lock poisoned")
             .insert(id.to_string(), child);
         self.mark_starting(id);
+        let _ = crate::plugins::api::emit_server_event(
+            "server.starting",
+            id,
+            serde_json::json!({ "server_id": id }),
+        );
 
         {
             let mut servers = self.servers.lock().expect("servers lock poisoned");
@@ -1096,15 +1238,17 @@ impl ServerManager {
             let mut procs = self.processes.lock().expect("processes lock poisoned");
             if let Some(child) = procs.get_mut(id) {
                 match child.try_wait() {
-                    Ok(Some(_)) => {
+                    Ok(Some(exit_status)) => {
                         procs.remove(id);
                         server_log_pipeline::shutdown_writer(id);
+                        self.notify_process_exit(id, Some(exit_status));
                         false
                     }
                     Ok(None) => true,
                     Err(_) => {
                         procs.remove(id);
                         server_log_pipeline::shutdown_writer(id);
+                        self.notify_process_exit(id, None);
                         false
                     }
                 }
@@ -1128,8 +1272,9 @@ impl ServerManager {
             let mut procs = self.processes.lock().expect("processes lock poisoned");
             if let Some(child) = procs.get_mut(id) {
                 match child.try_wait() {
-                    Ok(Some(_)) => {
+                    Ok(Some(exit_status)) => {
                         procs.remove(id);
+                        self.notify_process_exit(id, Some(exit_status));
                         self.clear_stopping(id);
                         let _ = server_log_pipeline::append_sealantern_log(
                             id,
@@ -1141,6 +1286,7 @@ impl ServerManager {
                     Ok(None) => {} // Still running
                     Err(_) => {
                         procs.remove(id);
+                        self.notify_process_exit(id, None);
                         self.clear_stopping(id);
                         server_log_pipeline::shutdown_writer(id);
                         return Ok(());
@@ -1158,7 +1304,8 @@ impl ServerManager {
         let mut procs = self.processes.lock().expect("processes lock poisoned");
         if let Some(mut child) = procs.remove(id) {
             let _ = child.kill();
-            let _ = child.wait();
+            let exit_status = child.wait().ok();
+            self.notify_process_exit(id, exit_status);
             let _ = server_log_pipeline::append_sealantern_log(
                 id,
                 "[Sea Lantern] 服务器超时，已强制终止",
@@ -1189,10 +1336,11 @@ impl ServerManager {
         let mut procs = self.processes.lock().expect("processes lock poisoned");
         let is_running = if let Some(child) = procs.get_mut(id) {
             match child.try_wait() {
-                Ok(Some(_)) => {
+                Ok(Some(exit_status)) => {
                     procs.remove(id);
                     server_log_pipeline::shutdown_writer(id);
                     self.clear_starting(id);
+                    self.notify_process_exit(id, Some(exit_status));
                     false
                 }
                 Ok(None) => true,
@@ -1200,6 +1348,7 @@ impl ServerManager {
                     procs.remove(id);
                     server_log_pipeline::shutdown_writer(id);
                     self.clear_starting(id);
+                    self.notify_process_exit(id, None);
                     false
                 }
             }
@@ -1276,6 +1425,159 @@ impl ServerManager {
         }
     }
 
+    /// 设置或清除服务器的 JVM 参数预设；能识别 Java 版本时立即校验兼容性
+    pub fn set_server_jvm_preset(
+        &self,
+        id: &str,
+        preset_id: Option<&str>,
+    ) -> Result<ServerInstance, String> {
+        let java_path = {
+            let servers = self.servers.lock().expect("servers lock poisoned");
+            servers
+                .iter()
+                .find(|s| s.id == id)
+                .ok_or_else(|| "未找到服务器".to_string())?
+                .java_path
+                .clone()
+        };
+
+        let selected = match preset_id.map(str::trim).filter(|p| !p.is_empty()) {
+            Some(preset_id) => {
+                let preset = super::jvm_presets::find_preset(preset_id, None)
+                    .ok_or_else(|| format!("未知的 JVM 参数预设: {}", preset_id))?;
+                if let Some(major_version) = detect_java_major_version(&java_path) {
+                    super::jvm_presets::validate_preset(&preset, major_version)?;
+                }
+                Some(crate::models::server::ServerJvmPreset {
+                    id: preset.id.to_string(),
+                    revision: preset.revision,
+                })
+            }
+            None => None,
+        };
+
+        let mut servers = self.servers.lock().expect("servers lock poisoned");
+        let server = servers
+            .iter_mut()
+            .find(|s| s.id == id)
+            .ok_or_else(|| "未找到服务器".to_string())?;
+        server.jvm_preset = selected;
+        let updated = server.clone();
+        drop(servers);
+        self.save();
+        Ok(updated)
+    }
+
+    /// 检查服务器当前配置的 Java 是否满足其 MC 版本的要求
+    pub fn check_server_java(
+        &self,
+        id: &str,
+    ) -> Result<super::java_requirement::JavaCompatibilityReport, String> {
+        let server = {
+            let servers = self.servers.lock().expect("servers lock poisoned");
+            servers
+                .iter()
+                .find(|s| s.id == id)
+                .ok_or_else(|| "未找到服务器".to_string())?
+                .clone()
+        };
+        let current_major = detect_java_major_version(&server.java_path);
+        Ok(super::java_requirement::check_java_compatibility(
+            &server.core_type,
+            &server.mc_version,
+            current_major,
+        ))
+    }
+
+    /// 更新服务器核心：下载并校验新构建，备份旧 jar 后原地替换，同步 core_version
+    pub fn update_server_core(
+        &self,
+        id: &str,
+        build: Option<&str>,
+    ) -> Result<ServerInstance, String> {
+        use super::server_core_catalogue;
+
+        let server = {
+            let servers = self.servers.lock().expect("servers lock poisoned");
+            servers
+                .iter()
+                .find(|s| s.id == id)
+                .ok_or_else(|| "未找到服务器".to_string())?
+                .clone()
+        };
+
+        if self.get_server_status(id).status != ServerStatus::Stopped {
+            return Err("请先停止服务器再更新核心".to_string());
+        }
+        if normalize_startup_mode(&server.startup_mode) != "jar" {
+            return Err("仅支持更新以 jar 方式启动的服务器核心".to_string());
+        }
+
+        let core_type = server_core_catalogue::parse_catalogue_core_type(&server.core_type)?;
+        let target =
+            server_core_catalogue::resolve_core_build(core_type, &server.mc_version, build)?;
+
+        let jar_path = {
+            let raw = Path::new(&server.jar_path);
+            if raw.is_absolute() {
+                raw.to_path_buf()
+            } else {
+                Path::new(&server.path).join(raw)
+            }
+        };
+        let jar_file_name = jar_path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| format!("核心路径无效: {}", jar_path.display()))?;
+
+        let download_path = jar_path.with_file_name(format!("{}.download", jar_file_name));
+        server_core_catalogue::download_core_build(&target, &download_path)?;
+
+        if jar_path.is_file() {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("time went backwards")
+                .as_secs();
+            let backup_dir = Path::new(&server.path).join("core_backups");
+            let backup_path = backup_dir.join(format!("{}.{}.bak", jar_file_name, timestamp));
+            let backup_result = std::fs::create_dir_all(&backup_dir)
+                .and_then(|_| std::fs::copy(&jar_path, &backup_path));
+            if let Err(e) = backup_result {
+                let _ = std::fs::remove_file(&download_path);
+                return Err(format!("备份旧核心失败: {}", e));
+            }
+            let _ = server_log_pipeline::append_sealantern_log(
+                id,
+                &format!("[Sea Lantern] 旧核心已备份到: {}", backup_path.display()),
+            );
+        }
+
+        if let Err(e) = std::fs::rename(&download_path, &jar_path) {
+            let _ = std::fs::remove_file(&download_path);
+            return Err(format!("替换核心文件失败: {}", e));
+        }
+
+        let updated = {
+            let mut servers = self.servers.lock().expect("servers lock poisoned");
+            let server = servers
+                .iter_mut()
+                .find(|s| s.id == id)
+                .ok_or_else(|| "未找到服务器".to_string())?;
+            server.core_version = target.build.clone();
+            server.clone()
+        };
+        self.save();
+
+        let _ = server_log_pipeline::append_sealantern_log(
+            id,
+            &format!(
+                "[Sea Lantern] 核心已更新: {} {} (构建 {})",
+                target.core_type, target.mc_version, target.build
+            ),
+        );
+        Ok(updated)
+    }
+
     pub fn stop_all_servers(&self) {
         let ids: Vec<String> = self
             .processes
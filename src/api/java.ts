@@ -30,10 +30,10 @@ export const javaApi = {
   },
 
   /**
-   * 安装 Java
+   * 从 Temurin/Zulu 目录安装指定主版本的 Java，安装包经 sha256 校验
    */
-  async installJava(url: string, versionName: string): Promise<string> {
-    return tauriInvoke("install_java", { url, versionName });
+  async installJavaPackage(distribution: string, majorVersion: number): Promise<string> {
+    return tauriInvoke("install_java_package", { distribution, majorVersion });
   },
 
   /**
@@ -22,10 +22,22 @@ export interface MarketPluginInfo {
   download_type?: "release" | "source";
   release_asset?: string;
   branch?: string;
+  sha256?: string;
+  signature?: string;
   tags?: string[];
   dependencies?: string[];
   optional_dependencies?: string[];
   _path?: string;
+  /** 条目来自的市场源 id */
+  source?: string;
+  /** 市场源的根地址 */
+  source_url?: string;
+}
+
+export interface MarketCatalog {
+  plugins: MarketPluginInfo[];
+  categories: Record<string, Record<string, string> | string>;
+  errors: { source: string; error: string }[];
 }
 
 export async function listPlugins(): Promise<PluginInfo[]> {
@@ -40,6 +52,111 @@ export async function enablePlugin(pluginId: string): Promise<void> {
   return tauriInvoke("enable_plugin", { pluginId });
 }
 
+export async function confirmUnsignedPlugin(pluginId: string): Promise<void> {
+  return tauriInvoke("confirm_unsigned_plugin", { pluginId });
+}
+
+export type PermissionGrantDecision = "granted" | "denied" | "ask";
+
+export interface PermissionGrant {
+  permission: string;
+  decision: PermissionGrantDecision;
+  danger_level: "normal" | "dangerous" | "critical";
+}
+
+export interface PermissionAuditEntry {
+  timestamp: number;
+  plugin_id: string;
+  permission: string;
+  decision: PermissionGrantDecision;
+  source: string;
+}
+
+export interface PendingPermissionPrompt {
+  plugin_id: string;
+  permissions: string[];
+}
+
+export async function getPluginPermissionGrants(pluginId: string): Promise<PermissionGrant[]> {
+  return tauriInvoke("get_plugin_permission_grants", { pluginId });
+}
+
+export async function setPluginPermissionGrant(
+  pluginId: string,
+  permission: string,
+  decision: PermissionGrantDecision,
+): Promise<void> {
+  return tauriInvoke("set_plugin_permission_grant", { pluginId, permission, decision });
+}
+
+export async function getPendingPermissionPrompts(): Promise<PendingPermissionPrompt[]> {
+  return tauriInvoke("get_pending_permission_prompts");
+}
+
+export async function getPluginPermissionAudit(
+  pluginId?: string,
+): Promise<PermissionAuditEntry[]> {
+  return tauriInvoke("get_plugin_permission_audit", { pluginId });
+}
+
+export interface HttpRequestLogEntry {
+  timestamp: number;
+  method: string;
+  url: string;
+  final_url: string | null;
+  status: number | null;
+  request_bytes: number;
+  response_bytes: number;
+  duration_ms: number;
+  error: string | null;
+}
+
+export async function getPluginNetworkLog(pluginId: string): Promise<HttpRequestLogEntry[]> {
+  return tauriInvoke("get_plugin_network_log", { pluginId });
+}
+
+export interface PluginLogEntry {
+  id: number;
+  timestamp: number;
+  level: string;
+  message: string;
+  source: string | null;
+}
+
+export interface PluginLogQuery {
+  levels?: string[];
+  search?: string;
+  before_id?: number;
+  after_id?: number;
+  limit?: number;
+}
+
+export async function queryPluginLogs(
+  pluginId: string,
+  query?: PluginLogQuery,
+): Promise<PluginLogEntry[]> {
+  return tauriInvoke("query_plugin_logs", { pluginId, query });
+}
+
+export async function tailPluginLogs(
+  pluginId: string,
+  afterId: number,
+  limit?: number,
+): Promise<PluginLogEntry[]> {
+  return tauriInvoke("tail_plugin_logs", { pluginId, afterId, limit });
+}
+
+export async function clearPluginLogs(pluginId: string): Promise<void> {
+  return tauriInvoke("clear_plugin_logs", { pluginId });
+}
+
+export async function exportPluginDiagnostics(
+  pluginId: string,
+  outputPath: string,
+): Promise<string> {
+  return tauriInvoke("export_plugin_diagnostics", { pluginId, outputPath });
+}
+
 export async function disablePlugin(pluginId: string): Promise<string[]> {
   return tauriInvoke("disable_plugin", { pluginId });
 }
@@ -95,6 +212,10 @@ export async function checkAllPluginUpdates(): Promise<PluginUpdateInfo[]> {
   return tauriInvoke("check_all_plugin_updates");
 }
 
+export async function fetchMarketCatalog(): Promise<MarketCatalog> {
+  return tauriInvoke("fetch_market_catalog");
+}
+
 export async function fetchMarketPlugins(marketUrl?: string): Promise<MarketPluginInfo[]> {
   return tauriInvoke("fetch_market_plugins", { marketUrl });
 }
@@ -157,6 +278,143 @@ export async function contextMenuCallback(
   return tauriInvoke("context_menu_callback", { pluginId, context, itemId, targetData });
 }
 
+export interface DependencyPlanStep {
+  plugin_id: string;
+  name: string;
+  action: "install" | "upgrade" | "enable";
+  current_version: string | null;
+  target_version: string;
+  required_by: string[];
+  optional: boolean;
+}
+
+export interface DependencyPlan {
+  plugin_id: string;
+  steps: DependencyPlanStep[];
+  skipped: { plugin_id: string; required_by: string[]; reason: string }[];
+}
+
+export async function previewDependencyPlan(
+  pluginId: string,
+  includeOptional = false,
+  marketUrl?: string,
+): Promise<DependencyPlan> {
+  return tauriInvoke("preview_dependency_plan", { pluginId, includeOptional, marketUrl });
+}
+
+// 执行预览过的计划；后端重新解析的结果与之不一致时拒绝执行
+export async function executeDependencyPlan(
+  pluginId: string,
+  expectedPlan: DependencyPlan,
+  includeOptional = false,
+  marketUrl?: string,
+): Promise<string[]> {
+  return tauriInvoke("execute_dependency_plan", {
+    pluginId,
+    expectedPlan,
+    includeOptional,
+    marketUrl,
+  });
+}
+
+export type PluginUpdatePolicy = "off" | "notify" | "auto";
+
+export interface PluginUpdateResult {
+  plugin_id: string;
+  from_version: string;
+  to_version: string;
+}
+
+export interface AutoUpdateReport {
+  updated: PluginUpdateResult[];
+  available: PluginUpdateInfo[];
+  failed: { plugin_id: string; error: string }[];
+}
+
+export async function getPluginUpdatePolicies(): Promise<Record<string, PluginUpdatePolicy>> {
+  return tauriInvoke("get_plugin_update_policies");
+}
+
+export async function setPluginUpdatePolicy(
+  pluginId: string,
+  policy: PluginUpdatePolicy,
+): Promise<void> {
+  return tauriInvoke("set_plugin_update_policy", { pluginId, policy });
+}
+
+export async function updatePlugin(pluginId: string): Promise<PluginUpdateResult> {
+  return tauriInvoke("update_plugin", { pluginId });
+}
+
+export async function rollbackPluginUpdate(pluginId: string): Promise<PluginUpdateResult> {
+  return tauriInvoke("rollback_plugin_update", { pluginId });
+}
+
+export async function runPluginAutoUpdates(): Promise<AutoUpdateReport> {
+  return tauriInvoke("run_plugin_auto_updates");
+}
+
+export interface PluginPackageResult {
+  plugin_id: string;
+  version: string;
+  package_path: string;
+  sha256: string;
+  size: number;
+  files: string[];
+  warnings: string[];
+}
+
+export async function packagePlugin(
+  pluginDir: string,
+  outputDir?: string,
+): Promise<PluginPackageResult> {
+  return tauriInvoke("package_plugin", { pluginDir, outputDir });
+}
+
+export interface TestCaseReport {
+  name: string;
+  passed: boolean;
+  assertions: number;
+  error: string | null;
+  duration_ms: number;
+}
+
+export interface TestFileReport {
+  file: string;
+  cases: TestCaseReport[];
+  error: string | null;
+}
+
+export interface PluginTestReport {
+  plugin_id: string;
+  withheld_permissions: string[];
+  files: TestFileReport[];
+  passed: number;
+  failed: number;
+}
+
+export async function runPluginTests(pluginDir: string): Promise<PluginTestReport> {
+  return tauriInvoke("run_plugin_tests", { pluginDir });
+}
+
+export interface CommandEntry {
+  id: string;
+  source: string;
+  command_id: string;
+  title: string;
+  shortcut: string | null;
+  shortcut_active: boolean;
+  conflicts: string[];
+}
+
+export async function listCommands(): Promise<CommandEntry[]> {
+  return tauriInvoke("list_commands");
+}
+
+export async function executeCommand(id: string, args?: Record<string, unknown>): Promise<void> {
+  return tauriInvoke("execute_command", { id, args });
+}
+
 export interface BufferedUiEvent {
   plugin_id: string;
   action: string;
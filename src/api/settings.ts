@@ -43,6 +43,22 @@ export interface AppSettings {
   close_action: string;
   last_run_path: string;
   minimal_mode: boolean;
+  trusted_plugin_publishers: TrustedPublisher[];
+  allow_incompatible_plugins: boolean;
+  plugin_market_sources: MarketSource[];
+}
+
+export interface MarketSource {
+  id: string;
+  name: string;
+  /** http(s) 地址、file:// 地址或本地目录；以 .json 结尾时视为聚合索引文件 */
+  url: string;
+  enabled: boolean;
+}
+
+export interface TrustedPublisher {
+  name: string;
+  public_key: string;
 }
 
 export interface PartialSettings {
@@ -78,6 +94,9 @@ export interface PartialSettings {
   close_action?: string;
   last_run_path?: string;
   minimal_mode?: boolean;
+  trusted_plugin_publishers?: TrustedPublisher[];
+  allow_incompatible_plugins?: boolean;
+  plugin_market_sources?: MarketSource[];
 }
 
 export interface UpdateSettingsResult {
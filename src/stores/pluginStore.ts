@@ -142,6 +142,7 @@ export const usePluginStore = defineStore("plugin", () => {
   const error = ref<string | null>(null);
   const icons = ref<Record<string, string>>({});
   const updates = ref<Record<string, PluginUpdateInfo>>({});
+  const updatePolicies = ref<Record<string, pluginApi.PluginUpdatePolicy>>({});
 
   const pendingDependencies = ref<MissingDependency[]>([]);
 
@@ -151,6 +152,8 @@ export const usePluginStore = defineStore("plugin", () => {
 
   const pluginLogs = ref<Record<string, PluginLogEvent[]>>({});
 
+  const permissionPrompts = ref<pluginApi.PendingPermissionPrompt[]>([]);
+
   const eventListenerRegistry = new Map<
     string,
     Array<{ element: Element; eventType: string; handler: EventListener }>
@@ -736,6 +739,57 @@ export const usePluginStore = defineStore("plugin", () => {
     }
   }
 
+  async function loadUpdatePolicies() {
+    try {
+      updatePolicies.value = await pluginApi.getPluginUpdatePolicies();
+    } catch (e) {
+      console.error("Failed to load plugin update policies:", e);
+    }
+  }
+
+  async function setUpdatePolicy(pluginId: string, policy: pluginApi.PluginUpdatePolicy) {
+    await pluginApi.setPluginUpdatePolicy(pluginId, policy);
+    updatePolicies.value[pluginId] = policy;
+  }
+
+  async function updatePlugin(pluginId: string) {
+    const result = await pluginApi.updatePlugin(pluginId);
+    delete updates.value[pluginId];
+    delete icons.value[pluginId];
+    await loadPlugins();
+    return result;
+  }
+
+  async function rollbackUpdate(pluginId: string) {
+    const result = await pluginApi.rollbackPluginUpdate(pluginId);
+    delete icons.value[pluginId];
+    await loadPlugins();
+    return result;
+  }
+
+  async function runAutoUpdates() {
+    try {
+      const report = await pluginApi.runPluginAutoUpdates();
+      for (const update of report.available) {
+        updates.value[update.plugin_id] = update;
+      }
+      for (const failure of report.failed) {
+        console.warn(`Auto update failed for ${failure.plugin_id}: ${failure.error}`);
+      }
+      if (report.updated.length > 0) {
+        for (const result of report.updated) {
+          delete updates.value[result.plugin_id];
+          delete icons.value[result.plugin_id];
+        }
+        await loadPlugins();
+      }
+      return report;
+    } catch (e) {
+      console.error("Failed to run plugin auto updates:", e);
+      return null;
+    }
+  }
+
   async function handlePluginUiEvent(event: PluginUiEvent) {
     const { plugin_id, action, element_id, html } = event;
 
@@ -1163,6 +1217,7 @@ export const usePluginStore = defineStore("plugin", () => {
   }
 
   let uiEventUnlisten: UnlistenFn | null = null;
+  let pluginReloadedUnlisten: UnlistenFn | null = null;
 
   async function initUiEventListener() {
     if (uiEventUnlisten) {
@@ -1174,6 +1229,17 @@ export const usePluginStore = defineStore("plugin", () => {
         console.log(`[PluginUI] Received: ${event.payload.action} for ${event.payload.element_id}`);
         handlePluginUiEvent(event.payload);
       });
+      // 开发者模式热重载后刷新插件列表并重新注入 manifest 中的 CSS
+      pluginReloadedUnlisten = await listen<{
+        plugin_id: string;
+        success: boolean;
+        error: string | null;
+      }>("plugin-reloaded", async (event) => {
+        await loadPlugins();
+        if (event.payload.success) {
+          await injectPluginCss(event.payload.plugin_id);
+        }
+      });
       console.log("[PluginUI] Event listener initialized");
     } catch (e) {
       console.error("[PluginUI] Failed to initialize event listener:", e);
@@ -1185,6 +1251,10 @@ export const usePluginStore = defineStore("plugin", () => {
       uiEventUnlisten();
       uiEventUnlisten = null;
     }
+    if (pluginReloadedUnlisten) {
+      pluginReloadedUnlisten();
+      pluginReloadedUnlisten = null;
+    }
   }
 
   function initSidebarEventListener() {
@@ -1278,6 +1348,55 @@ export const usePluginStore = defineStore("plugin", () => {
     }
   }
 
+  let permissionPromptUnlisten: UnlistenFn | null = null;
+
+  function enqueuePermissionPrompt(prompt: pluginApi.PendingPermissionPrompt) {
+    const existing = permissionPrompts.value.find((p) => p.plugin_id === prompt.plugin_id);
+    if (existing) {
+      existing.permissions = [...new Set([...existing.permissions, ...prompt.permissions])];
+    } else {
+      permissionPrompts.value = [...permissionPrompts.value, prompt];
+    }
+  }
+
+  async function initPermissionPromptListener() {
+    if (permissionPromptUnlisten) {
+      return;
+    }
+
+    try {
+      permissionPromptUnlisten = await listen<pluginApi.PendingPermissionPrompt>(
+        "plugin-permission-prompt",
+        (event) => enqueuePermissionPrompt(event.payload),
+      );
+      // 启动时自动启用的插件在监听建立前就发出了请求，主动拉取一次
+      const pending = await pluginApi.getPendingPermissionPrompts();
+      pending.forEach(enqueuePermissionPrompt);
+    } catch (e) {
+      console.error("[PluginPermission] Failed to initialize prompt listener:", e);
+    }
+  }
+
+  function cleanupPermissionPromptListener() {
+    if (permissionPromptUnlisten) {
+      permissionPromptUnlisten();
+      permissionPromptUnlisten = null;
+    }
+  }
+
+  async function resolvePermissionPrompt(decision: pluginApi.PermissionGrantDecision) {
+    const [prompt, ...rest] = permissionPrompts.value;
+    if (!prompt) return;
+    permissionPrompts.value = rest;
+    for (const permission of prompt.permissions) {
+      try {
+        await pluginApi.setPluginPermissionGrant(prompt.plugin_id, permission, decision);
+      } catch (e) {
+        console.error(`Failed to set permission ${permission} for ${prompt.plugin_id}:`, e);
+      }
+    }
+  }
+
   let pluginLogUnlisten: UnlistenFn | null = null;
 
   async function initPluginLogListener() {
@@ -1610,10 +1729,12 @@ export const usePluginStore = defineStore("plugin", () => {
     error,
     icons,
     updates,
+    updatePolicies,
     pendingDependencies,
     sidebarItems,
     permissionLogs,
     pluginLogs,
+    permissionPrompts,
     loadPlugins,
     refreshPlugins,
     togglePlugin,
@@ -1632,6 +1753,11 @@ export const usePluginStore = defineStore("plugin", () => {
     deletePlugins,
     checkUpdate,
     checkAllUpdates,
+    loadUpdatePolicies,
+    setUpdatePolicy,
+    updatePlugin,
+    rollbackUpdate,
+    runAutoUpdates,
     applyThemeProviderSettings,
     applyThemeWidgetsProviderSettings,
     hasCapability,
@@ -1647,6 +1773,9 @@ export const usePluginStore = defineStore("plugin", () => {
     clearPermissionLogs,
     initPermissionLogListener,
     cleanupPermissionLogListener,
+    initPermissionPromptListener,
+    cleanupPermissionPromptListener,
+    resolvePermissionPrompt,
 
     initPluginLogListener,
     cleanupPluginLogListener,
@@ -70,6 +70,16 @@ const defaultSettings: AppSettings = {
   close_action: "ask",
   last_run_path: "",
   minimal_mode: false,
+  trusted_plugin_publishers: [],
+  allow_incompatible_plugins: false,
+  plugin_market_sources: [
+    {
+      id: "official",
+      name: "SeaLantern",
+      url: "https://sealantern-studio.github.io/plugin-market",
+      enabled: true,
+    },
+  ],
 };
 
 export interface SettingsUpdateEvent {
@@ -15,6 +15,10 @@ export interface PluginSettingField {
   rows?: number;
 
   maxlength?: number;
+
+  min?: number;
+  max?: number;
+  renamed_from?: string[];
 }
 
 export interface PluginAuthor {
@@ -55,6 +59,12 @@ export interface PluginUiConfig {
   sidebar?: PluginSidebarConfig;
 }
 
+export interface PluginCommand {
+  id: string;
+  title: string;
+  shortcut?: string;
+}
+
 export interface PluginManifest {
   id: string;
   name: string;
@@ -65,7 +75,9 @@ export interface PluginManifest {
   icon?: string;
   repository?: string;
   permissions?: string[];
+  allowed_hosts?: string[];
   events?: string[];
+  commands?: PluginCommand[];
   settings?: PluginSettingField[];
   ui?: PluginUiConfig;
   dependencies?: PluginDependency[];
@@ -78,13 +90,19 @@ export interface PluginManifest {
   capabilities?: string[];
 
   include?: string[];
+  exports?: string[];
 
   theme_var_map?: Record<string, string>;
 
   presets?: Record<string, Record<string, string>>;
 }
 
-export type PluginState = "loaded" | "enabled" | "disabled" | { error: string };
+export type PluginState =
+  | "loaded"
+  | "enabled"
+  | "disabled"
+  | { error: string }
+  | { incompatible: string };
 
 export interface PluginInfo {
   manifest: PluginManifest;
@@ -92,6 +110,14 @@ export interface PluginInfo {
   path: string;
 
   missing_dependencies?: MissingDependency[];
+  signature?: PluginSignature;
+}
+
+export interface PluginSignature {
+  status: "verified" | "unsigned";
+  publisher?: string | null;
+  sha256?: string | null;
+  critical_confirmed: boolean;
 }
 
 export interface PluginNavItem {